@@ -0,0 +1,40 @@
+//! Times the grid's hot paths on the largest grid there is, a number for each of its 10k cells.
+//!
+//! ```text
+//! cargo bench --features bench --bench grid
+//! ```
+
+use std::hint::black_box;
+
+use bevy::math::Vec2;
+use criterion::{criterion_group, criterion_main, Criterion};
+use mdr::bench::GridBench;
+
+/// Columns and rows of the grid, the most it can be resized to.
+const SIZE: u32 = 100;
+
+/// Points hit-tested at a time.
+const POSITIONS: usize = 1_000;
+
+fn grid(c: &mut Criterion) {
+    let mut bench = GridBench::new(SIZE, SIZE);
+    c.bench_function("generate", |b| b.iter(|| black_box(bench.generate())));
+    c.bench_function("sync", |b| b.iter(|| bench.sync()));
+    // Back and forth across a cell and a half, so that a column of numbers crosses the edge of
+    // the view every time.
+    let mut offset = Vec2::new(30., 0.);
+    c.bench_function("cull", |b| {
+        b.iter(|| {
+            bench.cull(offset);
+            offset = -offset;
+        })
+    });
+    c.bench_function("drift", |b| b.iter(|| bench.drift()));
+    let positions = bench.positions(POSITIONS);
+    c.bench_function("hit test", |b| {
+        b.iter(|| black_box(bench.hit_test(black_box(&positions))))
+    });
+}
+
+criterion_group!(benches, grid);
+criterion_main!(benches);
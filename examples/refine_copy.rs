@@ -0,0 +1,95 @@
+//! Refines a real file copy: the bins fill as the bytes are copied across.
+//!
+//! ```text
+//! cargo run --example refine_copy -- <from> <to>
+//! ```
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+use bevy::prelude::*;
+use mdr::{Job, JobUpdate, MdrPlugin};
+
+/// Bytes copied at a time, between updates.
+const CHUNK: usize = 64 * 1024;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let (Some(from), Some(to)) = (args.next(), args.next()) else {
+        eprintln!("Usage: refine_copy <from> <to>");
+        return ExitCode::FAILURE;
+    };
+    let copy = match Copy::open(&from, &to) {
+        Ok(copy) => copy,
+        Err(error) => {
+            eprintln!("Could not copy {from} to {to}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    App::new()
+        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .insert_resource(Job::from_iter(format!("Copying {from}"), copy))
+        .add_plugins(MdrPlugin::default())
+        .run();
+    ExitCode::SUCCESS
+}
+
+/// A copy in progress, yielding how far along it is after every chunk.
+struct Copy {
+    from: File,
+    to: File,
+    copied: u64,
+    size: u64,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl Copy {
+    fn open(from: &str, to: &str) -> io::Result<Self> {
+        let from = File::open(from)?;
+        let size = from.metadata()?.len();
+        Ok(Self {
+            from,
+            to: File::create(to)?,
+            copied: 0,
+            size,
+            buffer: vec![0; CHUNK],
+            done: false,
+        })
+    }
+
+    fn step(&mut self) -> io::Result<usize> {
+        let read = self.from.read(&mut self.buffer)?;
+        self.to.write_all(&self.buffer[..read])?;
+        Ok(read)
+    }
+}
+
+impl Iterator for Copy {
+    type Item = JobUpdate;
+
+    fn next(&mut self) -> Option<JobUpdate> {
+        if self.done {
+            return None;
+        }
+        match self.step() {
+            Ok(0) => {
+                self.done = true;
+                Some(JobUpdate::Progress(1.))
+            }
+            Ok(read) => {
+                self.copied += read as u64;
+                Some(JobUpdate::Progress(
+                    self.copied as f32 / self.size.max(1) as f32,
+                ))
+            }
+            Err(error) => {
+                self.done = true;
+                Some(JobUpdate::Message(format!("Copy failed: {error}")))
+            }
+        }
+    }
+}
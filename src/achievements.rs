@@ -0,0 +1,257 @@
+//! Achievements: feats of refinement, each unlocked once and kept for good.
+//!
+//! What has been unlocked is kept in the [`FileLibrary`], alongside the files, with the count of
+//! numbers refined across all of them. Unlocking an achievement shows a toast. The gallery,
+//! opened from the main menu, lists every achievement and whether it has been unlocked. Nothing
+//! is unlocked while a replay plays back, which is somebody's session gone by.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    files::{ActiveFile, FileLibrary},
+    finale::FinaleEnded,
+    grid::{Cell, ClusterCache, GridModel, RefineSet, Refined, ResetRefinement},
+    replay::Playback,
+    state::AppState,
+    theme::Theme,
+    toast::Toast,
+    transition::{in_transition, TransitionEffect, TransitionTo},
+    ui::{spawn_menu, MenuChosen, MenuEntry},
+};
+
+/// Numbers to refine across every file for [`Achievement::TenThousand`].
+const TEN_THOUSAND: u64 = 10_000;
+
+/// Name of the file [`Achievement::ColdHarbor`] asks to be completed.
+const COLD_HARBOR: &str = "Cold Harbor";
+
+/// Height of an achievement's row in the gallery.
+const ROW_HEIGHT: f32 = 20.;
+
+const LOCKED_COLOR: Color = Color::srgb(0.35, 0.35, 0.35);
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (
+                    (count_refinements, complete_files)
+                        .after(RefineSet::React)
+                        .run_if(not(resource_exists::<Playback>)),
+                    unlock,
+                )
+                    .chain(),
+                leave_gallery.run_if(in_state(AppState::Achievements).and(not(in_transition))),
+            ),
+        )
+        .add_systems(OnEnter(AppState::Achievements), spawn_gallery)
+        .add_event::<Unlock>();
+    }
+}
+
+/// A feat of refinement.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Achievement {
+    /// Every number of a cluster refined.
+    FirstCluster,
+    /// Cold Harbor brought to 100%.
+    ColdHarbor,
+    /// Ten thousand numbers refined, over every file.
+    TenThousand,
+    /// A file completed without its refinement being reset along the way.
+    CleanSheet,
+}
+
+impl Achievement {
+    const ALL: [Achievement; 4] = [
+        Achievement::FirstCluster,
+        Achievement::ColdHarbor,
+        Achievement::TenThousand,
+        Achievement::CleanSheet,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstCluster => "First Cluster",
+            Achievement::ColdHarbor => "Cold Harbor",
+            Achievement::TenThousand => "Ten Thousand",
+            Achievement::CleanSheet => "Clean Sheet",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Achievement::FirstCluster => "Refine every number of a cluster",
+            Achievement::ColdHarbor => "Bring Cold Harbor to 100%",
+            Achievement::TenThousand => "Refine 10,000 numbers",
+            Achievement::CleanSheet => "Complete a file without a reset",
+        }
+    }
+}
+
+/// The achievements unlocked, as the library keeps them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Achievements {
+    /// Unlocked so far, in the order they were.
+    pub unlocked: Vec<Achievement>,
+    /// Numbers refined across every file.
+    pub refined: u64,
+}
+
+/// An achievement has been earned, if it was not already.
+#[derive(Event, Clone, Copy, Debug)]
+struct Unlock(Achievement);
+
+fn count_refinements(
+    mut refined: EventReader<Refined>,
+    model: Res<GridModel>,
+    file: Res<ActiveFile>,
+    mut library: ResMut<FileLibrary>,
+    mut cached: Local<ClusterCache>,
+    mut unlocks: EventWriter<Unlock>,
+) {
+    let clusters = cached.get(file.seed, model.size());
+    for event in refined.read() {
+        library.achievements.refined += u64::from(event.count);
+        if library.achievements.refined >= TEN_THOUSAND {
+            unlocks.write(Unlock(Achievement::TenThousand));
+        }
+        let refined = |cell: &Cell| model.get(*cell).is_some_and(|state| state.refined);
+        if clusters
+            .iter()
+            .any(|cluster| !cluster.is_empty() && cluster.iter().all(refined))
+        {
+            unlocks.write(Unlock(Achievement::FirstCluster));
+        }
+    }
+}
+
+/// Unlocks what completing the open file earns, once its finale has played.
+fn complete_files(
+    file: Res<ActiveFile>,
+    mut resets: EventReader<ResetRefinement>,
+    mut ended: EventReader<FinaleEnded>,
+    mut reset: Local<Option<(String, bool)>>,
+    mut unlocks: EventWriter<Unlock>,
+) {
+    if reset.as_ref().is_none_or(|(name, _)| *name != file.name) {
+        *reset = Some((file.name.clone(), false));
+    }
+    if resets.read().count() > 0 {
+        *reset = Some((file.name.clone(), true));
+    }
+    for _ in ended.read() {
+        if file.name == COLD_HARBOR {
+            unlocks.write(Unlock(Achievement::ColdHarbor));
+        }
+        if reset.as_ref().is_some_and(|(_, reset)| !reset) {
+            unlocks.write(Unlock(Achievement::CleanSheet));
+        }
+    }
+}
+
+fn unlock(
+    mut unlocks: EventReader<Unlock>,
+    mut library: ResMut<FileLibrary>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for Unlock(achievement) in unlocks.read() {
+        if library.achievements.unlocked.contains(achievement) {
+            continue;
+        }
+        info!("Achievement unlocked: {}", achievement.name());
+        library.achievements.unlocked.push(*achievement);
+        toasts.write(Toast::success(format!(
+            "Achievement: {}",
+            achievement.name()
+        )));
+    }
+}
+
+/// Marks the gallery's menu.
+#[derive(Component)]
+struct GalleryMenu;
+
+fn spawn_gallery(mut commands: Commands, library: Res<FileLibrary>, theme: Res<Theme>) {
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.0, 0.04, 0.05),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 19.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Achievements),
+    ));
+
+    commands.spawn((
+        Text2d::new("ACHIEVEMENTS"),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        CanvasAnchor::TOP.offset(0., -12.),
+        Transform::from_xyz(0., 0., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Achievements),
+    ));
+
+    let top = (Achievement::ALL.len() as f32 - 1.) / 2. * ROW_HEIGHT + 20.;
+    for (index, achievement) in Achievement::ALL.iter().enumerate() {
+        let unlocked = library.achievements.unlocked.contains(achievement);
+        let (mark, color) = if unlocked {
+            ('*', Color::srgb_from_array(theme.success))
+        } else {
+            ('-', LOCKED_COLOR)
+        };
+        commands.spawn((
+            Text2d::new(format!(
+                "{mark} {}\n{}",
+                achievement.name().to_uppercase(),
+                achievement.description()
+            )),
+            TextFont {
+                font_size: 8.0,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            TextColor(color),
+            Transform::from_xyz(0., top - index as f32 * ROW_HEIGHT, 20.),
+            PIXEL_PERFECT_LAYERS,
+            StateScoped(AppState::Achievements),
+        ));
+    }
+
+    spawn_menu(
+        &mut commands,
+        &format!(
+            "{} OF {}",
+            library.achievements.unlocked.len(),
+            Achievement::ALL.len()
+        ),
+        &[MenuEntry::new("Back")],
+        0,
+        Vec3::new(0., -72., 20.),
+        (GalleryMenu, StateScoped(AppState::Achievements)),
+    );
+}
+
+/// Back to the main menu, with Back or Esc.
+fn leave_gallery(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<(), With<GalleryMenu>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    let back = chosen.read().any(|event| menus.contains(event.menu));
+    if back || keys.just_pressed(KeyCode::Escape) {
+        transitions.write(TransitionTo::new(AppState::Menu, TransitionEffect::Wipe));
+    }
+}
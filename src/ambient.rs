@@ -0,0 +1,265 @@
+//! Ambient mode: with nobody refining, the grid shimmers with the sound of the room, and a thin
+//! bar along the bottom of the canvas rises and falls with it.
+//!
+//! Turned on in the ambient config, which names a program to listen with: anything that writes
+//! raw 16-bit mono samples to its output, a microphone by default or what the computer plays
+//! from a monitor source. The program runs for as long as the mode is on, read on a thread of
+//! its own. Once there has been no input for a while the grid starts following the loudness,
+//! each number swelling in its own time, and the moment anyone touches a key or the mouse it
+//! settles again. The numbers only swell, without rippling, for those who asked for less motion.
+
+use std::{
+    io::{self, Read},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasSize, Supersampling, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    grid::{Cell, Number},
+    idle::Idle,
+    jazz::DefiantJazz,
+    theme::Theme,
+    toast::Toast,
+};
+
+/// Times a second the loudness is measured.
+const MEASURES_PER_SECOND: u32 = 20;
+
+/// How quickly the level rises to louder sound, and falls back when it quietens, per second.
+const ATTACK_RATE: f32 = 20.;
+const DECAY_RATE: f32 = 3.;
+
+/// Seconds the grid takes to start following the sound once the wait is over.
+const FADE_IN: f32 = 3.;
+
+/// How much numbers grow at the loudest.
+const SHIMMER_SCALE: f32 = 0.3;
+
+/// Ripples a second of a number's shimmer.
+const SHIMMER_RATE: f32 = 1.5;
+
+/// Height of the level bar, and how opaque it is at its brightest.
+const BAR_HEIGHT: f32 = 1.;
+const BAR_ALPHA: f32 = 0.6;
+
+pub struct AmbientPlugin;
+
+impl Plugin for AmbientPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AmbientLevel>().add_systems(
+            Update,
+            (
+                listen.run_if(resource_changed::<Config>),
+                follow_level,
+                shimmer_numbers.run_if(shimmering.and(not(resource_exists::<DefiantJazz>))),
+                show_bar,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The program listening, and the loudness it last heard, from 0 to 1, as the bits of an `f32`.
+#[derive(Resource)]
+struct Listener {
+    child: Child,
+    loudness: Arc<AtomicU32>,
+    /// The command it was started with, to start again should the config change it.
+    command: String,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        // The program would otherwise go on recording after the app has gone.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// How loud the room is, and how far the grid is following it.
+#[derive(Resource, Default)]
+pub struct AmbientLevel {
+    /// Loudness, eased, from 0 to 1.
+    level: f32,
+    /// How much the grid follows the loudness, from 0 while someone is refining to 1 once they
+    /// have been gone a while.
+    strength: f32,
+}
+
+/// Whether the grid is following the sound, for systems that would otherwise move the numbers.
+pub fn shimmering(level: Res<AmbientLevel>) -> bool {
+    level.strength > 0.
+}
+
+/// Starts the listening program with `command`, which records at `sample_rate`, and measures
+/// what it hears [`MEASURES_PER_SECOND`] times a second.
+fn start_listener(command: &str, sample_rate: u32) -> io::Result<Listener> {
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no command given"))?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut output = child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?;
+    let loudness = Arc::new(AtomicU32::new(0));
+    let heard = loudness.clone();
+    let samples = (sample_rate / MEASURES_PER_SECOND).max(1) as usize;
+    thread::spawn(move || {
+        let mut buffer = vec![0; samples * 2];
+        // Once the program is stopped, or stops, there is nothing left to read.
+        while output.read_exact(&mut buffer).is_ok() {
+            let power = buffer
+                .chunks_exact(2)
+                .map(|sample| {
+                    let sample = f32::from(i16::from_le_bytes([sample[0], sample[1]])) / 32_768.;
+                    sample * sample
+                })
+                .sum::<f32>()
+                / samples as f32;
+            heard.store(power.sqrt().to_bits(), Ordering::Relaxed);
+        }
+    });
+    Ok(Listener {
+        child,
+        loudness,
+        command: command.to_string(),
+    })
+}
+
+/// Starts or stops the listening program as the mode is turned on and off.
+fn listen(
+    mut commands: Commands,
+    config: Res<Config>,
+    listener: Option<Res<Listener>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let ambient = &config.ambient;
+    let running = listener.is_some_and(|listener| listener.command == ambient.command);
+    if !ambient.enabled {
+        commands.remove_resource::<Listener>();
+        return;
+    }
+    if running {
+        return;
+    }
+    match start_listener(&ambient.command, ambient.sample_rate) {
+        Ok(listener) => {
+            info!("Listening with {}", ambient.command);
+            commands.insert_resource(listener);
+        }
+        Err(error) => {
+            warn!("Could not listen with {}: {error}", ambient.command);
+            commands.remove_resource::<Listener>();
+            toasts.write(Toast::warning("Could not listen"));
+        }
+    }
+}
+
+/// Eases the level towards the loudness heard, and the grid into following it once nobody has
+/// touched anything for long enough.
+fn follow_level(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    idle: Res<Idle>,
+    listener: Option<Res<Listener>>,
+    mut level: ResMut<AmbientLevel>,
+) {
+    let heard = listener.as_ref().map_or(0., |listener| {
+        f32::from_bits(listener.loudness.load(Ordering::Relaxed))
+    });
+    let target = (heard * config.ambient.gain).clamp(0., 1.);
+    let rate = if target > level.level {
+        ATTACK_RATE
+    } else {
+        DECAY_RATE
+    };
+    let eased = level.level + (target - level.level) * (rate * time.delta_secs()).min(1.);
+    let away = listener.is_some() && idle.seconds(&time) >= config.ambient.idle_seconds;
+    // Gone at once when someone is back, so the grid is still the moment they reach for it.
+    let strength = if away {
+        (level.strength + time.delta_secs() / FADE_IN).min(1.)
+    } else {
+        0.
+    };
+    if level.level != eased || level.strength != strength {
+        level.level = eased;
+        level.strength = strength;
+    }
+}
+
+/// Swells the numbers with the level, each in its own time.
+fn shimmer_numbers(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    level: Res<AmbientLevel>,
+    supersampling: Res<Supersampling>,
+    mut numbers: Query<(&Cell, &mut Transform), With<Number>>,
+) {
+    let swell = SHIMMER_SCALE * level.level * level.strength;
+    let t = time.elapsed_secs() * SHIMMER_RATE * std::f32::consts::TAU;
+    for (cell, mut transform) in &mut numbers {
+        let ripple = if config.accessibility.reduced_motion {
+            1.
+        } else {
+            let phase = (cell.col * 31 + cell.row * 17) as f32;
+            0.5 + 0.5 * (t + phase).sin()
+        };
+        let scale = Vec3::splat((1. + swell * ripple) * supersampling.text_scale());
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+/// The level bar along the bottom of the canvas.
+#[derive(Component)]
+struct LevelBar;
+
+/// Spawns the level bar while the grid follows the sound, sizes it to the level, and despawns
+/// it once someone is back.
+fn show_bar(
+    mut commands: Commands,
+    level: Res<AmbientLevel>,
+    canvas: Res<CanvasSize>,
+    theme: Res<Theme>,
+    bar: Option<Single<(Entity, &mut Sprite), With<LevelBar>>>,
+) {
+    let size = Vec2::new(canvas.size().x * level.level, BAR_HEIGHT);
+    let color = theme.numbers().with_alpha(BAR_ALPHA * level.strength);
+    match (level.strength > 0., bar) {
+        (true, None) => {
+            commands.spawn((
+                LevelBar,
+                Sprite {
+                    color,
+                    custom_size: Some(size),
+                    anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                CanvasAnchor::BOTTOM,
+                Transform::from_xyz(0., 0., 30.),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        }
+        (true, Some(mut bar)) => {
+            if bar.1.custom_size != Some(size) || bar.1.color != color {
+                bar.1.custom_size = Some(size);
+                bar.1.color = color;
+            }
+        }
+        (false, Some(bar)) => commands.entity(bar.0).despawn(),
+        (false, None) => {}
+    }
+}
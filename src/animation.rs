@@ -0,0 +1,225 @@
+//! Frame animations: a sprite stepped through frames of a sprite sheet at a frame rate.
+//!
+//! A [`SpriteSheet`] is a RON file ending in `.sheet.ron` in the assets, naming the image its
+//! frames are cut from, the size of a frame and how many there are across and down, and the
+//! clips that can be played from it, each a list of frames with a frame rate and a
+//! [`LoopMode`]. Replacing the image or retiming a clip takes no change here, only to the files.
+//!
+//! A [`FrameAnimation`] on an entity with a [`Sprite`] cut from the sheet plays a clip, replacing
+//! whatever clip it was playing. One that plays once is removed when it ends, and an
+//! [`AnimationFinished`] is sent. Like tweens, animations run on real time and end at once for
+//! those who asked for less motion.
+
+use std::{collections::HashMap, io};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SpriteSheet>()
+            .register_asset_loader(SpriteSheetLoader)
+            .add_event::<AnimationFinished>()
+            .add_systems(Update, animate_frames);
+    }
+}
+
+/// How a clip goes on once it reaches its last frame.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Stops there, and is done.
+    #[default]
+    Once,
+    /// Starts over from the first.
+    Loop,
+    /// Goes back the way it came, and then forwards again.
+    PingPong,
+}
+
+/// A run of frames of a sprite sheet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Clip {
+    /// Indices of the frames, in the order they are shown, counting across and then down.
+    pub frames: Vec<usize>,
+    /// Frames a second.
+    pub fps: f32,
+    #[serde(default)]
+    pub mode: LoopMode,
+}
+
+/// Frames cut from an image, and the clips they make up.
+#[derive(Asset, TypePath, Debug)]
+pub struct SpriteSheet {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    clips: HashMap<String, Clip>,
+}
+
+impl SpriteSheet {
+    pub fn clip(&self, name: &str) -> Option<&Clip> {
+        self.clips.get(name)
+    }
+
+    /// A sprite at rest where the clip leaves it, on its last frame, or on the first frame of the
+    /// sheet if it has no such clip.
+    pub fn sprite(&self, clip: &str) -> Sprite {
+        let index = self
+            .clip(clip)
+            .and_then(|clip| clip.frames.last().copied())
+            .unwrap_or_default();
+        Sprite::from_atlas_image(
+            self.image.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index,
+            },
+        )
+    }
+}
+
+/// A sprite sheet as its file has it.
+#[derive(Deserialize)]
+struct SheetFile {
+    /// Path of the image, from the directory of the sheet.
+    image: String,
+    /// Size of a frame, in pixels.
+    frame: UVec2,
+    columns: u32,
+    rows: u32,
+    clips: HashMap<String, Clip>,
+}
+
+#[derive(Default)]
+struct SpriteSheetLoader;
+
+impl AssetLoader for SpriteSheetLoader {
+    type Asset = SpriteSheet;
+    type Settings = ();
+    type Error = io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> io::Result<SpriteSheet> {
+        let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: SheetFile = ron::de::from_bytes(&bytes).map_err(|e| invalid(e.to_string()))?;
+        let frames = (file.columns * file.rows) as usize;
+        if let Some((name, _)) = file
+            .clips
+            .iter()
+            .find(|(_, clip)| clip.frames.iter().any(|&frame| frame >= frames))
+        {
+            return Err(invalid(format!(
+                "clip {name} has frames past the sheet's {frames}"
+            )));
+        }
+        let image = load_context
+            .asset_path()
+            .resolve_embed(&file.image)
+            .map_err(|e| invalid(e.to_string()))?;
+        let layout = TextureAtlasLayout::from_grid(file.frame, file.columns, file.rows, None, None);
+        Ok(SpriteSheet {
+            image: load_context.load(image),
+            layout: load_context.add_labeled_asset("layout".into(), layout),
+            clips: file.clips,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sheet.ron"]
+    }
+}
+
+/// Plays a clip on the entity's [`Sprite`], which is cut from the sheet the clip is from.
+#[derive(Component, Clone, Debug)]
+pub struct FrameAnimation {
+    clip: Clip,
+    /// Seconds since the clip started.
+    elapsed: f32,
+}
+
+impl FrameAnimation {
+    pub fn new(clip: Clip) -> Self {
+        Self { clip, elapsed: 0. }
+    }
+
+    /// The clip, `elapsed` seconds in already.
+    pub fn resumed(clip: Clip, elapsed: f32) -> Self {
+        Self { clip, elapsed }
+    }
+
+    pub fn clip(&self) -> &Clip {
+        &self.clip
+    }
+
+    /// Seconds since the clip started.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Which of the clip's frames is shown `elapsed` seconds in, and whether the clip is done.
+    fn frame(&self) -> (usize, bool) {
+        let count = self.clip.frames.len();
+        let step = (self.elapsed * self.clip.fps.max(0.)) as usize;
+        match self.clip.mode {
+            LoopMode::Once => (step.min(count.saturating_sub(1)), step >= count),
+            LoopMode::Loop => (step % count.max(1), false),
+            LoopMode::PingPong => {
+                // There and back again, without showing either end twice in a row.
+                let period = (2 * count).saturating_sub(2).max(1);
+                let step = step % period;
+                (step.min(period - step), false)
+            }
+        }
+    }
+}
+
+/// A [`FrameAnimation`] that plays once has ended, and been removed.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+}
+
+fn animate_frames(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut animations: Query<(Entity, &mut FrameAnimation, &mut Sprite)>,
+    mut finished: EventWriter<AnimationFinished>,
+) {
+    for (entity, mut animation, mut sprite) in &mut animations {
+        if config.accessibility.reduced_motion && animation.clip.mode == LoopMode::Once {
+            animation.elapsed = f32::INFINITY;
+        } else {
+            animation.elapsed += time.delta_secs();
+        }
+        let (step, done) = animation.frame();
+        let index = animation.clip.frames.get(step).copied();
+        // Only touch sprites whose frame changes, so the rest are not marked as changed.
+        if let Some(index) = index.filter(|&index| {
+            sprite
+                .texture_atlas
+                .as_ref()
+                .is_some_and(|atlas| atlas.index != index)
+        }) {
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = index;
+            }
+        }
+        if done {
+            commands.entity(entity).try_remove::<FrameAnimation>();
+            finished.write(AnimationFinished { entity });
+        }
+    }
+}
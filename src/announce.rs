@@ -0,0 +1,148 @@
+//! Announcements of what changes on screen, for screen readers and other assistive tools.
+//!
+//! With announcements on in the accessibility settings, each change the refiner would otherwise
+//! have to see ("Selected 9 numbers", "Bin 03 now 62 percent") is written to standard output as
+//! a line of its own: `ANNOUNCE ` followed by the announcement in RON, such as
+//! `ANNOUNCE (kind:Bin,text:"Bin 03 now 62 percent")`. The prefix sets the announcements apart
+//! from the log, which shares the stream.
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    bins::{percent, Bin, BinLabels},
+    config::Config,
+    files::ActiveFile,
+    finale::FinaleEnded,
+    grid::{RefineSet, Refined, Selection},
+    toast::Toast,
+};
+
+pub struct AnnouncePlugin;
+
+impl Plugin for AnnouncePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Announcement>().add_systems(
+            Update,
+            (
+                (
+                    announce_selection.run_if(resource_changed::<Selection>),
+                    announce_bins,
+                    announce_file.run_if(resource_changed::<ActiveFile>),
+                    announce_toasts,
+                    announce_finale,
+                ),
+                print_announcements,
+            )
+                .chain()
+                .after(RefineSet::React),
+        );
+    }
+}
+
+/// A change worth telling the refiner about.
+#[derive(Event, Serialize, Clone, Debug)]
+pub struct Announcement {
+    pub kind: AnnouncementKind,
+    pub text: String,
+}
+
+/// What an [`Announcement`] is about, for tools that only follow some of them.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnouncementKind {
+    Selection,
+    Bin,
+    File,
+    Notice,
+}
+
+impl Announcement {
+    pub fn new(kind: AnnouncementKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+fn announce_selection(selection: Res<Selection>, mut announcements: EventWriter<Announcement>) {
+    let text = match selection.0 {
+        Some(range) => {
+            let count = (range.width() + 1) * (range.height() + 1);
+            let plural = if count == 1 { "" } else { "s" };
+            format!("Selected {count} number{plural}")
+        }
+        None => "Selection cleared".to_string(),
+    };
+    announcements.write(Announcement::new(AnnouncementKind::Selection, text));
+}
+
+/// Tells how full a bin is once numbers are refined into it.
+fn announce_bins(
+    labels: Res<BinLabels>,
+    mut refined: EventReader<Refined>,
+    bins: Query<&Bin>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    for event in refined.read() {
+        let Some(bin) = bins.iter().find(|bin| bin.index == event.bin) else {
+            continue;
+        };
+        let name = match labels.name(bin.index) {
+            Some(name) => name.to_string(),
+            None => format!("Bin {:02}", bin.index + 1),
+        };
+        let full = percent(bin.progress).replace('%', " percent");
+        announcements.write(Announcement::new(
+            AnnouncementKind::Bin,
+            format!("{name} now {full}"),
+        ));
+    }
+}
+
+fn announce_file(
+    file: Res<ActiveFile>,
+    mut last: Local<Option<String>>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    // Sources that change how many bins there are change the file too, yet it is the same one.
+    if last.as_ref() == Some(&file.name) {
+        return;
+    }
+    *last = Some(file.name.clone());
+    announcements.write(Announcement::new(
+        AnnouncementKind::File,
+        format!("Opened {}", file.name),
+    ));
+}
+
+fn announce_toasts(mut toasts: EventReader<Toast>, mut announcements: EventWriter<Announcement>) {
+    for toast in toasts.read() {
+        announcements.write(Announcement::new(AnnouncementKind::Notice, &toast.text));
+    }
+}
+
+fn announce_finale(
+    file: Res<ActiveFile>,
+    mut ended: EventReader<FinaleEnded>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    for _ in ended.read() {
+        announcements.write(Announcement::new(
+            AnnouncementKind::File,
+            format!("{} complete", file.name),
+        ));
+    }
+}
+
+fn print_announcements(config: Res<Config>, mut announcements: EventReader<Announcement>) {
+    for announcement in announcements.read() {
+        if !config.accessibility.announcements {
+            continue;
+        }
+        match ron::to_string(announcement) {
+            Ok(line) => println!("ANNOUNCE {line}"),
+            Err(error) => warn!("Could not write announcement: {error}"),
+        }
+    }
+}
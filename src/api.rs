@@ -0,0 +1,213 @@
+//! What an application embedding the [`MdrPlugin`](crate::MdrPlugin) shows through it, such as
+//! the progress of its own long-running jobs.
+//!
+//! Its systems take [`MdrCommands`] to set how full a bin is, rename a bin or change the temper it
+//! expects, show a message in the header, or name the file the screen is refining. Requests are
+//! applied in order, ahead of the bins and the header reacting to them, and each that changes
+//! something is told of with a [`BinChanged`], [`BinRelabeled`], [`MessageChanged`] or
+//! [`FileNameChanged`] event. Once a bin has been set the host is driving the bins, and
+//! refinement no longer fills them, as with a bin source; renaming them leaves refinement be.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    bins::{Bin, BinLabels, BinLayout, DrivenBins, RelayBins, MAX_BIN_COUNT},
+    files::ActiveFile,
+    grid::{RefineSet, Temper},
+    header::{FileTitle, HeaderMessage},
+};
+
+pub struct ApiPlugin;
+
+impl Plugin for ApiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Request>()
+            .add_event::<BinChanged>()
+            .add_event::<BinRelabeled>()
+            .add_event::<MessageChanged>()
+            .add_event::<FileNameChanged>()
+            .add_systems(
+                Update,
+                apply_requests
+                    .run_if(on_event::<Request>)
+                    .before(RefineSet::Apply),
+            );
+    }
+}
+
+/// A bin was set to a new fill.
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct BinChanged {
+    pub index: usize,
+    /// How full the bin is, from 0 to 1.
+    pub progress: f32,
+}
+
+/// A bin was renamed, or set to expect another temper, with what it is named and expects now.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct BinRelabeled {
+    pub index: usize,
+    /// Its name, or `None` for its number.
+    pub name: Option<String>,
+    /// The temper it expects, or `None` for its turn in the round of them.
+    pub temper: Option<Temper>,
+}
+
+/// The header's message was changed, or cleared with `None`.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct MessageChanged(pub Option<String>);
+
+/// The file was given a new name to show by.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct FileNameChanged(pub String);
+
+/// Something asked of the screen through [`MdrCommands`].
+#[derive(Event, Clone, Debug)]
+enum Request {
+    SetBin(usize, f32),
+    BinName(usize, Option<String>),
+    BinTemper(usize, Option<Temper>),
+    Message(Option<String>),
+    FileName(String),
+}
+
+/// Shows the host application's work on the screen.
+#[derive(SystemParam)]
+pub struct MdrCommands<'w> {
+    requests: EventWriter<'w, Request>,
+}
+
+impl MdrCommands<'_> {
+    /// Sets bin `index`, counting from 0, to `progress`, from 0 to 1, adding bins up to it
+    /// where there are fewer, as many as there can be.
+    pub fn set_bin(&mut self, index: usize, progress: f32) {
+        self.requests
+            .write(Request::SetBin(index, progress.clamp(0., 1.)));
+    }
+
+    /// Names bin `index`, counting from 0; its label flips over to the new name.
+    pub fn set_bin_name(&mut self, index: usize, name: impl Into<String>) {
+        self.requests
+            .write(Request::BinName(index, Some(name.into())));
+    }
+
+    /// Takes bin `index`'s name away, showing its number again.
+    pub fn clear_bin_name(&mut self, index: usize) {
+        self.requests.write(Request::BinName(index, None));
+    }
+
+    /// Has bin `index` expect numbers of `temper`, or of its turn in the round of scary tempers
+    /// again with `None`.
+    pub fn set_bin_temper(&mut self, index: usize, temper: Option<Temper>) {
+        self.requests.write(Request::BinTemper(index, temper));
+    }
+
+    /// Shows `message` in the header in place of the file name.
+    pub fn push_message(&mut self, message: impl Into<String>) {
+        self.requests.write(Request::Message(Some(message.into())));
+    }
+
+    /// Takes the header's message down, showing the file name again.
+    pub fn clear_message(&mut self) {
+        self.requests.write(Request::Message(None));
+    }
+
+    /// Names the file being refined, as the header shows it. The file itself keeps its own name,
+    /// and with it its place in the stats.
+    pub fn set_file_name(&mut self, name: impl Into<String>) {
+        self.requests.write(Request::FileName(name.into()));
+    }
+}
+
+/// Applies the requests of the last frame, telling of each change.
+fn apply_requests(
+    mut commands: Commands,
+    mut requests: EventReader<Request>,
+    (file, layout, mut labels): (Res<ActiveFile>, Res<BinLayout>, ResMut<BinLabels>),
+    (mut message, mut title): (ResMut<HeaderMessage>, ResMut<FileTitle>),
+    mut bins: Query<&mut Bin>,
+    mut relays: EventWriter<RelayBins>,
+    (mut bins_changed, mut relabeled, mut messages_changed, mut names_changed): (
+        EventWriter<BinChanged>,
+        EventWriter<BinRelabeled>,
+        EventWriter<MessageChanged>,
+        EventWriter<FileNameChanged>,
+    ),
+) {
+    // Once the bins are to be laid out anew, they are only set on the new layout, to be spawned
+    // with.
+    let mut relay: Option<Vec<f32>> = None;
+    for request in requests.read() {
+        match request {
+            &Request::SetBin(index, progress) if index < MAX_BIN_COUNT => {
+                commands.insert_resource(DrivenBins);
+                if relay.is_none() && index >= layout.count() {
+                    // As full as they are now, where they already were.
+                    let mut kept = vec![0.; layout.count()];
+                    for bin in &bins {
+                        if let Some(slot) = kept.get_mut(bin.index) {
+                            *slot = bin.progress;
+                        }
+                    }
+                    relay = Some(kept);
+                }
+                if let Some(relay) = &mut relay {
+                    if relay.len() <= index {
+                        relay.resize(index + 1, 0.);
+                    }
+                    if relay[index] == progress {
+                        continue;
+                    }
+                    relay[index] = progress;
+                } else if let Some(mut bin) = bins.iter_mut().find(|bin| bin.index == index) {
+                    if bin.progress == progress {
+                        continue;
+                    }
+                    bin.progress = progress;
+                }
+                bins_changed.write(BinChanged { index, progress });
+            }
+            Request::BinName(index, name) if *index < MAX_BIN_COUNT => {
+                if labels.name(*index) == name.as_deref() {
+                    continue;
+                }
+                labels.rename(*index, name.clone());
+                relabeled.write(BinRelabeled {
+                    index: *index,
+                    name: name.clone(),
+                    temper: labels.temper(*index),
+                });
+            }
+            &Request::BinTemper(index, temper) if index < MAX_BIN_COUNT => {
+                if labels.temper(index) == temper {
+                    continue;
+                }
+                labels.remap(index, temper);
+                relabeled.write(BinRelabeled {
+                    index,
+                    name: labels.name(index).map(String::from),
+                    temper,
+                });
+            }
+            Request::SetBin(index, _)
+            | Request::BinName(index, _)
+            | Request::BinTemper(index, _) => {
+                warn!("There is no bin {index}; there can be at most {MAX_BIN_COUNT}");
+            }
+            Request::Message(text) => {
+                if message.set_if_neq(HeaderMessage(text.clone())) {
+                    messages_changed.write(MessageChanged(text.clone()));
+                }
+            }
+            Request::FileName(name) => {
+                if title.0.as_ref().unwrap_or(&file.name) != name {
+                    title.0 = Some(name.clone());
+                    names_changed.write(FileNameChanged(name.clone()));
+                }
+            }
+        }
+    }
+    if let Some(progress) = relay {
+        relays.write(RelayBins(progress));
+    }
+}
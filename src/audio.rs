@@ -0,0 +1,277 @@
+//! Sound effects, synthesized rather than loaded from files.
+//!
+//! A [`Tone`] is a short square-ish beep with a quick attack and decay, in the spirit of the
+//! terminals it imitates. Tones are assets like any other audio source.
+//!
+//! Sound effects are played by writing a [`PlaySound`], which borrows one of a fixed pool of
+//! emitter entities. When every emitter is busy the sound is dropped, which keeps bursts of
+//! effects from piling up into noise. They play at the effects volume of the config, apart from
+//! the [soundscape](crate::soundscape)'s.
+//!
+//! [`Envelope`] is a small analysis helper for anything that needs to follow the loudness of a
+//! piece of audio over time.
+
+use std::time::Duration;
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source, Volume},
+    prelude::*,
+};
+
+use crate::{config::Config, loading::LoadingAssets};
+
+/// Sample rate tones are synthesized at.
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Seconds a tone takes to reach full volume, and to fade out at its end.
+const RAMP: f32 = 0.002;
+
+/// Number of sounds that can play at once.
+const VOICES: usize = 8;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Tone>()
+            .add_event::<PlaySound>()
+            .add_systems(Startup, setup_sounds)
+            .add_systems(PostUpdate, play_sounds);
+    }
+}
+
+/// The sound effects of the app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sound {
+    /// The beep of a terminal powering on.
+    Beep,
+    /// A quiet tick, for the cursor passing over a number.
+    Tick,
+    /// A long, high chime, for a milestone of a file.
+    Chime,
+}
+
+impl Sound {
+    const ALL: [Sound; 3] = [Sound::Beep, Sound::Tick, Sound::Chime];
+
+    fn tone(self) -> Tone {
+        match self {
+            Sound::Beep => Tone::new(880., 0.2),
+            Sound::Tick => Tone {
+                frequency: 2400.,
+                duration: 0.012,
+                volume: 0.08,
+            },
+            Sound::Chime => Tone {
+                frequency: 1320.,
+                duration: 0.6,
+                volume: 0.25,
+            },
+        }
+    }
+}
+
+/// Plays a sound effect.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlaySound {
+    pub sound: Sound,
+    /// Playback speed, which shifts the pitch; 1 plays the sound as is.
+    pub pitch: f32,
+}
+
+impl PlaySound {
+    pub fn new(sound: Sound) -> Self {
+        Self { sound, pitch: 1. }
+    }
+
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+}
+
+/// The tone of each [`Sound`], in the order of [`Sound::ALL`].
+#[derive(Resource)]
+struct Sounds([Handle<Tone>; Sound::ALL.len()]);
+
+/// An emitter of the pool. It is idle while it has no [`AudioPlayer`].
+#[derive(Component)]
+struct Voice;
+
+/// A synthesized beep.
+#[derive(Asset, TypePath, Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    /// Pitch in hertz.
+    pub frequency: f32,
+    /// Length in seconds.
+    pub duration: f32,
+    /// Loudness from 0 to 1, before the global volume.
+    pub volume: f32,
+}
+
+impl Tone {
+    pub fn new(frequency: f32, duration: f32) -> Self {
+        Self {
+            frequency,
+            duration,
+            volume: 0.3,
+        }
+    }
+}
+
+/// Produces the samples of a [`Tone`].
+pub struct ToneDecoder {
+    tone: Tone,
+    sample: u32,
+    samples: u32,
+}
+
+impl Iterator for ToneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.samples {
+            return None;
+        }
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+
+        // A sine with a little of its third harmonic, softened at both ends so it doesn't click.
+        let phase = std::f32::consts::TAU * self.tone.frequency * t;
+        let wave = phase.sin() + (3. * phase).sin() / 3.;
+        let envelope = (t / RAMP)
+            .min((self.tone.duration - t) / RAMP)
+            .clamp(0., 1.);
+        Some(wave * envelope * self.tone.volume * 0.75)
+    }
+}
+
+impl Source for ToneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.samples - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.tone.duration))
+    }
+}
+
+impl Decodable for Tone {
+    type DecoderItem = f32;
+    type Decoder = ToneDecoder;
+
+    fn decoder(&self) -> ToneDecoder {
+        ToneDecoder {
+            tone: *self,
+            sample: 0,
+            samples: (self.duration.max(0.) * SAMPLE_RATE as f32) as u32,
+        }
+    }
+}
+
+fn setup_sounds(
+    mut commands: Commands,
+    mut tones: ResMut<Assets<Tone>>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    let sounds = Sound::ALL.map(|sound| tones.add(sound.tone()));
+    for handle in &sounds {
+        loading.wait_for(handle.clone());
+    }
+    commands.insert_resource(Sounds(sounds));
+    for _ in 0..VOICES {
+        commands.spawn(Voice);
+    }
+}
+
+fn play_sounds(
+    mut commands: Commands,
+    mut requests: EventReader<PlaySound>,
+    config: Res<Config>,
+    sounds: Res<Sounds>,
+    voices: Query<Entity, (With<Voice>, Without<AudioPlayer<Tone>>)>,
+) {
+    let mut idle = voices.iter();
+    for request in requests.read() {
+        let Some(voice) = idle.next() else {
+            requests.clear();
+            return;
+        };
+        let index = Sound::ALL
+            .iter()
+            .position(|&sound| sound == request.sound)
+            .unwrap_or_default();
+        // Removing the player once it finishes hands the voice back to the pool.
+        commands.entity(voice).insert((
+            AudioPlayer(sounds.0[index].clone()),
+            PlaybackSettings::REMOVE
+                .with_speed(request.pitch)
+                .with_volume(Volume::Linear(config.audio.effects_volume)),
+        ));
+    }
+}
+
+/// How loud a piece of audio is over time: the RMS of every short window of its samples, scaled
+/// so that the loudest window is 1.
+#[derive(Clone, Debug, Default)]
+pub struct Envelope {
+    levels: Vec<f32>,
+}
+
+impl Envelope {
+    /// Seconds covered by each level.
+    pub const WINDOW: f32 = 1. / 30.;
+
+    /// Measures interleaved samples with the given number of channels and sample rate.
+    pub fn analyse(
+        samples: impl IntoIterator<Item = f32>,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Self {
+        let window = ((sample_rate * u32::from(channels.max(1))) as f32 * Self::WINDOW) as usize;
+        let mut levels = Vec::new();
+        let (mut sum, mut count) = (0., 0);
+        for sample in samples {
+            sum += sample * sample;
+            count += 1;
+            if count == window.max(1) {
+                levels.push((sum / count as f32).sqrt());
+                (sum, count) = (0., 0);
+            }
+        }
+        if count > 0 {
+            levels.push((sum / count as f32).sqrt());
+        }
+        let peak = levels.iter().copied().fold(0., f32::max);
+        if peak > 0. {
+            levels.iter_mut().for_each(|level| *level /= peak);
+        }
+        Self { levels }
+    }
+
+    /// Length of the analysed audio in seconds.
+    pub fn duration(&self) -> f32 {
+        self.levels.len() as f32 * Self::WINDOW
+    }
+
+    /// Loudness at a point in time, from 0 to 1, blending neighbouring windows. Silent outside
+    /// the audio.
+    pub fn level(&self, seconds: f32) -> f32 {
+        let at = seconds / Self::WINDOW - 0.5;
+        if at < -0.5 || seconds > self.duration() {
+            return 0.;
+        }
+        let index = at.max(0.).floor() as usize;
+        let current = self.levels.get(index).copied().unwrap_or_default();
+        let next = self.levels.get(index + 1).copied().unwrap_or(current);
+        current + (next - current) * at.max(0.).fract()
+    }
+}
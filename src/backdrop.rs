@@ -0,0 +1,145 @@
+//! The backdrop: faint patterns over the background behind the grid, as the theme asks for.
+//!
+//! Each of the [`Backdrop`]'s patterns is drawn on [`BACKDROP_LAYERS`], the bottom layer of the
+//! canvas, so that the grid's numbers always sit over it and it stays put as the grid pans:
+//! a scan texture of every other row of pixels darkened, a vignette darkening the corners, and
+//! the Lumon name in the middle. All are off unless the theme turns them on, and are drawn anew
+//! whenever it changes.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    canvas::{CanvasFill, BACKDROP_LAYERS},
+    theme::{Backdrop, Theme},
+};
+
+/// Width and height of the vignette's image, stretched over the canvas.
+const VIGNETTE_SIZE: u32 = 32;
+
+/// How far from the middle the vignette starts to darken, as a fraction of the way to a corner.
+const VIGNETTE_START: f32 = 0.4;
+
+pub struct BackdropPlugin;
+
+impl Plugin for BackdropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_backdrop.run_if(resource_changed::<Theme>));
+    }
+}
+
+/// Marks a pattern of the backdrop.
+#[derive(Component)]
+struct BackdropPattern;
+
+/// Replaces the patterns with those the theme asks for, once they are not those already drawn.
+fn spawn_backdrop(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    mut drawn: Local<Option<(Backdrop, Color)>>,
+    mut images: ResMut<Assets<Image>>,
+    patterns: Query<Entity, With<BackdropPattern>>,
+) {
+    // The watermark is set in the numbers' color.
+    let wanted = Some((theme.backdrop, theme.numbers()));
+    if *drawn == wanted {
+        return;
+    }
+    *drawn = wanted;
+    for entity in &patterns {
+        commands.entity(entity).despawn();
+    }
+    let backdrop = theme.backdrop;
+    if backdrop.scanlines > 0. {
+        commands.spawn((
+            BackdropPattern,
+            Sprite {
+                image: images.add(scanlines()),
+                color: Color::BLACK.with_alpha(backdrop.scanlines.min(1.)),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: false,
+                    tile_y: true,
+                    stretch_value: 1.,
+                },
+                ..default()
+            },
+            CanvasFill,
+            Transform::from_xyz(0., 0., 1.),
+            BACKDROP_LAYERS,
+        ));
+    }
+    if backdrop.vignette > 0. {
+        commands.spawn((
+            BackdropPattern,
+            Sprite {
+                image: images.add(vignette()),
+                color: Color::BLACK.with_alpha(backdrop.vignette.min(1.)),
+                ..default()
+            },
+            CanvasFill,
+            Transform::from_xyz(0., 0., 2.),
+            BACKDROP_LAYERS,
+        ));
+    }
+    if backdrop.watermark > 0. {
+        commands.spawn((
+            BackdropPattern,
+            Text2d::new("LUMON"),
+            TextFont {
+                font_size: 40.0,
+                ..default()
+            },
+            TextColor(theme.numbers().with_alpha(backdrop.watermark.min(1.))),
+            Transform::from_xyz(0., 0., 3.),
+            BACKDROP_LAYERS,
+        ));
+    }
+}
+
+/// Two rows of pixels, the top one opaque and the bottom one clear, tiled down the canvas.
+fn scanlines() -> Image {
+    Image::new(
+        Extent3d {
+            width: 1,
+            height: 2,
+            ..default()
+        },
+        TextureDimension::D2,
+        vec![255, 255, 255, 255, 0, 0, 0, 0],
+        TextureFormat::Rgba8Unorm,
+        // Tiling needs the image's size on the main world.
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+/// Clear in the middle, growing opaque towards the corners; smoothly sampled when stretched.
+fn vignette() -> Image {
+    let half = VIGNETTE_SIZE as f32 / 2.;
+    let mut data = Vec::with_capacity((VIGNETTE_SIZE * VIGNETTE_SIZE * 4) as usize);
+    for y in 0..VIGNETTE_SIZE {
+        for x in 0..VIGNETTE_SIZE {
+            let from_middle = (Vec2::new(x as f32, y as f32) + 0.5 - half) / half;
+            let distance = from_middle.length() / std::f32::consts::SQRT_2;
+            let t = ((distance - VIGNETTE_START) / (1. - VIGNETTE_START)).clamp(0., 1.);
+            let alpha = t * t * (3. - 2. * t);
+            data.extend([255, 255, 255, (alpha * 255.) as u8]);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: VIGNETTE_SIZE,
+            height: VIGNETTE_SIZE,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::linear();
+    image
+}
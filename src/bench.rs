@@ -0,0 +1,145 @@
+//! Hooks for the benchmarks in `benches/`, which can only reach what the crate makes public.
+//!
+//! A [`GridBench`] is a world holding a grid with a number for every cell, as the app spawns
+//! them, and the resources its systems read, without a window or a renderer. Each of its methods
+//! runs one of the grid's hot paths over it once.
+
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemId, prelude::*};
+
+use crate::{
+    canvas::{CanvasSize, GridCamera},
+    config::{Config, DigitRenderer},
+    files::ActiveFile,
+    grid::{self, Cell, Glyph, GridModel, GridSize, Number, NUMBER_SPACING},
+    overtime::Overtime,
+    theme::Theme,
+    zoom::Zoom,
+};
+
+/// Time the virtual clock moves on by between drifts, a frame at 60 frames a second.
+const FRAME: Duration = Duration::from_micros(16_667);
+
+/// A grid of numbers, and everything its systems need around it.
+pub struct GridBench {
+    world: World,
+    size: GridSize,
+    sync: SystemId,
+    cull: SystemId,
+    drift: SystemId,
+}
+
+impl GridBench {
+    /// A grid of the given size holding the default file, its numbers drawn as text so that they
+    /// drift, and the grid camera at the bottom left corner.
+    pub fn new(columns: u32, rows: u32) -> Self {
+        let size = GridSize { columns, rows };
+        let mut config = Config::default();
+        config.video.digits = DigitRenderer::Text;
+        let file = ActiveFile::default();
+        let model = GridModel::new(&file, size);
+        let mut world = World::new();
+        for cell in size.cells() {
+            let glyph = model.get(cell).map_or(0, |state| state.value);
+            world.spawn((
+                Number,
+                Glyph(glyph),
+                cell,
+                Transform::from_translation(cell.position().extend(0.)),
+                Text2d::new(file.glyphs.text(glyph)),
+                Visibility::default(),
+            ));
+        }
+        world.spawn((
+            GridCamera,
+            Transform::from_translation(Cell { col: 0, row: 0 }.position().extend(0.)),
+        ));
+        world.insert_resource(CanvasSize(config.video.canvas.size()));
+        world.insert_resource(config);
+        world.insert_resource(file);
+        world.insert_resource(model);
+        world.insert_resource(size);
+        world.insert_resource(Time::<Virtual>::default());
+        world.init_resource::<Overtime>();
+        world.init_resource::<Theme>();
+        world.init_resource::<Zoom>();
+        // Registered rather than run once, so that they keep their state and see what has changed
+        // since they last ran, as they do in the app.
+        Self {
+            sync: world.register_system(grid::sync_numbers),
+            cull: world.register_system(grid::cull_numbers),
+            drift: world.register_system(grid::drift_numbers),
+            world,
+            size,
+        }
+    }
+
+    /// Lays the grid out anew for the open file, as opening it does.
+    pub fn generate(&self) -> impl Sized {
+        let file = self.world.resource::<ActiveFile>();
+        GridModel::new(file, self.size)
+    }
+
+    /// Replaces every number's glyph, and shows the glyphs on the numbers.
+    pub fn sync(&mut self) {
+        let glyphs = self.world.resource::<ActiveFile>().glyphs.count().max(1);
+        let mut model = self.world.resource_mut::<GridModel>();
+        for (_, state) in model.cells_mut() {
+            state.value = (state.value + 1) % glyphs;
+        }
+        self.run(self.sync);
+    }
+
+    /// Moves the grid camera by `offset` and hides the numbers it no longer shows.
+    pub fn cull(&mut self, offset: Vec2) {
+        let mut camera = self
+            .world
+            .query_filtered::<&mut Transform, With<GridCamera>>();
+        for mut transform in camera.iter_mut(&mut self.world) {
+            transform.translation += offset.extend(0.);
+        }
+        self.run(self.cull);
+    }
+
+    /// Moves the virtual clock on a frame and lets every number drift along with it.
+    pub fn drift(&mut self) {
+        self.world.resource_mut::<Time<Virtual>>().advance_by(FRAME);
+        self.run(self.drift);
+    }
+
+    /// The cell under each of `positions`, and the cells of a selection from the first to each.
+    pub fn hit_test(&self, positions: &[Vec2]) -> impl Sized + use<> {
+        let Some(&first) = positions.first() else {
+            return (Vec::new(), Vec::new());
+        };
+        let cells = positions
+            .iter()
+            .map(|&position| grid::cell_at(position, self.size))
+            .collect::<Vec<_>>();
+        let selections = positions
+            .iter()
+            .map(|&position| grid::cells_in(Rect::from_corners(first, position), self.size))
+            .collect::<Vec<_>>();
+        (cells, selections)
+    }
+
+    /// Positions spread over the whole grid and a cell past it on every side, between the
+    /// numbers as well as on them, for hit-testing.
+    pub fn positions(&self, count: usize) -> Vec<Vec2> {
+        let min = Cell { col: 0, row: 0 }.position() - Vec2::splat(NUMBER_SPACING);
+        let extent = self.size.last().position() + Vec2::splat(NUMBER_SPACING) - min;
+        (0..count)
+            .map(|index| {
+                let t = index as f32 / count.max(1) as f32;
+                min + extent * Vec2::new(t, (t * 7.3).fract())
+            })
+            .collect()
+    }
+
+    fn run(&mut self, system: SystemId) {
+        if let Err(error) = self.world.run_system(system) {
+            panic!("{error}");
+        }
+    }
+}
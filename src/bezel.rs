@@ -0,0 +1,68 @@
+//! Bezel art: an image around the canvas, like the frame of a CRT monitor or the housing of a
+//! Lumon terminal, drawn at the resolution of the screen rather than the canvas's.
+//!
+//! Set in the video config, which names the image in the assets folder and how far it reaches
+//! past each edge of the canvas. The canvas is inset in it by those margins, and fitted into the
+//! window with the bezel around it rather than on its own. A ghost has no bezel, so that the
+//! work under the window shows through around the canvas.
+
+use bevy::{
+    image::{ImageLoaderSettings, ImageSampler},
+    prelude::*,
+};
+
+use crate::{
+    canvas::{CanvasSize, HIGH_RES_LAYERS},
+    config::Config,
+};
+
+pub struct BezelPlugin;
+
+impl Plugin for BezelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            show_bezel.run_if(resource_changed::<Config>.or(resource_changed::<CanvasSize>)),
+        );
+    }
+}
+
+/// The bezel around the canvas.
+#[derive(Component)]
+struct Bezel;
+
+/// Loads the image smoothly sampled, unlike the canvas's pixel art, as it is drawn at the
+/// resolution of the screen.
+fn load(assets: &AssetServer, path: &str) -> Handle<Image> {
+    assets.load_with_settings(path.to_string(), |settings: &mut ImageLoaderSettings| {
+        settings.sampler = ImageSampler::linear();
+    })
+}
+
+/// Puts the bezel around the canvas anew whenever either changes.
+fn show_bezel(
+    mut commands: Commands,
+    config: Res<Config>,
+    canvas: Res<CanvasSize>,
+    assets: Res<AssetServer>,
+    bezel: Option<Single<Entity, With<Bezel>>>,
+) {
+    if let Some(bezel) = bezel {
+        commands.entity(*bezel).despawn();
+    }
+    let wanted = &config.video.bezel;
+    if wanted.image.is_empty() || config.video.ghost {
+        return;
+    }
+    let frame = wanted.frame(canvas.size());
+    commands.spawn((
+        Bezel,
+        Sprite {
+            custom_size: Some(frame.size()),
+            ..Sprite::from_image(load(&assets, &wanted.image))
+        },
+        // Over the canvas layers, so that their corners are hidden under its rim.
+        Transform::from_translation(frame.center().extend(10.)),
+        HIGH_RES_LAYERS,
+    ));
+}
@@ -0,0 +1,900 @@
+//! The bins refined numbers are sorted into, and their progress bars.
+//!
+//! A file may limit its bins (see [`BinLimits`]): a full bin refuses numbers and flashes a
+//! warning, and bins may slowly drain on their own while the file is worked on. It may also give
+//! each bin a name, an icon and a color of its own (see [`BinStyle`]); colors too close to the
+//! theme's background are lightened or darkened until the bins stand out from it.
+//!
+//! Each bin also expects numbers of one temper (see [`expected_temper`]). Numbers refined into a
+//! bin that mostly hold another flash it red and take some of its progress back, as a
+//! [`BinMissed`]; how mostly, and how much is taken, goes by the difficulty.
+//!
+//! Whatever drives the bins may rename them and change the tempers they expect as it goes, for a
+//! dashboard whose bins stand for changing things, such as queues of tickets. A label given a new
+//! name flips over to show it, like a split-flap board's. What they are named and expect now is
+//! kept in [`BinLabels`], apart from the file, which stays as it was opened.
+//!
+//! Bins line the bottom of the canvas, or its left side when the canvas is upright.
+//!
+//! The fill of each percentage bar tweens towards the bin's progress rather than jumping, and
+//! flashes brighter whenever it grows.
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    audio::{PlaySound, Sound},
+    canvas::{CanvasSize, Orientation, PIXEL_PERFECT_LAYERS},
+    config::{Config, Difficulty},
+    files::{ActiveFile, BinLimits, BinStyle},
+    grid::{Cell, GridModel, RefineRules, RefineSet, Refined, ResetRefinement, Temper},
+    state::AppState,
+    theme::{contrast, with_contrast, Theme},
+    tween::{Ease, Tween},
+    ui::AnimatedNumber,
+};
+
+/// Number of bins a file has unless it says otherwise.
+pub const DEFAULT_BIN_COUNT: usize = 5;
+
+/// Most bins a file can have.
+pub const MAX_BIN_COUNT: usize = 12;
+
+/// Most bins in a row, or in a column on a portrait canvas, before they wrap onto a second one.
+const MAX_ROW: usize = 6;
+
+const BIN_WIDTH: f32 = 80.0;
+const BIN_HEIGHT: f32 = 40.0;
+const BIN_SPACING: f32 = 10.0;
+const BAR_HEIGHT: f32 = 20.0;
+const BAR_SPACING: f32 = 5.0;
+
+/// Space kept free on either side of the bins.
+const MARGIN: f32 = 20.0;
+
+/// Gap between the bottom of the bars and the bottom of the canvas.
+const BOTTOM: f32 = 15.0;
+
+/// Space kept free above the bins of a portrait canvas, for the header.
+const TOP: f32 = 36.0;
+
+/// Height of wrapped rows, or width of wrapped columns, relative to a single one.
+const WRAPPED_SCALE: f32 = 0.6;
+
+/// Progress a bin gains for every number refined into it.
+const PROGRESS_PER_NUMBER: f32 = 0.01;
+
+const BIN_COLOR: Color = Color::srgba(0.0, 0.7, 0.8, 0.9);
+
+/// Color a bin flashes when it refuses numbers.
+const WARNING_COLOR: Color = Color::srgba(0.9, 0.15, 0.1, 0.95);
+
+/// Progress a bin loses for numbers refined into it that it did not expect, on easy, normal and
+/// hard.
+const MISS_PENALTY: [f32; 3] = [0.01, 0.02, 0.05];
+
+/// Seconds the warning flash takes to fade.
+const WARNING_TIME: f32 = 0.6;
+
+const FILL_COLOR: Color = Color::srgba(0.0, 0.9, 1.0, 0.9);
+
+const BAR_COLOR: Color = Color::srgba(0.0, 0.2, 0.25, 0.8);
+
+/// Least contrast a bin's color keeps against the background, and its label against the bin.
+const MIN_CONTRAST: f32 = 3.;
+
+/// Color a fill flashes when it grows.
+const FILL_FLASH_COLOR: Color = Color::srgba(0.85, 1.0, 1.0, 1.0);
+
+/// Seconds a fill takes to reach a new width, and to fade its flash.
+const FILL_TWEEN_TIME: f32 = 0.4;
+const FILL_FLASH_TIME: f32 = 0.3;
+
+/// Seconds a label takes to flip over to a new name.
+const LABEL_FLIP_TIME: f32 = 0.4;
+
+/// Smallest growth of a fill that flashes it, so that slow drifts such as draining don't.
+const FILL_FLASH_STEP: f32 = 0.005;
+
+pub struct BinsPlugin;
+
+impl Plugin for BinsPlugin {
+    fn build(&self, app: &mut App) {
+        let canvas = *app.world().resource::<CanvasSize>();
+        app.insert_resource(BinLayout::new(DEFAULT_BIN_COUNT, canvas))
+            .init_resource::<BinLabels>()
+            .add_event::<RelayBins>()
+            .add_event::<BinRefused>()
+            .add_event::<BinMissed>()
+            .add_systems(Startup, setup_bins)
+            .add_systems(
+                Update,
+                (
+                    (
+                        label_file.run_if(resource_changed::<ActiveFile>),
+                        reset_bins.run_if(on_event::<ResetRefinement>),
+                        relay_bins.run_if(on_event::<RelayBins>),
+                        relayout_bins.run_if(resource_changed::<CanvasSize>),
+                    )
+                        .chain()
+                        .in_set(RefineSet::Apply),
+                    (
+                        fill_bins,
+                        judge_refinements.run_if(not(resource_exists::<DrivenBins>)),
+                        drain_bins.run_if(in_state(AppState::Refining)),
+                        update_bars,
+                        retint_bins.run_if(resource_changed::<Theme>),
+                        relabel_bins.run_if(resource_changed::<BinLabels>),
+                        flip_labels,
+                        flash_fills,
+                        flash_warnings,
+                    )
+                        .chain()
+                        .in_set(RefineSet::React),
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Bin {
+    pub index: usize,
+    /// How full the bin is, from 0 to 1.
+    pub progress: f32,
+}
+
+/// Present while something other than refinement decides how full the bins are, such as a
+/// song or outside data, for systems that would otherwise fill them.
+#[derive(Resource)]
+pub struct DrivenBins;
+
+/// Lays the bins out anew, as many and as full as given, leaving the grid and the file be; for
+/// whatever drives the bins to have more or fewer of them than the file.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct RelayBins(pub Vec<f32>);
+
+/// A bin was full and refused the numbers refined into it; the selection stays as it was.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BinRefused(pub usize);
+
+/// Numbers of another temper than the bin expects were refined into it, which lost progress for
+/// it; the selection was refined all the same.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BinMissed(pub usize);
+
+/// What the bins are named and the tempers they expect: those of the file's styles as it is
+/// opened, and then whatever drives the bins gives them instead.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct BinLabels {
+    names: Vec<Option<String>>,
+    tempers: Vec<Option<Temper>>,
+}
+
+impl BinLabels {
+    /// The names and tempers bins with these styles start out with.
+    fn of(styles: &[BinStyle]) -> Self {
+        Self {
+            names: styles.iter().map(|style| style.name.clone()).collect(),
+            tempers: styles.iter().map(|style| style.temper).collect(),
+        }
+    }
+
+    /// What bin `index` is named, or `None` for its number.
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(index)?.as_deref()
+    }
+
+    /// The temper bin `index` was asked to expect, or `None` for its turn in the round of them.
+    pub fn temper(&self, index: usize) -> Option<Temper> {
+        self.tempers.get(index).copied().flatten()
+    }
+
+    /// Names bin `index`, or gives it its number back with `None`.
+    pub fn rename(&mut self, index: usize, name: Option<String>) {
+        if self.names.len() <= index {
+            self.names.resize(index + 1, None);
+        }
+        self.names[index] = name;
+    }
+
+    /// Has bin `index` expect `temper`, or its turn in the round of them with `None`.
+    pub fn remap(&mut self, index: usize, temper: Option<Temper>) {
+        if self.tempers.len() <= index {
+            self.tempers.resize(index + 1, None);
+        }
+        self.tempers[index] = temper;
+    }
+}
+
+/// The temper of the numbers a bin expects: the one it was asked to, or else its turn going
+/// around the scary tempers in order.
+pub fn expected_temper(bin: usize, labels: &BinLabels) -> Temper {
+    labels
+        .temper(bin)
+        .unwrap_or(Temper::SCARY[bin % Temper::SCARY.len()])
+}
+
+/// Whether numbers with `counts` of each scary temper, in the order of [`Temper::SCARY`], miss
+/// a bin expecting `expected`. Easy only minds a selection holding none of the temper, normal
+/// one holding more of another, and hard one holding any other at all. Calm numbers never miss.
+fn misses(difficulty: Difficulty, counts: [u32; 4], expected: Temper) -> bool {
+    let index = Temper::SCARY.iter().position(|&temper| temper == expected);
+    let wanted = index.map_or(0, |index| counts[index]);
+    let scary: u32 = counts.iter().sum();
+    let most = counts.iter().copied().max().unwrap_or(0);
+    match difficulty {
+        _ if scary == 0 => false,
+        Difficulty::Easy => wanted == 0,
+        Difficulty::Normal => wanted < most,
+        Difficulty::Hard => wanted < scary,
+    }
+}
+
+/// The warning a bin is flashing, with the seconds left of it.
+#[derive(Component)]
+struct Warning(f32);
+
+/// Marks every entity that makes up the bins, which are respawned when their number changes.
+#[derive(Component)]
+struct BinPart;
+
+/// Name or number of a bin.
+#[derive(Component)]
+struct BinLabel {
+    index: usize,
+    /// What it says.
+    text: String,
+    /// Font size of what it says, before the canvas supersamples it.
+    size: f32,
+    flip: Option<LabelFlip>,
+}
+
+/// A label folding shut and opening again on what it is to say instead.
+struct LabelFlip {
+    /// Seconds since it started.
+    elapsed: f32,
+    text: String,
+    size: f32,
+}
+
+/// What the label of bin `index` says, and the font size it says it in: its name, or its number,
+/// after the icon of its style if it has one.
+fn label_text(
+    index: usize,
+    style: Option<&BinStyle>,
+    labels: &BinLabels,
+    layout: &BinLayout,
+) -> (String, f32) {
+    let icon = style.and_then(|style| style.icon);
+    let (text, size) = match (icon, labels.name(index)) {
+        (Some(icon), Some(name)) => (format!("{icon} {name}"), 9.0),
+        (None, Some(name)) => (name.to_string(), 9.0),
+        (Some(icon), None) => (icon.to_string(), 14.0),
+        (None, None) => (format!("{:02}", index + 1), 14.0),
+    };
+    (text, size * layout.scale)
+}
+
+/// The fill sprite of a bin's percentage bar, a child of the bar's background.
+#[derive(Component)]
+struct BinBar {
+    fill: Entity,
+}
+
+/// Fill of a percentage bar, which tweens its width to the progress it is headed for.
+#[derive(Component)]
+struct BinFill {
+    to: f32,
+    /// Seconds left of the flash.
+    flash: f32,
+}
+
+/// Percentage text of a bin.
+#[derive(Component)]
+struct BinPercent(usize);
+
+/// Colors of a bin and its bar, kept by the bin and its fill.
+#[derive(Component, Clone, Copy, Debug)]
+struct BinTint {
+    body: Color,
+    fill: Color,
+    bar: Color,
+    label: Color,
+}
+
+impl BinTint {
+    /// The colors of a bin with the given style, against the theme's background.
+    fn new(style: Option<&BinStyle>, theme: &Theme) -> Self {
+        let color = style.and_then(|style| style.color).or(theme.bins);
+        let Some(color) = color else {
+            return Self {
+                body: BIN_COLOR,
+                fill: FILL_COLOR,
+                bar: BAR_COLOR,
+                label: Color::WHITE,
+            };
+        };
+        let asked = Color::srgb_from_array(color);
+        let accent = with_contrast(asked, theme.background(), MIN_CONTRAST);
+        if accent != asked {
+            warn!("Bin color {color:?} is too close to the background; adjusting it");
+        }
+        let body = accent.mix(&Color::BLACK, 0.22);
+        let label = if contrast(Color::WHITE, body) >= MIN_CONTRAST {
+            Color::WHITE
+        } else {
+            Color::BLACK
+        };
+        Self {
+            body: body.with_alpha(0.9),
+            fill: accent.with_alpha(0.9),
+            bar: accent.mix(&Color::BLACK, 0.75).with_alpha(0.8),
+            label,
+        }
+    }
+}
+
+/// Where the bins of the open file sit: as many to a row as fit across the canvas, shrinking
+/// to make room, and wrapping onto a second, flatter row past [`MAX_ROW`].
+///
+/// On a portrait canvas the bins are stacked down its left side instead, growing shorter to
+/// make room, and wrapping into a second, narrower column.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct BinLayout {
+    canvas: CanvasSize,
+    count: usize,
+    /// Bins in each row, or columns of bins on a portrait canvas.
+    columns: usize,
+    /// Rows of bins, or bins in each column on a portrait canvas.
+    rows: usize,
+    /// Size of each bin.
+    size: Vec2,
+    /// Scale of everything vertical, and of the labels.
+    scale: f32,
+}
+
+impl BinLayout {
+    pub fn new(count: usize, canvas: CanvasSize) -> Self {
+        let count = count.clamp(1, MAX_BIN_COUNT);
+        if canvas.orientation() == Orientation::Portrait {
+            return Self::stacked(count, canvas);
+        }
+        let rows = if count > MAX_ROW { 2 } else { 1 };
+        let columns = count.div_ceil(rows);
+        let room = canvas.size().x - 2. * MARGIN - BIN_SPACING * (columns - 1) as f32;
+        let width = (room / columns as f32).min(BIN_WIDTH);
+        let scale = if rows > 1 { WRAPPED_SCALE } else { 1. };
+        Self {
+            canvas,
+            count,
+            columns,
+            rows,
+            size: Vec2::new(width, BIN_HEIGHT * scale),
+            scale: scale.min(width / BIN_WIDTH),
+        }
+    }
+
+    /// The layout of a portrait canvas.
+    fn stacked(count: usize, canvas: CanvasSize) -> Self {
+        let columns = if count > MAX_ROW { 2 } else { 1 };
+        let rows = count.div_ceil(columns);
+        let room = canvas.size().y - TOP - BOTTOM - BIN_SPACING * (rows - 1) as f32;
+        let stack = BIN_HEIGHT + BAR_SPACING + BAR_HEIGHT;
+        let scale = (room / rows as f32 / stack).min(1.);
+        let width = BIN_WIDTH * if columns > 1 { WRAPPED_SCALE } else { 1. };
+        Self {
+            canvas,
+            count,
+            columns,
+            rows,
+            size: Vec2::new(width, BIN_HEIGHT * scale),
+            scale: scale.min(width / BIN_WIDTH),
+        }
+    }
+
+    /// Number of bins.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Height of a bin and its bar, and the gap between them.
+    fn stack_height(&self) -> f32 {
+        self.size.y + (BAR_SPACING + BAR_HEIGHT) * self.size.y / BIN_HEIGHT
+    }
+
+    fn bar_height(&self) -> f32 {
+        BAR_HEIGHT * self.size.y / BIN_HEIGHT
+    }
+
+    /// Centre of the bin with the given index.
+    fn bin_center(&self, index: usize) -> Vec2 {
+        let row_gap = BIN_SPACING * self.scale;
+        if self.canvas.orientation() == Orientation::Portrait {
+            // Columns fill from the top down, the first nearest the edge.
+            let (row, column) = (index % self.rows, index / self.rows);
+            let half = self.canvas.half();
+            return Vec2::new(
+                -half.x + MARGIN + self.size.x / 2. + column as f32 * (self.size.x + BIN_SPACING),
+                half.y - TOP - self.size.y / 2. - row as f32 * (self.stack_height() + row_gap),
+            );
+        }
+        let (row, column) = (index / self.columns, index % self.columns);
+        // Rows are centred, so a short last row sits in the middle.
+        let in_row = (self.count - row * self.columns).min(self.columns);
+        let x = (column as f32 - (in_row - 1) as f32 / 2.) * (self.size.x + BIN_SPACING);
+        let top = -self.canvas.half().y
+            + BOTTOM
+            + self.rows as f32 * self.stack_height()
+            + (self.rows - 1) as f32 * row_gap;
+        Vec2::new(
+            x,
+            top - self.size.y / 2. - row as f32 * (self.stack_height() + row_gap),
+        )
+    }
+
+    /// Centre of the percentage bar under the given bin.
+    fn bar_center(&self, index: usize) -> Vec2 {
+        let gap = BAR_SPACING * self.size.y / BIN_HEIGHT;
+        self.bin_center(index) - Vec2::new(0., self.size.y / 2. + gap + self.bar_height() / 2.)
+    }
+
+    /// Returns the bin under a world position, if any.
+    pub fn bin_at(&self, position: Vec2) -> Option<usize> {
+        (0..self.count).find(|&index| {
+            Rect::from_center_size(self.bin_center(index), self.size).contains(position)
+        })
+    }
+
+    /// Width of a fill sprite, which grows from the bar's left edge.
+    fn fill_width(&self, progress: f32) -> f32 {
+        self.size.x * progress
+    }
+}
+
+/// Formats progress as a percentage.
+pub fn percent(progress: f32) -> String {
+    format!("{}%", (progress * 100.0) as i32)
+}
+
+/// What a bin's percentage text shows instead of the number.
+fn percent_label(progress: f32, limits: &BinLimits) -> Option<&'static str> {
+    limits.is_full(progress).then_some("FULL")
+}
+
+fn setup_bins(
+    mut commands: Commands,
+    (file, theme): (Res<ActiveFile>, Res<Theme>),
+    canvas: Res<CanvasSize>,
+    (mut layout, mut labels): (ResMut<BinLayout>, ResMut<BinLabels>),
+) {
+    *layout = BinLayout::new(file.progress.len(), *canvas);
+    *labels = BinLabels::of(&file.styles);
+    spawn_bins(
+        &mut commands,
+        (&file, &labels),
+        &file.progress,
+        &theme,
+        &layout,
+    );
+}
+
+/// Spawns the bins of a file, as full as `progress` has them.
+fn spawn_bins(
+    commands: &mut Commands,
+    (file, labels): (&ActiveFile, &BinLabels),
+    progress: &[f32],
+    theme: &Theme,
+    layout: &BinLayout,
+) {
+    // Create bins at the bottom of the screen
+    for i in 0..layout.count() {
+        let progress = progress.get(i).copied().unwrap_or_default();
+        let style = file.styles.get(i);
+        let tint = BinTint::new(style, theme);
+        let bin = layout.bin_center(i);
+        let bar = layout.bar_center(i);
+
+        // Percentage bar fill (bright cyan unless styled), kept by its bin
+        let fill = commands
+            .spawn((
+                BinFill {
+                    to: progress,
+                    flash: 0.,
+                },
+                tint,
+                Sprite {
+                    color: tint.fill,
+                    custom_size: Some(Vec2::new(layout.fill_width(progress), layout.bar_height())),
+                    anchor: Anchor::CenterLeft,
+                    ..default()
+                },
+                Transform::from_xyz(-layout.size.x / 2., 0., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ))
+            .id();
+
+        // Main bin with cyan/teal color unless styled
+        commands.spawn((
+            Bin { index: i, progress },
+            BinBar { fill },
+            BinPart,
+            tint,
+            Sprite {
+                color: tint.body,
+                custom_size: Some(layout.size),
+                ..default()
+            },
+            Transform::from_translation(bin.extend(1.0)),
+            PIXEL_PERFECT_LAYERS,
+        ));
+
+        // Bin name, or number label (01, 02, ...)
+        let (text, size) = label_text(i, style, labels, layout);
+        commands.spawn((
+            BinPart,
+            Text2d::new(text.clone()),
+            TextFont {
+                font_size: size,
+                ..default()
+            },
+            BinLabel {
+                index: i,
+                text,
+                size,
+                flip: None,
+            },
+            TextColor(tint.label),
+            Transform::from_translation(bin.extend(1.3)),
+            PIXEL_PERFECT_LAYERS,
+        ));
+
+        // Percentage bar background (dark cyan unless styled)
+        commands
+            .spawn((
+                BinPart,
+                Sprite {
+                    color: tint.bar,
+                    custom_size: Some(Vec2::new(layout.size.x, layout.bar_height())),
+                    ..default()
+                },
+                Transform::from_translation(bar.extend(1.0)),
+                Visibility::default(),
+                PIXEL_PERFECT_LAYERS,
+            ))
+            .add_child(fill);
+
+        // Percentage text
+        commands.spawn((
+            BinPercent(i),
+            BinPart,
+            AnimatedNumber::new(progress, percent),
+            TextFont {
+                font_size: (10.0 * layout.scale).max(7.),
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_translation(bar.extend(1.2)),
+            PIXEL_PERFECT_LAYERS,
+        ));
+    }
+}
+
+fn fill_bins(mut refined: EventReader<Refined>, mut bins: Query<&mut Bin>) {
+    for event in refined.read() {
+        for mut bin in &mut bins {
+            if bin.index == event.bin {
+                bin.progress = (bin.progress + event.count as f32 * PROGRESS_PER_NUMBER).min(1.0);
+            }
+        }
+    }
+}
+
+/// Takes progress back from bins that numbers of another temper were refined into.
+fn judge_refinements(
+    (rules, labels): (Res<RefineRules>, Res<BinLabels>),
+    model: Res<GridModel>,
+    mut refined: EventReader<Refined>,
+    mut bins: Query<&mut Bin>,
+    mut missed: EventWriter<BinMissed>,
+) {
+    let difficulty = rules.difficulty;
+    for event in refined.read() {
+        let mut counts = [0; 4];
+        for row in event.cells.min.y..=event.cells.max.y {
+            for col in event.cells.min.x..=event.cells.max.x {
+                let temper = model.get(Cell { col, row }).map(|state| state.temper);
+                if let Some(index) = Temper::SCARY.iter().position(|&t| Some(t) == temper) {
+                    counts[index] += 1;
+                }
+            }
+        }
+        if !misses(difficulty, counts, expected_temper(event.bin, &labels)) {
+            continue;
+        }
+        let penalty = MISS_PENALTY[difficulty as usize];
+        for mut bin in &mut bins {
+            if bin.index == event.bin {
+                bin.progress = (bin.progress - penalty).max(0.);
+            }
+        }
+        missed.write(BinMissed(event.bin));
+    }
+}
+
+/// Empties the bins of a file that drains, while it is being worked on.
+fn drain_bins(time: Res<Time<Virtual>>, file: Res<ActiveFile>, mut bins: Query<&mut Bin>) {
+    if file.limits.drain <= 0. {
+        return;
+    }
+    let drained = file.limits.drain / 60. * time.delta_secs();
+    for mut bin in &mut bins {
+        if bin.progress > 0. {
+            bin.progress = (bin.progress - drained).max(0.);
+        }
+    }
+}
+
+/// Flashes bins that refused numbers, or were missed, with a low beep.
+fn flash_warnings(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    (mut refusals, mut misses): (EventReader<BinRefused>, EventReader<BinMissed>),
+    mut bins: Query<(Entity, &Bin, &BinTint, &mut Sprite, Option<&mut Warning>)>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    let refused = refusals.read().map(|&BinRefused(index)| (index, 0.5));
+    let missed = misses.read().map(|&BinMissed(index)| (index, 0.7));
+    for (index, pitch) in refused.chain(missed) {
+        for (entity, bin, ..) in &bins {
+            if bin.index == index {
+                commands.entity(entity).insert(Warning(WARNING_TIME));
+            }
+        }
+        sounds.write(PlaySound::new(Sound::Beep).with_pitch(pitch));
+    }
+    for (entity, _, tint, mut sprite, warning) in &mut bins {
+        let Some(mut warning) = warning else {
+            continue;
+        };
+        warning.0 -= time.delta_secs();
+        if warning.0 <= 0. {
+            sprite.color = tint.body;
+            commands.entity(entity).remove::<Warning>();
+        } else {
+            sprite.color = tint.body.mix(&WARNING_COLOR, warning.0 / WARNING_TIME);
+        }
+    }
+}
+
+/// Names the bins and has them expect the tempers of the file just opened.
+fn label_file(file: Res<ActiveFile>, mut labels: ResMut<BinLabels>) {
+    labels.set_if_neq(BinLabels::of(&file.styles));
+}
+
+/// Puts the bins back the way the file was opened, laying them out anew if it has a different
+/// number of them or they look different.
+fn reset_bins(
+    mut commands: Commands,
+    mut resets: EventReader<ResetRefinement>,
+    (file, labels, theme): (Res<ActiveFile>, Res<BinLabels>, Res<Theme>),
+    mut layout: ResMut<BinLayout>,
+    mut styles: Local<Vec<BinStyle>>,
+    mut bins: Query<&mut Bin>,
+    parts: Query<Entity, With<BinPart>>,
+) {
+    resets.clear();
+    if file.progress.len() != layout.count() || file.styles != *styles {
+        for entity in &parts {
+            commands.entity(entity).despawn();
+        }
+        *layout = BinLayout::new(file.progress.len(), layout.canvas);
+        *styles = file.styles.clone();
+        spawn_bins(
+            &mut commands,
+            (&file, &labels),
+            &file.progress,
+            &theme,
+            &layout,
+        );
+        return;
+    }
+    for mut bin in &mut bins {
+        bin.progress = file.progress.get(bin.index).copied().unwrap_or_default();
+    }
+}
+
+/// Lays the bins out anew as whatever drives them asks.
+fn relay_bins(
+    mut commands: Commands,
+    mut relays: EventReader<RelayBins>,
+    (file, labels, theme): (Res<ActiveFile>, Res<BinLabels>, Res<Theme>),
+    mut layout: ResMut<BinLayout>,
+    parts: Query<Entity, With<BinPart>>,
+) {
+    let Some(RelayBins(progress)) = relays.read().last() else {
+        return;
+    };
+    for entity in &parts {
+        commands.entity(entity).despawn();
+    }
+    *layout = BinLayout::new(progress.len(), layout.canvas);
+    spawn_bins(&mut commands, (&file, &labels), progress, &theme, &layout);
+}
+
+/// Lays the bins out anew to fit the canvas as it changes shape, as full as they were.
+fn relayout_bins(
+    mut commands: Commands,
+    (file, labels, theme): (Res<ActiveFile>, Res<BinLabels>, Res<Theme>),
+    canvas: Res<CanvasSize>,
+    mut layout: ResMut<BinLayout>,
+    bins: Query<&Bin>,
+    parts: Query<Entity, With<BinPart>>,
+) {
+    if layout.canvas == *canvas {
+        return;
+    }
+    let mut progress = vec![0.; layout.count()];
+    for bin in &bins {
+        if let Some(slot) = progress.get_mut(bin.index) {
+            *slot = bin.progress;
+        }
+    }
+    for entity in &parts {
+        commands.entity(entity).despawn();
+    }
+    *layout = BinLayout::new(layout.count(), *canvas);
+    spawn_bins(&mut commands, (&file, &labels), &progress, &theme, &layout);
+}
+
+/// Sends each changed bin's fill easing towards its new progress.
+fn update_bars(
+    mut commands: Commands,
+    file: Res<ActiveFile>,
+    layout: Res<BinLayout>,
+    bins: Query<(&Bin, &BinBar), Changed<Bin>>,
+    mut fills: Query<(&mut BinFill, &Sprite)>,
+    mut labels: Query<(&BinPercent, &mut AnimatedNumber)>,
+) {
+    for (bin, bar) in &bins {
+        if let Ok((mut fill, sprite)) = fills.get_mut(bar.fill) {
+            if fill.to != bin.progress {
+                if bin.progress - fill.to >= FILL_FLASH_STEP {
+                    fill.flash = FILL_FLASH_TIME;
+                }
+                fill.to = bin.progress;
+                let from = sprite.custom_size.unwrap_or_default().x;
+                let to = layout.fill_width(bin.progress);
+                let height = layout.bar_height();
+                let tween = Tween::new(FILL_TWEEN_TIME, move |sprite: &mut Sprite, t| {
+                    sprite.custom_size = Some(Vec2::new(from + (to - from) * t, height));
+                });
+                commands
+                    .entity(bar.fill)
+                    .insert(tween.with_ease(Ease::Cubic));
+            }
+        }
+        for (label, mut number) in &mut labels {
+            if label.0 == bin.index {
+                number.set(bin.progress);
+                number.set_label(percent_label(bin.progress, &file.limits));
+            }
+        }
+    }
+}
+
+/// Gives the bins the colors of a new theme.
+fn retint_bins(
+    file: Res<ActiveFile>,
+    theme: Res<Theme>,
+    bins: Query<(Entity, &Bin, &BinBar)>,
+    bars: Query<&ChildOf, With<BinFill>>,
+    mut parts: Query<(&mut Sprite, Option<&mut BinTint>)>,
+    mut labels: Query<(&BinLabel, &mut TextColor)>,
+) {
+    for (entity, bin, &BinBar { fill }) in &bins {
+        let tint = BinTint::new(file.styles.get(bin.index), &theme);
+        let bar = bars.get(fill).map(ChildOf::parent);
+        let colors = [
+            (Ok(entity), tint.body),
+            (Ok(fill), tint.fill),
+            (bar, tint.bar),
+        ];
+        for (part, color) in colors {
+            let Ok((mut sprite, part_tint)) = part.and_then(|part| parts.get_mut(part)) else {
+                continue;
+            };
+            sprite.color = color;
+            if let Some(mut part_tint) = part_tint {
+                *part_tint = tint;
+            }
+        }
+        for (label, mut color) in &mut labels {
+            if label.index == bin.index {
+                color.0 = tint.label;
+            }
+        }
+    }
+}
+
+/// Starts the labels of bins given another name flipping over to it.
+fn relabel_bins(
+    (file, names): (Res<ActiveFile>, Res<BinLabels>),
+    layout: Res<BinLayout>,
+    mut labels: Query<&mut BinLabel>,
+) {
+    for mut label in &mut labels {
+        let style = file.styles.get(label.index);
+        let (text, size) = label_text(label.index, style, &names, &layout);
+        let label = &mut *label;
+        match &mut label.flip {
+            Some(flip) => {
+                if flip.text != text || flip.size != size {
+                    (flip.text, flip.size) = (text, size);
+                }
+            }
+            None => {
+                if label.text != text || label.size != size {
+                    label.flip = Some(LabelFlip {
+                        elapsed: 0.,
+                        text,
+                        size,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Folds flipping labels shut, changes what they say once they are, and opens them again; at
+/// once for those who asked for less motion.
+fn flip_labels(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut labels: Query<(&mut BinLabel, &mut Text2d, &mut TextFont, &mut Transform)>,
+) {
+    for (mut label, mut text, mut font, mut transform) in &mut labels {
+        let label = &mut *label;
+        let Some(flip) = &mut label.flip else {
+            continue;
+        };
+        flip.elapsed = if config.accessibility.reduced_motion {
+            LABEL_FLIP_TIME
+        } else {
+            flip.elapsed + time.delta_secs()
+        };
+        let t = (flip.elapsed / LABEL_FLIP_TIME).min(1.);
+        if t >= 0.5 && (label.text != flip.text || label.size != flip.size) {
+            // Kept as much larger as the canvas supersamples it.
+            font.font_size *= flip.size / label.size;
+            label.text.clone_from(&flip.text);
+            label.size = flip.size;
+            text.0.clone_from(&label.text);
+        }
+        // Folded about its middle, keeping the width the canvas scaled it to.
+        transform.scale.y = transform.scale.x * (2. * t - 1.).abs().max(0.05);
+        if t >= 1. {
+            label.flip = None;
+        }
+    }
+}
+
+/// Fades the flash of fills that grew.
+fn flash_fills(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut fills: Query<(&mut BinFill, &BinTint, &mut Sprite)>,
+) {
+    for (mut fill, tint, mut sprite) in &mut fills {
+        if fill.flash <= 0. && !fill.is_changed() {
+            continue;
+        }
+        // Fading is no change of its own, or the flash would never settle.
+        fill.bypass_change_detection().flash = if config.accessibility.reduced_motion {
+            0.
+        } else {
+            (fill.flash - time.delta_secs()).max(0.)
+        };
+        sprite.color = tint
+            .fill
+            .mix(&FILL_FLASH_COLOR, fill.flash / FILL_FLASH_TIME);
+    }
+}
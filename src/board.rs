@@ -0,0 +1,269 @@
+//! The quarterly board: every file of the library on one screen, each with how far it is
+//! refined and when it was last worked on.
+//!
+//! It is opened from the main menu and lists the files in the order they were made, as many as
+//! fit at a time, scrolled through with the arrow keys or the wheel. Each row's bar is marked at
+//! the quarters, the milestones the refiner is praised for. Files last opened before the date was
+//! kept track of show none.
+
+use bevy::{input::mouse::MouseWheel, prelude::*, sprite::Anchor};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    clock::utc_date,
+    files::{FileLibrary, FileRecord},
+    state::AppState,
+    theme::Theme,
+    transition::{in_transition, TransitionEffect, TransitionTo},
+    ui::{spawn_menu, MenuChosen, MenuEntry},
+};
+
+/// Rows shown at a time.
+const ROWS_SHOWN: usize = 7;
+
+/// Height of a file's row.
+const ROW_HEIGHT: f32 = 14.;
+
+/// Where the top row is.
+const TOP_ROW: f32 = 52.;
+
+/// Where each column of a row starts, from the left of the board.
+const NAME_X: f32 = -140.;
+const BAR_X: f32 = -20.;
+const PERCENT_X: f32 = 92.;
+const DATE_X: f32 = 140.;
+
+/// Size of a row's bar.
+const BAR_SIZE: Vec2 = Vec2::new(80., 5.);
+
+const BAR_COLOR: Color = Color::srgba(0.0, 0.2, 0.25, 0.8);
+const QUARTER_COLOR: Color = Color::srgb(0.0, 0.04, 0.05);
+const HEADING_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+pub struct BoardPlugin;
+
+impl Plugin for BoardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Board), spawn_board)
+            .add_systems(
+                Update,
+                (
+                    (
+                        scroll_board,
+                        show_rows.run_if(resource_exists_and_changed::<BoardScroll>),
+                    )
+                        .chain(),
+                    leave_board.run_if(not(in_transition)),
+                )
+                    .run_if(in_state(AppState::Board)),
+            );
+    }
+}
+
+/// Marks the board's menu.
+#[derive(Component)]
+struct BoardMenu;
+
+/// Marks what makes up a file's row, to be replaced as the board scrolls.
+#[derive(Component)]
+struct BoardRow;
+
+/// Index of the file in the top row.
+#[derive(Resource, Default)]
+struct BoardScroll(usize);
+
+fn spawn_board(mut commands: Commands, library: Res<FileLibrary>) {
+    commands.insert_resource(BoardScroll::default());
+
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.0, 0.04, 0.05),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 19.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Board),
+    ));
+
+    commands.spawn((
+        Text2d::new("QUARTERLY BOARD"),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(HEADING_COLOR),
+        CanvasAnchor::TOP.offset(0., -12.),
+        Transform::from_xyz(0., 0., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Board),
+    ));
+
+    let headings = [
+        (NAME_X, Anchor::CenterLeft, "FILE"),
+        (BAR_X, Anchor::CenterLeft, "PROGRESS"),
+        (DATE_X, Anchor::CenterRight, "LAST PLAYED"),
+    ];
+    for (x, anchor, heading) in headings {
+        commands.spawn((
+            Text2d::new(heading),
+            TextFont {
+                font_size: 8.0,
+                ..default()
+            },
+            TextColor(HEADING_COLOR),
+            anchor,
+            Transform::from_xyz(x, TOP_ROW + ROW_HEIGHT, 20.),
+            PIXEL_PERFECT_LAYERS,
+            StateScoped(AppState::Board),
+        ));
+    }
+
+    let complete = library
+        .files
+        .iter()
+        .filter(|file| file.completion() >= 1.)
+        .count();
+    spawn_menu(
+        &mut commands,
+        &format!("{complete} OF {} COMPLETE", library.files.len()),
+        &[MenuEntry::new("Back")],
+        0,
+        Vec3::new(0., -72., 20.),
+        (BoardMenu, StateScoped(AppState::Board)),
+    );
+}
+
+/// Scrolls the rows with the arrow keys, Page Up and Page Down, and the wheel.
+fn scroll_board(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    library: Res<FileLibrary>,
+    mut scroll: ResMut<BoardScroll>,
+) {
+    let mut step: isize = wheel.read().map(|event| -event.y.signum() as isize).sum();
+    if keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        step -= 1;
+    }
+    if keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        step += 1;
+    }
+    if keys.just_pressed(KeyCode::PageUp) {
+        step -= ROWS_SHOWN as isize;
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        step += ROWS_SHOWN as isize;
+    }
+    let last = library.files.len().saturating_sub(ROWS_SHOWN);
+    let top = scroll.0.saturating_add_signed(step).min(last);
+    if scroll.0 != top {
+        scroll.0 = top;
+    }
+}
+
+/// Replaces the rows with those of the files scrolled to.
+fn show_rows(
+    mut commands: Commands,
+    library: Res<FileLibrary>,
+    scroll: Res<BoardScroll>,
+    theme: Res<Theme>,
+    rows: Query<Entity, With<BoardRow>>,
+) {
+    for entity in &rows {
+        commands.entity(entity).despawn();
+    }
+    let files = library.files.iter().skip(scroll.0).take(ROWS_SHOWN);
+    for (index, file) in files.enumerate() {
+        spawn_row(
+            &mut commands,
+            file,
+            TOP_ROW - index as f32 * ROW_HEIGHT,
+            &theme,
+        );
+    }
+    if library.files.is_empty() {
+        spawn_text(&mut commands, "No files yet", 0., TOP_ROW, Anchor::Center);
+    }
+}
+
+fn spawn_row(commands: &mut Commands, file: &FileRecord, y: f32, theme: &Theme) {
+    let completion = file.completion().clamp(0., 1.);
+    let fill = if completion >= 1. {
+        Color::srgb_from_array(theme.success)
+    } else {
+        theme.numbers()
+    };
+    spawn_text(commands, &file.name, NAME_X, y, Anchor::CenterLeft);
+    commands.spawn((
+        BoardRow,
+        Sprite {
+            color: BAR_COLOR,
+            custom_size: Some(BAR_SIZE),
+            anchor: Anchor::CenterLeft,
+            ..default()
+        },
+        Transform::from_xyz(BAR_X, y, 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Board),
+    ));
+    commands.spawn((
+        BoardRow,
+        Sprite {
+            color: fill,
+            custom_size: Some(Vec2::new(BAR_SIZE.x * completion, BAR_SIZE.y)),
+            anchor: Anchor::CenterLeft,
+            ..default()
+        },
+        Transform::from_xyz(BAR_X, y, 21.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Board),
+    ));
+    for quarter in 1..4 {
+        commands.spawn((
+            BoardRow,
+            Sprite {
+                color: QUARTER_COLOR,
+                custom_size: Some(Vec2::new(1., BAR_SIZE.y)),
+                ..default()
+            },
+            Transform::from_xyz(BAR_X + BAR_SIZE.x * quarter as f32 / 4., y, 22.),
+            PIXEL_PERFECT_LAYERS,
+            StateScoped(AppState::Board),
+        ));
+    }
+    let percent = format!("{:.0}%", completion * 100.);
+    spawn_text(commands, &percent, PERCENT_X, y, Anchor::CenterRight);
+    let date = file
+        .last_played
+        .map_or_else(|| "-".to_string(), |seconds| utc_date(seconds as i64));
+    spawn_text(commands, &date, DATE_X, y, Anchor::CenterRight);
+}
+
+fn spawn_text(commands: &mut Commands, text: &str, x: f32, y: f32, anchor: Anchor) {
+    commands.spawn((
+        BoardRow,
+        Text2d::new(text),
+        TextFont {
+            font_size: 8.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        anchor,
+        Transform::from_xyz(x, y, 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Board),
+    ));
+}
+
+/// Back to the main menu, with Back or Esc.
+fn leave_board(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<(), With<BoardMenu>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    let back = chosen.read().any(|event| menus.contains(event.menu));
+    if back || keys.just_pressed(KeyCode::Escape) {
+        transitions.write(TransitionTo::new(AppState::Menu, TransitionEffect::Wipe));
+    }
+}
@@ -0,0 +1,155 @@
+//! The boot sequence shown before the main menu.
+//!
+//! A black screen with a blinking cursor types out a boot log, then shows the logo with a beep.
+//! Any key or click skips straight to the menu, and the whole sequence can be turned off in the
+//! config.
+
+use bevy::{prelude::*, sprite::Anchor, text::TextLayout};
+
+use crate::{
+    audio::{PlaySound, Sound},
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    state::AppState,
+    transition::{in_transition, TransitionEffect, TransitionTo},
+};
+
+/// Lines typed out before the logo appears.
+const BOOT_LOG: &str = "\
+LUMON INDUSTRIES (R) TERMINAL BIOS v4.1
+MEMORY CHECK .............. 640K OK
+MOUNTING SEVERED FLOOR .... OK
+VERIFYING INNIE CONSENT ... OK
+LOADING MACRODATA REFINEMENT";
+
+/// Characters of the boot log typed per second.
+const TYPING_SPEED: f32 = 60.;
+
+/// Seconds of blinking cursor before typing starts, and between the log and the logo.
+const PAUSE: f32 = 0.6;
+
+/// Seconds the logo stays up before the menu.
+const LOGO_TIME: f32 = 1.8;
+
+/// Seconds per on-and-off blink of the cursor.
+const BLINK_PERIOD: f32 = 0.5;
+
+pub struct BootPlugin;
+
+impl Plugin for BootPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Boot), start_boot)
+            .add_systems(
+                Update,
+                (skip_boot, play_boot)
+                    .chain()
+                    .run_if(in_state(AppState::Boot).and(not(in_transition))),
+            );
+    }
+}
+
+/// How far into the boot sequence we are.
+#[derive(Resource, Default)]
+struct BootSequence {
+    elapsed: f32,
+    logo_shown: bool,
+}
+
+impl BootSequence {
+    /// When the logo appears.
+    fn logo_at() -> f32 {
+        PAUSE + BOOT_LOG.chars().count() as f32 / TYPING_SPEED + PAUSE
+    }
+}
+
+#[derive(Component)]
+struct BootLog;
+
+fn start_boot(mut commands: Commands, config: Res<Config>, mut next: ResMut<NextState<AppState>>) {
+    if !config.video.boot_intro {
+        next.set(AppState::Menu);
+        return;
+    }
+    commands.insert_resource(BootSequence::default());
+
+    commands.spawn((
+        Sprite {
+            color: Color::BLACK,
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 30.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Boot),
+    ));
+
+    commands.spawn((
+        BootLog,
+        Text2d::default(),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Left),
+        TextColor(Color::srgb(0.0, 0.9, 1.0)),
+        Anchor::TopLeft,
+        CanvasAnchor::TOP_LEFT.offset(12., -12.),
+        Transform::from_xyz(0., 0., 31.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Boot),
+    ));
+}
+
+/// Any key or mouse button goes straight to the menu.
+fn skip_boot(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    if keys.get_just_pressed().next().is_some() || buttons.get_just_pressed().next().is_some() {
+        transitions.write(TransitionTo::new(AppState::Menu, TransitionEffect::Fade));
+    }
+}
+
+fn play_boot(
+    mut commands: Commands,
+    time: Res<Time>,
+    boot: Option<ResMut<BootSequence>>,
+    mut log: Single<&mut Text2d, With<BootLog>>,
+    mut sounds: EventWriter<PlaySound>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    let Some(mut boot) = boot else {
+        return;
+    };
+    boot.elapsed += time.delta_secs();
+
+    let typed = ((boot.elapsed - PAUSE).max(0.) * TYPING_SPEED) as usize;
+    let cursor = if (boot.elapsed / BLINK_PERIOD).fract() < 0.5 {
+        "_"
+    } else {
+        " "
+    };
+    let text: String = BOOT_LOG.chars().take(typed).collect();
+    log.0 = format!("{text}{cursor}");
+
+    if !boot.logo_shown && boot.elapsed >= BootSequence::logo_at() {
+        boot.logo_shown = true;
+        commands.spawn((
+            Text2d::new("LUMON"),
+            TextFont {
+                font_size: 32.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_xyz(0., -24., 31.),
+            PIXEL_PERFECT_LAYERS,
+            StateScoped(AppState::Boot),
+        ));
+        sounds.write(PlaySound::new(Sound::Beep));
+    }
+
+    if boot.elapsed >= BootSequence::logo_at() + LOGO_TIME {
+        transitions.write(TransitionTo::new(AppState::Menu, TransitionEffect::Fade));
+    }
+}
@@ -0,0 +1,567 @@
+//! Shows how to create graphics that snap to the pixel grid by rendering to a texture in 2D
+//!
+//! The canvas is made of layers, each a texture of its own: the [backdrop](crate::backdrop) at
+//! the bottom, cleared to the theme's background, the grid over it, and the screen chrome
+//! (header, bins, menus and messages) over that, so that the grid's camera can pan without
+//! moving anything else.
+//!
+//! The canvas is as large as the [`CanvasSize`] the config picks, and may be upright as well as
+//! wide (see [`Orientation`]). Screen chrome that belongs at an edge of it is placed with a
+//! [`CanvasAnchor`], which may put it elsewhere on an upright canvas, and backdrops that cover
+//! it with a [`CanvasFill`], so that both follow when it changes shape. A [bezel](crate::bezel)
+//! around it is fitted into the window along with it.
+//!
+//! The canvas can also be supersampled: rendered at a multiple of its size and smoothly scaled
+//! onto the screen, for smooth text instead of chunky pixels. Layout is unaffected, as the
+//! cameras zoom in to match and text is set at a larger size and shrunk back down (see
+//! [`Supersampling::text_scale`]). Supersampling more than the GPU's textures can hold falls back
+//! to the most that fits, and is reported as a [`Fault`].
+
+use bevy::{
+    ecs::system::SystemParam,
+    image::ImageSampler,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::RenderDevice,
+        view::RenderLayers,
+    },
+    text::Update2dText,
+    transform::TransformSystem,
+    window::{PrimaryWindow, WindowResized, WindowScaleFactorChanged},
+};
+
+use crate::{
+    config::{Config, ScaleMode, VideoConfig},
+    fault::{Fault, FaultKind, Faults},
+    grid::{cell_at, Cell, GridSize},
+    shift::PixelShift,
+    theme::Theme,
+    zoom::Zoom,
+};
+
+/// Default render layers for pixel-perfect rendering.
+/// You can skip adding this component, as this is the default.
+pub const PIXEL_PERFECT_LAYERS: RenderLayers = RenderLayers::layer(0);
+
+/// Render layers for high-resolution rendering.
+pub const HIGH_RES_LAYERS: RenderLayers = RenderLayers::layer(1);
+
+/// Supersampling factors the canvas can be rendered at; 1 is off.
+pub const SUPERSAMPLING: [u32; 3] = [1, 2, 4];
+
+/// Render layers of the grid, which is drawn to the canvas by the [`GridCamera`] underneath
+/// everything on [`PIXEL_PERFECT_LAYERS`].
+pub const GRID_LAYERS: RenderLayers = RenderLayers::layer(2);
+
+/// Render layers of the [result card](crate::card), which has a camera and an image of its own
+/// and is never drawn to the canvas.
+pub const CARD_LAYERS: RenderLayers = RenderLayers::layer(3);
+
+/// Render layers of the backdrop, which is drawn to the canvas by the [`BackdropCamera`] under
+/// the grid.
+pub const BACKDROP_LAYERS: RenderLayers = RenderLayers::layer(4);
+
+pub struct CanvasPlugin;
+
+impl Plugin for CanvasPlugin {
+    fn build(&self, app: &mut App) {
+        let size = app.world().resource::<Config>().video.canvas.size();
+        app.insert_resource(CanvasSize(size))
+            .init_resource::<Supersampling>()
+            .add_systems(Startup, (setup_camera, resample_canvas, fit_canvas).chain())
+            .add_systems(
+                Update,
+                (
+                    fit_canvas
+                        .run_if(on_event::<WindowResized>.or(on_event::<WindowScaleFactorChanged>)),
+                    (resample_canvas, fit_canvas).run_if(resource_changed::<Config>),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    supersample_text.before(Update2dText),
+                    place_on_canvas.before(TransformSystem::TransformPropagate),
+                ),
+            );
+    }
+}
+
+/// Sprite showing one layer of the low-resolution canvas in the high-resolution world.
+#[derive(Component)]
+pub struct Canvas;
+
+/// Camera that renders the pixel-perfect world, the screen chrome, to a [`Canvas`] layer of its
+/// own over the grid's.
+#[derive(Component)]
+pub struct InGameCamera;
+
+/// Camera that renders the grid to a [`Canvas`] layer over the [`BackdropCamera`]'s and under the
+/// [`InGameCamera`]'s. It pans over the grid while everything else stays put.
+#[derive(Component)]
+pub struct GridCamera;
+
+/// Camera that renders the backdrop to the bottom [`Canvas`] layer, cleared to the color behind
+/// the grid.
+#[derive(Component)]
+pub struct BackdropCamera;
+
+/// Camera that renders the [`Canvas`] (and other graphics on [`HIGH_RES_LAYERS`]) to the screen.
+#[derive(Component)]
+pub struct OuterCamera;
+
+/// Size of the canvas's layout, in canvas pixels, as the config's [`CanvasPreset`] has it.
+///
+/// [`CanvasPreset`]: crate::config::CanvasPreset
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanvasSize(pub UVec2);
+
+impl CanvasSize {
+    pub fn size(self) -> Vec2 {
+        self.0.as_vec2()
+    }
+
+    /// Distance from the centre of the canvas to its right and top edges.
+    pub fn half(self) -> Vec2 {
+        self.size() / 2.
+    }
+
+    pub fn orientation(self) -> Orientation {
+        if self.0.y > self.0.x {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+}
+
+/// Which way round the canvas is, for layouts that differ between the two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    /// At least as wide as it is high.
+    Landscape,
+    /// Higher than it is wide, for monitors mounted upright.
+    Portrait,
+}
+
+/// Keeps an entity at a point of the canvas, however large it is: `offset` from `point`, which
+/// runs from -1 at the left and bottom edges to 1 at the right and top ones.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct CanvasAnchor {
+    pub point: Vec2,
+    pub offset: Vec2,
+    /// The point and offset used instead on a portrait canvas, for chrome that has to move out
+    /// of the way there.
+    pub portrait: Option<(Vec2, Vec2)>,
+}
+
+impl CanvasAnchor {
+    pub const TOP: Self = Self::at(0., 1.);
+    pub const BOTTOM: Self = Self::at(0., -1.);
+    pub const TOP_LEFT: Self = Self::at(-1., 1.);
+    pub const TOP_RIGHT: Self = Self::at(1., 1.);
+    pub const BOTTOM_LEFT: Self = Self::at(-1., -1.);
+    pub const BOTTOM_RIGHT: Self = Self::at(1., -1.);
+
+    const fn at(x: f32, y: f32) -> Self {
+        Self {
+            point: Vec2::new(x, y),
+            offset: Vec2::ZERO,
+            portrait: None,
+        }
+    }
+
+    /// The same point, `x` and `y` away from it.
+    pub const fn offset(self, x: f32, y: f32) -> Self {
+        Self {
+            offset: Vec2::new(x, y),
+            ..self
+        }
+    }
+
+    /// The same, but at `anchor` on a portrait canvas.
+    pub const fn in_portrait(self, anchor: CanvasAnchor) -> Self {
+        Self {
+            portrait: Some((anchor.point, anchor.offset)),
+            ..self
+        }
+    }
+
+    /// Where on a canvas of the given size the anchor puts its entity.
+    pub fn position(self, canvas: CanvasSize) -> Vec2 {
+        let (point, offset) = match (canvas.orientation(), self.portrait) {
+            (Orientation::Portrait, Some(portrait)) => portrait,
+            _ => (self.point, self.offset),
+        };
+        point * canvas.half() + offset
+    }
+}
+
+/// Stretches a sprite over the whole canvas, such as the backdrop of a screen.
+#[derive(Component)]
+pub struct CanvasFill;
+
+/// How many times larger than its layout the canvas is rendered.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Supersampling(pub u32);
+
+impl Default for Supersampling {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl Supersampling {
+    /// Scale of text on the canvas, which is set this much larger so that it has the pixels to
+    /// be smooth. Systems that scale text themselves multiply their scale by this.
+    pub fn text_scale(self) -> f32 {
+        1. / self.0 as f32
+    }
+}
+
+/// One layer of the low-resolution canvas: an image that a camera draws some of the world's
+/// render layers to, shown on the screen by the [`OuterCamera`].
+///
+/// Layers are stacked by the order of their cameras, each drawn over the ones before it, so that
+/// every layer's camera moves independently of the others.
+struct CanvasLayer {
+    /// Render layers of the world drawn to this layer.
+    layers: RenderLayers,
+    /// Order of the layer's camera, which also stacks the layer from the bottom up.
+    order: isize,
+    clear_color: ClearColorConfig,
+}
+
+impl CanvasLayer {
+    /// Spawns the layer's image, its camera with `marker` on it, and the sprite that shows it.
+    fn spawn(
+        self,
+        commands: &mut Commands,
+        images: &mut Assets<Image>,
+        canvas: CanvasSize,
+        marker: impl Component,
+    ) {
+        let size = Extent3d {
+            width: canvas.0.x,
+            height: canvas.0.y,
+            ..default()
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: None,
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Bgra8UnormSrgb,
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::COPY_SRC
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+            ..default()
+        };
+        // Fill image.data with zeroes
+        image.resize(size);
+        let image = images.add(image);
+
+        commands.spawn((
+            Camera2d,
+            Camera {
+                // Render before the "main pass" camera
+                order: self.order,
+                target: RenderTarget::Image(image.clone().into()),
+                clear_color: self.clear_color,
+                ..default()
+            },
+            Msaa::Off,
+            marker,
+            self.layers,
+        ));
+        // Always the size of its layout however many pixels it has
+        commands.spawn((
+            Sprite {
+                custom_size: Some(canvas.size()),
+                ..Sprite::from_image(image)
+            },
+            Transform::from_xyz(0., 0., self.order as f32),
+            Canvas,
+            HIGH_RES_LAYERS,
+        ));
+    }
+}
+
+fn setup_camera(
+    mut commands: Commands,
+    canvas: Res<CanvasSize>,
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // The backdrop, at the bottom
+    CanvasLayer {
+        layers: BACKDROP_LAYERS,
+        order: -3,
+        clear_color: ClearColorConfig::Custom(theme.background()),
+    }
+    .spawn(&mut commands, &mut images, *canvas, BackdropCamera);
+
+    // The grid, over it and clear everywhere else
+    CanvasLayer {
+        layers: GRID_LAYERS,
+        order: -2,
+        clear_color: ClearColorConfig::Custom(Color::NONE),
+    }
+    .spawn(&mut commands, &mut images, *canvas, GridCamera);
+
+    // Whatever is on `PIXEL_PERFECT_LAYERS`: the screen chrome, over the grid and clear
+    // everywhere else
+    CanvasLayer {
+        layers: PIXEL_PERFECT_LAYERS,
+        order: -1,
+        clear_color: ClearColorConfig::Custom(Color::NONE),
+    }
+    .spawn(&mut commands, &mut images, *canvas, InGameCamera);
+
+    // The "outer" camera renders whatever is on `HIGH_RES_LAYERS` to the screen.
+    // here, the canvas layers and one of the sample sprites will be rendered by this camera
+    commands.spawn((Camera2d, Msaa::Off, OuterCamera, HIGH_RES_LAYERS));
+}
+
+/// Points the outer camera at the canvas and the bezel around it, if any, and scales its
+/// projection to fit both into `window`.
+///
+/// The scale is worked out in the window's physical pixels, so that an integer scale is a whole
+/// number of the screen's own pixels per canvas pixel at any scale factor, such as 150% on a
+/// HiDPI laptop screen, rather than of its logical ones.
+fn fit_outer_camera(
+    window: &Window,
+    canvas: CanvasSize,
+    video: &VideoConfig,
+    (projection, transform): (&mut OrthographicProjection, &mut Transform),
+) {
+    let frame = video.bezel.frame(canvas.size());
+    let h_scale = window.physical_width() as f32 / frame.width();
+    let v_scale = window.physical_height() as f32 / frame.height();
+    let physical_scale = match video.scale_mode {
+        ScaleMode::Integer => h_scale.min(v_scale).round().max(1.),
+        ScaleMode::Fractional => h_scale.min(v_scale),
+    };
+    // The projection counts in logical pixels.
+    projection.scale = window.scale_factor() / physical_scale;
+    // Margins that differ put the canvas off the middle of the window.
+    let center = frame.center().extend(transform.translation.z);
+    if transform.translation != center {
+        transform.translation = center;
+    }
+}
+
+/// Scales camera projection to fit the window (integer multiples only, unless configured
+/// otherwise), bezel and all: at startup, whenever the window is resized or moved to a monitor of
+/// another scale factor, and whenever the scale mode, the bezel or the canvas's size changes.
+fn fit_canvas(
+    config: Res<Config>,
+    canvas: Res<CanvasSize>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&mut Projection, &mut Transform), With<OuterCamera>>,
+) {
+    let (mut projection, mut transform) = camera.into_inner();
+    let Projection::Orthographic(projection) = &mut *projection else {
+        return;
+    };
+    fit_outer_camera(
+        &window,
+        *canvas,
+        &config.video,
+        (projection, &mut transform),
+    );
+}
+
+/// Rebuilds the canvas layers at the configured size and supersampling, zooming the cameras that
+/// draw to them to match.
+fn resample_canvas(
+    config: Res<Config>,
+    mut canvas: ResMut<CanvasSize>,
+    mut supersampling: ResMut<Supersampling>,
+    mut images: ResMut<Assets<Image>>,
+    mut layers: Query<&mut Sprite, With<Canvas>>,
+    // Every camera but the outer one draws to the canvas.
+    mut projections: Query<&mut Projection, Without<OuterCamera>>,
+    (device, mut faults): (Option<Res<RenderDevice>>, ResMut<Faults>),
+) {
+    let wanted = if SUPERSAMPLING.contains(&config.video.supersampling) {
+        config.video.supersampling
+    } else {
+        1
+    };
+    let size = CanvasSize(config.video.canvas.size());
+    // Textures the GPU cannot hold fall back to the most supersampling that fits.
+    let limit = device.map(|device| device.limits().max_texture_dimension_2d);
+    let fits = |factor: u32| limit.is_none_or(|limit| size.0.max_element() * factor <= limit);
+    let factor = SUPERSAMPLING
+        .into_iter()
+        .filter(|&factor| factor <= wanted && fits(factor))
+        .max()
+        .unwrap_or(1);
+    if *supersampling == Supersampling(factor) && *canvas == size {
+        return;
+    }
+    if factor != wanted {
+        let (width, height) = (size.0.x * wanted, size.0.y * wanted);
+        faults.report(Fault::new(
+            FaultKind::Canvas,
+            "CANVAS TOO LARGE",
+            format!(
+                "{wanted}x supersampling needs {width}x{height} textures; this GPU holds at most \
+                 {}. Drawing at {factor}x instead.",
+                limit.unwrap_or_default()
+            ),
+        ));
+    }
+    for mut layer in &mut layers {
+        layer.custom_size = Some(size.size());
+        let Some(image) = images.get_mut(&layer.image) else {
+            continue;
+        };
+        image.resize(Extent3d {
+            width: size.0.x * factor,
+            height: size.0.y * factor,
+            ..default()
+        });
+        // Nearest sampling keeps whole canvas pixels sharp; smooth text wants them blended.
+        image.sampler = if factor > 1 {
+            ImageSampler::linear()
+        } else {
+            ImageSampler::Default
+        };
+    }
+    for mut projection in &mut projections {
+        if let Projection::Orthographic(projection) = &mut *projection {
+            projection.scale = 1. / factor as f32;
+        }
+    }
+    supersampling.set_if_neq(Supersampling(factor));
+    canvas.set_if_neq(size);
+}
+
+/// Puts anchored entities in their places on the canvas, and stretches fills over it, as they
+/// are spawned and as the canvas changes size.
+fn place_on_canvas(
+    canvas: Res<CanvasSize>,
+    mut anchored: Query<(Ref<CanvasAnchor>, &mut Transform)>,
+    mut fills: Query<(Ref<CanvasFill>, &mut Sprite)>,
+) {
+    for (anchor, mut transform) in &mut anchored {
+        if canvas.is_changed() || anchor.is_changed() {
+            let position = anchor.position(*canvas);
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+    for (fill, mut sprite) in &mut fills {
+        if canvas.is_changed() || fill.is_added() {
+            sprite.custom_size = Some(canvas.size());
+        }
+    }
+}
+
+/// Sets text on the canvas larger and shrinks it back down by the same amount, so that it is
+/// rasterized at the supersampled resolution.
+fn supersample_text(
+    supersampling: Res<Supersampling>,
+    mut last: Local<Option<Supersampling>>,
+    mut texts: Query<(Ref<Text2d>, &mut TextFont, &mut Transform)>,
+) {
+    let previous = last.replace(*supersampling).unwrap_or_default();
+    for (text, mut font, mut transform) in &mut texts {
+        let ratio = if text.is_added() {
+            supersampling.0 as f32
+        } else if *supersampling != previous {
+            supersampling.0 as f32 / previous.0 as f32
+        } else {
+            continue;
+        };
+        if ratio != 1. {
+            font.font_size *= ratio;
+            transform.scale /= ratio;
+        }
+    }
+}
+
+/// The OS cursor, followed down onto the canvas and into the worlds drawn to it. This is the one
+/// place the window's cursor is turned into canvas coordinates, for every input that points.
+///
+/// The cursor is first projected through the [`OuterCamera`] onto the [`Canvas`] sprites, which
+/// takes care of however the canvas is scaled and letterboxed to fit the window and shifted
+/// against burn-in, then through the [`InGameCamera`] or the [`GridCamera`] into the world it
+/// renders. Every position is `None` while the cursor is outside the window or over the
+/// letterboxing around the canvas.
+#[derive(SystemParam)]
+pub struct CanvasCursor<'w> {
+    window: Single<'w, &'static Window, With<PrimaryWindow>>,
+    outer: Single<'w, CameraView, With<OuterCamera>>,
+    in_game: Single<'w, CameraView, (With<InGameCamera>, Without<OuterCamera>)>,
+    grid: Single<'w, &'static GlobalTransform, With<GridCamera>>,
+    canvas: Res<'w, CanvasSize>,
+    shift: Res<'w, PixelShift>,
+    zoom: Res<'w, Zoom>,
+    size: Res<'w, GridSize>,
+}
+
+/// What the [`CanvasCursor`] needs of a camera to see through it.
+type CameraView = (&'static Camera, &'static GlobalTransform);
+
+impl CanvasCursor<'_> {
+    /// Size of the canvas the cursor is over.
+    pub fn canvas(&self) -> CanvasSize {
+        *self.canvas
+    }
+
+    /// Canvas pixel under the cursor, counted from the top left corner, with the fraction of
+    /// the way across that pixel kept.
+    pub fn pixel(&self) -> Option<Vec2> {
+        let cursor = self.window.cursor_position()?;
+        let (camera, transform) = *self.outer;
+        let on_canvas = camera.viewport_to_world_2d(transform, cursor).ok()? - self.shift.0;
+        let size = self.canvas.size();
+        let pixel = Vec2::new(on_canvas.x + size.x / 2., size.y / 2. - on_canvas.y);
+        let inside = pixel.cmpge(Vec2::ZERO).all() && pixel.cmple(size).all();
+        inside.then_some(pixel)
+    }
+
+    /// World position of the cursor in the pixel-perfect world of the screen chrome.
+    pub fn world(&self) -> Option<Vec2> {
+        let pixel = self.pixel()?;
+        let (camera, transform) = *self.in_game;
+        // The canvas may have more pixels than its layout when supersampled.
+        let supersampling = camera
+            .physical_target_size()
+            .map_or(1., |target| target.x as f32 / self.canvas.size().x);
+        camera
+            .viewport_to_world_2d(transform, pixel * supersampling)
+            .ok()
+    }
+
+    /// World position of the cursor in the grid, as seen through the [`GridCamera`] and its
+    /// [`Zoom`].
+    pub fn grid(&self) -> Option<Vec2> {
+        let position = self.world()?;
+        let (_, in_game) = *self.in_game;
+        let from_center = (position - in_game.translation().truncate()) / self.zoom.scale();
+        Some(from_center + self.grid.translation().truncate())
+    }
+
+    /// The cell of the grid whose number is under the cursor, if any.
+    pub fn cell(&self) -> Option<Cell> {
+        self.grid()
+            .and_then(|position| cell_at(position, *self.size))
+    }
+
+    /// Size of the grid the cursor is over.
+    pub fn grid_size(&self) -> GridSize {
+        *self.size
+    }
+}
@@ -0,0 +1,261 @@
+//! The result card: a picture of how a session went, saved as a PNG for sharing.
+//!
+//! Asked for from the summary screen with [`SaveCard`]. The card is laid out like the canvas,
+//! only on [`CARD_LAYERS`], where a camera of its own draws it to an image of [`CARD_SIZE`]
+//! whatever size the canvas and the window are: the file's name, how complete it is, the time the
+//! session took and the date, in the colors of the theme. Once it has been drawn it is saved to
+//! `cards` in the config directory, and taken down again.
+
+use std::{fs, path::Path};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::screenshot::{Screenshot, ScreenshotCaptured},
+    },
+};
+
+use crate::{
+    bins::percent,
+    canvas::CARD_LAYERS,
+    clock::today,
+    summary::{clock_time, SessionStats},
+    theme::Theme,
+    toast::Toast,
+};
+
+/// Size of the card, in pixels.
+const CARD_SIZE: UVec2 = UVec2::new(320, 180);
+
+/// Frames the card is given to be laid out and drawn before it is saved.
+const SETTLE_FRAMES: u32 = 2;
+
+/// Pixels between the scanlines drawn over the card.
+const SCANLINE_SPACING: u32 = 3;
+
+const SCANLINE_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.15);
+const LABEL_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+pub struct CardPlugin;
+
+impl Plugin for CardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveCard>().add_systems(
+            Update,
+            (lay_out_card.run_if(on_event::<SaveCard>), save_card).chain(),
+        );
+    }
+}
+
+/// Saves a result card of the session just ended.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SaveCard;
+
+/// Marks everything the card is made of, its camera included.
+#[derive(Component)]
+struct CardPart;
+
+/// The card's camera, until the card is saved.
+#[derive(Component)]
+struct CardShot {
+    image: Handle<Image>,
+    /// Frames left before the card is saved.
+    frames: u32,
+}
+
+/// An image of [`CARD_SIZE`] for the card's camera to draw to, and to be read back from.
+fn card_image(images: &mut Assets<Image>) -> Handle<Image> {
+    let size = Extent3d {
+        width: CARD_SIZE.x,
+        height: CARD_SIZE.y,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    images.add(image)
+}
+
+fn lay_out_card(
+    mut commands: Commands,
+    mut requests: EventReader<SaveCard>,
+    stats: Res<SessionStats>,
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+    parts: Query<(), With<CardPart>>,
+) {
+    requests.clear();
+    // One card at a time; asking again while one is being saved saves just the one.
+    if !parts.is_empty() {
+        return;
+    }
+    let image = card_image(&mut images);
+    let background = Color::srgb_from_array(theme.background);
+    commands.spawn((
+        CardPart,
+        CardShot {
+            image: image.clone(),
+            frames: SETTLE_FRAMES,
+        },
+        Camera2d,
+        Camera {
+            // Before every camera of the canvas, which it has nothing to do with.
+            order: -10,
+            target: RenderTarget::Image(image.into()),
+            clear_color: ClearColorConfig::Custom(background),
+            ..default()
+        },
+        Msaa::Off,
+        CARD_LAYERS,
+    ));
+
+    let size = CARD_SIZE.as_vec2();
+    let selection = theme.selection();
+    let numbers = Color::srgb_from_array(theme.numbers);
+    // A frame in the selection color, inset from the edges.
+    for (color, inset, z) in [(selection, 6., 0.), (background, 8., 0.1)] {
+        commands.spawn((
+            CardPart,
+            Sprite::from_color(color, size - inset * 2.),
+            Transform::from_xyz(0., 0., z),
+            CARD_LAYERS,
+        ));
+    }
+
+    let texts = [
+        ("LUMON INDUSTRIES".to_string(), 8., LABEL_COLOR, 68.),
+        (stats.file.to_uppercase(), 16., numbers, 46.),
+        (percent(stats.completion), 32., selection, 10.),
+        (
+            format!("TIME {}    {}", clock_time(stats.duration), today()),
+            8.,
+            numbers,
+            -30.,
+        ),
+    ];
+    for (text, font_size, color, y) in texts {
+        commands.spawn((
+            CardPart,
+            Text2d::new(text),
+            TextFont {
+                font_size,
+                ..default()
+            },
+            TextColor(color),
+            Transform::from_xyz(0., y, 1.),
+            CARD_LAYERS,
+        ));
+    }
+
+    // The theme's palette along the bottom.
+    let swatches = [
+        theme.numbers,
+        theme.selection,
+        theme.info,
+        theme.success,
+        theme.warning,
+        theme.error,
+    ];
+    for (index, swatch) in swatches.iter().enumerate() {
+        let x = (index as f32 - (swatches.len() - 1) as f32 / 2.) * 24.;
+        commands.spawn((
+            CardPart,
+            Sprite::from_color(Color::srgb_from_array(*swatch), Vec2::new(20., 6.)),
+            Transform::from_xyz(x, -62., 1.),
+            CARD_LAYERS,
+        ));
+    }
+
+    for line in (0..CARD_SIZE.y).step_by(SCANLINE_SPACING as usize) {
+        let y = size.y / 2. - line as f32 - 0.5;
+        commands.spawn((
+            CardPart,
+            Sprite::from_color(SCANLINE_COLOR, Vec2::new(size.x, 1.)),
+            Transform::from_xyz(0., y, 2.),
+            CARD_LAYERS,
+        ));
+    }
+}
+
+/// Saves the card once it has been drawn, and takes it down once saved.
+fn save_card(
+    mut commands: Commands,
+    stats: Res<SessionStats>,
+    mut shots: Query<(Entity, &mut CardShot)>,
+    parts: Query<Entity, With<CardPart>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for (entity, mut shot) in &mut shots {
+        if shot.frames > 0 {
+            shot.frames -= 1;
+            continue;
+        }
+        commands.entity(entity).remove::<CardShot>();
+        let path = stats.export_path("cards", "png");
+        if let Some(error) = path
+            .parent()
+            .and_then(|parent| fs::create_dir_all(parent).err())
+        {
+            error!("Could not save result card to {}: {error}", path.display());
+            toasts.write(Toast::warning("Could not save card"));
+            for part in &parts {
+                commands.entity(part).despawn();
+            }
+            continue;
+        }
+        commands
+            .spawn(Screenshot::image(shot.image.clone()))
+            .observe(
+                move |captured: Trigger<ScreenshotCaptured>,
+                      mut commands: Commands,
+                      parts: Query<Entity, With<CardPart>>,
+                      mut toasts: EventWriter<Toast>| {
+                    // Only a card that made it to disk is worth telling the player about as saved.
+                    match write_card(captured.event(), &path) {
+                        Ok(()) => {
+                            info!("Saved result card to {}", path.display());
+                            toasts.write(Toast::success("Result card saved"));
+                        }
+                        Err(error) => {
+                            error!("Could not save result card to {}: {error}", path.display());
+                            toasts.write(Toast::warning("Could not save card"));
+                        }
+                    }
+                    for part in &parts {
+                        commands.entity(part).despawn();
+                    }
+                },
+            );
+    }
+}
+
+/// Writes a captured card to `path` as a PNG, without the alpha channel, which holds brightness
+/// rather than transparency where HDR is on.
+fn write_card(image: &Image, path: &Path) -> Result<(), String> {
+    let image = image
+        .clone()
+        .try_into_dynamic()
+        .map_err(|error| error.to_string())?;
+    image
+        .to_rgb8()
+        .save(path)
+        .map_err(|error| error.to_string())
+}
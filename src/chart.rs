@@ -0,0 +1,204 @@
+//! Small charts drawn pixel by pixel on the canvas.
+//!
+//! A [`Sparkline`] scrolls a line or step chart of its most recent values, a few pixel columns
+//! apart, in the theme's selection color. Values are kept in a ring
+//! buffer as wide as the chart, and the chart is only redrawn when a value is pushed or the theme
+//! changes. Anything can add one: spawn it with a [`Transform`] on [`PIXEL_PERFECT_LAYERS`] and
+//! [`push`](Sparkline::push) values into it.
+//!
+//! [`PIXEL_PERFECT_LAYERS`]: crate::canvas::PIXEL_PERFECT_LAYERS
+
+use std::collections::VecDeque;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::theme::Theme;
+
+/// Opacity of the area under a sparkline's line.
+const AREA_ALPHA: f32 = 0.25;
+
+pub struct ChartPlugin;
+
+impl Plugin for ChartPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, (give_canvas, draw_sparklines).chain());
+    }
+}
+
+/// How a sparkline gets from one value to the next.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SparkStyle {
+    /// Straight lines between the values.
+    #[default]
+    Line,
+    /// Each value held until the next.
+    Step,
+}
+
+/// A scrolling chart of recent values.
+#[derive(Component, Clone, Debug)]
+#[require(Sprite)]
+pub struct Sparkline {
+    /// Size of the chart, in canvas pixels.
+    size: UVec2,
+    /// Pixels between one value and the next.
+    spacing: u32,
+    style: SparkStyle,
+    /// Values at the bottom and top of the chart, or `None` to fit the values shown.
+    range: Option<(f32, f32)>,
+    /// The most recent values, oldest first.
+    values: VecDeque<f32>,
+}
+
+impl Sparkline {
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size: size.max(UVec2::ONE),
+            spacing: 1,
+            style: SparkStyle::default(),
+            range: None,
+            values: VecDeque::new(),
+        }
+    }
+
+    pub fn with_spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing.max(1);
+        self
+    }
+
+    pub fn with_style(mut self, style: SparkStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_range(mut self, bottom: f32, top: f32) -> Self {
+        self.range = Some((bottom, top));
+        self
+    }
+
+    /// Most values the chart shows at once.
+    fn capacity(&self) -> usize {
+        ((self.size.x - 1) / self.spacing + 1) as usize
+    }
+
+    /// Adds a value at the right, scrolling the oldest off the left once the chart is full.
+    pub fn push(&mut self, value: f32) {
+        if self.values.len() == self.capacity() {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    /// Height in pixels of every value, from the bottom.
+    fn heights(&self) -> Vec<f32> {
+        let (bottom, top) = self.range.unwrap_or_else(|| {
+            let low = self.values.iter().copied().fold(f32::INFINITY, f32::min);
+            let high = self
+                .values
+                .iter()
+                .copied()
+                .fold(f32::NEG_INFINITY, f32::max);
+            (low, high)
+        });
+        let span = (top - bottom).max(f32::EPSILON);
+        let rows = (self.size.y - 1) as f32;
+        self.values
+            .iter()
+            .map(|value| ((value - bottom) / span).clamp(0., 1.) * rows)
+            .collect()
+    }
+
+    /// Height of the line in every pixel column, or `None` left of the oldest value.
+    fn columns(&self) -> Vec<Option<f32>> {
+        let heights = self.heights();
+        let mut columns = vec![None; self.size.x as usize];
+        // The newest value sits at the right edge.
+        let first = self.size.x as i64 - 1 - (heights.len() as i64 - 1) * self.spacing as i64;
+        for (index, column) in columns.iter_mut().enumerate() {
+            let offset = index as i64 - first;
+            if offset < 0 {
+                continue;
+            }
+            let at = (offset / self.spacing as i64) as usize;
+            let t = (offset % self.spacing as i64) as f32 / self.spacing as f32;
+            let from = heights[at];
+            let to = heights.get(at + 1).copied().unwrap_or(from);
+            *column = Some(match self.style {
+                SparkStyle::Line => from + (to - from) * t,
+                SparkStyle::Step => from,
+            });
+        }
+        columns
+    }
+}
+
+/// Gives new sparklines an image to draw into.
+fn give_canvas(
+    mut images: ResMut<Assets<Image>>,
+    mut sparklines: Query<(&Sparkline, &mut Sprite), Added<Sparkline>>,
+) {
+    for (sparkline, mut sprite) in &mut sparklines {
+        let image = Image::new_fill(
+            Extent3d {
+                width: sparkline.size.x,
+                height: sparkline.size.y,
+                ..default()
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        );
+        sprite.image = images.add(image);
+        sprite.custom_size = Some(sparkline.size.as_vec2());
+    }
+}
+
+fn draw_sparklines(
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+    sparklines: Query<(Ref<Sparkline>, &Sprite)>,
+) {
+    let line = theme.selection().to_srgba().to_u8_array();
+    let area = theme
+        .selection()
+        .with_alpha(AREA_ALPHA)
+        .to_srgba()
+        .to_u8_array();
+    for (sparkline, sprite) in &sparklines {
+        if !sparkline.is_changed() && !theme.is_changed() {
+            continue;
+        }
+        let Some(data) = images
+            .get_mut(&sprite.image)
+            .and_then(|image| image.data.as_mut())
+        else {
+            continue;
+        };
+        data.fill(0);
+        let (width, height) = (sparkline.size.x as usize, sparkline.size.y as usize);
+        let mut last: Option<usize> = None;
+        for (x, column) in sparkline.columns().into_iter().enumerate() {
+            let Some(height_at) = column else {
+                continue;
+            };
+            let y = height_at.round() as usize;
+            // Join the line to the last column, so steep changes stay unbroken.
+            let (low, high) = match last {
+                Some(last) => (y.min(last), y.max(last)),
+                None => (y, y),
+            };
+            last = Some(y);
+            for row in 0..=high.min(height - 1) {
+                let color = if row >= low { line } else { area };
+                // Images start at the top.
+                let index = ((height - 1 - row) * width + x) * 4;
+                data[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+}
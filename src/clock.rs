@@ -0,0 +1,262 @@
+//! A clock in the top left corner of the canvas, so that a grid left running on a spare screen
+//! doubles as a desk clock.
+//!
+//! The time is local where the platform says what local time is (everywhere but Windows, for
+//! now, which shows UTC). It follows the clock settings in the config: 12 or 24 hours, whether
+//! the colon blinks, and whether the date is shown under the time.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    canvas::{CanvasAnchor, PIXEL_PERFECT_LAYERS},
+    config::{ClockConfig, Config},
+};
+
+const CLOCK_COLOR: Color = Color::srgb(0.0, 0.9, 1.0);
+const BACKING_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+pub const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                show_clock.run_if(resource_changed::<Config>),
+                tell_time.run_if(|config: Res<Config>| config.clock.enabled),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The clock's text.
+#[derive(Component)]
+struct ClockFace;
+
+/// The panel behind the clock.
+#[derive(Component)]
+struct ClockBacking;
+
+/// A moment as the wall clock shows it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct WallTime {
+    hour: u32,
+    minute: u32,
+    /// Whether this is the first half of a second, when a blinking colon shows.
+    first_half: bool,
+    /// Day of the week, from 0 for Sunday.
+    weekday: u32,
+    day: u32,
+    /// Month, from 0 for January.
+    month: u32,
+    year: i64,
+}
+
+impl WallTime {
+    fn now() -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let first_half = since_epoch.subsec_millis() < 500;
+        let seconds = since_epoch.as_secs() as i64;
+        local(seconds)
+            .unwrap_or_else(|| utc(seconds))
+            .with_half(first_half)
+    }
+
+    fn with_half(mut self, first_half: bool) -> Self {
+        self.first_half = first_half;
+        self
+    }
+
+    fn text(&self, config: &ClockConfig) -> String {
+        let colon = if config.blinking_colon && !self.first_half {
+            ' '
+        } else {
+            ':'
+        };
+        let mut text = if config.twenty_four_hour {
+            format!("{:02}{colon}{:02}", self.hour, self.minute)
+        } else {
+            let hour = (self.hour + 11) % 12 + 1;
+            let half = if self.hour < 12 { "AM" } else { "PM" };
+            format!("{hour}{colon}{:02} {half}", self.minute)
+        };
+        if config.date {
+            text += &format!(
+                "\n{} {} {}",
+                WEEKDAYS[self.weekday as usize % 7],
+                self.day,
+                MONTHS[self.month as usize % 12]
+            );
+        }
+        text
+    }
+}
+
+/// The local time `seconds` after the epoch, as the C library works it out.
+#[cfg(unix)]
+fn local(seconds: i64) -> Option<WallTime> {
+    let time = seconds as libc::time_t;
+    // SAFETY: `tm` is plain data that `localtime_r` fills in, and both pointers are valid for
+    // the duration of the call.
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return None;
+        }
+        tm
+    };
+    Some(WallTime {
+        hour: tm.tm_hour as u32,
+        minute: tm.tm_min as u32,
+        first_half: true,
+        weekday: tm.tm_wday as u32,
+        day: tm.tm_mday as u32,
+        month: tm.tm_mon as u32,
+        year: i64::from(tm.tm_year) + 1900,
+    })
+}
+
+#[cfg(not(unix))]
+fn local(_: i64) -> Option<WallTime> {
+    None
+}
+
+/// The hour of the day it is, from 0 to 23, in local time where the platform says what that is.
+pub fn local_hour() -> u32 {
+    WallTime::now().hour
+}
+
+/// The UTC date `seconds` after the epoch, as in `2026-10-14`.
+pub fn utc_date(seconds: i64) -> String {
+    let time = utc(seconds);
+    format!("{:04}-{:02}-{:02}", time.year, time.month + 1, time.day)
+}
+
+/// The local time `seconds` after the epoch to the minute, as in `20261014T1530`, which sorts
+/// as the times do.
+pub fn local_stamp(seconds: i64) -> String {
+    let time = local(seconds).unwrap_or_else(|| utc(seconds));
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}",
+        time.year,
+        time.month + 1,
+        time.day,
+        time.hour,
+        time.minute
+    )
+}
+
+/// Seconds after the epoch of a UTC date and time, the month and day counting from 1.
+pub fn utc_seconds(year: i64, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+    // Days from a civil date, after Howard Hinnant's `days_from_civil`.
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = i64::from((month + 9) % 12);
+    let of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + of_year;
+    let days = era * 146_097 + of_era - 719_468;
+    days * 86_400 + i64::from(hour) * 3600 + i64::from(minute) * 60
+}
+
+/// The time in UTC `seconds` after the epoch.
+fn utc(seconds: i64) -> WallTime {
+    let days = seconds.div_euclid(86_400);
+    let of_day = seconds.rem_euclid(86_400);
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let of_era = shifted - era * 146_097;
+    let year_of_era = (of_era - of_era / 1460 + of_era / 36_524 - of_era / 146_096) / 365;
+    let of_year = of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * of_year + 2) / 153;
+    let day = of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12;
+    // Years start in March here, so January and February belong to the next.
+    let year = year_of_era + era * 400 + i64::from(month < 2);
+    WallTime {
+        hour: (of_day / 3600) as u32,
+        minute: (of_day / 60 % 60) as u32,
+        first_half: true,
+        // The epoch was a Thursday.
+        weekday: (days + 4).rem_euclid(7) as u32,
+        day: day as u32,
+        month: month as u32,
+        year,
+    }
+}
+
+/// Today's date, such as `14 OCT 2026`.
+pub fn today() -> String {
+    let now = WallTime::now();
+    format!(
+        "{} {} {}",
+        now.day,
+        MONTHS[now.month as usize % 12],
+        now.year
+    )
+}
+
+/// Spawns or despawns the clock as it is turned on and off, and sizes it for the date.
+fn show_clock(
+    mut commands: Commands,
+    config: Res<Config>,
+    backing: Option<Single<(Entity, &mut Sprite), With<ClockBacking>>>,
+) {
+    let height = if config.clock.date { 20. } else { 11. };
+    let size = Vec2::new(48., height);
+    match (config.clock.enabled, backing) {
+        (true, None) => {
+            commands
+                .spawn((
+                    ClockBacking,
+                    Sprite {
+                        color: BACKING_COLOR,
+                        custom_size: Some(size),
+                        anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    CanvasAnchor::TOP_LEFT.offset(2., -2.5),
+                    Transform::from_xyz(0., 0., 16.),
+                    PIXEL_PERFECT_LAYERS,
+                ))
+                .with_child((
+                    ClockFace,
+                    Text2d::default(),
+                    TextFont {
+                        font_size: 8.0,
+                        ..default()
+                    },
+                    TextColor(CLOCK_COLOR),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    Anchor::TopCenter,
+                    Transform::from_xyz(size.x / 2., -1., 0.1),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+        }
+        (true, Some(mut backing)) => {
+            if backing.1.custom_size != Some(size) {
+                backing.1.custom_size = Some(size);
+            }
+        }
+        (false, Some(backing)) => commands.entity(backing.0).despawn(),
+        (false, None) => {}
+    }
+}
+
+fn tell_time(config: Res<Config>, mut face: Single<&mut Text2d, With<ClockFace>>) {
+    let text = WallTime::now().text(&config.clock);
+    if face.0 != text {
+        face.0 = text;
+    }
+}
@@ -0,0 +1,884 @@
+//! User configuration, loaded from and saved to a RON file.
+//!
+//! The file lives at `$MDR_CONFIG` if set, and otherwise at `config.ron` in [`config_dir`]. A
+//! missing file means defaults; a broken one is reported as a [`Fault`] and replaced by defaults
+//! rather than stopping the app, the fault's Retry reading it again.
+//!
+//! The file is watched while the app runs, and edits made to it by hand are applied live.
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bevy::{
+    app::AppExit,
+    audio::{GlobalVolume, Volume},
+    prelude::*,
+    time::common_conditions::on_real_timer,
+    window::{MonitorSelection, PresentMode, PrimaryWindow, WindowFocused, WindowMode},
+    winit::{UpdateMode, WinitSettings},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    bins::DEFAULT_BIN_COUNT,
+    fault::{Fault, FaultAnswered, FaultKind, Faults},
+    grid::GridSize,
+    kiosk::Kiosk,
+    soundscape::SoundscapeLayer,
+};
+
+/// How often watched files are checked for changes.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let path = config_path();
+        let config = match Config::load(&path) {
+            Ok(Some(config)) => {
+                info!("Loaded config from {}", path.display());
+                config
+            }
+            Ok(None) => Config::default(),
+            Err(error) => {
+                let fault = unreadable(&path, error);
+                app.world_mut()
+                    .get_resource_or_init::<Faults>()
+                    .report(fault);
+                Config::default()
+            }
+        };
+        app.insert_resource(config)
+            .insert_resource(ConfigPath(path))
+            .add_systems(
+                Update,
+                (
+                    reload_config.run_if(on_real_timer(WATCH_INTERVAL)),
+                    retry_config.run_if(on_event::<FaultAnswered>),
+                    (
+                        apply_video.run_if(resource_changed::<Config>),
+                        apply_audio
+                            .run_if(resource_changed::<Config>.or(on_event::<WindowFocused>)),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_systems(Last, save_on_exit.run_if(on_event::<AppExit>));
+    }
+}
+
+/// Everything the player can configure.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    pub video: VideoConfig,
+    pub audio: AudioConfig,
+    pub input: InputConfig,
+    pub gameplay: GameplayConfig,
+    pub accessibility: AccessibilityConfig,
+    pub pomodoro: PomodoroConfig,
+    pub directory: DirectoryConfig,
+    pub clock: ClockConfig,
+    pub footer: FooterConfig,
+    pub ambient: AmbientConfig,
+    pub tray: TrayConfig,
+    pub mqtt: MqttConfig,
+    pub discord: DiscordConfig,
+    pub notifications: NotificationsConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct VideoConfig {
+    /// Shape of the canvas, which the grid and the screen chrome are laid out to fit.
+    pub canvas: CanvasPreset,
+    pub scale_mode: ScaleMode,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Whether the boot sequence plays before the main menu; dashboards usually turn it off.
+    pub boot_intro: bool,
+    /// Opacity of the film grain over the canvas, from 0 (off) to 1.
+    pub film_grain: f32,
+    /// How many times larger than its layout the canvas is rendered, for smoother text: 1 (off),
+    /// 2 or 4.
+    pub supersampling: u32,
+    /// How the numbers of the grid are drawn.
+    pub digits: DigitRenderer,
+    /// Minutes without input before the canvas dims against burn-in, or 0 for never.
+    pub idle_dim_minutes: u32,
+    /// Frames a second while the window is in the background, or 0 to keep the full rate.
+    pub unfocused_fps: u32,
+    /// Whether the window is see-through, to sit over other windows (toggled with F8).
+    pub ghost: bool,
+    /// Opacity of the window while it is see-through, from 0 to 1.
+    pub ghost_opacity: f32,
+    /// Whether the picture slowly wanders by a fraction of a pixel, like a CRT's.
+    pub crt_drift: bool,
+    /// Art drawn around the canvas.
+    pub bezel: BezelConfig,
+    /// The warm tint over the screen for late hours.
+    pub night_shift: NightShiftConfig,
+}
+
+impl Default for VideoConfig {
+    fn default() -> Self {
+        Self {
+            canvas: CanvasPreset::Wide,
+            scale_mode: ScaleMode::Integer,
+            fullscreen: false,
+            vsync: true,
+            boot_intro: true,
+            film_grain: 0.06,
+            supersampling: 1,
+            digits: DigitRenderer::Auto,
+            idle_dim_minutes: 10,
+            unfocused_fps: 10,
+            ghost: false,
+            ghost_opacity: 0.35,
+            crt_drift: false,
+            bezel: BezelConfig::default(),
+            night_shift: NightShiftConfig::default(),
+        }
+    }
+}
+
+/// Logical resolutions the canvas can have.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CanvasPreset {
+    /// 512×256, twice as wide as it is high.
+    #[default]
+    Wide,
+    /// 320×240, the 4:3 of a CRT.
+    Crt,
+    /// 320×256, 5:4.
+    FiveFour,
+    /// 608×256, about 21:9.
+    Ultrawide,
+    /// 256×512, upright, for monitors mounted on their side.
+    Portrait,
+}
+
+impl CanvasPreset {
+    /// Width and height of the canvas, in pixels.
+    pub fn size(self) -> UVec2 {
+        match self {
+            CanvasPreset::Wide => UVec2::new(512, 256),
+            CanvasPreset::Crt => UVec2::new(320, 240),
+            CanvasPreset::FiveFour => UVec2::new(320, 256),
+            CanvasPreset::Ultrawide => UVec2::new(608, 256),
+            CanvasPreset::Portrait => UVec2::new(256, 512),
+        }
+    }
+}
+
+/// An image drawn around the canvas at the resolution of the screen, like the frame of a CRT
+/// monitor or the housing of a Lumon terminal.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct BezelConfig {
+    /// Path of the image in the assets folder, or empty for none.
+    pub image: String,
+    /// How far the image reaches past each edge of the canvas, in canvas pixels: left, top,
+    /// right, then bottom. The canvas shows through where the image is see-through.
+    pub margins: [f32; 4],
+}
+
+impl BezelConfig {
+    /// Where the image is drawn around a canvas of `size` centered on the origin, or just the
+    /// canvas without one.
+    pub fn frame(&self, size: Vec2) -> Rect {
+        let canvas = Rect::from_center_size(Vec2::ZERO, size);
+        if self.image.is_empty() {
+            return canvas;
+        }
+        let [left, top, right, bottom] = self.margins.map(|margin| margin.max(0.));
+        Rect::from_corners(
+            canvas.min - Vec2::new(left, bottom),
+            canvas.max + Vec2::new(right, top),
+        )
+    }
+}
+
+/// A warm tint over everything on the screen, so that a display left running overnight doesn't
+/// light the room up blue.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NightShiftConfig {
+    pub mode: NightShift,
+    /// Color temperature of the tint, in kelvin; 6500 is no tint at all, and lower is warmer.
+    pub temperature: u32,
+    /// Local hours, from 0 to 23, at which a scheduled tint comes on and goes off again.
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl Default for NightShiftConfig {
+    fn default() -> Self {
+        Self {
+            mode: NightShift::Off,
+            temperature: 3400,
+            start_hour: 21,
+            end_hour: 7,
+        }
+    }
+}
+
+impl NightShiftConfig {
+    /// Whether the tint is on at `hour`, local time.
+    pub fn active(&self, hour: u32) -> bool {
+        match self.mode {
+            NightShift::Off => false,
+            NightShift::On => true,
+            // Across midnight when it ends earlier in the day than it starts.
+            NightShift::Scheduled if self.start_hour <= self.end_hour => {
+                (self.start_hour..self.end_hour).contains(&hour)
+            }
+            NightShift::Scheduled => hour >= self.start_hour || hour < self.end_hour,
+        }
+    }
+}
+
+/// When the night shift tint is on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NightShift {
+    #[default]
+    Off,
+    On,
+    /// Between the start and end hours only.
+    Scheduled,
+}
+
+/// How the numbers of the grid are drawn.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DigitRenderer {
+    /// As a field on grids too large for text, and as text otherwise.
+    #[default]
+    Auto,
+    /// All at once in a pixel font, however small the grid.
+    Field,
+    /// Each as text of its own, however large the grid.
+    Text,
+}
+
+/// How the canvas is scaled up to fill the window.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Whole multiples only, so every canvas pixel covers the same number of screen pixels.
+    #[default]
+    Integer,
+    /// As large as fits, at the cost of uneven pixels.
+    Fractional,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Linear volume of all audio, from 0 to 1.
+    pub master_volume: f32,
+    /// Volume of the sound effects, from 0 to 1, under the master volume.
+    pub effects_volume: f32,
+    /// Volume of the soundscape, from 0 (off) to 1, under the master volume.
+    pub soundscape_volume: f32,
+    pub muted: bool,
+    /// Whether audio is muted while the window is in the background.
+    pub mute_unfocused: bool,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            master_volume: 0.8,
+            effects_volume: 1.,
+            soundscape_volume: 0.,
+            muted: false,
+            mute_unfocused: false,
+        }
+    }
+}
+
+impl AudioConfig {
+    /// The volume audio should actually play at, with the window focused or not.
+    pub fn effective_volume(&self, focused: bool) -> f32 {
+        if self.muted || (self.mute_unfocused && !focused) {
+            0.
+        } else {
+            self.master_volume
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct InputConfig {
+    /// Whether the number keys refine the selection into the matching bin.
+    pub bin_hotkeys: bool,
+    /// Whether right clicking drops the selection.
+    pub right_click_clears: bool,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            bin_hotkeys: true,
+            right_click_clears: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GameplayConfig {
+    pub difficulty: Difficulty,
+    /// Whether scary numbers near the cursor start pulsing after a long search.
+    pub hints: bool,
+    /// Refinements between wellness sessions, or 0 for none.
+    pub wellness_interval: u32,
+    /// Minutes of refining without a break before a wellness check is suggested, or 0 for never.
+    pub break_reminder_minutes: u32,
+    /// Minutes a snoozed wellness check waits before it is suggested again.
+    pub snooze_minutes: u32,
+    /// Number of bins new files are created with.
+    pub bins: usize,
+    /// Whether the tutorial runs as the next file is opened, until it is finished or skipped.
+    pub tutorial: bool,
+    /// How many columns and rows the grid has.
+    pub grid: GridSize,
+    /// Name of the [`ScaryDetector`](crate::grid::detect::ScaryDetector) deciding which refinements
+    /// catch a cluster, or `None` for the difficulty's own.
+    pub detector: Option<String>,
+    /// Most cells a selection may hold and still be refined, or 0 for no limit.
+    pub max_selection: u32,
+    /// How many times as fast as real time the grid and its animations run, from 0.5 to 3.
+    pub time_scale: f32,
+    /// Tint the cells of the grid by how much has gone on in them.
+    pub heatmap: bool,
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::Normal,
+            hints: true,
+            wellness_interval: 0,
+            break_reminder_minutes: 0,
+            snooze_minutes: 10,
+            bins: DEFAULT_BIN_COUNT,
+            tutorial: true,
+            grid: GridSize::default(),
+            detector: None,
+            max_selection: 0,
+            time_scale: 1.,
+            heatmap: false,
+        }
+    }
+}
+
+/// How much help the refiner gets.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Tone down motion that is purely decorative.
+    pub reduced_motion: bool,
+    /// Draw gridlines and coordinate rulers over the grid.
+    pub gridlines: bool,
+    /// After a moment over a number, show its coordinates, and its temper on easier difficulties.
+    pub tooltips: bool,
+    /// Show the high-contrast theme in place of the one loaded.
+    pub high_contrast: bool,
+    /// Write what changes on screen to standard output, for assistive tools.
+    pub announcements: bool,
+}
+
+/// The work timer, which fills the bins over a work session instead of as numbers are refined.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub enabled: bool,
+    /// Length of a work session, in minutes.
+    pub work_minutes: u32,
+    /// Length of the break after a work session, in minutes, or 0 for none.
+    pub break_minutes: u32,
+    /// Whether the next work session starts as the break ends, rather than with the next
+    /// refinement.
+    pub auto_continue: bool,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            work_minutes: 25,
+            break_minutes: 5,
+            auto_continue: true,
+        }
+    }
+}
+
+/// The clock in the corner of the canvas.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ClockConfig {
+    pub enabled: bool,
+    /// Whether hours run to 23 rather than to 12 with AM and PM.
+    pub twenty_four_hour: bool,
+    /// Whether the colon between hours and minutes blinks every second.
+    pub blinking_colon: bool,
+    /// Whether the date is shown under the time.
+    pub date: bool,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            twenty_four_hour: true,
+            blinking_colon: true,
+            date: true,
+        }
+    }
+}
+
+/// The widgets along the bottom of the canvas: the weather on the left and the next event of a
+/// calendar on the right.
+///
+/// Feeds are fetched without TLS, so `https://` URLs cannot be fetched and a config naming one
+/// is refused as it loads. Put a local proxy in front of such a feed, or have another program keep
+/// a copy on disk and give its `file://` URL.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct FooterConfig {
+    /// Plain `http://` or `file://` URL answering with the weather as a line of text, or empty
+    /// for none.
+    pub weather: String,
+    /// Plain `http://` or `file://` URL of an iCalendar (`.ics`) document, or empty for none.
+    pub calendar: String,
+    /// Minutes between fetches of the weather and of the calendar.
+    pub weather_minutes: u32,
+    pub calendar_minutes: u32,
+}
+
+impl Default for FooterConfig {
+    fn default() -> Self {
+        Self {
+            weather: String::new(),
+            calendar: String::new(),
+            weather_minutes: 15,
+            calendar_minutes: 5,
+        }
+    }
+}
+
+impl FooterConfig {
+    /// Why the feeds cannot be fetched as given, if they cannot.
+    fn check(&self) -> Result<(), String> {
+        for (name, url) in [("weather", &self.weather), ("calendar", &self.calendar)] {
+            if url.is_empty() || url.starts_with("http://") || url.starts_with("file://") {
+                continue;
+            }
+            return Err(if url.starts_with("https://") {
+                format!(
+                    "footer {name} feed {url} uses https://, which cannot be fetched; \
+                     only plain http:// and file:// URLs can"
+                )
+            } else {
+                format!("footer {name} feed {url} is not a plain http:// or file:// URL")
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The ambient mode, where the sound of the room moves the grid while nobody is refining.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AmbientConfig {
+    pub enabled: bool,
+    /// Command line of a program writing what it hears to its output, as raw signed 16-bit
+    /// little-endian mono samples. Recording a monitor source instead of a microphone, as with
+    /// `parec --device=<sink>.monitor --format=s16le --channels=1`, follows what the computer
+    /// plays rather than the room.
+    pub command: String,
+    /// Sample rate the command records at.
+    pub sample_rate: u32,
+    /// How much louder than it is the sound is taken to be.
+    pub gain: f32,
+    /// Seconds without input before the grid starts following the sound.
+    pub idle_seconds: f32,
+}
+
+impl Default for AmbientConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: "arecord -q -t raw -f S16_LE -c 1 -r 16000".to_string(),
+            sample_rate: 16_000,
+            gain: 4.,
+            idle_seconds: 60.,
+        }
+    }
+}
+
+/// The tray icon, and the program that shows it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TrayConfig {
+    pub enabled: bool,
+    /// Command line of a program that shows a tray icon and speaks the protocol of yad's
+    /// `--notification --listen`: it takes `menu:` and `tooltip:` lines on its input, and runs
+    /// the actions of the menu, which write what was picked to its output.
+    pub command: String,
+}
+
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: "yad --notification --listen --image=utilities-terminal".to_string(),
+        }
+    }
+}
+
+/// Topics of an MQTT broker that drive the bins and the header, in builds with the `mqtt`
+/// feature.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Address of the broker, as `host:port`, or empty to stay off MQTT.
+    pub broker: String,
+    pub client_id: String,
+    /// What each topic drives. Topics may use the `+` and `#` wildcards.
+    pub topics: Vec<MqttBinding>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            broker: String::new(),
+            client_id: "mdr".to_string(),
+            topics: Vec::new(),
+        }
+    }
+}
+
+/// A topic, and what its messages drive.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MqttBinding {
+    pub topic: String,
+    pub target: MqttTarget,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MqttTarget {
+    /// How full the bin with this index is, from messages like `0.4` or `40%`.
+    Bin(usize),
+    /// A message shown in the header in place of the file name, until an empty one clears it.
+    Header,
+}
+
+/// The Discord application the refiner's status is shown under, in builds with the `discord`
+/// feature.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// Id of the application, or empty to stay off Discord.
+    pub application_id: String,
+}
+
+/// Which quotas send a desktop notification, in builds with the `notifications` feature.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// When a bin reaches 100%.
+    pub bin_full: bool,
+    /// When the file is complete.
+    pub file_complete: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            bin_full: true,
+            file_complete: true,
+        }
+    }
+}
+
+/// What refining files of a real directory does to them (see `--directory`).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DirectoryConfig {
+    /// What happens to the files refined into each bin, in order. The directory gets a bin per
+    /// rule, or the usual number of bins that keep their files if there are none.
+    pub rules: Vec<FileRule>,
+}
+
+/// What happens to a real file refined into a bin.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum FileRule {
+    /// Leave the file as it is.
+    #[default]
+    Keep,
+    /// Move the file into a directory, relative to the refined one unless absolute.
+    Move(PathBuf),
+    /// Add a tag to the file's name, as in `report [tag].pdf`.
+    Tag(String),
+    /// Move the file into an `archive` directory inside the refined one.
+    Archive,
+}
+
+/// Reads a RON file at `path`, or `None` if there is no file there.
+pub fn load_ron<T: DeserializeOwned>(path: &Path) -> io::Result<Option<T>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Writes `contents` to `path` so that it holds either what it held before or all of the new
+/// contents, even if power is lost midway: they are written and flushed to a file alongside,
+/// which then takes the place of the old one.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    let mut file = fs::File::create(&temporary)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)?;
+    // The rename only lasts once the directory is flushed too, which only Unix can do.
+    #[cfg(unix)]
+    fs::File::open(parent)?.sync_all()?;
+    Ok(())
+}
+
+/// A file checked now and then for changes made outside the app, such as in a text editor.
+pub struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file has been modified, created or removed since this was last asked.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+impl Config {
+    /// Reads the config at `path`, or `None` if there is no file there.
+    fn load(path: &Path) -> io::Result<Option<Self>> {
+        let config: Option<Self> = load_ron(path)?;
+        if let Some(config) = &config {
+            config
+                .footer
+                .check()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        write_atomic(path, &contents)
+    }
+}
+
+/// Where the [`Config`] is persisted.
+#[derive(Resource, Clone, Debug)]
+pub struct ConfigPath(pub PathBuf);
+
+/// The directory the app keeps its files in, falling back to the working directory.
+pub fn config_dir() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map_or_else(|| PathBuf::from("."), |dir| dir.join("mdr"))
+}
+
+fn config_path() -> PathBuf {
+    match env::var_os("MDR_CONFIG") {
+        Some(path) => path.into(),
+        None => config_dir().join("config.ron"),
+    }
+}
+
+/// Writes the config to its file, reporting rather than failing, and says whether it did.
+pub fn save_config(config: &Config, path: &ConfigPath) -> bool {
+    match config.save(&path.0) {
+        Ok(()) => {
+            info!("Saved config to {}", path.0.display());
+            true
+        }
+        Err(error) => {
+            error!("Could not save config to {}: {error}", path.0.display());
+            false
+        }
+    }
+}
+
+/// Applies edits made to the config file while the app runs.
+///
+/// Saving the config from the app also touches the file, but reads back what is already in
+/// effect and so changes nothing.
+fn reload_config(
+    path: Res<ConfigPath>,
+    mut watched: Local<Option<WatchedFile>>,
+    mut config: ResMut<Config>,
+    mut faults: ResMut<Faults>,
+) {
+    let watched = watched.get_or_insert_with(|| WatchedFile::new(path.0.clone()));
+    if !watched.changed() {
+        return;
+    }
+    let loaded = Config::load(&path.0);
+    // A file fixed in place takes its fault down.
+    if loaded.is_ok() && faults.contains(&FaultKind::Config) {
+        faults.resolve(&FaultKind::Config);
+    }
+    match loaded {
+        Ok(Some(loaded)) if loaded != *config => {
+            info!("Reloaded config from {}", path.0.display());
+            *config = loaded;
+        }
+        // A removed file keeps the config as it is, to be saved again on exit.
+        Ok(_) => {}
+        Err(error) => faults.report(unreadable(&path.0, error)),
+    }
+}
+
+/// Reads the config again when its fault is retried.
+fn retry_config(
+    mut answered: EventReader<FaultAnswered>,
+    path: Res<ConfigPath>,
+    mut config: ResMut<Config>,
+    mut faults: ResMut<Faults>,
+) {
+    let retried = answered
+        .read()
+        .any(|answer| answer.kind == FaultKind::Config && answer.retry);
+    if !retried {
+        return;
+    }
+    match Config::load(&path.0) {
+        Ok(loaded) => {
+            info!("Reloaded config from {}", path.0.display());
+            faults.resolve(&FaultKind::Config);
+            config.set_if_neq(loaded.unwrap_or_default());
+        }
+        Err(error) => faults.report(unreadable(&path.0, error)),
+    }
+}
+
+/// The fault of a config file that cannot be read.
+fn unreadable(path: &Path, error: io::Error) -> Fault {
+    Fault::new(
+        FaultKind::Config,
+        "CONFIG UNREADABLE",
+        format!("{}: {error}", path.display()),
+    )
+}
+
+fn apply_video(
+    config: Res<Config>,
+    kiosk: Option<Res<Kiosk>>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    mut winit: ResMut<WinitSettings>,
+) {
+    let mode = if config.video.fullscreen || kiosk.is_some() {
+        WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+    } else {
+        WindowMode::Windowed
+    };
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let present_mode = if config.video.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+    // In the background, only as often as configured, or sooner for events such as a redraw.
+    winit.unfocused_mode = match config.video.unfocused_fps {
+        0 => UpdateMode::Continuous,
+        fps => UpdateMode::reactive_low_power(Duration::from_secs_f32(1. / fps as f32)),
+    };
+}
+
+/// Sets the global volume from the config and the window's focus. Audio already playing only took
+/// the global volume as it started, so each sink is set again from its own volume, apart from the
+/// soundscape's layers, which mix their own.
+fn apply_audio(
+    config: Res<Config>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut volume: ResMut<GlobalVolume>,
+    mut sinks: Query<(&PlaybackSettings, &mut AudioSink), Without<SoundscapeLayer>>,
+) {
+    let effective = config.audio.effective_volume(window.focused);
+    volume.volume = Volume::Linear(effective);
+    for (settings, mut sink) in &mut sinks {
+        sink.set_volume(Volume::Linear(effective * settings.volume.to_linear()));
+    }
+}
+
+fn save_on_exit(config: Res<Config>, path: Res<ConfigPath>) {
+    save_config(&config, &path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footer_feeds_over_https_are_refused() {
+        let mut footer = FooterConfig::default();
+        assert!(footer.check().is_ok());
+        footer.weather = "http://localhost:8080/weather".to_string();
+        footer.calendar = "file:///home/refiner/calendar.ics".to_string();
+        assert!(footer.check().is_ok());
+        footer.calendar = "https://example.com/calendar.ics".to_string();
+        assert!(footer.check().unwrap_err().contains("https://"));
+    }
+}
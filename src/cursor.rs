@@ -0,0 +1,204 @@
+//! A chunky pixel-art cursor drawn on the canvas in place of the OS cursor.
+//!
+//! The cursor snaps to canvas pixels and changes shape with what it is over: a crosshair over
+//! numbers, a grabbing hand over bins, and an arrow anywhere else. The OS cursor is only shown
+//! over the letterboxing around the canvas, where there is nothing to draw ours on.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    sprite::Anchor,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    bins::BinLayout,
+    canvas::{CanvasCursor, PIXEL_PERFECT_LAYERS},
+    idle::Idle,
+    kiosk::{Kiosk, CURSOR_TIMEOUT},
+    state::AppState,
+};
+
+/// Arrow pointer, with its hotspot at the top-left pixel.
+const ARROW: &[&str] = &[
+    "X.......", "XX......", "X#X.....", "X##X....", "X###X...", "X####X..", "X#####X.", "X###XXXX",
+    "X#X#X...", "XX.X#X..", "....XX..",
+];
+
+/// Crosshair, with its hotspot at the centre pixel.
+const CROSSHAIR: &[&str] = &[
+    "...X...", "...#...", "...#...", "XX#.#XX", "...#...", "...#...", "...X...",
+];
+
+/// Grabbing hand, with its hotspot near the top-left.
+const HAND: &[&str] = &[
+    "...XX.XX.XX.",
+    "..X##X##X##X",
+    "..X########X",
+    "XXX########X",
+    "X#X########X",
+    "X##########X",
+    ".X########X.",
+    "..X######X..",
+    "...XXXXXX...",
+];
+
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_cursor)
+            .add_systems(Last, (follow_cursor, toggle_os_cursor).chain());
+    }
+}
+
+/// What the cursor looks like.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CursorShape {
+    Arrow,
+    Crosshair,
+    Hand,
+}
+
+impl CursorShape {
+    fn pattern(self) -> &'static [&'static str] {
+        match self {
+            CursorShape::Arrow => ARROW,
+            CursorShape::Crosshair => CROSSHAIR,
+            CursorShape::Hand => HAND,
+        }
+    }
+
+    /// Which part of the image sits on the pointed-at pixel.
+    fn anchor(self) -> Anchor {
+        match self {
+            CursorShape::Arrow => Anchor::TopLeft,
+            CursorShape::Crosshair => Anchor::Center,
+            CursorShape::Hand => Anchor::Custom(Vec2::new(-0.25, 0.4)),
+        }
+    }
+}
+
+/// The cursor sprite, and the image of each of its shapes.
+#[derive(Component)]
+struct RetroCursor {
+    shape: CursorShape,
+    arrow: Handle<Image>,
+    crosshair: Handle<Image>,
+    hand: Handle<Image>,
+}
+
+impl RetroCursor {
+    fn image(&self, shape: CursorShape) -> Handle<Image> {
+        match shape {
+            CursorShape::Arrow => self.arrow.clone(),
+            CursorShape::Crosshair => self.crosshair.clone(),
+            CursorShape::Hand => self.hand.clone(),
+        }
+    }
+}
+
+/// Turns a pattern into an image: `#` is white, `X` is the dark outline, anything else is clear.
+fn cursor_image(pattern: &[&str]) -> Image {
+    let width = pattern.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+    let height = pattern.len() as u32;
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for row in pattern {
+        for x in 0..width as usize {
+            data.extend_from_slice(match row.as_bytes().get(x) {
+                Some(b'#') => &[255, 255, 255, 255],
+                Some(b'X') => &[0, 20, 26, 255],
+                _ => &[0, 0, 0, 0],
+            });
+        }
+    }
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn setup_cursor(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let cursor = RetroCursor {
+        shape: CursorShape::Arrow,
+        arrow: images.add(cursor_image(CursorShape::Arrow.pattern())),
+        crosshair: images.add(cursor_image(CursorShape::Crosshair.pattern())),
+        hand: images.add(cursor_image(CursorShape::Hand.pattern())),
+    };
+    commands.spawn((
+        Sprite::from_image(cursor.image(CursorShape::Arrow)),
+        CursorShape::Arrow.anchor(),
+        Transform::from_xyz(0., 0., 100.),
+        Visibility::Hidden,
+        PIXEL_PERFECT_LAYERS,
+        cursor,
+    ));
+}
+
+/// Moves the cursor to the canvas pixel under the OS cursor and picks its shape.
+fn follow_cursor(
+    pointer: CanvasCursor,
+    state: Res<State<AppState>>,
+    layout: Res<BinLayout>,
+    (time, idle, kiosk): (Res<Time<Real>>, Res<Idle>, Option<Res<Kiosk>>),
+    mut cursor: Single<(
+        &mut RetroCursor,
+        &mut Sprite,
+        &mut Anchor,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let (retro, sprite, anchor, transform, visibility) = &mut *cursor;
+    // A kiosk's cursor keeps out of sight while nobody is using it.
+    let asleep = kiosk.is_some() && idle.seconds(&time) >= CURSOR_TIMEOUT;
+    let Some(position) = pointer.world().filter(|_| !asleep) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+
+    let shape = if *state.get() != AppState::Refining {
+        CursorShape::Arrow
+    } else if layout.bin_at(position).is_some() {
+        CursorShape::Hand
+    } else if pointer.cell().is_some() {
+        CursorShape::Crosshair
+    } else {
+        CursorShape::Arrow
+    };
+    if retro.shape != shape {
+        retro.shape = shape;
+        sprite.image = retro.image(shape);
+        **anchor = shape.anchor();
+    }
+
+    // Snap to the pixel grid; odd-sized centred shapes sit on pixel centres.
+    let snapped = position.floor();
+    let offset = match shape.anchor() {
+        Anchor::Center => Vec2::splat(0.5),
+        _ => Vec2::new(0., 1.),
+    };
+    // Leave the sprite alone while the cursor rests, so that an idle screen changes nothing.
+    let translation = (snapped + offset).extend(transform.translation.z);
+    if transform.translation != translation {
+        transform.translation = translation;
+    }
+}
+
+/// Shows the OS cursor only where ours can't be drawn, and never on a kiosk.
+fn toggle_os_cursor(
+    cursor: Single<&Visibility, (With<RetroCursor>, Changed<Visibility>)>,
+    kiosk: Option<Res<Kiosk>>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    window.cursor_options.visible = **cursor == Visibility::Hidden && kiosk.is_none();
+}
@@ -0,0 +1,279 @@
+//! The daily challenge: one grid a day, the same for every refiner.
+//!
+//! Started with `--daily`. The grid is a file named for the UTC date, and like every file its
+//! seed comes from its name, so refiners on any machine get the same grid on the same day
+//! wherever they are. The date is only as good as the clock it is read from, so with
+//! `--time-server [HOST]` the clock is checked against a time server over SNTP once the grid is
+//! up, and a toast warns of a clock that is off, or worse, on another day. In a shared session
+//! the host tells those who join which day its grid is, and they are warned if theirs is not.
+
+use std::{
+    io,
+    net::UdpSocket,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, IoTaskPool, Task},
+};
+
+use crate::{
+    bins::DEFAULT_BIN_COUNT,
+    clock::utc_date,
+    files::{name_seed, ActiveFile},
+    toast::Toast,
+};
+
+/// Time server asked when `--time-server` is given without one.
+const DEFAULT_TIME_SERVER: &str = "pool.ntp.org";
+
+/// Port time servers answer on.
+const NTP_PORT: u16 = 123;
+
+/// How long the time server has to answer.
+const NTP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Seconds from the start of 1900, where NTP counts from, to the Unix epoch.
+const NTP_EPOCH: f64 = 2_208_988_800.;
+
+/// Seconds the clock may be off before the refiner is warned.
+const MAX_SKEW: f64 = 30.;
+
+pub struct DailyPlugin {
+    pub enabled: bool,
+    /// The time server to check the clock against, if any.
+    pub time_server: Option<String>,
+}
+
+impl DailyPlugin {
+    /// Reads `--daily` and `--time-server [HOST]` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter().peekable();
+        let mut plugin = Self {
+            enabled: false,
+            time_server: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--daily" => plugin.enabled = true,
+                "--time-server" => {
+                    let host = args
+                        .next_if(|next| !next.starts_with("--"))
+                        .unwrap_or_else(|| DEFAULT_TIME_SERVER.to_string());
+                    plugin.time_server = Some(host);
+                }
+                _ => {}
+            }
+        }
+        plugin
+    }
+}
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        if !self.enabled {
+            return;
+        }
+        let picked = unix_now();
+        let daily = DailyChallenge::at(picked);
+        info!("Refining the daily grid of {}", daily.date);
+        // The day stands in for a file of its own, which starts out empty.
+        let name = format!("Daily {}", daily.date);
+        app.insert_resource(ActiveFile {
+            seed: name_seed(&name),
+            name,
+            progress: vec![0.; DEFAULT_BIN_COUNT],
+            ..default()
+        })
+        .insert_resource(daily);
+        if let Some(server) = &self.time_server {
+            let server = server.clone();
+            let task = IoTaskPool::get().spawn(async move {
+                let offset = clock_offset(&server);
+                (server, offset)
+            });
+            app.insert_resource(ClockCheck { picked, task })
+                .add_systems(Update, check_clock.run_if(resource_exists::<ClockCheck>));
+        }
+    }
+}
+
+/// The day whose grid is being refined.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct DailyChallenge {
+    /// Days since the Unix epoch, in UTC.
+    pub day: i64,
+    /// The day as a date, as in `2026-10-14`.
+    pub date: String,
+}
+
+impl DailyChallenge {
+    fn at(seconds: f64) -> Self {
+        let seconds = seconds.floor() as i64;
+        Self {
+            day: seconds.div_euclid(86_400),
+            date: utc_date(seconds),
+        }
+    }
+}
+
+/// The clock being checked against a time server.
+#[derive(Resource)]
+struct ClockCheck {
+    /// When the grid was picked, by the clock, in seconds since the epoch.
+    picked: f64,
+    /// Answers with the server asked, and how many seconds the clock is behind it.
+    task: Task<(String, io::Result<f64>)>,
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Asks `server` the time with a single SNTP request, and returns how many seconds the local
+/// clock is behind it, allowing for the time the answer took.
+fn clock_offset(server: &str) -> io::Result<f64> {
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{server}:{NTP_PORT}")
+    };
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(NTP_TIMEOUT))?;
+    socket.connect(address)?;
+    // No leap second warning, version 3, from a client.
+    let mut request = [0; 48];
+    request[0] = 0x1b;
+    let sent = unix_now();
+    socket.send(&request)?;
+    let mut reply = [0; 48];
+    let read = socket.recv(&mut reply)?;
+    let received = unix_now();
+    let transmitted = transmit_time(&reply[..read])?;
+    Ok(transmitted - (sent + received) / 2.)
+}
+
+/// When the server sent `reply`, in seconds since the Unix epoch.
+fn transmit_time(reply: &[u8]) -> io::Result<f64> {
+    // Only a server's answer, with a stratum, tells the time; stratum 0 is a refusal.
+    if reply.len() < 48 || reply[0] & 0x7 != 4 || reply[1] == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an answer from a time server",
+        ));
+    }
+    let seconds = u32::from_be_bytes([reply[40], reply[41], reply[42], reply[43]]);
+    let fraction = u32::from_be_bytes([reply[44], reply[45], reply[46], reply[47]]);
+    // NTP seconds wrap in 2036; small ones are from after that.
+    let era = if seconds < 0x8000_0000 {
+        4_294_967_296.
+    } else {
+        0.
+    };
+    Ok(f64::from(seconds) + era - NTP_EPOCH + f64::from(fraction) / 4_294_967_296.)
+}
+
+/// Warns of a clock that is off once the time server has answered.
+fn check_clock(
+    mut commands: Commands,
+    mut check: ResMut<ClockCheck>,
+    daily: Res<DailyChallenge>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some((server, offset)) = block_on(future::poll_once(&mut check.task)) else {
+        return;
+    };
+    commands.remove_resource::<ClockCheck>();
+    let offset = match offset {
+        Ok(offset) => offset,
+        Err(error) => {
+            warn!("Could not check the clock against {server}: {error}");
+            toasts.write(Toast::warning("Could not check clock"));
+            return;
+        }
+    };
+    // Judged at the moment the grid was picked rather than now, which may be past midnight.
+    let actual = DailyChallenge::at(check.picked + offset);
+    if actual.day != daily.day {
+        error!(
+            "The clock is {offset:.1}s off {server}: the daily grid is {}'s, but it was {}",
+            daily.date, actual.date
+        );
+        toasts.write(Toast::error("Clock is on the wrong day"));
+    } else if offset.abs() > MAX_SKEW {
+        warn!("The clock is {offset:.1}s off {server}");
+        let direction = if offset > 0. { "slow" } else { "fast" };
+        toasts.write(Toast::warning(format!(
+            "Clock is {:.0}s {direction}",
+            offset.abs()
+        )));
+    } else {
+        info!(
+            "The clock agrees with {server} to within {:.2}s",
+            offset.abs()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server's reply, at stratum 2, sent `seconds` and `fraction` after the start of 1900.
+    fn reply(seconds: u32, fraction: u32) -> [u8; 48] {
+        let mut reply = [0; 48];
+        // Version 3, from a server.
+        reply[0] = 0x1c;
+        reply[1] = 2;
+        reply[40..44].copy_from_slice(&seconds.to_be_bytes());
+        reply[44..48].copy_from_slice(&fraction.to_be_bytes());
+        reply
+    }
+
+    #[test]
+    fn reads_when_the_server_answered() {
+        let seconds = (NTP_EPOCH as u32) + 1_790_000_000;
+        let time = transmit_time(&reply(seconds, 0x8000_0000)).unwrap();
+        assert_eq!(time, 1_790_000_000.5);
+        assert_eq!(DailyChallenge::at(time).date, "2026-09-21");
+    }
+
+    #[test]
+    fn reads_answers_from_after_ntp_wraps() {
+        // The first second of 2036's era is 2^32 seconds after 1900.
+        let time = transmit_time(&reply(0, 0)).unwrap();
+        assert_eq!(time, 4_294_967_296. - NTP_EPOCH);
+        assert_eq!(DailyChallenge::at(time).date, "2036-02-07");
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_server_answer() {
+        let answer = reply(NTP_EPOCH as u32, 0);
+        assert!(transmit_time(&answer).is_ok());
+        assert!(transmit_time(&answer[..47]).is_err());
+        let mut from_client = answer;
+        from_client[0] = 0x1b;
+        assert!(transmit_time(&from_client).is_err());
+        // Stratum 0, a kiss of death.
+        let mut refusal = answer;
+        refusal[1] = 0;
+        assert!(transmit_time(&refusal).is_err());
+    }
+
+    #[test]
+    fn reads_the_time_server() {
+        let args = |args: &[&str]| DailyPlugin::from_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(args(&["--daily"]).time_server, None);
+        assert_eq!(
+            args(&["--time-server", "--daily"]).time_server.as_deref(),
+            Some(DEFAULT_TIME_SERVER)
+        );
+        let plugin = args(&["--time-server", "time.example:1123"]);
+        assert!(!plugin.enabled);
+        assert_eq!(plugin.time_server.as_deref(), Some("time.example:1123"));
+    }
+}
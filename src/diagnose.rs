@@ -0,0 +1,416 @@
+//! Self-test: started with `--diagnose`, for finding settings that suit unusual hardware, such as
+//! a Raspberry Pi, an old integrated GPU or a browser.
+//!
+//! Straight after loading, the canvas is put through its paces with vsync off. The bottom layer
+//! of the canvas is read back from the GPU, to make sure that rendering to a texture works at
+//! all; the backdrop camera clears it to the theme's background, so every pixel must come back
+//! opaque. Then the frame rate is measured on grids of a few sizes in turn, resized without
+//! touching the settings, so that quitting halfway saves none of them. The report names the
+//! adapter and backend the renderer picked, tells how each test went and suggests the largest
+//! grid that kept up; it is printed to standard output and shown on the canvas until any key or
+//! click takes it down. The grid goes back to the size it was, and vsync back to what the config
+//! says.
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        gpu_readback::{Readback, ReadbackComplete},
+        renderer::RenderAdapterInfo,
+    },
+    window::{PresentMode, PrimaryWindow},
+};
+
+use crate::{
+    canvas::{BackdropCamera, CanvasAnchor, CanvasFill, CanvasSize, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    grid::{GridSize, ResizeGrid},
+    state::AppState,
+};
+
+/// Grids the frame rate is measured on, from small to large.
+const GRID_SIZES: [GridSize; 3] = [
+    GridSize {
+        columns: 25,
+        rows: 25,
+    },
+    GridSize {
+        columns: 50,
+        rows: 50,
+    },
+    GridSize {
+        columns: 100,
+        rows: 100,
+    },
+];
+
+/// Real seconds given to every test to settle before it starts, such as for a resized grid's
+/// numbers to be spawned.
+const SETTLE_TIME: f32 = 1.;
+
+/// Real seconds the frame rate is measured over on each grid.
+const MEASURE_TIME: f32 = 3.;
+
+/// Real seconds the GPU has to read the canvas back before the test fails.
+const READBACK_TIMEOUT: f32 = 5.;
+
+/// Frame rate a grid has to keep up for it to be suggested.
+const TARGET_FPS: f32 = 55.;
+
+const REPORT_COLOR: Color = Color::srgb(0.0, 0.9, 1.0);
+const FAILED_COLOR: Color = Color::srgb(1.0, 0.45, 0.45);
+
+pub struct DiagnosePlugin {
+    pub enabled: bool,
+}
+
+impl DiagnosePlugin {
+    /// Reads `--diagnose` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            enabled: args.into_iter().any(|arg| arg == "--diagnose"),
+        }
+    }
+}
+
+impl Plugin for DiagnosePlugin {
+    fn build(&self, app: &mut App) {
+        if !self.enabled {
+            return;
+        }
+        app.add_systems(OnEnter(AppState::Refining), start_diagnosis)
+            .add_systems(
+                Update,
+                (
+                    run_diagnosis.run_if(resource_exists::<Diagnosis>),
+                    dismiss_report.run_if(resource_exists::<DiagnosisReport>),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                keep_vsync_off.run_if(resource_exists::<Diagnosis>),
+            );
+    }
+}
+
+/// The test under way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    /// Reading the bottom layer of the canvas back, once it has been drawn.
+    Readback,
+    /// Measuring the frame rate on the grid of [`GRID_SIZES`] with this index.
+    Measure(usize),
+}
+
+/// The self-test under way, present until it has finished.
+#[derive(Resource)]
+struct Diagnosis {
+    stage: Stage,
+    /// Real seconds since the stage started.
+    elapsed: f32,
+    /// Frames drawn since the stage's measurement started.
+    frames: u32,
+    /// Whether the canvas was asked to be read back.
+    requested: bool,
+    /// What reading the canvas back came to, once it has.
+    readback: Option<Result<(), String>>,
+    /// Frames a second on each grid measured so far.
+    rates: Vec<f32>,
+    /// The size the grid was before the test, to go back to.
+    original: GridSize,
+}
+
+/// Marks what makes up the self-test's status line and report.
+#[derive(Component)]
+struct DiagnosisScreen;
+
+/// Marks the status line shown while the test runs.
+#[derive(Component)]
+struct DiagnosisStatus;
+
+/// Present while the report is on screen.
+#[derive(Resource)]
+struct DiagnosisReport;
+
+fn start_diagnosis(mut commands: Commands, size: Res<GridSize>, mut started: Local<bool>) {
+    if *started {
+        return;
+    }
+    *started = true;
+    info!("Running the self-test");
+    commands.insert_resource(Diagnosis {
+        stage: Stage::Readback,
+        elapsed: 0.,
+        frames: 0,
+        requested: false,
+        readback: None,
+        rates: Vec::new(),
+        original: *size,
+    });
+    commands.spawn((
+        DiagnosisScreen,
+        DiagnosisStatus,
+        Text2d::new("SELF-TEST"),
+        TextFont {
+            font_size: 8.0,
+            ..default()
+        },
+        TextColor(REPORT_COLOR),
+        CanvasAnchor::TOP.offset(0., -28.),
+        Transform::from_xyz(0., 0., 45.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+}
+
+/// Moves the test on as each stage finishes, and reports once the last one has.
+fn run_diagnosis(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    (config, canvas, adapter): (Res<Config>, Res<CanvasSize>, Option<Res<RenderAdapterInfo>>),
+    mut diagnosis: ResMut<Diagnosis>,
+    backdrop: Single<&Camera, With<BackdropCamera>>,
+    mut status: Single<&mut Text2d, With<DiagnosisStatus>>,
+    mut resizes: EventWriter<ResizeGrid>,
+) {
+    let diagnosis = &mut *diagnosis;
+    diagnosis.elapsed += time.delta_secs();
+    match diagnosis.stage {
+        Stage::Readback => {
+            if diagnosis.elapsed < SETTLE_TIME {
+                return;
+            }
+            if !diagnosis.requested {
+                diagnosis.requested = true;
+                status.0 = "SELF-TEST: READING THE CANVAS BACK".to_string();
+                let RenderTarget::Image(target) = &backdrop.target else {
+                    diagnosis.readback = Some(Err("the canvas is not a texture".to_string()));
+                    return;
+                };
+                commands
+                    .spawn(Readback::texture(target.handle.clone()))
+                    .observe(read_back);
+                return;
+            }
+            if diagnosis.readback.is_none() {
+                if diagnosis.elapsed < SETTLE_TIME + READBACK_TIMEOUT {
+                    return;
+                }
+                diagnosis.readback = Some(Err("no answer from the GPU".to_string()));
+            }
+            begin_measuring(diagnosis, 0, &mut resizes, &mut status);
+        }
+        Stage::Measure(index) => {
+            if diagnosis.elapsed < SETTLE_TIME {
+                return;
+            }
+            if diagnosis.elapsed < SETTLE_TIME + MEASURE_TIME {
+                diagnosis.frames += 1;
+                return;
+            }
+            let seconds = diagnosis.elapsed - SETTLE_TIME;
+            diagnosis.rates.push(diagnosis.frames as f32 / seconds);
+            if index + 1 < GRID_SIZES.len() {
+                begin_measuring(diagnosis, index + 1, &mut resizes, &mut status);
+                return;
+            }
+            resizes.write(ResizeGrid(diagnosis.original));
+            let info = adapter.as_deref().map(|adapter| &***adapter);
+            let adapter = info.map(|info| {
+                (
+                    format!("{} ({:?})", info.name, info.device_type),
+                    format!("{:?} {}", info.backend, info.driver),
+                )
+            });
+            let report = report(diagnosis, adapter, config.video.supersampling, *canvas);
+            for line in &report {
+                println!("DIAGNOSE {}", line.0);
+            }
+            info!("Self-test finished");
+            commands.remove_resource::<Diagnosis>();
+            commands.insert_resource(DiagnosisReport);
+            status.0.clear();
+            spawn_report(&mut commands, &report);
+        }
+    }
+}
+
+/// Resizes the grid to the one of [`GRID_SIZES`] at `index`, to measure the frame rate on.
+fn begin_measuring(
+    diagnosis: &mut Diagnosis,
+    index: usize,
+    resizes: &mut EventWriter<ResizeGrid>,
+    status: &mut Text2d,
+) {
+    let size = GRID_SIZES[index];
+    resizes.write(ResizeGrid(size));
+    diagnosis.stage = Stage::Measure(index);
+    diagnosis.elapsed = 0.;
+    diagnosis.frames = 0;
+    status.0 = format!("SELF-TEST: MEASURING {}x{}", size.columns, size.rows);
+}
+
+/// Takes what the GPU read back of the canvas, which should have every pixel opaque.
+fn read_back(
+    trigger: Trigger<ReadbackComplete>,
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    backdrop: Single<&Camera, With<BackdropCamera>>,
+    mut diagnosis: Option<ResMut<Diagnosis>>,
+) {
+    commands.entity(trigger.target()).despawn();
+    let Some(diagnosis) = diagnosis.as_deref_mut() else {
+        return;
+    };
+    if diagnosis.readback.is_some() {
+        return;
+    }
+    let size = match &backdrop.target {
+        RenderTarget::Image(target) => images.get(&target.handle).map(Image::size),
+        _ => None,
+    };
+    diagnosis.readback = Some(size.map_or_else(
+        || Err("the canvas went missing".to_string()),
+        |size| check_cleared(&trigger.event().0, size),
+    ));
+}
+
+/// Whether `data`, an image `size` pixels large of four bytes to a pixel, has every pixel
+/// opaque. Rows may be padded out past the end of their pixels.
+fn check_cleared(data: &[u8], size: UVec2) -> Result<(), String> {
+    let width = size.x as usize * 4;
+    let stride = data.len() / (size.y as usize).max(1);
+    if width == 0 || stride < width {
+        return Err(format!("{} bytes came back", data.len()));
+    }
+    let opaque = data.chunks_exact(stride).all(|row| {
+        row[..width]
+            .chunks_exact(4)
+            .all(|pixel| pixel[3] == u8::MAX)
+    });
+    if opaque {
+        Ok(())
+    } else {
+        Err("the canvas came back blank".to_string())
+    }
+}
+
+/// The report's lines, each with whether it tells of a failure.
+fn report(
+    diagnosis: &Diagnosis,
+    adapter: Option<(String, String)>,
+    supersampling: u32,
+    canvas: CanvasSize,
+) -> Vec<(String, bool)> {
+    let mut lines = Vec::new();
+    match adapter {
+        Some((adapter, backend)) => {
+            lines.push((format!("ADAPTER {adapter}"), false));
+            lines.push((format!("BACKEND {backend}"), false));
+        }
+        None => lines.push(("ADAPTER UNKNOWN".to_string(), true)),
+    }
+    lines.push((
+        format!("CANVAS {}x{} AT {supersampling}x", canvas.0.x, canvas.0.y),
+        false,
+    ));
+    match &diagnosis.readback {
+        Some(Ok(())) => lines.push(("RENDER TO TEXTURE OK".to_string(), false)),
+        Some(Err(error)) => lines.push((format!("RENDER TO TEXTURE FAILED: {error}"), true)),
+        None => lines.push(("RENDER TO TEXTURE NOT TRIED".to_string(), true)),
+    }
+    for (size, rate) in GRID_SIZES.iter().zip(&diagnosis.rates) {
+        lines.push((
+            format!("GRID {}x{}: {rate:.0} FPS", size.columns, size.rows),
+            *rate < TARGET_FPS,
+        ));
+    }
+    let kept_up = GRID_SIZES
+        .iter()
+        .zip(&diagnosis.rates)
+        .filter(|(_, &rate)| rate >= TARGET_FPS)
+        .map(|(size, _)| size)
+        .next_back();
+    let suggestion = match kept_up {
+        Some(size) => format!("SUGGESTED: GRID UP TO {}x{}", size.columns, size.rows),
+        None if supersampling > 1 => "SUGGESTED: SMALLEST GRID, SUPERSAMPLING 1".to_string(),
+        None => "SUGGESTED: SMALLEST GRID".to_string(),
+    };
+    lines.push((suggestion, false));
+    lines
+}
+
+/// Shows the report over everything else, until it is dismissed.
+fn spawn_report(commands: &mut Commands, report: &[(String, bool)]) {
+    commands.spawn((
+        DiagnosisScreen,
+        Sprite {
+            color: Color::srgba(0.0, 0.02, 0.03, 0.92),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 45.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    commands.spawn((
+        DiagnosisScreen,
+        Text2d::new("SELF-TEST REPORT"),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        CanvasAnchor::TOP.offset(0., -12.),
+        Transform::from_xyz(0., 0., 46.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    let top = report.len() as f32 * 6.;
+    for (index, (line, failed)) in report.iter().enumerate() {
+        commands.spawn((
+            DiagnosisScreen,
+            Text2d::new(line.clone()),
+            TextFont {
+                font_size: 8.0,
+                ..default()
+            },
+            TextColor(if *failed { FAILED_COLOR } else { REPORT_COLOR }),
+            Transform::from_xyz(0., top - index as f32 * 12., 46.),
+            PIXEL_PERFECT_LAYERS,
+        ));
+    }
+    commands.spawn((
+        DiagnosisScreen,
+        Text2d::new("PRESS ANY KEY"),
+        TextFont {
+            font_size: 7.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        CanvasAnchor::BOTTOM.offset(0., 12.),
+        Transform::from_xyz(0., 0., 46.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+}
+
+/// Takes the report down at any key or click.
+fn dismiss_report(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    screen: Query<Entity, With<DiagnosisScreen>>,
+) {
+    if keys.get_just_pressed().next().is_none() && buttons.get_just_pressed().next().is_none() {
+        return;
+    }
+    for entity in &screen {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<DiagnosisReport>();
+}
+
+/// Keeps vsync off while the test runs, for frame rates past the display's, even as the config
+/// is changed, which would otherwise put it back.
+fn keep_vsync_off(mut window: Single<&mut Window, With<PrimaryWindow>>) {
+    if window.present_mode != PresentMode::AutoNoVsync {
+        window.present_mode = PresentMode::AutoNoVsync;
+    }
+}
@@ -0,0 +1,350 @@
+//! Real-directory refinement: a mode where the grid holds the files of a real directory, and
+//! refining them does something to them.
+//!
+//! Started with `--directory <path>`. Every file in the directory is given a cluster of numbers,
+//! labelled with its name, and refining a selection that covers a whole cluster into a bin
+//! applies that bin's [`FileRule`] from the config to the file: moving, tagging or archiving it.
+//! Each bin fills with the share of the directory's files refined into it.
+//!
+//! Only files directly in the directory are laid out, in name order and as many of them as the
+//! grid has room for at its current size, laid out anew as it is resized; hidden files and
+//! subdirectories are left alone. Nothing ever replaces an existing file, and a file that could
+//! not be moved stays unrefined.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::{Bin, DrivenBins, DEFAULT_BIN_COUNT, MAX_BIN_COUNT},
+    canvas::GRID_LAYERS,
+    config::{Config, FileRule},
+    files::ActiveFile,
+    grid::{Cell, GridSize, RefineSet, Refined, NUMBER_SPACING},
+    toast::Toast,
+};
+
+/// Cells of a file's cluster, across and up; the row beneath holds its name plate.
+const CLUSTER_SIZE: UVec2 = UVec2::new(3, 2);
+
+/// First row of name plates, above the bins.
+const FIRST_ROW: u32 = 4;
+
+/// Longest name a plate shows before cutting it short.
+const NAME_LENGTH: usize = 10;
+
+const PLATE_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+const NAME_COLOR: Color = Color::srgb(0.0, 0.9, 1.0);
+const REFINED_NAME_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+
+pub struct DirectoryPlugin {
+    pub path: Option<PathBuf>,
+}
+
+impl DirectoryPlugin {
+    /// Reads `--directory <path>` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut path = None;
+        while let Some(arg) = args.next() {
+            if arg == "--directory" {
+                path = args.next().map(PathBuf::from);
+            }
+        }
+        Self { path }
+    }
+}
+
+impl Plugin for DirectoryPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(root) = &self.path else {
+            return;
+        };
+        let paths = match list_files(root) {
+            Ok(paths) => paths,
+            Err(error) => {
+                error!("Could not read directory {}: {error}", root.display());
+                return;
+            }
+        };
+        let files = paths
+            .into_iter()
+            .map(|path| DirectoryFile {
+                path,
+                cluster: None,
+                bin: None,
+            })
+            .collect();
+
+        let rules = app.world().resource::<Config>().directory.rules.len();
+        let bins = if rules == 0 { DEFAULT_BIN_COUNT } else { rules };
+        // The directory stands in for a file of its own, which starts out empty.
+        let name = root.file_name().unwrap_or(root.as_os_str());
+        app.insert_resource(ActiveFile {
+            name: name.to_string_lossy().into_owned(),
+            progress: vec![0.; bins.min(MAX_BIN_COUNT)],
+            ..default()
+        })
+        .insert_resource(DrivenBins)
+        .insert_resource(RealDirectory {
+            root: root.clone(),
+            files,
+        })
+        .add_systems(
+            Update,
+            (
+                lay_out_files.run_if(resource_changed::<GridSize>),
+                refine_files.run_if(on_event::<Refined>),
+                fill_bins,
+            )
+                .chain()
+                .in_set(RefineSet::React),
+        );
+    }
+}
+
+/// The real directory being refined, present while the mode is on.
+#[derive(Resource)]
+struct RealDirectory {
+    root: PathBuf,
+    files: Vec<DirectoryFile>,
+}
+
+/// A real file on the grid.
+struct DirectoryFile {
+    path: PathBuf,
+    /// The cells of its cluster, if the grid has room for it.
+    cluster: Option<URect>,
+    /// The bin it was refined into, if it has been.
+    bin: Option<usize>,
+}
+
+/// The backing of a name plate, holding its [`NamePlate`] text.
+#[derive(Component)]
+struct Plate;
+
+/// The name plate of the file with this index.
+#[derive(Component)]
+struct NamePlate(usize);
+
+/// The files directly in `root`, in name order.
+fn list_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Clusters a grid of the given size has room for across and up, beside a column left empty and
+/// above the bottom rows, which are left to the bins.
+fn clusters(size: GridSize) -> UVec2 {
+    UVec2::new(
+        size.columns.saturating_sub(1) / CLUSTER_SIZE.x,
+        size.rows.saturating_sub(FIRST_ROW + 1) / (CLUSTER_SIZE.y + 1),
+    )
+}
+
+/// The cells of the cluster of the file with this index, on a grid with room for `clusters`.
+fn cluster(index: usize, clusters: UVec2) -> Option<URect> {
+    let index = u32::try_from(index).ok()?;
+    if clusters.x == 0 || index >= clusters.x * clusters.y {
+        return None;
+    }
+    let min = UVec2::new(
+        1 + index % clusters.x * CLUSTER_SIZE.x,
+        FIRST_ROW + 1 + index / clusters.x * (CLUSTER_SIZE.y + 1),
+    );
+    Some(URect::from_corners(min, min + CLUSTER_SIZE - 1))
+}
+
+/// A file's name, cut short to fit its plate.
+fn plate_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if name.chars().count() <= NAME_LENGTH {
+        name.into_owned()
+    } else {
+        let short: String = name.chars().take(NAME_LENGTH - 1).collect();
+        format!("{short}~")
+    }
+}
+
+/// Gives each file a cluster on the grid at its current size, with a name plate beneath, for as
+/// many files as it has room for.
+fn lay_out_files(
+    mut commands: Commands,
+    size: Res<GridSize>,
+    mut directory: ResMut<RealDirectory>,
+    plates: Query<Entity, With<Plate>>,
+) {
+    for plate in &plates {
+        commands.entity(plate).despawn();
+    }
+    let clusters = clusters(*size);
+    let directory = &mut *directory;
+    let mut shown = 0;
+    for (index, file) in directory.files.iter_mut().enumerate() {
+        file.cluster = cluster(index, clusters);
+        let Some(cluster) = file.cluster else {
+            continue;
+        };
+        shown += 1;
+        let below = Cell {
+            col: cluster.min.x + 1,
+            row: cluster.min.y - 1,
+        };
+        let color = if file.bin.is_some() {
+            REFINED_NAME_COLOR
+        } else {
+            NAME_COLOR
+        };
+        commands
+            .spawn((
+                Plate,
+                Sprite {
+                    color: PLATE_COLOR,
+                    custom_size: Some(Vec2::new(CLUSTER_SIZE.x as f32 * NUMBER_SPACING, 10.)),
+                    ..default()
+                },
+                // Over the numbers of the row beneath the cluster, under the selection box.
+                Transform::from_translation(below.position().extend(1.)),
+                GRID_LAYERS,
+            ))
+            .with_child((
+                NamePlate(index),
+                Text2d::new(plate_name(&file.path)),
+                TextFont {
+                    font_size: 8.0,
+                    ..default()
+                },
+                TextColor(color),
+                Transform::from_xyz(0., 0., 0.1),
+                GRID_LAYERS,
+            ));
+    }
+    if shown < directory.files.len() {
+        warn!(
+            "Only refining the first {shown} of the {} files in {}, as many as a {}x{} grid has \
+             room for",
+            directory.files.len(),
+            directory.root.display(),
+            size.columns,
+            size.rows
+        );
+    }
+}
+
+/// Applies the bin's rule to every unrefined file whose whole cluster was refined into it.
+fn refine_files(
+    config: Res<Config>,
+    mut refined: EventReader<Refined>,
+    mut directory: ResMut<RealDirectory>,
+    mut plates: Query<(&NamePlate, &mut TextColor)>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let directory = &mut *directory;
+    for refined in refined.read() {
+        let rule = config
+            .directory
+            .rules
+            .get(refined.bin)
+            .unwrap_or(&FileRule::Keep);
+        for (index, file) in directory.files.iter_mut().enumerate() {
+            let cells = refined.cells;
+            let covered = file
+                .cluster
+                .is_some_and(|cluster| cells.contains(cluster.min) && cells.contains(cluster.max));
+            if file.bin.is_some() || !covered {
+                continue;
+            }
+            match apply(rule, &directory.root, &file.path) {
+                Ok(path) => {
+                    if path != file.path {
+                        info!("Refined {} into {}", file.path.display(), path.display());
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        toasts.write(Toast::info(format!("Refined {name}")));
+                    }
+                    file.path = path;
+                    file.bin = Some(refined.bin);
+                }
+                Err(error) => {
+                    error!("Could not refine {}: {error}", file.path.display());
+                    let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+                    toasts.write(Toast::error(format!("Could not refine {name}")));
+                    continue;
+                }
+            }
+            for (plate, mut color) in &mut plates {
+                if plate.0 == index {
+                    color.0 = REFINED_NAME_COLOR;
+                }
+            }
+        }
+    }
+}
+
+/// Applies `rule` to the file at `path` in `root`, returning where the file is afterwards.
+fn apply(rule: &FileRule, root: &Path, path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default();
+    match rule {
+        FileRule::Keep => Ok(path.to_path_buf()),
+        FileRule::Move(directory) => move_file(path, &root.join(directory).join(name)),
+        FileRule::Archive => move_file(path, &root.join("archive").join(name)),
+        FileRule::Tag(tag) => {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let mut tagged = format!("{stem} [{tag}]");
+            if let Some(extension) = path.extension() {
+                tagged = format!("{tagged}.{}", extension.to_string_lossy());
+            }
+            move_file(path, &path.with_file_name(tagged))
+        }
+    }
+}
+
+/// Moves a file, copying it where it can't simply be renamed, as across drives.
+fn move_file(from: &Path, to: &Path) -> io::Result<PathBuf> {
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Err(error) = fs::rename(from, to) {
+        fs::copy(from, to).map_err(|_| error)?;
+        fs::remove_file(from)?;
+    }
+    Ok(to.to_path_buf())
+}
+
+/// Fills every bin with the share of the directory's files refined into it, of those the grid
+/// has room for or already refined.
+fn fill_bins(directory: Res<RealDirectory>, mut bins: Query<&mut Bin>) {
+    let total = directory
+        .files
+        .iter()
+        .filter(|file| file.cluster.is_some() || file.bin.is_some())
+        .count()
+        .max(1) as f32;
+    for mut bin in &mut bins {
+        let refined = directory
+            .files
+            .iter()
+            .filter(|file| file.bin == Some(bin.index))
+            .count();
+        let progress = refined as f32 / total;
+        if bin.progress != progress {
+            bin.progress = progress;
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! The refiner's status on Discord: the open file, how complete it is, and how long it has been
+//! open.
+//!
+//! Only built with the `discord` feature, and only shown when the config names the Discord
+//! application to show it under. The app talks to the Discord client over its local IPC socket,
+//! the way every rich presence does: a handshake naming the application, then a `SET_ACTIVITY`
+//! command whenever the status changes, which is as a file is opened, as it passes a milestone,
+//! and as it is complete. Discord counts the time itself, from when the file was opened.
+//!
+//! Discord not running is no error. Nothing is shown or logged beyond debug, and the socket is
+//! looked for again at the next change, so a Discord started later picks the status up from
+//! there. The socket never blocks a frame: what it does not take at once is written over the
+//! frames after. Only platforms with Unix sockets are supported, for now.
+
+use std::{
+    env,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::{percent, Bin},
+    config::Config,
+    files::ActiveFile,
+    finale::FinaleEnded,
+    milestone::MilestoneReached,
+};
+
+/// Opcodes of the frames the client sends.
+const HANDSHAKE: u32 = 0;
+const FRAME: u32 = 1;
+
+/// Sockets the Discord client may be listening on, tried in turn.
+const SOCKETS: u32 = 10;
+
+pub struct DiscordPlugin;
+
+impl Plugin for DiscordPlugin {
+    fn build(&self, app: &mut App) {
+        let config = &app.world().resource::<Config>().discord;
+        if config.application_id.is_empty() {
+            return;
+        }
+        app.insert_resource(Presence {
+            application_id: config.application_id.clone(),
+            socket: None,
+            outbox: Vec::new(),
+            opened: None,
+            sent: 0,
+        })
+        .add_systems(
+            Last,
+            (
+                show_presence.run_if(
+                    resource_changed::<ActiveFile>
+                        .or(on_event::<MilestoneReached>)
+                        .or(on_event::<FinaleEnded>),
+                ),
+                flush_presence.run_if(|presence: Res<Presence>| !presence.outbox.is_empty()),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The link to the Discord client.
+#[derive(Resource)]
+struct Presence {
+    application_id: String,
+    socket: Option<UnixStream>,
+    /// Frames the socket has yet to take.
+    outbox: Vec<u8>,
+    /// Name of the open file, and the second of the epoch it was opened at.
+    opened: Option<(String, u64)>,
+    /// Commands sent so far, which tells their replies apart.
+    sent: u32,
+}
+
+impl Presence {
+    /// Sends a command, connecting first if need be. Fails once Discord is not there, and lets
+    /// go of the socket to look for it afresh next time.
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        let sent = self.write(command);
+        if sent.is_err() {
+            self.release();
+        }
+        sent
+    }
+
+    fn write(&mut self, command: &str) -> io::Result<()> {
+        let socket = match &mut self.socket {
+            Some(socket) => socket,
+            None => {
+                let socket = connect()?;
+                let handshake = format!(
+                    r#"{{"v":1,"client_id":{}}}"#,
+                    json_string(&self.application_id)
+                );
+                self.outbox = frame(HANDSHAKE, &handshake);
+                self.socket.insert(socket)
+            }
+        };
+        // Replies are of no interest, but are read so that they do not pile up.
+        let mut buffer = [0; 4096];
+        loop {
+            match socket.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        self.outbox.extend(frame(FRAME, command));
+        self.flush()
+    }
+
+    /// Writes as much of the pending frames as the socket accepts.
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(socket) = &mut self.socket else {
+            return Ok(());
+        };
+        while !self.outbox.is_empty() {
+            match socket.write(&self.outbox) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outbox.drain(..written);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lets go of the socket, and of the frames it never took, which a new one would not follow.
+    fn release(&mut self) {
+        self.socket = None;
+        self.outbox.clear();
+    }
+}
+
+/// Directory the Discord client puts its sockets in.
+fn socket_dir() -> PathBuf {
+    ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(env::var_os)
+        .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from)
+}
+
+/// Connects to the Discord client, which the application is then introduced to.
+fn connect() -> io::Result<UnixStream> {
+    let dir = socket_dir();
+    let socket = (0..SOCKETS)
+        .find_map(|index| UnixStream::connect(dir.join(format!("discord-ipc-{index}"))).ok())
+        .ok_or(io::ErrorKind::NotFound)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+/// A frame of the IPC protocol: its opcode and length, then its JSON.
+fn frame(opcode: u32, json: &str) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + json.len());
+    frame.extend(opcode.to_le_bytes());
+    frame.extend((json.len() as u32).to_le_bytes());
+    frame.extend(json.as_bytes());
+    frame
+}
+
+/// A string as JSON writes it, quoted and escaped.
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn show_presence(file: Res<ActiveFile>, bins: Query<&Bin>, mut presence: ResMut<Presence>) {
+    // The same file opened again, or with another number of bins, is still the session going on.
+    if presence.opened.as_ref().map(|(name, _)| name) != Some(&file.name) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        presence.opened = Some((file.name.clone(), now));
+    }
+    let started = presence.opened.as_ref().map_or(0, |(_, started)| *started);
+    let completion =
+        bins.iter().map(|bin| bin.progress).sum::<f32>() / bins.iter().len().max(1) as f32;
+    let activity = format!(
+        r#"{{"details":{},"state":{},"timestamps":{{"start":{started}}}}}"#,
+        json_string(&format!("Refining {}", file.name)),
+        json_string(&format!("{} complete", percent(completion))),
+    );
+    presence.sent += 1;
+    let command = format!(
+        r#"{{"cmd":"SET_ACTIVITY","args":{{"pid":{},"activity":{activity}}},"nonce":"{}"}}"#,
+        std::process::id(),
+        presence.sent,
+    );
+    if let Err(error) = presence.send(&command) {
+        debug!("Discord is not there to show the presence: {error}");
+    }
+}
+
+/// Writes what the socket did not take at once, as it takes more.
+fn flush_presence(mut presence: ResMut<Presence>) {
+    if let Err(error) = presence.flush() {
+        debug!("Discord went away while showing the presence: {error}");
+        presence.release();
+    }
+}
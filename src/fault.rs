@@ -0,0 +1,327 @@
+//! The fault screen: what went wrong, shown on the canvas in place of a crash, for problems the
+//! app can go on without, such as a config file it cannot read, an asset that failed to load, or
+//! a canvas too large for the GPU.
+//!
+//! Whatever runs into one reports a [`Fault`] to [`Faults`], which logs it with the error
+//! underneath and queues it to be shown, one at a time, over everything else. The refiner retries
+//! what can be retried, goes on without it, or quits; whoever reported the fault is told of the
+//! answer with a [`FaultAnswered`]. A kiosk has nobody to answer, so it retries by itself every
+//! little while, a few times, and then goes on; nor does it offer to quit.
+
+use std::{collections::VecDeque, fmt::Display};
+
+use bevy::{app::AppExit, platform::collections::HashMap, prelude::*, text::TextBounds};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    kiosk::Kiosk,
+    ui::{spawn_menu, MenuChosen, MenuEntry, Modal},
+};
+
+/// Width the detail of a fault wraps at.
+const DETAIL_WIDTH: f32 = 320.;
+
+/// Seconds a kiosk leaves a fault up before answering it.
+const KIOSK_DELAY: f32 = 30.;
+
+/// Times a kiosk retries a fault before going on without it.
+const KIOSK_RETRIES: u32 = 3;
+
+const FAULT_COLOR: Color = Color::srgb(1.0, 0.45, 0.45);
+
+pub struct FaultPlugin;
+
+impl Plugin for FaultPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Faults>()
+            .add_event::<FaultAnswered>()
+            .add_systems(
+                Update,
+                (
+                    show_fault.run_if(resource_changed::<Faults>),
+                    answer_fault,
+                    answer_for_kiosk.run_if(resource_exists::<Kiosk>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// What a fault is with, for whoever reported it to tell its answer apart from others'.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FaultKind {
+    /// The config file.
+    Config,
+    /// The asset at a path.
+    Asset(String),
+    /// The canvas's render targets.
+    Canvas,
+}
+
+impl FaultKind {
+    /// Whether trying again might go differently.
+    fn retryable(&self) -> bool {
+        !matches!(self, FaultKind::Canvas)
+    }
+}
+
+/// A problem the app went on through.
+#[derive(Clone, Debug)]
+pub struct Fault {
+    pub kind: FaultKind,
+    /// What went wrong, in a few words, as the screen's heading.
+    pub title: &'static str,
+    /// The error underneath.
+    pub detail: String,
+}
+
+impl Fault {
+    pub fn new(kind: FaultKind, title: &'static str, detail: impl Display) -> Self {
+        Self {
+            kind,
+            title,
+            detail: detail.to_string(),
+        }
+    }
+}
+
+/// Faults waiting to be answered, the first of them on screen.
+#[derive(Resource, Default)]
+pub struct Faults {
+    queue: VecDeque<Fault>,
+    /// How many times each kind of fault has been retried, since it last went away.
+    retried: HashMap<FaultKind, u32>,
+}
+
+impl Faults {
+    /// Logs `fault` and queues it to be shown, in place of any of its kind still waiting.
+    pub fn report(&mut self, fault: Fault) {
+        error!(
+            fault = ?fault.kind,
+            error = %fault.detail,
+            "{}",
+            fault.title
+        );
+        match self
+            .queue
+            .iter_mut()
+            .find(|queued| queued.kind == fault.kind)
+        {
+            Some(queued) => *queued = fault,
+            None => self.queue.push_back(fault),
+        }
+    }
+
+    /// Takes down a fault that has gone away by itself, such as a config file fixed in place.
+    pub fn resolve(&mut self, kind: &FaultKind) {
+        self.retried.remove(kind);
+        self.queue.retain(|fault| fault.kind != *kind);
+    }
+
+    /// Whether a fault of `kind` is waiting to be answered.
+    pub fn contains(&self, kind: &FaultKind) -> bool {
+        self.queue.iter().any(|fault| fault.kind == *kind)
+    }
+}
+
+/// The refiner answered a fault, retrying it or going on without.
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub struct FaultAnswered {
+    pub kind: FaultKind,
+    pub retry: bool,
+}
+
+/// What the refiner can answer a fault with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Answer {
+    Retry,
+    Continue,
+    Quit,
+}
+
+impl Answer {
+    fn label(self) -> &'static str {
+        match self {
+            Answer::Retry => "Retry",
+            Answer::Continue => "Continue",
+            Answer::Quit => "Quit",
+        }
+    }
+}
+
+/// The answers `fault` offers, a kiosk's not including quitting.
+fn answers(fault: &Fault, kiosk: bool) -> Vec<Answer> {
+    let retry = fault.kind.retryable().then_some(Answer::Retry);
+    let quit = (!kiosk).then_some(Answer::Quit);
+    retry
+        .into_iter()
+        .chain([Answer::Continue])
+        .chain(quit)
+        .collect()
+}
+
+/// Marks what makes up the fault screen.
+#[derive(Component)]
+struct FaultScreen;
+
+/// Marks the fault screen's menu, with when a kiosk answers it, in real seconds since startup.
+#[derive(Component)]
+struct FaultMenu {
+    answer_at: f32,
+}
+
+/// Shows the first fault waiting, or takes the screen down once there are none.
+fn show_fault(
+    mut commands: Commands,
+    faults: Res<Faults>,
+    time: Res<Time<Real>>,
+    kiosk: Option<Res<Kiosk>>,
+    screens: Query<Entity, With<FaultScreen>>,
+) {
+    for entity in &screens {
+        commands.entity(entity).despawn();
+    }
+    let Some(fault) = faults.queue.front() else {
+        return;
+    };
+    commands.spawn((
+        FaultScreen,
+        Sprite {
+            color: Color::srgb(0.05, 0.0, 0.0),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 50.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    commands.spawn((
+        FaultScreen,
+        Text2d::new("SYSTEM FAULT"),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        CanvasAnchor::TOP.offset(0., -12.),
+        Transform::from_xyz(0., 0., 51.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    let mut detail = fault.detail.clone();
+    if kiosk.is_some() {
+        detail.push_str(&format!(
+            "\n\nANSWERED BY ITSELF IN {KIOSK_DELAY:.0} SECONDS"
+        ));
+    }
+    commands.spawn((
+        FaultScreen,
+        Text2d::new(detail),
+        TextFont {
+            font_size: 8.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextBounds::new_horizontal(DETAIL_WIDTH),
+        TextColor(FAULT_COLOR),
+        Transform::from_xyz(0., 44., 51.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    let entries: Vec<MenuEntry> = answers(fault, kiosk.is_some())
+        .into_iter()
+        .map(|answer| MenuEntry::new(answer.label()))
+        .collect();
+    spawn_menu(
+        &mut commands,
+        fault.title,
+        &entries,
+        0,
+        Vec3::new(0., -40., 51.),
+        (
+            FaultScreen,
+            FaultMenu {
+                answer_at: time.elapsed_secs() + KIOSK_DELAY,
+            },
+            Modal,
+        ),
+    );
+}
+
+/// Answers the fault on screen with what the refiner chose from its menu.
+fn answer_fault(
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<(), With<FaultMenu>>,
+    kiosk: Option<Res<Kiosk>>,
+    mut faults: ResMut<Faults>,
+    mut answered: EventWriter<FaultAnswered>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in chosen.read() {
+        if !menus.contains(event.menu) {
+            continue;
+        }
+        let Some(fault) = faults.queue.front() else {
+            continue;
+        };
+        if let Some(&answer) = answers(fault, kiosk.is_some()).get(event.item) {
+            answer_first(&mut faults, answer, &mut answered, &mut exit);
+        }
+    }
+}
+
+/// Answers the fault on screen of a kiosk once it has been up a while: retrying it, until it
+/// has been retried enough, and then going on.
+fn answer_for_kiosk(
+    time: Res<Time<Real>>,
+    menu: Query<&FaultMenu>,
+    mut faults: ResMut<Faults>,
+    mut answered: EventWriter<FaultAnswered>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Ok(menu) = menu.single() else {
+        return;
+    };
+    let Some(fault) = faults.queue.front() else {
+        return;
+    };
+    if time.elapsed_secs() < menu.answer_at {
+        return;
+    }
+    let retried = faults.retried.get(&fault.kind).copied().unwrap_or_default();
+    let answer = if fault.kind.retryable() && retried < KIOSK_RETRIES {
+        Answer::Retry
+    } else {
+        Answer::Continue
+    };
+    answer_first(&mut faults, answer, &mut answered, &mut exit);
+}
+
+/// Takes the first fault down with `answer`, telling whoever reported it.
+fn answer_first(
+    faults: &mut Faults,
+    answer: Answer,
+    answered: &mut EventWriter<FaultAnswered>,
+    exit: &mut EventWriter<AppExit>,
+) {
+    let Some(fault) = faults.queue.pop_front() else {
+        return;
+    };
+    info!("Answered {:?} with {answer:?}", fault.kind);
+    match answer {
+        Answer::Retry => {
+            *faults.retried.entry(fault.kind.clone()).or_default() += 1;
+            answered.write(FaultAnswered {
+                kind: fault.kind,
+                retry: true,
+            });
+        }
+        Answer::Continue => {
+            faults.retried.remove(&fault.kind);
+            answered.write(FaultAnswered {
+                kind: fault.kind,
+                retry: false,
+            });
+        }
+        Answer::Quit => {
+            exit.write(AppExit::error());
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! The numbers of the grid drawn as one field, for grids too large to draw a number at a time.
+//!
+//! Each number is normally a text entity of its own, which gets slow to lay out and draw beyond
+//! a few thousand. The field instead draws them all at once: one grid-sized quad with a
+//! [`FieldMaterial`], whose shader draws each number from a map of where every number is, how
+//! large, which glyph and in what color, using an atlas of the open file's glyphs. The number
+//! entities are still there, and every system moves and tints them as usual; they only have no
+//! text to lay out or draw, and the map copies what changed about them each frame.
+//!
+//! The numbers' idle drift is worked out by the shader too, from the time and the phase of each
+//! cell, so that nothing has to move thousands of numbers every frame. Only the motion that
+//! matters to play, from hints and the like, still moves the number entities, and reaches the
+//! field through the map.
+//!
+//! Which way numbers are drawn is the `digits` video setting. By default the field takes over
+//! once a grid has more than [`FIELD_CELLS`] cells; the text entities remain as the fallback.
+
+use bevy::{
+    asset::{load_internal_asset, weak_handle, RenderAssetUsages},
+    prelude::*,
+    render::{
+        render_resource::{AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat},
+        view::VisibilityClass,
+    },
+    sprite::{AlphaMode2d, Anchor, Material2d, Material2dPlugin},
+    text::{ComputedTextBlock, TextBounds, TextLayoutInfo},
+};
+
+use crate::{
+    canvas::{Supersampling, GRID_LAYERS},
+    config::{Config, DigitRenderer},
+    files::ActiveFile,
+    glyphs::{GlyphSet, PIXEL_GLYPHS},
+    grid::{drift_time, grid_bounds, number_text, Cell, Glyph, GridSize, Number, NUMBER_SPACING},
+    overtime::Overtime,
+    theme::Theme,
+};
+
+/// Cells a grid needs more of for the field to take over from text by default, around 70 by 70,
+/// well short of the largest grids.
+const FIELD_CELLS: u32 = 5_000;
+
+/// Size of a pixel glyph.
+const GLYPH_SIZE: UVec2 = UVec2::new(3, 5);
+
+/// World units a pixel of a pixel glyph covers, so that digits are about as large as the text
+/// ones. Glyphs of an atlas are scaled to be as tall.
+const GLYPH_PIXEL: f32 = 2.;
+
+const FIELD_SHADER: Handle<Shader> = weak_handle!("4f0c8a2e-6b1d-4e93-a7c5-3d9e1b0f2a58");
+
+pub struct FieldPlugin;
+
+impl Plugin for FieldPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, FIELD_SHADER, "shaders/field.wgsl", Shader::from_wgsl);
+        app.add_plugins(Material2dPlugin::<FieldMaterial>::default())
+            .add_systems(Startup, setup_field)
+            .add_systems(
+                PostUpdate,
+                (
+                    fit_field
+                        .run_if(resource_changed::<GridSize>.and(not(resource_added::<GridSize>))),
+                    choose_renderer,
+                    load_glyphs,
+                    paint_field,
+                    drift_field,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Material of the field quad.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct FieldMaterial {
+    /// Grid position of the first cell, the spacing between cells, and the size of a glyph's
+    /// pixel, as laid out in the shader's `Field` struct.
+    #[uniform(0)]
+    grid: Vec4,
+    /// Size of a glyph in the atlas.
+    #[uniform(0)]
+    glyph: Vec4,
+    /// How far the numbers have drifted along their paths, and how far from their cells they
+    /// drift, in pixels.
+    #[uniform(0)]
+    drift: Vec4,
+    /// A pixel per cell, holding the number's offset from its cell, its scale and its glyph.
+    #[texture(1, sample_type = "float", filterable = false)]
+    numbers: Handle<Image>,
+    /// A pixel per cell, holding the number's color.
+    #[texture(2, sample_type = "float", filterable = false)]
+    colors: Handle<Image>,
+    #[texture(3)]
+    glyphs: Handle<Image>,
+}
+
+impl Material2d for FieldMaterial {
+    fn fragment_shader() -> ShaderRef {
+        FIELD_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// Marks the field quad.
+#[derive(Component)]
+struct Field;
+
+fn cell_map(size: GridSize) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: size.columns,
+            height: size.rows,
+            ..default()
+        },
+        TextureDimension::D2,
+        float_bytes(&[0., 0., 1., -1.]).as_slice(),
+        TextureFormat::Rgba32Float,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+/// The bytes of some floats, as textures hold them.
+fn float_bytes(floats: &[f32]) -> Vec<u8> {
+    floats
+        .iter()
+        .flat_map(|float| float.to_ne_bytes())
+        .collect()
+}
+
+/// An atlas of pixel glyphs, side by side.
+fn glyph_atlas(pixels: &[[u8; 5]]) -> Image {
+    let width = GLYPH_SIZE.x * pixels.len() as u32;
+    let mut data = vec![0; (width * GLYPH_SIZE.y * 4) as usize];
+    for (glyph, rows) in pixels.iter().enumerate() {
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..GLYPH_SIZE.x {
+                if row >> (GLYPH_SIZE.x - 1 - x) & 1 == 0 {
+                    continue;
+                }
+                let column = glyph as u32 * GLYPH_SIZE.x + x;
+                let index = ((y as u32 * width + column) * 4) as usize;
+                data[index..index + 4].fill(255);
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width,
+            height: GLYPH_SIZE.y,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn setup_field(
+    mut commands: Commands,
+    size: Res<GridSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<FieldMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let origin = Cell { col: 0, row: 0 }.position();
+    let bounds = grid_bounds(*size);
+    commands.spawn((
+        Field,
+        Mesh2d(meshes.add(Rectangle::from_size(bounds.size() + NUMBER_SPACING))),
+        MeshMaterial2d(materials.add(FieldMaterial {
+            grid: Vec4::new(origin.x, origin.y, NUMBER_SPACING, GLYPH_PIXEL),
+            glyph: GLYPH_SIZE.as_vec2().extend(0.).extend(0.),
+            drift: Vec4::ZERO,
+            numbers: images.add(cell_map(*size)),
+            colors: images.add(cell_map(*size)),
+            glyphs: images.add(glyph_atlas(&PIXEL_GLYPHS[..10])),
+        })),
+        // Where the numbers would be.
+        Transform::from_translation(bounds.center().extend(0.)),
+        Visibility::Hidden,
+        GRID_LAYERS,
+    ));
+}
+
+/// Fits the field to the grid as it is resized, with maps as large as it, to be painted afresh.
+fn fit_field(
+    size: Res<GridSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<FieldMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut field: Single<(&mut Mesh2d, &MeshMaterial2d<FieldMaterial>, &mut Transform), With<Field>>,
+) {
+    let (mesh, material, transform) = &mut *field;
+    let bounds = grid_bounds(*size);
+    mesh.0 = meshes.add(Rectangle::from_size(bounds.size() + NUMBER_SPACING));
+    transform.translation = bounds.center().extend(transform.translation.z);
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.numbers = images.add(cell_map(*size));
+        material.colors = images.add(cell_map(*size));
+    }
+}
+
+/// Whether the field draws the numbers of a grid of the given size, in the given glyphs, rather
+/// than their text.
+pub fn field_shown(config: &Config, glyphs: &GlyphSet, size: GridSize) -> bool {
+    // Glyphs only one of the two can draw are drawn by that one, whatever the config says.
+    if !glyphs.has_text() {
+        return true;
+    }
+    if glyphs.pixels().is_none() {
+        return false;
+    }
+    match config.video.digits {
+        DigitRenderer::Auto => size.columns * size.rows > FIELD_CELLS,
+        DigitRenderer::Field => true,
+        DigitRenderer::Text => false,
+    }
+}
+
+/// Everything that makes a number text, which numbers the field draws go without.
+type NumberText = (
+    Text2d,
+    TextFont,
+    TextLayout,
+    TextBounds,
+    Anchor,
+    ComputedTextBlock,
+    TextLayoutInfo,
+    VisibilityClass,
+);
+
+/// Shows the field or the text of the numbers, whichever the config asks for, including for
+/// numbers spawned since: numbers the field draws lose their text, and get it back once it hides.
+fn choose_renderer(
+    mut commands: Commands,
+    (config, file, size): (Res<Config>, Res<ActiveFile>, Res<GridSize>),
+    supersampling: Res<Supersampling>,
+    mut last: Local<Option<Supersampling>>,
+    mut field: Single<&mut Visibility, With<Field>>,
+    added: Query<(), Added<Number>>,
+    mut numbers: Query<(Entity, &Glyph, &mut Transform, Has<Text2d>), With<Number>>,
+) {
+    // Numbers without text are shrunk as text is, which canvas.rs only keeps up for text.
+    let previous = last.replace(*supersampling).unwrap_or(*supersampling);
+    if previous != *supersampling {
+        let ratio = previous.0 as f32 / supersampling.0 as f32;
+        for (_, _, mut transform, text) in &mut numbers {
+            if !text {
+                transform.scale *= ratio;
+            }
+        }
+    }
+    if !config.is_changed() && !file.is_changed() && !size.is_changed() && added.is_empty() {
+        return;
+    }
+    let shown = field_shown(&config, &file.glyphs, *size);
+    field.set_if_neq(if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    for (entity, glyph, mut transform, text) in &mut numbers {
+        if shown && text {
+            commands.entity(entity).remove::<NumberText>();
+        } else if !shown && !text {
+            // Supersampling the new text shrinks it again.
+            transform.scale /= supersampling.text_scale();
+            commands.entity(entity).insert(number_text(glyph.0, &file));
+        }
+    }
+}
+
+/// Draws the field in the glyphs of each file as it is opened: its pixel glyphs, or those of its
+/// atlas once the image has loaded.
+fn load_glyphs(
+    file: Res<ActiveFile>,
+    asset_server: Res<AssetServer>,
+    quad: Single<&MeshMaterial2d<FieldMaterial>, With<Field>>,
+    mut materials: ResMut<Assets<FieldMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut loading: Local<Option<(Handle<Image>, u32)>>,
+) {
+    let glyphs = if file.is_changed() {
+        *loading = file
+            .glyphs
+            .atlas()
+            .map(|(path, count)| (asset_server.load(path), count));
+        file.glyphs
+            .pixels()
+            .map(|pixels| (images.add(glyph_atlas(pixels)), GLYPH_SIZE))
+    } else {
+        None
+    };
+    // The size of an atlas's glyphs is only known once it has loaded.
+    let glyphs = glyphs.or_else(|| {
+        let (atlas, count) = loading.as_ref()?;
+        let size = images.get(atlas)?.size() / UVec2::new(*count, 1);
+        let atlas = atlas.clone();
+        *loading = None;
+        Some((atlas, size))
+    });
+    let Some((atlas, size)) = glyphs else {
+        return;
+    };
+    if let Some(material) = materials.get_mut(&quad.0) {
+        material.glyphs = atlas;
+        material.glyph = size.as_vec2().extend(0.).extend(0.);
+        material.grid.w = GLYPH_PIXEL * GLYPH_SIZE.y as f32 / size.y.max(1) as f32;
+    }
+}
+
+/// What the field draws of a number.
+type PaintedNumber<'a> = (
+    &'a Glyph,
+    &'a Cell,
+    &'a Transform,
+    &'a TextColor,
+    &'a Visibility,
+);
+
+/// Numbers whose drawing has changed since the field was last painted.
+type RepaintedNumber = Or<(
+    Changed<Glyph>,
+    Changed<Transform>,
+    Changed<TextColor>,
+    Changed<Visibility>,
+)>;
+
+/// Copies what changed about the numbers into the field's maps, only their own pixels unless the
+/// whole field has to be painted afresh.
+fn paint_field(
+    (config, file, size): (Res<Config>, Res<ActiveFile>, Res<GridSize>),
+    (supersampling, quad): (
+        Res<Supersampling>,
+        Single<&MeshMaterial2d<FieldMaterial>, With<Field>>,
+    ),
+    materials: Res<Assets<FieldMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut painted: Local<bool>,
+    numbers: Query<PaintedNumber>,
+    changed: Query<PaintedNumber, RepaintedNumber>,
+) {
+    if !field_shown(&config, &file.glyphs, *size) {
+        // Whatever changes meanwhile is painted afresh once the field is back.
+        *painted = false;
+        return;
+    }
+    let fresh = !*painted || supersampling.is_changed() || file.is_changed() || size.is_changed();
+    if !fresh && changed.is_empty() {
+        return;
+    }
+    let Some(material) = materials.get(&quad.0) else {
+        return;
+    };
+    let painting = || -> Box<dyn Iterator<Item = _>> {
+        if fresh {
+            Box::new(numbers.iter())
+        } else {
+            Box::new(changed.iter())
+        }
+    };
+    let index = |cell: &Cell| size.index(*cell) * 16;
+    if let Some(data) = images
+        .get_mut(&material.numbers)
+        .and_then(|image| image.data.as_mut())
+    {
+        for (number, cell, transform, _, visibility) in painting() {
+            let offset = transform.translation.truncate() - cell.position();
+            let glyph = if *visibility == Visibility::Hidden || number.0 >= file.glyphs.count() {
+                -1.
+            } else {
+                number.0 as f32
+            };
+            // Text is scaled down by as much as it is rendered larger, which digits are not.
+            let scale = transform.scale.x / supersampling.text_scale();
+            let pixel = float_bytes(&[offset.x, offset.y, scale, glyph]);
+            if let Some(target) = data.get_mut(index(cell)..index(cell) + 16) {
+                target.copy_from_slice(&pixel);
+            }
+        }
+    }
+    if let Some(data) = images
+        .get_mut(&material.colors)
+        .and_then(|image| image.data.as_mut())
+    {
+        for (_, cell, _, color, _) in painting() {
+            let pixel = float_bytes(&LinearRgba::from(color.0).to_f32_array());
+            if let Some(target) = data.get_mut(index(cell)..index(cell) + 16) {
+                target.copy_from_slice(&pixel);
+            }
+        }
+    }
+    *painted = true;
+}
+
+/// Moves the numbers of the field along their drift.
+fn drift_field(
+    time: Res<Time<Virtual>>,
+    config: Res<Config>,
+    overtime: Res<Overtime>,
+    theme: Res<Theme>,
+    (file, size): (Res<ActiveFile>, Res<GridSize>),
+    quad: Single<&MeshMaterial2d<FieldMaterial>, With<Field>>,
+    mut materials: ResMut<Assets<FieldMaterial>>,
+) {
+    if !field_shown(&config, &file.glyphs, *size) {
+        return;
+    }
+    let reach = if config.accessibility.reduced_motion {
+        0.
+    } else {
+        theme.drift
+    };
+    let drift = Vec4::new(drift_time(&time, *overtime), reach, 0., 0.);
+    // Looking first keeps the material from being sent again while nothing drifts.
+    if materials
+        .get(&quad.0)
+        .is_some_and(|material| material.drift != drift)
+    {
+        if let Some(material) = materials.get_mut(&quad.0) {
+            material.drift = drift;
+        }
+    }
+}
@@ -0,0 +1,632 @@
+//! The files a refiner works through, and the progress made on each.
+//!
+//! Every file has its own seed, which decides the digits of its grid, and remembers how full its
+//! bins were left. The library of files is kept in `files.ron` next to the config file, in a
+//! versioned format that older libraries are migrated from (see [`migrate`]). A library that
+//! cannot be read is left alone on disk, and the main menu says why.
+//!
+//! The library is saved every [`AUTOSAVE_INTERVAL`] while it changes, on the way back to the
+//! menu and on exit. Saves are atomic, and the library they replace is kept as `files.ron.bak`,
+//! which is read instead should `files.ron` ever go missing or be torn.
+
+mod migrate;
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*, time::common_conditions::on_real_timer};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Achievements,
+    bins::{Bin, MAX_BIN_COUNT},
+    config::{write_atomic, ConfigPath},
+    glyphs::GlyphSet,
+    grid::{GridLayout, ResetRefinement, Temper, TEMPER_SCALE},
+    state::AppState,
+};
+
+use migrate::{read_library, LoadError, SAVE_VERSION};
+
+/// Names given to new files, in order, before falling back to numbered ones.
+const NEW_FILE_NAMES: [&str; 8] = [
+    "Siena",
+    "Tumwater",
+    "Allentown",
+    "Dranesville",
+    "Jesup",
+    "Moonbeam",
+    "Labrador",
+    "Eagan",
+];
+
+/// How often the library is saved while it changes.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct FilesPlugin;
+
+impl Plugin for FilesPlugin {
+    fn build(&self, app: &mut App) {
+        let path = files_path(app.world().resource::<ConfigPath>());
+        let library = match load_library(&path) {
+            Ok(Some(library)) => library,
+            Ok(None) => FileLibrary::default(),
+            Err(error) => {
+                error!("Could not read file library {}: {error}", path.display());
+                app.insert_resource(LibraryError(format!(
+                    "{} could not be read: {error}. It is left as it is; files refined now \
+                     will not be saved.",
+                    path.display()
+                )));
+                FileLibrary::default()
+            }
+        };
+        let writable = !app.world().contains_resource::<LibraryError>();
+        app.insert_resource(library.clone())
+            .insert_resource(LibraryPath {
+                path,
+                writable,
+                saved: library,
+            })
+            .init_resource::<ActiveFile>()
+            .add_event::<OpenFile>()
+            .add_systems(PreUpdate, open_files.run_if(on_event::<OpenFile>))
+            .add_systems(
+                PostUpdate,
+                (
+                    track_progress,
+                    save_library.run_if(on_real_timer(AUTOSAVE_INTERVAL)),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(AppState::Menu), save_library)
+            .add_systems(Last, save_library.run_if(on_event::<AppExit>));
+    }
+}
+
+/// A file of the library.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileRecord {
+    pub name: String,
+    pub seed: u64,
+    /// How full each bin is, from 0 to 1. The file has as many bins as there are entries.
+    pub progress: Vec<f32>,
+    #[serde(default)]
+    pub limits: BinLimits,
+    /// How each bin looks, in order; bins past the end keep the usual look.
+    #[serde(default)]
+    pub bins: Vec<BinStyle>,
+    /// Quarters of completion the refiner has been praised for, or `None` for files saved before
+    /// praise was kept track of.
+    #[serde(default)]
+    pub praised: Option<u8>,
+    /// Scale of the noise laying out regions of each temper across the grid, in cycles per cell,
+    /// or `None` for the usual [`TEMPER_SCALE`]. The smaller it is, the larger the regions.
+    #[serde(default)]
+    pub temper_scale: Option<f32>,
+    /// The glyphs the grid is drawn with.
+    #[serde(default)]
+    pub glyphs: GlyphSet,
+    /// The order the glyphs fill the grid in.
+    #[serde(default)]
+    pub layout: GridLayout,
+    /// When the file was last opened, in seconds since the Unix epoch, or `None` for files last
+    /// opened before that was kept track of.
+    #[serde(default)]
+    pub last_played: Option<u64>,
+}
+
+/// How a bin of a file looks, where it differs from the rest.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct BinStyle {
+    /// Name shown on the bin in place of its number.
+    pub name: Option<String>,
+    /// Color of the bin and its bar, as an sRGB `(red, green, blue)` triple from 0 to 1.
+    pub color: Option<[f32; 3]>,
+    /// Character shown on the bin ahead of its name or in place of its number.
+    pub icon: Option<char>,
+    /// Temper of the numbers the bin expects, in place of its turn in the round of scary
+    /// tempers.
+    pub temper: Option<Temper>,
+}
+
+impl BinStyle {
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..default()
+        }
+    }
+}
+
+/// How much the bins of a file hold, and how quickly they empty on their own.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct BinLimits {
+    /// Progress at which a bin is full and refuses more numbers, or `None` for bins that always
+    /// take more.
+    pub capacity: Option<f32>,
+    /// Progress every bin loses per minute.
+    pub drain: f32,
+}
+
+impl BinLimits {
+    /// Whether a bin this full refuses more numbers.
+    pub fn is_full(&self, progress: f32) -> bool {
+        self.capacity.is_some_and(|capacity| progress >= capacity)
+    }
+}
+
+impl FileRecord {
+    fn new(name: &str, progress: Vec<f32>) -> Self {
+        let mut file = Self {
+            name: name.to_string(),
+            seed: name_seed(name),
+            progress,
+            limits: BinLimits::default(),
+            bins: Vec::new(),
+            praised: None,
+            temper_scale: None,
+            glyphs: GlyphSet::default(),
+            layout: GridLayout::default(),
+            last_played: None,
+        };
+        // Milestones the file starts past were never reached by the refiner.
+        file.praised = Some(file.quarters());
+        file
+    }
+
+    /// Empties the file's bins, as if it had never been refined.
+    pub fn start_over(&mut self) {
+        self.progress.fill(0.);
+        self.praised = Some(self.quarters());
+    }
+
+    /// How much of the file is refined, from 0 to 1.
+    pub fn completion(&self) -> f32 {
+        self.progress.iter().sum::<f32>() / self.progress.len().max(1) as f32
+    }
+
+    /// How many of the milestones at 25, 50 and 75% the file is past.
+    pub fn quarters(&self) -> u8 {
+        (self.completion() * 4.).clamp(0., 3.) as u8
+    }
+}
+
+/// Every file the refiner has, persisted between runs.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct FileLibrary {
+    /// Version of the save format the library follows.
+    version: u32,
+    pub files: Vec<FileRecord>,
+    /// Index of the file opened last, which the menu offers to continue.
+    pub last_opened: Option<usize>,
+    pub achievements: Achievements,
+}
+
+impl Default for FileLibrary {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            files: vec![FileRecord::new(
+                "Cold Harbor",
+                vec![0.75, 0.45, 0.90, 0.30, 0.60],
+            )],
+            last_opened: None,
+            achievements: Achievements::default(),
+        }
+    }
+}
+
+impl FileLibrary {
+    /// Reads the library at `path`, or `None` if there is no file there.
+    fn load(path: &Path) -> io::Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        read_library(&contents).map(Some).map_err(|error| {
+            let kind = match error {
+                LoadError::Invalid(_) => io::ErrorKind::InvalidData,
+                LoadError::Newer(_) => io::ErrorKind::Unsupported,
+            };
+            io::Error::new(kind, error)
+        })
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if path.exists() {
+            let backup = backup_path(path);
+            if let Err(error) = fs::copy(path, &backup) {
+                warn!(
+                    "Could not back up file library to {}: {error}",
+                    backup.display()
+                );
+            }
+        }
+        write_atomic(path, &contents)
+    }
+
+    /// Adds an untouched file with the next free name and the given number of bins, and returns
+    /// its index.
+    pub fn create(&mut self, bins: usize) -> usize {
+        let taken = |name: &str| self.files.iter().any(|file| file.name == name);
+        let name = NEW_FILE_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain((1..).map(|n| format!("File {n:03}")))
+            .find(|name| !taken(name))
+            .expect("there are infinitely many file names");
+        let bins = bins.clamp(1, MAX_BIN_COUNT);
+        self.files.push(FileRecord::new(&name, vec![0.; bins]));
+        self.files.len() - 1
+    }
+
+    /// Starts the file named `name` over, untouched, with the given number of bins and a grid
+    /// of its own, adding it if there is none, and returns its index.
+    pub fn recycle(&mut self, name: &str, bins: usize) -> usize {
+        let mut file = FileRecord::new(name, vec![0.; bins.clamp(1, MAX_BIN_COUNT)]);
+        file.seed = fastrand::u64(..);
+        match self.files.iter().position(|file| file.name == name) {
+            Some(index) => {
+                self.files[index] = file;
+                index
+            }
+            None => {
+                self.files.push(file);
+                self.files.len() - 1
+            }
+        }
+    }
+}
+
+/// Where the [`FileLibrary`] is persisted.
+#[derive(Resource, Clone, Debug)]
+struct LibraryPath {
+    path: PathBuf,
+    /// Whether the library may be saved there, which it may not when what is there could not be
+    /// read.
+    writable: bool,
+    /// The library as it was last read or saved, so that saving it unchanged can be skipped.
+    saved: FileLibrary,
+}
+
+/// Why the library on disk could not be read, shown by the main menu until dismissed.
+#[derive(Resource, Clone, Debug)]
+pub struct LibraryError(pub String);
+
+fn files_path(config: &ConfigPath) -> PathBuf {
+    config.0.with_file_name("files.ron")
+}
+
+/// Where the library a save replaced is kept.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    backup.into()
+}
+
+/// Reads the library at `path`, falling back to its backup if it is missing or torn.
+fn load_library(path: &Path) -> io::Result<Option<FileLibrary>> {
+    let loaded = FileLibrary::load(path);
+    let lost = match &loaded {
+        Ok(library) => library.is_none(),
+        Err(error) => error.kind() == io::ErrorKind::InvalidData,
+    };
+    if lost {
+        let backup = backup_path(path);
+        if let Ok(Some(library)) = FileLibrary::load(&backup) {
+            warn!("Restored file library from {}", backup.display());
+            return Ok(Some(library));
+        }
+    }
+    loaded
+}
+
+/// Derives a file's seed from its name (FNV-1a), so files keep their grid across runs.
+pub fn name_seed(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// The file the grid and bins currently show.
+///
+/// This is the state the file was opened in; the grid and bins are put back to it whenever the
+/// refinement is reset.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ActiveFile {
+    pub name: String,
+    /// Decides the initial digits of the grid.
+    pub seed: u64,
+    /// How full each bin was when the file was opened, which also decides how many bins it has.
+    pub progress: Vec<f32>,
+    pub limits: BinLimits,
+    /// How each bin looks; bins past the end keep the usual look.
+    pub styles: Vec<BinStyle>,
+    /// Scale of the noise laying out the tempers of the grid.
+    pub temper_scale: f32,
+    /// The glyphs the grid is drawn with.
+    pub glyphs: GlyphSet,
+    /// The order the glyphs fill the grid in.
+    pub layout: GridLayout,
+    /// Index of the file in the [`FileLibrary`], or `None` for files that are not the
+    /// refiner's own, such as a shared session's. Never written out, so that files read back are
+    /// nobody's.
+    #[serde(skip)]
+    pub record: Option<usize>,
+}
+
+impl Default for ActiveFile {
+    fn default() -> Self {
+        let file = &FileLibrary::default().files[0];
+        Self {
+            name: file.name.clone(),
+            seed: file.seed,
+            progress: file.progress.clone(),
+            limits: file.limits,
+            styles: file.bins.clone(),
+            temper_scale: file.temper_scale.unwrap_or(TEMPER_SCALE),
+            glyphs: file.glyphs.clone(),
+            layout: file.layout,
+            record: None,
+        }
+    }
+}
+
+/// Files are written as one line of text, `FILE <seed> <bins> <capacity> <drain>
+/// noise:<temper scale> glyphs:<glyph set> layout:<grid layout> <progress>... <name>` with `-` for
+/// no capacity, which is how they are stored in replays. Lines from before tempers followed noise
+/// have no `noise:`, which reads as the usual scale, `glyphs:` is only written for files not drawn
+/// in digits, and `layout:` only for files not laid out in the usual way.
+impl fmt::Display for ActiveFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FILE {} {}", self.seed, self.progress.len())?;
+        match self.limits.capacity {
+            Some(capacity) => write!(f, " {capacity}")?,
+            None => write!(f, " -")?,
+        }
+        write!(f, " {} noise:{}", self.limits.drain, self.temper_scale)?;
+        if self.glyphs != GlyphSet::Digits {
+            write!(f, " glyphs:{}", self.glyphs)?;
+        }
+        if self.layout != GridLayout::default() {
+            write!(f, " layout:{}", self.layout)?;
+        }
+        for progress in &self.progress {
+            write!(f, " {progress}")?;
+        }
+        write!(f, " {}", self.name)
+    }
+}
+
+/// Error returned when a line of text is not an [`ActiveFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFileError;
+
+impl fmt::Display for ParseFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a file")
+    }
+}
+
+impl FromStr for ActiveFile {
+    type Err = ParseFileError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let rest = line.trim().strip_prefix("FILE ").ok_or(ParseFileError)?;
+        let (seed, rest) = rest.split_once(' ').ok_or(ParseFileError)?;
+        let seed = seed.parse().map_err(|_| ParseFileError)?;
+        let (bins, rest) = rest.split_once(' ').ok_or(ParseFileError)?;
+        let bins: usize = bins.parse().map_err(|_| ParseFileError)?;
+        if !(1..=MAX_BIN_COUNT).contains(&bins) {
+            return Err(ParseFileError);
+        }
+        let (capacity, rest) = rest.split_once(' ').ok_or(ParseFileError)?;
+        let capacity = match capacity {
+            "-" => None,
+            capacity => Some(capacity.parse().map_err(|_| ParseFileError)?),
+        };
+        let (drain, mut rest) = rest.split_once(' ').ok_or(ParseFileError)?;
+        let drain = drain.parse().map_err(|_| ParseFileError)?;
+        let mut temper_scale = TEMPER_SCALE;
+        if let Some((scale, tail)) = rest
+            .strip_prefix("noise:")
+            .and_then(|rest| rest.split_once(' '))
+        {
+            temper_scale = scale.parse().map_err(|_| ParseFileError)?;
+            rest = tail;
+        }
+        let mut glyphs = GlyphSet::Digits;
+        if let Some((set, tail)) = rest
+            .strip_prefix("glyphs:")
+            .and_then(|rest| rest.split_once(' '))
+        {
+            glyphs = set.parse().map_err(|_| ParseFileError)?;
+            rest = tail;
+        }
+        let mut layout = GridLayout::default();
+        if let Some((word, tail)) = rest
+            .strip_prefix("layout:")
+            .and_then(|rest| rest.split_once(' '))
+        {
+            layout = word.parse().map_err(|_| ParseFileError)?;
+            rest = tail;
+        }
+        let mut progress = vec![0.; bins];
+        for bin in &mut progress {
+            let (value, tail) = rest.split_once(' ').ok_or(ParseFileError)?;
+            *bin = value.parse().map_err(|_| ParseFileError)?;
+            rest = tail;
+        }
+        Ok(Self {
+            name: rest.to_string(),
+            seed,
+            progress,
+            limits: BinLimits { capacity, drain },
+            styles: Vec::new(),
+            temper_scale,
+            glyphs,
+            layout,
+            record: None,
+        })
+    }
+}
+
+/// Opens the file of the library with the given index, regenerating the grid for it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct OpenFile(pub usize);
+
+fn open_files(
+    mut opens: EventReader<OpenFile>,
+    mut library: ResMut<FileLibrary>,
+    mut active: ResMut<ActiveFile>,
+    mut resets: EventWriter<ResetRefinement>,
+) {
+    for &OpenFile(index) in opens.read() {
+        let Some(file) = library.files.get(index) else {
+            continue;
+        };
+        *active = ActiveFile {
+            name: file.name.clone(),
+            seed: file.seed,
+            progress: file.progress.clone(),
+            limits: file.limits,
+            styles: file.bins.clone(),
+            temper_scale: file.temper_scale.unwrap_or(TEMPER_SCALE),
+            glyphs: file.glyphs.clone(),
+            layout: file.layout,
+            record: Some(index),
+        };
+        library.last_opened = Some(index);
+        library.files[index].last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|since| since.as_secs());
+        resets.write(ResetRefinement);
+    }
+}
+
+/// Keeps the library's copy of the open file up to date with its bins.
+pub fn track_progress(
+    active: Res<ActiveFile>,
+    mut library: ResMut<FileLibrary>,
+    bins: Query<&Bin, Changed<Bin>>,
+) {
+    let Some(file) = active.record.and_then(|index| library.files.get_mut(index)) else {
+        return;
+    };
+    for bin in &bins {
+        if let Some(progress) = file.progress.get_mut(bin.index) {
+            if *progress != bin.progress {
+                *progress = bin.progress;
+            }
+        }
+    }
+}
+
+fn save_library(library: Res<FileLibrary>, mut path: ResMut<LibraryPath>) {
+    if !path.writable || path.saved == *library {
+        return;
+    }
+    match library.save(&path.path) {
+        Ok(()) => path.saved = library.clone(),
+        Err(error) => error!(
+            "Could not save file library to {}: {error}",
+            path.path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_started_over_are_untouched() {
+        let mut file = FileLibrary::default().files.remove(0);
+        file.start_over();
+        assert_eq!(file.progress, vec![0.; 5]);
+        assert_eq!(file.praised, Some(0));
+    }
+
+    #[test]
+    fn recycled_files_start_over_in_place() {
+        let mut library = FileLibrary::default();
+        let index = library.recycle("Kiosk", 3);
+        assert_eq!(library.files.len(), 2);
+        library.files[index].progress = vec![1.; 3];
+        let seed = library.files[index].seed;
+        assert_eq!(library.recycle("Kiosk", 3), index);
+        assert_eq!(library.files.len(), 2);
+        assert_eq!(library.files[index].progress, vec![0.; 3]);
+        assert_ne!(library.files[index].seed, seed);
+    }
+
+    #[test]
+    fn file_lines_round_trip() {
+        let file = ActiveFile {
+            name: "Cold Harbor 2".to_string(),
+            seed: 42,
+            progress: vec![0.25, 1., 0.],
+            limits: BinLimits {
+                capacity: Some(0.8),
+                drain: 0.05,
+            },
+            temper_scale: 0.12,
+            glyphs: GlyphSet::Custom("abc".to_string()),
+            layout: "tr-columns".parse().unwrap(),
+            ..default()
+        };
+        let line = file.to_string();
+        let read: ActiveFile = line.parse().unwrap();
+        // Lines leave out how the bins look, and whose the file is.
+        assert_eq!(
+            read,
+            ActiveFile {
+                styles: Vec::new(),
+                ..file
+            }
+        );
+        assert_eq!(read.to_string(), line);
+    }
+
+    #[test]
+    fn file_lines_leave_out_the_usual() {
+        let line = "FILE 7 2 - 0 noise:0.08 0.5 0.75 Siena";
+        let file: ActiveFile = line.parse().unwrap();
+        assert_eq!(file.limits.capacity, None);
+        assert_eq!(file.glyphs, GlyphSet::Digits);
+        assert_eq!(file.layout, GridLayout::default());
+        assert_eq!(file.to_string(), line);
+    }
+
+    #[test]
+    fn file_lines_without_noise_take_the_usual_scale() {
+        let file: ActiveFile = "FILE 7 1 - 0 0.5 Siena".parse().unwrap();
+        assert_eq!(file.temper_scale, TEMPER_SCALE);
+        assert_eq!(file.progress, vec![0.5]);
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_file_line() {
+        for line in [
+            "",
+            "SELECT 1 2 3 4",
+            "FILE x 1 - 0 0.5 Siena",
+            "FILE 7 0 - 0 Siena",
+            "FILE 7 3 - 0 0.5 0.5",
+        ] {
+            assert_eq!(line.parse::<ActiveFile>(), Err(ParseFileError), "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! The versions of the save format of the [`FileLibrary`], and how older ones are brought up
+//! to date.
+//!
+//! Every library is written with the version of the format it follows. Reading one first looks
+//! only at that version, then reads the rest as that version's schema and migrates it one
+//! version at a time to the current one. A library from a newer release is refused rather than
+//! guessed at, so that it is not overwritten with what this release made of it.
+//!
+//! The versions so far:
+//!
+//! 1. Libraries written before the version was: files have a list of bin progress, or a tuple
+//!    of five in the oldest ones, and may lack their limits, bin styles and praise.
+//! 2. The version is written, and the progress of bins is always a list.
+
+use std::{error::Error, fmt};
+
+use serde::{Deserialize, Deserializer};
+
+use super::{Achievements, BinLimits, BinStyle, FileLibrary, FileRecord};
+use crate::{glyphs::GlyphSet, grid::GridLayout};
+
+/// Version of the save format this release writes.
+pub const SAVE_VERSION: u32 = 2;
+
+/// Why a library could not be read.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The library is not one of a version this release knows.
+    Invalid(ron::error::SpannedError),
+    /// The library was written by a newer release, in a version this one does not know.
+    Newer(u32),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Invalid(error) => write!(f, "{error}"),
+            LoadError::Newer(version) => write!(
+                f,
+                "saved in format version {version}, but this release only reads up to \
+                 {SAVE_VERSION}"
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Reads a library of any version up to [`SAVE_VERSION`], migrating it to the current one.
+pub fn read_library(contents: &str) -> Result<FileLibrary, LoadError> {
+    let Versioned { version } = ron::from_str(contents).map_err(LoadError::Invalid)?;
+    match version {
+        1 => ron::from_str::<LibraryV1>(contents)
+            .map(FileLibrary::from)
+            .map_err(LoadError::Invalid),
+        SAVE_VERSION => ron::from_str(contents).map_err(LoadError::Invalid),
+        newer => Err(LoadError::Newer(newer)),
+    }
+}
+
+/// Just the version of a library, whatever else it holds.
+#[derive(Deserialize)]
+#[serde(rename = "FileLibrary")]
+struct Versioned {
+    #[serde(default = "unversioned")]
+    version: u32,
+}
+
+/// Version of libraries that do not say.
+fn unversioned() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FileLibrary")]
+struct LibraryV1 {
+    #[serde(default)]
+    files: Vec<FileRecordV1>,
+    #[serde(default)]
+    last_opened: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FileRecord")]
+struct FileRecordV1 {
+    name: String,
+    seed: u64,
+    #[serde(deserialize_with = "progress_list")]
+    progress: Vec<f32>,
+    #[serde(default)]
+    limits: BinLimits,
+    #[serde(default)]
+    bins: Vec<BinStyle>,
+    #[serde(default)]
+    praised: Option<u8>,
+}
+
+/// Reads the progress of a file's bins, which the oldest libraries wrote as a tuple of five.
+fn progress_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Progress {
+        List(Vec<f32>),
+        Legacy((f32, f32, f32, f32, f32)),
+    }
+    Ok(match Progress::deserialize(deserializer)? {
+        Progress::List(list) => list,
+        Progress::Legacy((a, b, c, d, e)) => vec![a, b, c, d, e],
+    })
+}
+
+impl From<LibraryV1> for FileLibrary {
+    fn from(library: LibraryV1) -> Self {
+        let files = library
+            .files
+            .into_iter()
+            .map(|file| FileRecord {
+                name: file.name,
+                seed: file.seed,
+                progress: file.progress,
+                limits: file.limits,
+                bins: file.bins,
+                praised: file.praised,
+                temper_scale: None,
+                glyphs: GlyphSet::default(),
+                layout: GridLayout::default(),
+                last_played: None,
+            })
+            .collect();
+        Self {
+            version: SAVE_VERSION,
+            files,
+            last_opened: library.last_opened,
+            achievements: Achievements::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_current_version() {
+        let library = FileLibrary::default();
+        let contents =
+            ron::ser::to_string_pretty(&library, ron::ser::PrettyConfig::default()).unwrap();
+        assert_eq!(read_library(&contents).unwrap(), library);
+    }
+
+    #[test]
+    fn migrates_unversioned_libraries() {
+        let contents = r#"(
+            files: [
+                (name: "Siena", seed: 7, progress: [0.5, 0.25]),
+                (
+                    name: "Dranesville",
+                    seed: 9,
+                    progress: (0.1, 0.2, 0.3, 0.4, 0.5),
+                    praised: Some(2),
+                ),
+            ],
+            last_opened: Some(1),
+        )"#;
+        let library = read_library(contents).unwrap();
+        assert_eq!(library.version, SAVE_VERSION);
+        assert_eq!(library.last_opened, Some(1));
+        assert_eq!(library.files[0].name, "Siena");
+        assert_eq!(library.files[0].progress, vec![0.5, 0.25]);
+        assert_eq!(library.files[1].progress, vec![0.1, 0.2, 0.3, 0.4, 0.5]);
+        assert_eq!(library.files[1].praised, Some(2));
+        assert_eq!(library.files[1].glyphs, GlyphSet::default());
+        assert_eq!(library.files[1].temper_scale, None);
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let contents = format!("(version: {}, files: [])", SAVE_VERSION + 1);
+        assert!(matches!(
+            read_library(&contents),
+            Err(LoadError::Newer(version)) if version == SAVE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_library() {
+        assert!(matches!(
+            read_library("not a library"),
+            Err(LoadError::Invalid(_))
+        ));
+    }
+}
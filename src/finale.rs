@@ -0,0 +1,134 @@
+//! The celebration as a file reaches 100%: balloons drift up and confetti falls over the grid.
+//!
+//! The finale plays for a few seconds on the [particle system](crate::particles), then sends
+//! [`FinaleEnded`], which is the cue for whatever follows the file. For those who asked for less
+//! motion there is nothing to watch, so it ends at once.
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::CanvasSize,
+    config::Config,
+    files::{track_progress, ActiveFile, FileLibrary},
+    particles::{Motion, ParticleBurst},
+    theme::Theme,
+};
+
+/// Seconds the finale plays for.
+const FINALE_TIME: f32 = 4.;
+
+/// Seconds between handfuls of confetti.
+const CONFETTI_INTERVAL: f32 = 0.12;
+
+/// Pieces of confetti in a handful.
+const CONFETTI_HANDFUL: usize = 4;
+
+/// Balloons let go as the finale starts.
+const BALLOONS: usize = 10;
+
+pub struct FinalePlugin;
+
+impl Plugin for FinalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FinaleEnded>()
+            .add_systems(
+                PostUpdate,
+                start_finale
+                    .after(track_progress)
+                    .run_if(resource_changed::<FileLibrary>),
+            )
+            .add_systems(Update, play_finale.run_if(resource_exists::<Finale>));
+    }
+}
+
+/// The finale of the open file has played out.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FinaleEnded;
+
+/// The finale under way.
+#[derive(Resource)]
+struct Finale {
+    /// Seconds left of it.
+    left: f32,
+    /// Seconds until the next handful of confetti.
+    confetti: f32,
+}
+
+/// Starts the finale as the open file goes from under 100% to complete.
+fn start_finale(
+    mut commands: Commands,
+    active: Res<ActiveFile>,
+    library: Res<FileLibrary>,
+    mut was: Local<Option<(usize, bool)>>,
+) {
+    let Some(index) = active.record else {
+        return;
+    };
+    let Some(file) = library.files.get(index) else {
+        return;
+    };
+    let complete = file.completion() >= 1.;
+    // A file opened already complete has had its finale.
+    let finished = *was == Some((index, false)) && complete;
+    *was = Some((index, complete));
+    if finished {
+        info!("{} is complete", file.name);
+        commands.insert_resource(Finale {
+            left: FINALE_TIME,
+            confetti: 0.,
+        });
+    }
+}
+
+fn play_finale(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    (config, theme): (Res<Config>, Res<Theme>),
+    canvas: Res<CanvasSize>,
+    mut finale: ResMut<Finale>,
+    mut bursts: EventWriter<ParticleBurst>,
+    mut ended: EventWriter<FinaleEnded>,
+) {
+    let colors = [
+        theme.selection(),
+        Color::srgb_from_array(theme.success),
+        Color::srgb_from_array(theme.warning),
+        Color::srgb_from_array(theme.info),
+        Color::srgb_from_array(theme.error),
+    ];
+    let color = || colors[fastrand::usize(..colors.len())];
+    let (width, height) = (canvas.size().x, canvas.size().y);
+    let motion = !config.accessibility.reduced_motion;
+    if motion && finale.left == FINALE_TIME {
+        for balloon in 0..BALLOONS {
+            // Spread across the canvas, each a little off its place.
+            let x = (balloon as f32 + 0.2 + fastrand::f32() * 0.6) / BALLOONS as f32 - 0.5;
+            bursts.write(ParticleBurst {
+                position: Vec2::new(x * width, -height / 2. - fastrand::f32() * 40.),
+                color: color(),
+                count: 1,
+                motion: Motion::Float,
+            });
+        }
+    }
+    finale.left -= time.delta_secs();
+    finale.confetti -= time.delta_secs();
+    // The last handful is thrown a second before the end, to be mostly down by then.
+    while motion && finale.confetti <= 0. && finale.left > FINALE_TIME / 4. {
+        finale.confetti += CONFETTI_INTERVAL;
+        for _ in 0..CONFETTI_HANDFUL {
+            let x = (fastrand::f32() - 0.5) * width;
+            let y = height / 2. + fastrand::f32() * 8.;
+            bursts.write(ParticleBurst {
+                position: Vec2::new(x, y),
+                color: color(),
+                count: 1,
+                motion: Motion::Flutter,
+            });
+        }
+    }
+    if !motion || finale.left <= 0. {
+        commands.remove_resource::<Finale>();
+        ended.write(FinaleEnded);
+    }
+}
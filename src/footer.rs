@@ -0,0 +1,294 @@
+//! The footer: widgets along the bottom of the canvas, beneath the bins, for a dashboard to be
+//! read at a glance. On the left, the weather; on the right, the next event of a calendar.
+//!
+//! Each is turned on by naming a URL in the footer config, fetched as a [`Feed`] every few
+//! minutes. The weather is whatever line of text the provider answers with, such as wttr.in's
+//! `http://wttr.in/?format=%C+%t`. The calendar is an iCalendar document, of which the next
+//! event to start is shown, all-day events through the day they are on; repeating events only
+//! count where they first start. Both keep what they last showed while their feed is failing.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::{prelude::*, sprite::Anchor, time::common_conditions::on_real_timer};
+
+use crate::{
+    canvas::{CanvasAnchor, PIXEL_PERFECT_LAYERS},
+    clock::{local_stamp, utc_seconds, MONTHS},
+    config::Config,
+    net::http::Feed,
+    toast::Toast,
+};
+
+const TEXT_COLOR: Color = Color::srgba(0.0, 0.9, 1.0, 0.7);
+
+/// Most characters a widget shows.
+const MAX_CHARS: usize = 32;
+
+/// How often the widgets take what their feeds have fetched and look for the next event.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct FooterPlugin;
+
+impl Plugin for FooterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Feeds>().add_systems(
+            Update,
+            (
+                start_feeds.run_if(resource_changed::<Config>),
+                show_widgets.run_if(on_real_timer(REFRESH_INTERVAL)),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// An event of the calendar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CalendarEvent {
+    /// When it starts, in local time, as [`local_stamp`] writes it, or just the date of an
+    /// all-day event, as in `20261014`.
+    start: String,
+    summary: String,
+}
+
+/// The feeds of the widgets that are on, and what they last fetched.
+#[derive(Resource, Default)]
+struct Feeds {
+    weather: Option<Feed<String>>,
+    calendar: Option<Feed<Vec<CalendarEvent>>>,
+    forecast: Option<String>,
+    events: Vec<CalendarEvent>,
+}
+
+/// A widget's text.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+enum Widget {
+    Weather,
+    Calendar,
+}
+
+/// Fetches each widget's URL, starting again whenever the config names another, and stopping
+/// once it names none.
+fn start_feeds(config: Res<Config>, mut feeds: ResMut<Feeds>) {
+    let footer = &config.footer;
+    let minutes = |minutes: u32| Duration::from_secs(u64::from(minutes.max(1)) * 60);
+    if feeds.weather.as_ref().map(Feed::url) != Some(footer.weather.as_str()) {
+        feeds.forecast = None;
+        feeds.weather = (!footer.weather.is_empty()).then(|| {
+            Feed::spawn(
+                &footer.weather,
+                minutes(footer.weather_minutes),
+                parse_weather,
+            )
+        });
+    }
+    if feeds.calendar.as_ref().map(Feed::url) != Some(footer.calendar.as_str()) {
+        feeds.events.clear();
+        feeds.calendar = (!footer.calendar.is_empty()).then(|| {
+            Feed::spawn(
+                &footer.calendar,
+                minutes(footer.calendar_minutes),
+                parse_calendar,
+            )
+        });
+    }
+}
+
+/// Takes what the feeds have fetched, and spawns, updates or despawns each widget's text.
+fn show_widgets(
+    mut commands: Commands,
+    mut feeds: ResMut<Feeds>,
+    mut widgets: Query<(Entity, &Widget, &mut Text2d)>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let feeds = &mut *feeds;
+    if let Some(weather) = &feeds.weather {
+        if let Some(forecast) = weather.take_update() {
+            feeds.forecast = Some(forecast);
+        }
+        if weather.take_error().is_some() {
+            toasts.write(Toast::warning("Lost the weather"));
+        }
+    }
+    if let Some(calendar) = &feeds.calendar {
+        if let Some(events) = calendar.take_update() {
+            feeds.events = events;
+        }
+        if calendar.take_error().is_some() {
+            toasts.write(Toast::warning("Lost the calendar"));
+        }
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64);
+    let texts = [
+        (Widget::Weather, feeds.forecast.clone()),
+        (
+            Widget::Calendar,
+            next_event(&feeds.events, &local_stamp(now)),
+        ),
+    ];
+    for (widget, text) in texts {
+        let text = text.map(|text| shown(&text));
+        let existing = widgets.iter_mut().find(|(_, kind, _)| **kind == widget);
+        match (text, existing) {
+            (Some(text), Some((_, _, mut shown))) => {
+                if shown.0 != text {
+                    shown.0 = text;
+                }
+            }
+            (Some(text), None) => {
+                let (anchor, corner) = match widget {
+                    Widget::Weather => {
+                        (Anchor::BottomLeft, CanvasAnchor::BOTTOM_LEFT.offset(4., 3.))
+                    }
+                    Widget::Calendar => (
+                        Anchor::BottomRight,
+                        CanvasAnchor::BOTTOM_RIGHT.offset(-4., 3.),
+                    ),
+                };
+                commands.spawn((
+                    widget,
+                    Text2d::new(text),
+                    TextFont {
+                        font_size: 8.0,
+                        ..default()
+                    },
+                    TextColor(TEXT_COLOR),
+                    anchor,
+                    corner,
+                    Transform::from_xyz(0., 0., 16.),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            }
+            (None, Some((entity, ..))) => commands.entity(entity).despawn(),
+            (None, None) => {}
+        }
+    }
+}
+
+/// `text` as a widget shows it: in capitals, within [`MAX_CHARS`], and only in the characters
+/// the font has.
+fn shown(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii)
+        .take(MAX_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_uppercase()
+}
+
+/// The first line of the provider's answer.
+fn parse_weather(body: &str) -> Result<String, String> {
+    body.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| "no weather in the answer".to_string())
+}
+
+/// The events of an iCalendar document, in the order they start.
+fn parse_calendar(body: &str) -> Result<Vec<CalendarEvent>, String> {
+    if !body.contains("BEGIN:VCALENDAR") {
+        return Err("not an iCalendar document".to_string());
+    }
+    // Long lines go on after a line break and a space or tab.
+    let mut lines: Vec<String> = Vec::new();
+    for line in body.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    let mut events = Vec::new();
+    let (mut start, mut summary) = (None, None);
+    let mut in_event = false;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters such as the time zone follow the property's name.
+        let property = name.split(';').next().unwrap_or(name);
+        match (property, value) {
+            ("BEGIN", "VEVENT") => {
+                in_event = true;
+                (start, summary) = (None, None);
+            }
+            ("END", "VEVENT") => {
+                in_event = false;
+                if let (Some(start), Some(summary)) = (start.take(), summary.take()) {
+                    events.push(CalendarEvent { start, summary });
+                }
+            }
+            ("DTSTART", _) if in_event => start = parse_start(value),
+            ("SUMMARY", _) if in_event => summary = Some(unescape(value)),
+            _ => {}
+        }
+    }
+    events.sort_by(|a, b| a.start.cmp(&b.start));
+    Ok(events)
+}
+
+/// When an event starts, from its `DTSTART`: a date, a local time, or a time in UTC ending in
+/// `Z`, which is turned into local time. Times in other zones are taken to be local.
+fn parse_start(value: &str) -> Option<String> {
+    let digits = |text: &str| !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+    let (date, time) = value.split_once('T').unwrap_or((value, ""));
+    if date.len() != 8 || !digits(date) {
+        return None;
+    }
+    if time.is_empty() {
+        return Some(date.to_string());
+    }
+    let (time, utc) = match time.strip_suffix('Z') {
+        Some(time) => (time, true),
+        None => (time, false),
+    };
+    if time.len() < 4 || !digits(time) {
+        return None;
+    }
+    if !utc {
+        return Some(format!("{date}T{}", &time[..4]));
+    }
+    let number = |text: &str| text.parse::<u32>().unwrap_or_default();
+    let seconds = utc_seconds(
+        i64::from(number(&date[..4])),
+        number(&date[4..6]),
+        number(&date[6..]),
+        number(&time[..2]),
+        number(&time[2..4]),
+    );
+    Some(local_stamp(seconds))
+}
+
+/// Text with its escapes undone, and line breaks as spaces.
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// The next event to start after `now`, as a [`local_stamp`], with when it starts.
+fn next_event(events: &[CalendarEvent], now: &str) -> Option<String> {
+    let today = &now[..8];
+    let event = events.iter().find(|event| {
+        if event.start.len() == 8 {
+            event.start.as_str() >= today
+        } else {
+            event.start.as_str() >= now
+        }
+    })?;
+    let date = &event.start[..8];
+    let when = if event.start.len() == 8 && date == today {
+        "TODAY".to_string()
+    } else if date == today {
+        format!("{}:{}", &event.start[9..11], &event.start[11..13])
+    } else {
+        let month: usize = date[4..6].parse().unwrap_or(1);
+        let day: u32 = date[6..].parse().unwrap_or(1);
+        format!("{day} {}", MONTHS[(month + 11) % 12])
+    };
+    Some(format!("NEXT {when} {}", event.summary))
+}
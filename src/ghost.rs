@@ -0,0 +1,47 @@
+//! Ghost mode: the window turned see-through, so the screen can sit over the refiner's actual
+//! work and be refined in between.
+//!
+//! Toggled with F8 anywhere, or set in the video config along with how opaque the window is
+//! while a ghost. The canvas and the letterboxing around it are both faded, and whatever is under
+//! the window shows through, where the platform's compositor allows for it.
+
+use bevy::prelude::*;
+
+use crate::{canvas::Canvas, config::Config};
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (toggle_key, apply_opacity.run_if(resource_changed::<Config>)).chain(),
+        );
+    }
+}
+
+fn toggle_key(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<Config>) {
+    if keys.just_pressed(KeyCode::F8) {
+        config.video.ghost ^= true;
+    }
+}
+
+fn apply_opacity(
+    config: Res<Config>,
+    mut clear_color: ResMut<ClearColor>,
+    mut layers: Query<&mut Sprite, With<Canvas>>,
+) {
+    let opacity = if config.video.ghost {
+        config.video.ghost_opacity.clamp(0., 1.)
+    } else {
+        1.
+    };
+    if clear_color.0.alpha() != opacity {
+        clear_color.0.set_alpha(opacity);
+    }
+    for mut layer in &mut layers {
+        if layer.color.alpha() != opacity {
+            layer.color.set_alpha(opacity);
+        }
+    }
+}
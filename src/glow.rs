@@ -0,0 +1,206 @@
+//! A soft glow behind the numbers near the cursor, brighter for the scary ones once hints have
+//! started.
+//!
+//! Text can't take a custom material, so the glow is drawn beneath the glyphs instead: one
+//! grid-sized quad with a [`GlowMaterial`], whose shader works out the glow around every number
+//! from the cursor position and a one-pixel-per-cell map of the unfound scary numbers. Being a
+//! single draw it costs the same however many numbers glow, and leaves the text batched as
+//! before. The map is only repainted when the scary numbers or the found ones change.
+
+use bevy::{
+    asset::{load_internal_asset, weak_handle, RenderAssetUsages},
+    prelude::*,
+    render::render_resource::{AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat},
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+
+use crate::{
+    canvas::{CanvasCursor, GRID_LAYERS},
+    config::Config,
+    files::ActiveFile,
+    grid::{grid_bounds, Cell, GridSize, RefineSet, Refined, ResetRefinement, NUMBER_SPACING},
+    hints::{track_found, Hints},
+    overtime::Overtime,
+    state::AppState,
+    theme::Theme,
+};
+
+/// How far from the cursor numbers glow, in world units.
+const GLOW_REACH: f32 = 50.;
+
+/// Opacity of the brightest glow.
+const GLOW_ALPHA: f32 = 0.6;
+
+const GLOW_SHADER: Handle<Shader> = weak_handle!("9b2e6f4a-3c71-4d8e-b5a0-7e1f2c9d4a36");
+
+pub struct GlowPlugin;
+
+impl Plugin for GlowPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, GLOW_SHADER, "shaders/glow.wgsl", Shader::from_wgsl);
+        app.add_plugins(Material2dPlugin::<GlowMaterial>::default())
+            .add_systems(Startup, setup_glow)
+            .add_systems(
+                Update,
+                (
+                    fit_glow
+                        .run_if(resource_changed::<GridSize>.and(not(resource_added::<GridSize>))),
+                    paint_scary_map.after(track_found).run_if(
+                        on_event::<Refined>
+                            .or(on_event::<ResetRefinement>)
+                            .or(resource_changed::<ActiveFile>)
+                            .or(resource_changed::<Overtime>)
+                            .or(resource_changed::<GridSize>),
+                    ),
+                    follow_cursor,
+                )
+                    .chain()
+                    .in_set(RefineSet::React),
+            );
+    }
+}
+
+/// Material of the glow quad.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct GlowMaterial {
+    /// Grid position of the cursor, how far from it numbers glow (0 for not at all), and how
+    /// much brighter scary numbers glow, as laid out in the shader's `Glow` struct.
+    #[uniform(0)]
+    cursor: Vec4,
+    /// Grid position of the first cell, and the spacing between cells.
+    #[uniform(0)]
+    grid: Vec4,
+    #[uniform(0)]
+    color: LinearRgba,
+    /// One pixel per cell, red where an unfound scary number is.
+    #[texture(1)]
+    scary_map: Handle<Image>,
+}
+
+impl Material2d for GlowMaterial {
+    fn fragment_shader() -> ShaderRef {
+        GLOW_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// Marks the glow quad.
+#[derive(Component)]
+struct Glow;
+
+/// A map of no scary numbers, a pixel per cell.
+fn scary_map(size: GridSize) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: size.columns,
+            height: size.rows,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+fn setup_glow(
+    mut commands: Commands,
+    (theme, size): (Res<Theme>, Res<GridSize>),
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let origin = Cell { col: 0, row: 0 }.position();
+    let bounds = grid_bounds(*size);
+    commands.spawn((
+        Glow,
+        Mesh2d(meshes.add(Rectangle::from_size(bounds.size() + NUMBER_SPACING))),
+        MeshMaterial2d(materials.add(GlowMaterial {
+            cursor: Vec4::ZERO,
+            grid: Vec4::new(origin.x, origin.y, NUMBER_SPACING, 0.),
+            color: theme.selection().with_alpha(GLOW_ALPHA).into(),
+            scary_map: images.add(scary_map(*size)),
+        })),
+        // Above the gridlines, below the numbers.
+        Transform::from_translation(bounds.center().extend(-0.25)),
+        GRID_LAYERS,
+    ));
+}
+
+/// Fits the glow to the grid as it is resized, with a map as large as it.
+fn fit_glow(
+    size: Res<GridSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut glow: Single<(&mut Mesh2d, &MeshMaterial2d<GlowMaterial>, &mut Transform), With<Glow>>,
+) {
+    let (mesh, material, transform) = &mut *glow;
+    let bounds = grid_bounds(*size);
+    mesh.0 = meshes.add(Rectangle::from_size(bounds.size() + NUMBER_SPACING));
+    transform.translation = bounds.center().extend(transform.translation.z);
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.scary_map = images.add(scary_map(*size));
+    }
+}
+
+/// Marks the unfound scary numbers on the map.
+fn paint_scary_map(
+    hints: Res<Hints>,
+    size: Res<GridSize>,
+    quad: Single<&MeshMaterial2d<GlowMaterial>, With<Glow>>,
+    materials: Res<Assets<GlowMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(material) = materials.get(&quad.0) else {
+        return;
+    };
+    let Some(data) = images
+        .get_mut(&material.scary_map)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+    data.fill(0);
+    for cell in hints.unfound().filter(|cell| size.contains(*cell)) {
+        // Images start at the top, the grid at the bottom.
+        let index = size.image_index(cell) * 4;
+        if let Some(pixel) = data.get_mut(index) {
+            *pixel = 255;
+        }
+    }
+}
+
+/// Moves the glow with the cursor, and follows the hints and the theme.
+fn follow_cursor(
+    pointer: CanvasCursor,
+    config: Res<Config>,
+    state: Res<State<AppState>>,
+    hints: Res<Hints>,
+    theme: Res<Theme>,
+    quad: Single<&MeshMaterial2d<GlowMaterial>, With<Glow>>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+) {
+    let cursor = pointer
+        .grid()
+        .filter(|_| *state.get() == AppState::Refining);
+    let uniform = match cursor {
+        Some(cursor) => cursor.extend(GLOW_REACH).extend(hints.ramp(&config)),
+        None => Vec4::ZERO,
+    };
+    let color = LinearRgba::from(theme.selection().with_alpha(GLOW_ALPHA));
+    // Only touch the material when it changes, as that uploads it again.
+    let Some(material) = materials.get(&quad.0) else {
+        return;
+    };
+    if material.cursor == uniform && material.color == color {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&quad.0) {
+        material.cursor = uniform;
+        material.color = color;
+    }
+}
@@ -0,0 +1,191 @@
+//! The glyphs the numbers of a file's grid are drawn with, which are digits unless the file says
+//! otherwise.
+//!
+//! Every cell holds the index of a glyph of its file's [`GlyphSet`] rather than a digit, and its
+//! number shows whichever glyph that is. Digits, hex and custom characters are drawn as text, and
+//! digits and hex also by the [field](crate::field) from pixel glyphs of their own. Katakana and
+//! custom characters need the canvas font to have them, and are always drawn as text. A set cut
+//! from an atlas image in the assets is drawn by the field only, whatever the `digits` setting.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Pixel glyphs of the hex digits, three pixels wide and five high, a row per entry from the
+/// top. The decimal digits are the first ten.
+pub const PIXEL_GLYPHS: [[u8; 5]; 16] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b010, 0b101, 0b111, 0b101, 0b101],
+    [0b110, 0b101, 0b110, 0b101, 0b110],
+    [0b011, 0b100, 0b100, 0b100, 0b011],
+    [0b110, 0b101, 0b101, 0b101, 0b110],
+    [0b111, 0b100, 0b110, 0b100, 0b111],
+    [0b111, 0b100, 0b110, 0b100, 0b100],
+];
+
+const HEX: &str = "0123456789ABCDEF";
+
+/// The basic katakana, from a to n.
+const KATAKANA: &str =
+    "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワヲン";
+
+/// The glyphs a file's grid is drawn with.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum GlyphSet {
+    #[default]
+    Digits,
+    /// The hex digits, 0 to F.
+    Hex,
+    Katakana,
+    /// The characters of the string, in order.
+    Custom(String),
+    /// `count` glyphs side by side across an image in the assets, all of a size.
+    Atlas {
+        image: String,
+        count: u32,
+    },
+}
+
+impl GlyphSet {
+    /// The characters of the set, or `None` for one cut from an atlas.
+    fn chars(&self) -> Option<&str> {
+        match self {
+            GlyphSet::Digits => Some(&HEX[..10]),
+            GlyphSet::Hex => Some(HEX),
+            GlyphSet::Katakana => Some(KATAKANA),
+            GlyphSet::Custom(chars) => Some(chars),
+            GlyphSet::Atlas { .. } => None,
+        }
+    }
+
+    /// Number of glyphs in the set, never fewer than one.
+    pub fn count(&self) -> u32 {
+        let count = match (self, self.chars()) {
+            (GlyphSet::Atlas { count, .. }, _) => *count,
+            (_, Some(chars)) => chars.chars().count() as u32,
+            (_, None) => 0,
+        };
+        count.max(1)
+    }
+
+    /// The text of the glyph with the given index, which is empty for glyphs only the field can
+    /// draw.
+    pub fn text(&self, index: u32) -> String {
+        self.chars()
+            .and_then(|chars| chars.chars().nth(index as usize))
+            .map(String::from)
+            .unwrap_or_default()
+    }
+
+    /// Whether the glyphs can be drawn as text.
+    pub fn has_text(&self) -> bool {
+        self.chars().is_some()
+    }
+
+    /// The pixel glyphs the field draws the set with, if it has any.
+    pub fn pixels(&self) -> Option<&'static [[u8; 5]]> {
+        match self {
+            GlyphSet::Digits => Some(&PIXEL_GLYPHS[..10]),
+            GlyphSet::Hex => Some(&PIXEL_GLYPHS),
+            _ => None,
+        }
+    }
+
+    /// Path of the atlas image in the assets, and how many glyphs are across it.
+    pub fn atlas(&self) -> Option<(&str, u32)> {
+        match self {
+            GlyphSet::Atlas { image, count } => Some((image, *count)),
+            _ => None,
+        }
+    }
+}
+
+/// Glyph sets are written as one word for the lines of files in replays: `digits`, `hex`,
+/// `katakana`, `custom:<characters>` or `atlas:<count>:<image>`, neither of which may hold
+/// spaces.
+impl fmt::Display for GlyphSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GlyphSet::Digits => write!(f, "digits"),
+            GlyphSet::Hex => write!(f, "hex"),
+            GlyphSet::Katakana => write!(f, "katakana"),
+            GlyphSet::Custom(chars) => write!(f, "custom:{chars}"),
+            GlyphSet::Atlas { image, count } => write!(f, "atlas:{count}:{image}"),
+        }
+    }
+}
+
+/// Error returned when a word is not a [`GlyphSet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseGlyphSetError;
+
+impl fmt::Display for ParseGlyphSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a glyph set")
+    }
+}
+
+impl FromStr for GlyphSet {
+    type Err = ParseGlyphSetError;
+
+    fn from_str(word: &str) -> Result<Self, Self::Err> {
+        match word.split_once(':') {
+            None => match word {
+                "digits" => Ok(GlyphSet::Digits),
+                "hex" => Ok(GlyphSet::Hex),
+                "katakana" => Ok(GlyphSet::Katakana),
+                _ => Err(ParseGlyphSetError),
+            },
+            Some(("custom", chars)) if !chars.is_empty() => Ok(GlyphSet::Custom(chars.into())),
+            Some(("atlas", rest)) => {
+                let (count, image) = rest.split_once(':').ok_or(ParseGlyphSetError)?;
+                Ok(GlyphSet::Atlas {
+                    image: image.to_string(),
+                    count: count.parse().map_err(|_| ParseGlyphSetError)?,
+                })
+            }
+            Some(_) => Err(ParseGlyphSetError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_sets_round_trip() {
+        for set in [
+            GlyphSet::Digits,
+            GlyphSet::Hex,
+            GlyphSet::Katakana,
+            GlyphSet::Custom("ABC".to_string()),
+            GlyphSet::Atlas {
+                image: "glyphs/runes.png".to_string(),
+                count: 12,
+            },
+        ] {
+            assert_eq!(set.to_string().parse(), Ok(set));
+        }
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_glyph_set() {
+        for word in ["", "binary", "custom:", "atlas:many:runes.png", "atlas:12"] {
+            assert_eq!(
+                word.parse::<GlyphSet>(),
+                Err(ParseGlyphSetError),
+                "{word:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Animated film grain drawn over the canvas, to break up flat colors.
+//!
+//! The grain is a shader pass of its own: one canvas-sized quad with a [`GrainMaterial`] at
+//! [`GRAIN_Z`], above everything on the canvas but the cursor. Other full-screen passes layer
+//! themselves relative to it. With the grain turned off the quad is despawned, so it costs
+//! nothing.
+
+use bevy::{
+    asset::{load_internal_asset, weak_handle},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+
+use crate::{
+    canvas::{CanvasSize, PIXEL_PERFECT_LAYERS},
+    config::Config,
+};
+
+/// Depth of the grain pass on the canvas.
+pub const GRAIN_Z: f32 = 90.;
+
+const GRAIN_SHADER: Handle<Shader> = weak_handle!("5d3b1c1e-8f0a-4c57-9a43-2e5f7a6b9c10");
+
+pub struct GrainPlugin;
+
+impl Plugin for GrainPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, GRAIN_SHADER, "shaders/grain.wgsl", Shader::from_wgsl);
+        app.add_plugins(Material2dPlugin::<GrainMaterial>::default())
+            .add_systems(
+                Update,
+                (
+                    toggle_grain
+                        .run_if(resource_changed::<Config>.or(resource_changed::<CanvasSize>)),
+                    animate_grain,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Material of the grain quad.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct GrainMaterial {
+    /// Intensity, seconds since startup, and the canvas size in pixels, as laid out in the
+    /// shader's `Grain` struct.
+    #[uniform(0)]
+    grain: Vec4,
+}
+
+impl Material2d for GrainMaterial {
+    fn fragment_shader() -> ShaderRef {
+        GRAIN_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// Marks the grain quad.
+#[derive(Component)]
+struct Grain;
+
+/// Spawns or despawns the grain quad as it is turned on and off, and follows its intensity and
+/// the size of the canvas.
+fn toggle_grain(
+    mut commands: Commands,
+    config: Res<Config>,
+    canvas: Res<CanvasSize>,
+    mut quads: Query<(Entity, &mut Mesh2d, &MeshMaterial2d<GrainMaterial>), With<Grain>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GrainMaterial>>,
+) {
+    let intensity = config.video.film_grain;
+    let size = canvas.size();
+    match quads.single_mut() {
+        Ok((entity, ..)) if intensity <= 0. => commands.entity(entity).despawn(),
+        Ok((_, mut mesh, material)) => {
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.grain.x = intensity;
+                if material.grain.zw() != size {
+                    material.grain.z = size.x;
+                    material.grain.w = size.y;
+                    mesh.0 = meshes.add(Rectangle::from_size(size));
+                }
+            }
+        }
+        Err(_) if intensity > 0. => {
+            commands.spawn((
+                Grain,
+                Mesh2d(meshes.add(Rectangle::from_size(size))),
+                MeshMaterial2d(materials.add(GrainMaterial {
+                    grain: Vec4::new(intensity, 0., size.x, size.y),
+                })),
+                Transform::from_xyz(0., 0., GRAIN_Z),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        }
+        Err(_) => {}
+    }
+}
+
+fn animate_grain(
+    time: Res<Time<Real>>,
+    quad: Option<Single<&MeshMaterial2d<GrainMaterial>, With<Grain>>>,
+    mut materials: ResMut<Assets<GrainMaterial>>,
+) {
+    let Some(quad) = quad else {
+        return;
+    };
+    if let Some(material) = materials.get_mut(&quad.0) {
+        material.grain.y = time.elapsed_secs_wrapped();
+    }
+}
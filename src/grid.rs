@@ -0,0 +1,1170 @@
+//! The field of numbers being refined, and the shared selection on it.
+//!
+//! What every cell holds is kept in the [`GridModel`], which the number entities follow. The grid
+//! is much larger than the canvas, so numbers outside the grid camera's view are hidden and
+//! skipped by the systems that animate them, and shown again as the view pans onto them.
+//!
+//! The grid can be resized as it is being refined, from the settings, which it follows, or with a
+//! [`ResizeGrid`], which leaves them be. Either way the resize is a [`GridAction`], so that peers
+//! and replays resize along with it. Cells it keeps go on holding what they held, and numbers are
+//! spawned for the cells it gains and despawned for those it loses, to be culled like the rest.
+//!
+//! Which refinements catch a cluster of scary numbers is up to a
+//! [`ScaryDetector`](detect::ScaryDetector). Selections it says catch none are refused, and
+//! their numbers [scatter](scatter) instead, unless something else drives the bins; so are those
+//! of more cells than the config's `max_selection`.
+
+pub mod detect;
+mod layout;
+mod model;
+pub mod scatter;
+
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use bevy::{prelude::*, render::view::VisibilitySystems};
+use serde::{Deserialize, Serialize};
+
+use self::{
+    detect::{AnyTagged, ClusterCaught, ExactMatch, Overlap, RegisterScaryDetector},
+    scatter::{RefinementRefused, Scatter},
+};
+use crate::{
+    audio::{PlaySound, Sound},
+    bins::{Bin, BinLayout, BinRefused, DrivenBins},
+    canvas::{CanvasCursor, CanvasSize, GridCamera, Supersampling, GRID_LAYERS},
+    config::{Config, Difficulty, GameplayConfig},
+    field::field_shown,
+    files::ActiveFile,
+    minimap,
+    overtime::Overtime,
+    picking::HoverChanged,
+    signature::{Noticed, Tilt},
+    theme::Theme,
+    tween::RegisterTween,
+    zoom::Zoom,
+};
+
+pub use layout::GridLayout;
+pub use model::{GridModel, Temper};
+
+/// Spacing between numbers.
+pub const NUMBER_SPACING: f32 = 20.;
+
+/// Size of the numbers' digits, before any temper's signature weighs them.
+pub const NUMBER_FONT_SIZE: f32 = 12.;
+
+/// Number of grid columns, unless the settings say otherwise.
+pub const GRID_COLUMNS: u32 = 50;
+
+/// Number of grid rows, unless the settings say otherwise.
+pub const GRID_ROWS: u32 = 50;
+
+/// Fewest and most columns, or rows, a grid can have. The minimap has a pixel for every cell.
+pub const GRID_SIZE_RANGE: RangeInclusive<u32> = 10..=100;
+
+/// World position of the first cell, at the bottom left of the grid, where the bottom left
+/// corner of the view starts out.
+const GRID_ORIGIN: Vec2 = Vec2::new(-256., -128.);
+
+/// Opacity of the selection box over the numbers.
+const SELECTION_BOX_ALPHA: f32 = 0.2;
+
+/// Shortest time between two hover ticks, in seconds.
+const TICK_INTERVAL: f32 = 0.035;
+
+/// Range of the random pitch of hover ticks.
+const TICK_PITCH: std::ops::Range<f32> = 0.8..1.25;
+
+/// Radians per second numbers drift at, before [`Overtime`] speeds them up.
+const DRIFT_RATE: f32 = 0.4;
+
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        let gameplay = &app.world().resource::<Config>().gameplay;
+        let (size, rules) = (gameplay.grid.clamped(), RefineRules::of(gameplay));
+        app.init_resource::<Selection>()
+            .insert_resource(rules)
+            .init_resource::<Dragging>()
+            .insert_resource(size)
+            .add_event::<ResizeGrid>()
+            .add_event::<RequestAction>()
+            .add_event::<ApplyAction>()
+            .add_event::<Refined>()
+            .add_event::<ResetRefinement>()
+            .add_event::<ClusterCaught>()
+            .add_event::<RefinementRefused>()
+            .register_tween::<Scatter>()
+            .init_resource::<detect::DetectorRegistry>()
+            .register_scary_detector("any", AnyTagged)
+            .register_scary_detector("overlap", Overlap(detect::OVERLAP_SHARE))
+            .register_scary_detector("exact", ExactMatch)
+            .configure_sets(
+                Update,
+                (
+                    RefineSet::Input,
+                    RefineSet::Route,
+                    RefineSet::Apply,
+                    RefineSet::React,
+                    RefineSet::Sync,
+                )
+                    .chain(),
+            )
+            .add_systems(Startup, (setup_numbers, setup_selection_box))
+            .add_systems(
+                Update,
+                (
+                    (
+                        pointer_input,
+                        keyboard_input,
+                        hover_ticks,
+                        (
+                            refit_view.run_if(
+                                resource_changed::<CanvasSize>.or(resource_changed::<GridSize>),
+                            ),
+                            pan_view,
+                        )
+                            .chain(),
+                    )
+                        .in_set(RefineSet::Input),
+                    // Outside the input set, which pauses, so that the grid follows the settings
+                    // as they change.
+                    (
+                        request_resizes,
+                        follow_rules.run_if(
+                            (resource_changed::<Config>.or(resource_removed::<HostRules>))
+                                .and(not(resource_exists::<HostRules>)),
+                        ),
+                    )
+                        .after(RefineSet::Input)
+                        .before(RefineSet::Route),
+                    (
+                        reset_grid.run_if(on_event::<ResetRefinement>),
+                        apply_actions,
+                        respawn_numbers.run_if(resource_changed::<GridSize>),
+                        detect::detect_clusters,
+                    )
+                        .chain()
+                        .in_set(RefineSet::Apply),
+                    (
+                        tint_selection,
+                        (scatter::scatter_numbers, drift_numbers).chain(),
+                        recolor_selection_box.run_if(resource_changed::<Theme>),
+                        outline_selection_box,
+                    )
+                        .in_set(RefineSet::React),
+                    sync_numbers
+                        .run_if(resource_changed::<GridModel>)
+                        .in_set(RefineSet::Sync),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                cull_numbers.before(VisibilitySystems::VisibilityPropagate),
+            );
+    }
+}
+
+/// Ordering of the refinement pipeline within [`Update`].
+///
+/// Input systems emit [`RequestAction`]s, the router turns them into [`ApplyAction`]s (possibly
+/// by way of the network), and the grid and bins only ever change in response to the latter.
+/// The numbers show what the [`GridModel`] holds once everything else has had its say.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RefineSet {
+    Input,
+    Route,
+    Apply,
+    React,
+    Sync,
+}
+
+/// A number of the grid, showing the glyph its cell of the [`GridModel`] holds.
+#[derive(Component)]
+pub struct Number;
+
+/// Index of the glyph a number shows, in the open file's [`GlyphSet`].
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyph(pub u32);
+
+/// Position of a number in the grid.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cell {
+    pub col: u32,
+    pub row: u32,
+}
+
+impl Cell {
+    /// World position of the centre of this cell.
+    pub fn position(self) -> Vec2 {
+        GRID_ORIGIN + Vec2::new(self.col as f32, self.row as f32) * NUMBER_SPACING
+    }
+}
+
+/// How many columns and rows the grid has.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct GridSize {
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Default for GridSize {
+    fn default() -> Self {
+        Self {
+            columns: GRID_COLUMNS,
+            rows: GRID_ROWS,
+        }
+    }
+}
+
+impl GridSize {
+    /// The size, brought within the [`GRID_SIZE_RANGE`].
+    pub fn clamped(self) -> Self {
+        let (min, max) = (*GRID_SIZE_RANGE.start(), *GRID_SIZE_RANGE.end());
+        Self {
+            columns: self.columns.clamp(min, max),
+            rows: self.rows.clamp(min, max),
+        }
+    }
+
+    pub fn contains(self, cell: Cell) -> bool {
+        cell.col < self.columns && cell.row < self.rows
+    }
+
+    /// The cell at the top right.
+    pub fn last(self) -> Cell {
+        Cell {
+            col: self.columns.saturating_sub(1),
+            row: self.rows.saturating_sub(1),
+        }
+    }
+
+    /// Every cell, row by row from the bottom.
+    pub fn cells(self) -> impl Iterator<Item = Cell> {
+        (0..self.rows).flat_map(move |row| (0..self.columns).map(move |col| Cell { col, row }))
+    }
+
+    /// Index of a cell counting row by row from the bottom, as images of the grid lay it out.
+    pub fn index(self, cell: Cell) -> usize {
+        (cell.row * self.columns + cell.col) as usize
+    }
+
+    /// Index of a cell counting row by row from the top, as images start at the top.
+    pub fn image_index(self, cell: Cell) -> usize {
+        ((self.rows - 1 - cell.row) * self.columns + cell.col) as usize
+    }
+}
+
+/// Resizes the grid, keeping what the cells it keeps hold, without changing the setting it
+/// otherwise follows; it is back to the setting's size once that changes.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ResizeGrid(pub GridSize);
+
+/// World-space rectangle covered by the grid's numbers.
+pub fn grid_bounds(size: GridSize) -> Rect {
+    Rect::from_corners(Cell { col: 0, row: 0 }.position(), size.last().position())
+}
+
+/// Keeps a view of the grid `view` pixels in size centred at `center` from wandering more than a
+/// cell past the grid.
+pub fn clamp_view(center: Vec2, view: Vec2, size: GridSize) -> Vec2 {
+    let half = view / 2.;
+    let bounds = grid_bounds(size);
+    let min = bounds.min + half - NUMBER_SPACING;
+    let max = (bounds.max - half + NUMBER_SPACING).max(min);
+    center.clamp(min, max)
+}
+
+/// Returns the cell whose number is under a world position, if any.
+pub fn cell_at(position: Vec2, size: GridSize) -> Option<Cell> {
+    let origin = Cell { col: 0, row: 0 }.position();
+    let cell = ((position - origin) / NUMBER_SPACING).round();
+    let cell = cell.cmpge(Vec2::ZERO).all().then_some(Cell {
+        col: cell.x as u32,
+        row: cell.y as u32,
+    });
+    let cell = cell.filter(|cell| size.contains(*cell))?;
+    // Only close to the digit itself, not the gaps between numbers.
+    (position.distance(cell.position()) <= NUMBER_SPACING / 3.).then_some(cell)
+}
+
+/// The settings that decide which refinements count and how they fill the bins, which follow the
+/// config unless [`HostRules`] are in place.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct RefineRules {
+    pub difficulty: Difficulty,
+    /// Name of the [`ScaryDetector`](detect::ScaryDetector) in use, or `None` for the
+    /// difficulty's own.
+    pub detector: Option<String>,
+    /// Most cells a selection may hold and still be refined, or 0 for no limit.
+    pub max_selection: u32,
+}
+
+impl RefineRules {
+    pub fn of(gameplay: &GameplayConfig) -> Self {
+        Self {
+            difficulty: gameplay.difficulty,
+            detector: gameplay.detector.clone(),
+            max_selection: gameplay.max_selection,
+        }
+    }
+}
+
+/// Present while the [`RefineRules`] are a shared session's host's, which every peer has to refine
+/// by to keep in step, rather than the config's.
+#[derive(Resource)]
+pub struct HostRules;
+
+/// The inclusive range of cells currently selected, shared by every refiner of the grid.
+#[derive(Resource, Default, Debug)]
+pub struct Selection(pub Option<URect>);
+
+impl Selection {
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.0.is_some_and(|range| {
+            (range.min.x..=range.max.x).contains(&cell.col)
+                && (range.min.y..=range.max.y).contains(&cell.row)
+        })
+    }
+
+    /// How many cells `range` holds.
+    pub fn cell_count(range: URect) -> u32 {
+        (range.width() + 1) * (range.height() + 1)
+    }
+}
+
+/// The range of cells the selection being dragged out would select, updated every frame of the
+/// drag, and `None` outside of one.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct Dragging(pub Option<URect>);
+
+/// Translucent rectangle drawn while dragging out a selection.
+#[derive(Component)]
+struct SelectionBox;
+
+/// A side of the selection box's border, as the direction it faces from the middle.
+#[derive(Component)]
+struct SelectionEdge(Vec2);
+
+/// Something a refiner did to the grid.
+///
+/// Actions are expressed in grid cells rather than world positions so that every peer of a shared
+/// session applies them identically, whatever their window size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GridAction {
+    /// Replace the selection with every cell in the inclusive range.
+    Select(URect),
+    /// Drop the selection.
+    ClearSelection,
+    /// Send the selected numbers to a bin.
+    Refine { bin: usize },
+    /// Give the grid this many columns and rows, brought within the [`GRID_SIZE_RANGE`].
+    Resize(GridSize),
+}
+
+/// Actions are written as one line of text, e.g. `SELECT 3 4 10 12` or `REFINE 2`, which is how
+/// they travel over the network and are stored in replays.
+impl fmt::Display for GridAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridAction::Select(range) => write!(
+                f,
+                "SELECT {} {} {} {}",
+                range.min.x, range.min.y, range.max.x, range.max.y
+            ),
+            GridAction::ClearSelection => write!(f, "CLEAR"),
+            GridAction::Refine { bin } => write!(f, "REFINE {bin}"),
+            GridAction::Resize(size) => write!(f, "SIZE {} {}", size.columns, size.rows),
+        }
+    }
+}
+
+/// Error returned when a line of text is not a [`GridAction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseActionError;
+
+impl fmt::Display for ParseActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a grid action")
+    }
+}
+
+impl FromStr for GridAction {
+    type Err = ParseActionError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let kind = words.next().ok_or(ParseActionError)?;
+        let numbers: Vec<u32> = words
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| ParseActionError)?;
+        match (kind, numbers.as_slice()) {
+            ("SELECT", &[c0, r0, c1, r1]) => Ok(GridAction::Select(URect::new(c0, r0, c1, r1))),
+            ("CLEAR", &[]) => Ok(GridAction::ClearSelection),
+            ("REFINE", &[bin]) => Ok(GridAction::Refine { bin: bin as usize }),
+            ("SIZE", &[columns, rows]) => Ok(GridAction::Resize(GridSize { columns, rows })),
+            _ => Err(ParseActionError),
+        }
+    }
+}
+
+/// A local refiner wants to perform an action.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RequestAction(pub GridAction);
+
+/// An action has been agreed upon and must be applied to the grid.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ApplyAction(pub GridAction);
+
+/// Puts the grid and the bins back the way they were when the [`ActiveFile`] was opened.
+///
+/// Handled at the start of [`RefineSet::Apply`], so actions applied in the same frame build on it.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct ResetRefinement;
+
+/// Numbers were refined into a bin.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Refined {
+    pub bin: usize,
+    pub count: u32,
+    /// The selection the numbers were refined from.
+    pub cells: URect,
+}
+
+/// The glyph a cell holds when a file with the given seed and number of glyphs is opened.
+fn initial_glyph(seed: u64, cell: Cell, glyphs: u32) -> u32 {
+    let hash = (seed ^ (u64::from(cell.col) << 32 | u64::from(cell.row)))
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    ((hash >> 32) % u64::from(glyphs.max(1))) as u32
+}
+
+/// Number of clusters of scary numbers hidden in every file.
+const CLUSTER_COUNT: u64 = 12;
+
+/// How far a cluster reaches from its centre, in cells.
+const CLUSTER_RADIUS: f32 = 2.2;
+
+/// Centres of the clusters of scary numbers in a file with the given seed and grid size, in cells.
+fn cluster_centers(seed: u64, size: GridSize) -> impl Iterator<Item = Vec2> {
+    (0..CLUSTER_COUNT).map(move |index| {
+        let hash =
+            (seed ^ index.wrapping_mul(0xd6e8_feb8_6659_fd93)).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        Vec2::new(
+            ((hash >> 16) % u64::from(size.columns.max(1))) as f32,
+            ((hash >> 40) % u64::from(size.rows.max(1))) as f32,
+        )
+    })
+}
+
+/// How close together regions of the same temper are unless a file says otherwise, as the scale
+/// of the noise that lays them out, in cycles per cell.
+pub const TEMPER_SCALE: f32 = 0.08;
+
+/// Gradient noise over the grid for a file with the given seed, from about -1 to 1 and changing
+/// smoothly from one place to the next.
+fn temper_noise(seed: u64, at: Vec2) -> f32 {
+    let seed = seed ^ 0x5851_f42d_4c95_7f2d;
+    let gradient = |corner: IVec2| {
+        let hash = (seed ^ (u64::from(corner.x as u32) << 32 | u64::from(corner.y as u32)))
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        Vec2::from_angle((hash >> 40) as f32 / (1 << 24) as f32 * std::f32::consts::TAU)
+    };
+    let corner = at.floor();
+    let within = at - corner;
+    let slope = |x, y| {
+        let offset = Vec2::new(x, y);
+        gradient(corner.as_ivec2() + offset.as_ivec2()).dot(within - offset)
+    };
+    // Eased across the square, so the noise has no creases along its edges.
+    let fade = within * within * within * (within * (within * 6. - 15.) + 10.);
+    let bottom = slope(0., 0.) + (slope(1., 0.) - slope(0., 0.)) * fade.x;
+    let top = slope(0., 1.) + (slope(1., 1.) - slope(0., 1.)) * fade.x;
+    bottom + (top - bottom) * fade.y
+}
+
+/// The temper of each cluster of scary numbers of a file with the given seed on a grid of the
+/// given size, in the order of [`cluster_centers`].
+///
+/// The tempers follow a noise field of the given scale laid over the grid, so that clusters near
+/// each other tend to share one, as if the file had regions of woe or of malice. Each temper
+/// still goes to as many clusters as the others: the clusters are ranked by the noise where they
+/// are, and the tempers shared out along the ranking.
+fn cluster_tempers(seed: u64, scale: f32, size: GridSize) -> Vec<Temper> {
+    let noise: Vec<f32> = cluster_centers(seed, size)
+        .map(|center| temper_noise(seed, center * scale))
+        .collect();
+    let mut ranking: Vec<usize> = (0..noise.len()).collect();
+    ranking.sort_by(|a, b| noise[*a].total_cmp(&noise[*b]));
+    let mut tempers = vec![Temper::Calm; noise.len()];
+    for (rank, index) in ranking.into_iter().enumerate() {
+        tempers[index] = Temper::SCARY[rank * Temper::SCARY.len() / noise.len()];
+    }
+    tempers
+}
+
+/// Every cell holding one of the scary numbers of a file with the given seed on a grid of the
+/// given size, with clusters reaching `spread` times as far as usual.
+///
+/// Scary numbers lie in rough clusters, which is what refiners are looking for. Like the digits,
+/// they depend on nothing but the seed, and the size of the grid they are spread across.
+pub fn scary_cells(seed: u64, spread: f32, size: GridSize) -> Vec<Cell> {
+    let centers: Vec<Vec2> = cluster_centers(seed, size).collect();
+    size.cells()
+        .filter(|cell| {
+            let at = Vec2::new(cell.col as f32, cell.row as f32);
+            centers
+                .iter()
+                .any(|center| at.distance(*center) <= CLUSTER_RADIUS * spread)
+        })
+        .collect()
+}
+
+/// The cells of each cluster of scary numbers of a file with the given seed on a grid of the
+/// given size, a list per cluster.
+pub fn clusters(seed: u64, size: GridSize) -> Vec<Vec<Cell>> {
+    cluster_centers(seed, size)
+        .map(|center| {
+            size.cells()
+                .filter(|cell| {
+                    let at = Vec2::new(cell.col as f32, cell.row as f32);
+                    at.distance(center) <= CLUSTER_RADIUS
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The number of a cell holding a glyph.
+fn number(cell: Cell, glyph: u32, color: Color) -> impl Bundle {
+    (
+        Number,
+        Glyph(glyph),
+        cell,
+        Transform::from_translation(cell.position().extend(0.)),
+        TextColor(color),
+        Visibility::default(),
+        Tilt::default(),
+        GRID_LAYERS,
+    )
+}
+
+/// The text of a number showing `glyph`, at the canvas's own resolution, which supersampling
+/// enlarges it from.
+pub(crate) fn number_text(glyph: u32, file: &ActiveFile) -> impl Bundle {
+    (
+        Text2d::new(file.glyphs.text(glyph)),
+        TextFont {
+            font_size: NUMBER_FONT_SIZE,
+            ..default()
+        },
+    )
+}
+
+/// Spawns the number of a cell, with its text unless the field draws it. Numbers the field draws
+/// are shrunk as supersampled text is instead, as every system scaling numbers expects.
+fn spawn_number(
+    commands: &mut Commands,
+    (cell, glyph): (Cell, u32),
+    (file, color): (&ActiveFile, Color),
+    field: Option<Supersampling>,
+) {
+    let mut number = commands.spawn(number(cell, glyph, color));
+    match field {
+        Some(supersampling) => number.insert(
+            Transform::from_translation(cell.position().extend(0.))
+                .with_scale(Vec3::splat(supersampling.text_scale())),
+        ),
+        None => number.insert(number_text(glyph, file)),
+    };
+}
+
+/// The clusters of the open file, worked out again only for another file or grid size.
+#[derive(Default)]
+pub struct ClusterCache {
+    key: Option<(u64, GridSize)>,
+    clusters: Vec<Vec<Cell>>,
+}
+
+impl ClusterCache {
+    /// The clusters of scary numbers of a file with the given seed on a grid of the given size.
+    pub fn get(&mut self, seed: u64, size: GridSize) -> &[Vec<Cell>] {
+        if self.key != Some((seed, size)) {
+            self.key = Some((seed, size));
+            self.clusters = clusters(seed, size);
+        }
+        &self.clusters
+    }
+}
+
+fn setup_numbers(
+    mut commands: Commands,
+    (config, supersampling): (Res<Config>, Res<Supersampling>),
+    file: Res<ActiveFile>,
+    size: Res<GridSize>,
+    overtime: Res<Overtime>,
+    theme: Res<Theme>,
+) {
+    let field = field_shown(&config, &file.glyphs, *size).then_some(*supersampling);
+    let mut model = GridModel::new(&file, *size);
+    let color = overtime.number_color(&theme);
+    for (cell, state) in model.cells_mut() {
+        spawn_number(&mut commands, (cell, state.value), (&file, color), field);
+    }
+    commands.insert_resource(model);
+}
+
+/// Asks for the grid to be resized as the setting changes, or as a [`ResizeGrid`] asks.
+fn request_resizes(
+    mut resizes: EventReader<ResizeGrid>,
+    (config, size): (Res<Config>, Res<GridSize>),
+    mut followed: Local<Option<GridSize>>,
+    mut requests: EventWriter<RequestAction>,
+) {
+    // The grid starts out at the setting's size, so only changes to it are followed.
+    let setting = config.gameplay.grid.clamped();
+    let mut target = followed
+        .filter(|followed| *followed != setting)
+        .map(|_| setting);
+    *followed = Some(setting);
+    if let Some(ResizeGrid(resized)) = resizes.read().last() {
+        target = Some(resized.clamped());
+    }
+    if let Some(target) = target.filter(|target| target != &*size) {
+        requests.write(RequestAction(GridAction::Resize(target)));
+    }
+}
+
+fn follow_rules(config: Res<Config>, mut rules: ResMut<RefineRules>) {
+    rules.set_if_neq(RefineRules::of(&config.gameplay));
+}
+
+/// Resizes the grid, keeping the cells it keeps and dropping what is selected of the rest.
+fn resize(
+    target: GridSize,
+    size: &mut GridSize,
+    model: &mut GridModel,
+    selection: &mut Selection,
+    file: &ActiveFile,
+) {
+    let target = target.clamped();
+    if *size == target {
+        return;
+    }
+    *size = target;
+    model.resize(file, target);
+    // What remains of the selection stays selected.
+    if let Some(range) = selection.0 {
+        let last = target.last();
+        let cropped = URect::from_corners(range.min, range.max.min(UVec2::new(last.col, last.row)));
+        selection.0 = (range.min.x <= last.col && range.min.y <= last.row).then_some(cropped);
+    }
+}
+
+/// Spawns numbers for the cells the grid has gained, and despawns those of the cells it lost.
+fn respawn_numbers(
+    mut commands: Commands,
+    (config, supersampling): (Res<Config>, Res<Supersampling>),
+    (size, model, file): (Res<GridSize>, Res<GridModel>, Res<ActiveFile>),
+    (overtime, theme): (Res<Overtime>, Res<Theme>),
+    numbers: Query<(Entity, &Cell), With<Number>>,
+) {
+    let mut spawned = vec![false; (size.columns * size.rows) as usize];
+    for (entity, cell) in &numbers {
+        if size.contains(*cell) {
+            spawned[size.index(*cell)] = true;
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    let field = field_shown(&config, &file.glyphs, *size).then_some(*supersampling);
+    let color = overtime.number_color(&theme);
+    for cell in size.cells().filter(|cell| !spawned[size.index(*cell)]) {
+        let glyph = model.get(cell).map_or(0, |state| state.value);
+        spawn_number(&mut commands, (cell, glyph), (&file, color), field);
+    }
+}
+
+fn setup_selection_box(mut commands: Commands, theme: Res<Theme>) {
+    commands
+        .spawn((
+            SelectionBox,
+            Sprite {
+                color: theme.selection().with_alpha(SELECTION_BOX_ALPHA),
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 2.),
+            Visibility::Hidden,
+            GRID_LAYERS,
+        ))
+        .with_children(|selection_box| {
+            for side in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+                selection_box.spawn((
+                    SelectionEdge(side),
+                    Sprite::default(),
+                    Transform::from_xyz(0., 0., 0.01),
+                    GRID_LAYERS,
+                ));
+            }
+        });
+}
+
+fn recolor_selection_box(theme: Res<Theme>, mut sprite: Single<&mut Sprite, With<SelectionBox>>) {
+    sprite.color = theme.selection().with_alpha(SELECTION_BOX_ALPHA);
+}
+
+/// Fits the border the theme asks for inside the selection box.
+fn outline_selection_box(
+    theme: Res<Theme>,
+    selection_box: Single<Ref<Sprite>, With<SelectionBox>>,
+    mut edges: Query<(&SelectionEdge, &mut Sprite, &mut Transform), Without<SelectionBox>>,
+) {
+    if !selection_box.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let size = selection_box.custom_size.unwrap_or_default();
+    let width = theme.outline.min(size.x / 2.).min(size.y / 2.);
+    for (SelectionEdge(side), mut sprite, mut transform) in &mut edges {
+        let offset = *side * (size - width) / 2.;
+        transform.translation = offset.extend(transform.translation.z);
+        sprite.color = theme.selection();
+        sprite.custom_size = Some(if side.x == 0. {
+            Vec2::new(size.x, width)
+        } else {
+            Vec2::new(width, size.y)
+        });
+    }
+}
+
+/// Turns a world-space rectangle into the inclusive range of cells whose centres it contains.
+pub(crate) fn cells_in(rect: Rect, size: GridSize) -> Option<URect> {
+    let origin = Cell { col: 0, row: 0 }.position();
+    let min = ((rect.min - origin) / NUMBER_SPACING)
+        .ceil()
+        .max(Vec2::ZERO);
+    let max = ((rect.max - origin) / NUMBER_SPACING)
+        .floor()
+        .min(UVec2::new(size.last().col, size.last().row).as_vec2());
+    (min.x <= max.x && min.y <= max.y).then(|| URect::from_corners(min.as_uvec2(), max.as_uvec2()))
+}
+
+/// Drag to select, click a bin to refine the selection into it, right click to deselect.
+fn pointer_input(
+    mut drag: Local<Option<(Vec2, Vec2)>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    (config, layout): (Res<Config>, Res<BinLayout>),
+    mut dragging: ResMut<Dragging>,
+    pointer: CanvasCursor,
+    mut selection_box: Single<(&mut Transform, &mut Sprite, &mut Visibility), With<SelectionBox>>,
+    mut requests: EventWriter<RequestAction>,
+) {
+    let screen = pointer.world();
+    let cursor = pointer.grid();
+    let (transform, sprite, visibility) = &mut *selection_box;
+
+    if config.input.right_click_clears && buttons.just_pressed(MouseButton::Right) {
+        requests.write(RequestAction(GridAction::ClearSelection));
+    }
+
+    if buttons.just_pressed(MouseButton::Left) {
+        if let Some(bin) = screen.and_then(|screen| layout.bin_at(screen)) {
+            requests.write(RequestAction(GridAction::Refine { bin }));
+        } else if !screen
+            .is_some_and(|screen| minimap::covers(screen, pointer.canvas(), pointer.grid_size()))
+        {
+            *drag = cursor.map(|start| (start, start));
+        }
+    }
+
+    let Some((start, end)) = &mut *drag else {
+        return;
+    };
+
+    // Keep the last known corner when the cursor leaves the canvas mid-drag.
+    if let Some(cursor) = cursor {
+        *end = cursor;
+    }
+    let rect = Rect::from_corners(*start, *end);
+    transform.translation = rect.center().extend(transform.translation.z);
+    sprite.custom_size = Some(rect.size());
+    **visibility = Visibility::Inherited;
+    let cells = cells_in(rect, pointer.grid_size());
+    dragging.set_if_neq(Dragging(cells));
+
+    if buttons.just_released(MouseButton::Left) {
+        let action = match cells {
+            Some(range) => GridAction::Select(range),
+            None => GridAction::ClearSelection,
+        };
+        requests.write(RequestAction(action));
+        *drag = None;
+        dragging.set_if_neq(Dragging(None));
+        **visibility = Visibility::Hidden;
+    }
+}
+
+/// Ticks when the cursor moves onto a number, at most every [`TICK_INTERVAL`] so that sweeping
+/// across the grid chatters instead of blaring.
+fn hover_ticks(
+    mut last_tick: Local<f32>,
+    time: Res<Time<Real>>,
+    mut changed: EventReader<HoverChanged>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    let Some(event) = changed.read().last() else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    if event.0.cell.is_some() && now - *last_tick >= TICK_INTERVAL {
+        *last_tick = now;
+        let pitch = TICK_PITCH.start + fastrand::f32() * (TICK_PITCH.end - TICK_PITCH.start);
+        sounds.write(PlaySound::new(Sound::Tick).with_pitch(pitch));
+    }
+}
+
+/// Dragging with the middle mouse button pans the grid.
+fn pan_view(
+    mut last: Local<Option<Vec2>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    pointer: CanvasCursor,
+    zoom: Res<Zoom>,
+    mut camera: Single<&mut Transform, With<GridCamera>>,
+) {
+    let cursor = pointer
+        .world()
+        .filter(|_| buttons.pressed(MouseButton::Middle));
+    if let (Some(last), Some(cursor)) = (*last, cursor) {
+        let center = camera.translation.truncate() + (last - cursor) / zoom.scale();
+        let view = zoom.view(pointer.canvas());
+        camera.translation =
+            clamp_view(center, view, pointer.grid_size()).extend(camera.translation.z);
+    }
+    *last = cursor;
+}
+
+/// Keeps the bottom left corner of the view where it was as the canvas or the grid changes shape,
+/// as far as the view stays over the grid.
+fn refit_view(
+    mut last: Local<Option<CanvasSize>>,
+    canvas: Res<CanvasSize>,
+    size: Res<GridSize>,
+    zoom: Res<Zoom>,
+    mut camera: Single<&mut Transform, With<GridCamera>>,
+) {
+    let corner = match *last {
+        Some(last) => camera.translation.truncate() - zoom.view(last) / 2.,
+        None => GRID_ORIGIN,
+    };
+    *last = Some(*canvas);
+    let view = zoom.view(*canvas);
+    let center = clamp_view(corner + view / 2., view, *size);
+    camera.translation = center.extend(camera.translation.z);
+}
+
+/// Number keys refine the selection into the matching bin.
+fn keyboard_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<Config>,
+    layout: Res<BinLayout>,
+    mut requests: EventWriter<RequestAction>,
+) {
+    if !config.input.bin_hotkeys {
+        return;
+    }
+    const BIN_KEYS: [KeyCode; 9] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    for (bin, key) in BIN_KEYS.iter().enumerate().take(layout.count()) {
+        if keys.just_pressed(*key) {
+            requests.write(RequestAction(GridAction::Refine { bin }));
+        }
+    }
+}
+
+/// Picks the glyph a refined number is replaced with, from a set of `glyphs`.
+///
+/// This is deterministic so that every peer of a shared session regenerates the same glyphs.
+fn regenerate(glyph: u32, cell: Cell, glyphs: u32) -> u32 {
+    let glyphs = glyphs.max(1);
+    (glyph + 1 + (cell.col * 7 + cell.row * 13) % (glyphs - 1).max(1)) % glyphs
+}
+
+fn apply_actions(
+    mut actions: EventReader<ApplyAction>,
+    (mut selection, mut model): (ResMut<Selection>, ResMut<GridModel>),
+    (file, mut size, driven, rules): (
+        Res<ActiveFile>,
+        ResMut<GridSize>,
+        Option<Res<DrivenBins>>,
+        Res<RefineRules>,
+    ),
+    (detectors, mut cached): (Res<detect::DetectorRegistry>, Local<ClusterCache>),
+    bins: Query<&Bin>,
+    mut refined: EventWriter<Refined>,
+    (mut refused, mut grabs_refused): (EventWriter<BinRefused>, EventWriter<RefinementRefused>),
+) {
+    for ApplyAction(action) in actions.read() {
+        match *action {
+            GridAction::Select(range) => selection.0 = Some(range),
+            GridAction::ClearSelection => selection.0 = None,
+            GridAction::Resize(target) => {
+                resize(target, &mut size, &mut model, &mut selection, &file);
+            }
+            GridAction::Refine { bin } => {
+                let full = bins
+                    .iter()
+                    .any(|b| b.index == bin && file.limits.is_full(b.progress));
+                if full {
+                    if selection.0.is_some() {
+                        refused.write(BinRefused(bin));
+                    }
+                    continue;
+                }
+                let Some(cells) = selection.0 else {
+                    continue;
+                };
+                // Whether the selection counts is the detector's to say, caught clusters included, so
+                // that the numbers of one can go on being refined.
+                let counts = match detectors.pick(&rules) {
+                    Some(detector) => cached
+                        .get(file.seed, *size)
+                        .iter()
+                        .any(|cluster| !cluster.is_empty() && detector.catches(cells, cluster)),
+                    None => model
+                        .range_mut(cells)
+                        .any(|(_, state)| state.temper != Temper::Calm),
+                };
+                let limit = rules.max_selection;
+                let too_large = limit > 0 && Selection::cell_count(cells) > limit;
+                if (driven.is_none() && !counts) || too_large {
+                    grabs_refused.write(RefinementRefused { cells });
+                    continue;
+                }
+                selection.0 = None;
+                let mut count = 0;
+                for (cell, state) in model.range_mut(cells) {
+                    state.value = regenerate(state.value, cell, file.glyphs.count());
+                    state.refined = true;
+                    count += 1;
+                }
+                if count > 0 {
+                    refined.write(Refined { bin, count, cells });
+                }
+            }
+        }
+    }
+}
+
+fn reset_grid(
+    mut resets: EventReader<ResetRefinement>,
+    file: Res<ActiveFile>,
+    size: Res<GridSize>,
+    mut selection: ResMut<Selection>,
+    mut model: ResMut<GridModel>,
+) {
+    resets.clear();
+    selection.0 = None;
+    *model = GridModel::new(&file, *size);
+}
+
+/// Shows every cell's glyph on its number, in the glyphs of the open file.
+pub(crate) fn sync_numbers(
+    model: Res<GridModel>,
+    file: Res<ActiveFile>,
+    mut numbers: Query<(&Cell, &mut Glyph, Option<&mut Text2d>)>,
+) {
+    for (cell, mut glyph, text) in &mut numbers {
+        let Some(state) = model.get(*cell) else {
+            continue;
+        };
+        if glyph.0 != state.value || file.is_changed() {
+            glyph.0 = state.value;
+            // Numbers the field draws have no text to show it on.
+            if let Some(mut text) = text {
+                text.0 = file.glyphs.text(state.value);
+            }
+        }
+    }
+}
+
+/// Colors the numbers for whether they are selected, shaded by their tempers' signatures.
+fn tint_selection(
+    selection: Res<Selection>,
+    overtime: Res<Overtime>,
+    theme: Res<Theme>,
+    (model, noticed): (Res<GridModel>, Res<Noticed>),
+    mut numbers: Query<(&Cell, &mut TextColor)>,
+) {
+    if !selection.is_changed()
+        && !overtime.is_changed()
+        && !theme.is_changed()
+        && !model.is_changed()
+        && !noticed.is_changed()
+    {
+        return;
+    }
+    for (cell, mut color) in &mut numbers {
+        let base = if selection.contains(*cell) {
+            theme.selection()
+        } else {
+            overtime.number_color(&theme)
+        };
+        let temper = model.get(*cell).map_or(Temper::Calm, |state| state.temper);
+        color.0 = theme
+            .signatures
+            .of(temper, noticed.contains(*cell))
+            .shade(base);
+    }
+}
+
+/// Radians along its path a number has drifted by now, leaving out the phase of its cell.
+pub fn drift_time(time: &Time<Virtual>, overtime: Overtime) -> f32 {
+    time.elapsed_secs() * DRIFT_RATE * overtime.drift_speed()
+}
+
+/// Lets the numbers wander around their cells, each on its own path, a whole pixel at a time.
+///
+/// Where the [field](crate::field) draws the numbers, it drifts them itself, so they are only
+/// settled back on their cells here.
+pub(crate) fn drift_numbers(
+    time: Res<Time<Virtual>>,
+    config: Res<Config>,
+    overtime: Res<Overtime>,
+    theme: Res<Theme>,
+    (model, file, size): (Res<GridModel>, Res<ActiveFile>, Res<GridSize>),
+    mut numbers: Query<(&Cell, &mut Transform, &Visibility, Option<&Scatter>), With<Number>>,
+) {
+    let settle = field_shown(&config, &file.glyphs, *size);
+    if settle && !config.is_changed() && !file.is_changed() {
+        return;
+    }
+    let t = drift_time(&time, *overtime);
+    for (cell, mut transform, visibility, scatter) in &mut numbers {
+        if visibility == Visibility::Hidden && !settle {
+            continue;
+        }
+        let offset = if settle || config.accessibility.reduced_motion {
+            Vec2::ZERO
+        } else {
+            let phase = model.get(*cell).map_or(0., |state| state.phase);
+            let scatter = scatter.map_or(Vec2::ZERO, |scatter| scatter.0);
+            Vec2::new((t + phase).sin(), (t * 0.8 + phase * 1.3).cos()) * theme.drift + scatter
+        };
+        let translation = (cell.position() + offset.round()).extend(transform.translation.z);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
+
+/// Cells the grid camera shows of a view `view` pixels in size centred at `center`, with a cell
+/// to spare on every side for numbers drifting or pulsing over the edge.
+fn cells_in_view(center: Vec2, view: Vec2, size: GridSize) -> URect {
+    let origin = Cell { col: 0, row: 0 }.position();
+    let half = view / 2.;
+    let min = ((center - half - origin) / NUMBER_SPACING).floor() - 1.;
+    let max = ((center + half - origin) / NUMBER_SPACING).ceil() + 1.;
+    let last = UVec2::new(size.last().col, size.last().row).as_vec2();
+    URect::from_corners(
+        min.clamp(Vec2::ZERO, last).as_uvec2(),
+        max.clamp(Vec2::ZERO, last).as_uvec2(),
+    )
+}
+
+/// Hides the numbers outside the view, updating only those that cross its edge as it pans, or
+/// that have just been spawned.
+pub(crate) fn cull_numbers(
+    mut in_view: Local<Option<URect>>,
+    (canvas, size): (Res<CanvasSize>, Res<GridSize>),
+    zoom: Res<Zoom>,
+    camera: Single<&Transform, With<GridCamera>>,
+    added: Query<(), Added<Number>>,
+    mut numbers: Query<(&Cell, &mut Visibility), With<Number>>,
+) {
+    let cells = cells_in_view(camera.translation.truncate(), zoom.view(*canvas), *size);
+    if *in_view == Some(cells) && added.is_empty() {
+        return;
+    }
+    *in_view = Some(cells);
+    for (cell, mut visibility) in &mut numbers {
+        visibility.set_if_neq(if cells.contains(UVec2::new(cell.col, cell.row)) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_round_trip() {
+        for action in [
+            GridAction::Select(URect::new(3, 4, 10, 12)),
+            GridAction::ClearSelection,
+            GridAction::Refine { bin: 2 },
+            GridAction::Resize(GridSize {
+                columns: 40,
+                rows: 25,
+            }),
+        ] {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+    }
+
+    #[test]
+    fn refuses_what_is_not_an_action() {
+        for line in [
+            "",
+            "SELECT 1 2 3",
+            "REFINE",
+            "REFINE -1",
+            "SIZE 40",
+            "JUMP 1",
+        ] {
+            assert_eq!(
+                line.parse::<GridAction>(),
+                Err(ParseActionError),
+                "{line:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn selections_cover_the_cells_they_are_dragged_over() {
+        let size = GridSize {
+            columns: 10,
+            rows: 5,
+        };
+        let (from, to) = (Cell { col: 2, row: 1 }, Cell { col: 5, row: 3 });
+        let rect = Rect::from_corners(from.position(), to.position());
+        assert_eq!(cells_in(rect, size), Some(URect::new(2, 1, 5, 3)));
+    }
+
+    #[test]
+    fn cells_are_found_under_their_numbers() {
+        let size = GridSize {
+            columns: 10,
+            rows: 5,
+        };
+        for cell in size.cells() {
+            assert_eq!(cell_at(cell.position(), size), Some(cell));
+        }
+        let between = Cell { col: 3, row: 2 }.position() + Vec2::new(NUMBER_SPACING / 2., 0.);
+        assert_eq!(cell_at(between, size), None);
+        let past = size.last().position() + Vec2::splat(NUMBER_SPACING);
+        assert_eq!(cell_at(past, size), None);
+    }
+}
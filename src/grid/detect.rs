@@ -0,0 +1,233 @@
+//! What counts as catching a cluster of scary numbers, through the [`ScaryDetector`] trait.
+//!
+//! The detector in use decides which selections count: one it says catches none of the file's
+//! clusters, caught already or not, is refused rather than refined. Every refinement is then
+//! shown to it against each cluster not yet caught, and a [`ClusterCaught`] is sent for each it
+//! says the refinement caught. Unless the [rules](RefineRules) name a detector, each difficulty
+//! has its own. Built in are:
+//!
+//! - `any`: a selection holding any number of the cluster, on easy
+//! - `overlap`: a selection holding at least [`OVERLAP_SHARE`] of its numbers, on normal
+//! - `exact`: a selection that is just the cluster, the smallest rectangle around it, on hard
+//!
+//! Plugins add their own detectors with [`RegisterScaryDetector::register_scary_detector`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use bevy::prelude::*;
+
+use super::{Cell, ClusterCache, GridSize, RefineRules, Refined, ResetRefinement};
+use crate::{config::Difficulty, files::ActiveFile};
+
+/// Share of a cluster's numbers the `overlap` detector asks for.
+pub const OVERLAP_SHARE: f32 = 0.8;
+
+/// Something that decides whether a refinement caught a cluster.
+pub trait ScaryDetector: Send + Sync + 'static {
+    /// Whether refining the inclusive range of cells `selection` catches the cluster made up of
+    /// `cluster`, which is never empty.
+    fn catches(&self, selection: URect, cluster: &[Cell]) -> bool;
+}
+
+/// Catches a cluster only when the selection is the smallest rectangle around it.
+pub struct ExactMatch;
+
+impl ScaryDetector for ExactMatch {
+    fn catches(&self, selection: URect, cluster: &[Cell]) -> bool {
+        // Built field by field, as the constructors would swap the corners.
+        let empty = URect {
+            min: UVec2::MAX,
+            max: UVec2::ZERO,
+        };
+        let bounds = cluster.iter().fold(empty, |bounds, cell| URect {
+            min: bounds.min.min(UVec2::new(cell.col, cell.row)),
+            max: bounds.max.max(UVec2::new(cell.col, cell.row)),
+        });
+        selection == bounds
+    }
+}
+
+/// Catches a cluster when the selection holds at least a share of its numbers, from 0 to 1.
+pub struct Overlap(pub f32);
+
+impl ScaryDetector for Overlap {
+    fn catches(&self, selection: URect, cluster: &[Cell]) -> bool {
+        let held = cluster
+            .iter()
+            .filter(|cell| holds(selection, **cell))
+            .count();
+        held as f32 >= self.0 * cluster.len() as f32
+    }
+}
+
+/// Catches a cluster when the selection holds any of its numbers.
+pub struct AnyTagged;
+
+impl ScaryDetector for AnyTagged {
+    fn catches(&self, selection: URect, cluster: &[Cell]) -> bool {
+        cluster.iter().any(|cell| holds(selection, *cell))
+    }
+}
+
+fn holds(selection: URect, cell: Cell) -> bool {
+    (selection.min.x..=selection.max.x).contains(&cell.col)
+        && (selection.min.y..=selection.max.y).contains(&cell.row)
+}
+
+/// Name of the detector used at a difficulty unless the config names another.
+fn difficulty_detector(difficulty: Difficulty) -> &'static str {
+    match difficulty {
+        Difficulty::Easy => "any",
+        Difficulty::Normal => "overlap",
+        Difficulty::Hard => "exact",
+    }
+}
+
+/// Registers detectors that the config can pick by name.
+pub trait RegisterScaryDetector {
+    fn register_scary_detector(
+        &mut self,
+        name: &'static str,
+        detector: impl ScaryDetector,
+    ) -> &mut Self;
+}
+
+impl RegisterScaryDetector for App {
+    fn register_scary_detector(
+        &mut self,
+        name: &'static str,
+        detector: impl ScaryDetector,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<DetectorRegistry>()
+            .0
+            .insert(name, Arc::new(detector));
+        self
+    }
+}
+
+/// Detectors by the name they were registered under.
+#[derive(Resource, Default)]
+pub(super) struct DetectorRegistry(HashMap<&'static str, Arc<dyn ScaryDetector>>);
+
+impl DetectorRegistry {
+    /// The detector the rules name, or the difficulty's own if they name none, or one that is
+    /// not registered.
+    pub(super) fn pick(&self, rules: &RefineRules) -> Option<&dyn ScaryDetector> {
+        let difficulty = difficulty_detector(rules.difficulty);
+        rules
+            .detector
+            .as_deref()
+            .and_then(|name| self.0.get(name))
+            .or_else(|| self.0.get(difficulty))
+            .map(|detector| &**detector)
+    }
+}
+
+/// A refinement caught a cluster of scary numbers.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ClusterCaught {
+    /// Index of the cluster among the file's, as [`clusters`](super::clusters) lists them.
+    pub cluster: usize,
+}
+
+/// Shows every refinement to the detector, starting over when the file is reopened, reset or
+/// resized.
+pub(super) fn detect_clusters(
+    (file, size, rules): (Res<ActiveFile>, Res<GridSize>, Res<RefineRules>),
+    detectors: Res<DetectorRegistry>,
+    mut resets: EventReader<ResetRefinement>,
+    mut refined: EventReader<Refined>,
+    mut cached: Local<ClusterCache>,
+    mut caught: Local<HashSet<usize>>,
+    mut clusters_caught: EventWriter<ClusterCaught>,
+) {
+    if file.is_changed() || size.is_changed() || !resets.is_empty() {
+        resets.clear();
+        caught.clear();
+    }
+    let clusters = cached.get(file.seed, *size);
+    let Some(detector) = detectors.pick(&rules) else {
+        refined.clear();
+        return;
+    };
+    for event in refined.read() {
+        for (index, cluster) in clusters.iter().enumerate() {
+            if !cluster.is_empty()
+                && !caught.contains(&index)
+                && detector.catches(event.cells, cluster)
+            {
+                caught.insert(index);
+                clusters_caught.write(ClusterCaught { cluster: index });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An L of four cells, from (2, 2) down to (2, 4) and across to (3, 4).
+    const CLUSTER: [Cell; 4] = [
+        Cell { col: 2, row: 2 },
+        Cell { col: 2, row: 3 },
+        Cell { col: 2, row: 4 },
+        Cell { col: 3, row: 4 },
+    ];
+
+    fn selection(min: (u32, u32), max: (u32, u32)) -> URect {
+        URect::new(min.0, min.1, max.0, max.1)
+    }
+
+    #[test]
+    fn exact_matches_catch_only_the_rectangle_around_a_cluster() {
+        assert!(ExactMatch.catches(selection((2, 2), (3, 4)), &CLUSTER));
+        assert!(!ExactMatch.catches(selection((1, 2), (3, 4)), &CLUSTER));
+        assert!(!ExactMatch.catches(selection((2, 2), (2, 4)), &CLUSTER));
+        let single = [Cell { col: 5, row: 6 }];
+        assert!(ExactMatch.catches(selection((5, 6), (5, 6)), &single));
+    }
+
+    #[test]
+    fn overlaps_catch_a_share_of_a_cluster() {
+        let overlap = Overlap(0.75);
+        assert!(overlap.catches(selection((0, 0), (9, 9)), &CLUSTER));
+        // Three of the four.
+        assert!(overlap.catches(selection((2, 2), (2, 4)), &CLUSTER));
+        assert!(!overlap.catches(selection((2, 3), (3, 3)), &CLUSTER));
+        assert!(Overlap(0.).catches(selection((8, 8), (9, 9)), &CLUSTER));
+    }
+
+    #[test]
+    fn any_tagged_catches_a_cluster_by_any_of_its_numbers() {
+        assert!(AnyTagged.catches(selection((3, 4), (3, 4)), &CLUSTER));
+        assert!(AnyTagged.catches(selection((0, 0), (2, 2)), &CLUSTER));
+        assert!(!AnyTagged.catches(selection((3, 2), (5, 3)), &CLUSTER));
+    }
+
+    #[test]
+    fn difficulties_pick_their_own_detector_unless_the_config_names_one() {
+        let mut registry = DetectorRegistry::default();
+        registry.0.insert("any", Arc::new(AnyTagged));
+        registry.0.insert("exact", Arc::new(ExactMatch));
+        let mut rules = RefineRules {
+            difficulty: Difficulty::Hard,
+            detector: None,
+            max_selection: 0,
+        };
+        // Only the exact detector refuses a selection one cell too wide.
+        let wide = selection((1, 2), (3, 4));
+        let catches = |rules: &RefineRules| registry.pick(rules).unwrap().catches(wide, &CLUSTER);
+        assert!(!catches(&rules));
+        rules.detector = Some("any".to_string());
+        assert!(catches(&rules));
+        rules.detector = Some("missing".to_string());
+        assert!(!catches(&rules));
+        rules.difficulty = Difficulty::Normal;
+        assert!(registry.pick(&rules).is_none());
+    }
+}
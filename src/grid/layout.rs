@@ -0,0 +1,183 @@
+//! The order a file's glyphs fill its grid in, the way a terminal fills its screen with text.
+//!
+//! A file's glyphs are a stream, of which the [`GridLayout`] decides where each lands: starting in
+//! one corner, along rows or down columns, and with every other line turned back on itself
+//! should it be serpentine. The usual layout fills rows from the bottom left, left to right.
+//! Everything else about the grid is laid out the same whatever the file's layout, so that the
+//! number on screen at a cell is still the cell that is selected and refined.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Cell, GridSize};
+
+/// The corner of the grid the first glyph lands in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Corner {
+    #[default]
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+impl Corner {
+    const ALL: [(Corner, &'static str); 4] = [
+        (Corner::BottomLeft, "bl"),
+        (Corner::BottomRight, "br"),
+        (Corner::TopLeft, "tl"),
+        (Corner::TopRight, "tr"),
+    ];
+
+    fn right(self) -> bool {
+        matches!(self, Corner::BottomRight | Corner::TopRight)
+    }
+
+    fn top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+}
+
+/// Where each glyph of a file's stream lands in its grid.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct GridLayout {
+    pub origin: Corner,
+    /// Whether glyphs go down columns rather than along rows. From the top right, this is how
+    /// vertical text is set, in columns from right to left.
+    pub columns: bool,
+    /// Whether every other line runs back the way the one before it came.
+    pub serpentine: bool,
+}
+
+impl GridLayout {
+    /// How many glyphs of the stream come before the one landing in `cell` of a grid of the
+    /// given size.
+    pub fn index(self, cell: Cell, size: GridSize) -> u32 {
+        let last = size.last();
+        let x = if self.origin.right() {
+            last.col - cell.col
+        } else {
+            cell.col
+        };
+        let y = if self.origin.top() {
+            last.row - cell.row
+        } else {
+            cell.row
+        };
+        let (line, along, length) = if self.columns {
+            (x, y, size.rows)
+        } else {
+            (y, x, size.columns)
+        };
+        let along = if self.serpentine && line % 2 == 1 {
+            length - 1 - along
+        } else {
+            along
+        };
+        line * length + along
+    }
+
+    /// The cell the usual layout would put the glyph landing in `cell`, which is what decides
+    /// the glyph.
+    pub fn source(self, cell: Cell, size: GridSize) -> Cell {
+        let index = self.index(cell, size);
+        Cell {
+            col: index % size.columns.max(1),
+            row: index / size.columns.max(1),
+        }
+    }
+}
+
+/// Layouts are written as one word for the lines of files in replays: the corner, `bl`, `br`,
+/// `tl` or `tr`, then `-columns` and `-serpentine` for those that are.
+impl fmt::Display for GridLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (_, corner) = Corner::ALL
+            .iter()
+            .find(|(corner, _)| *corner == self.origin)
+            .ok_or(fmt::Error)?;
+        write!(f, "{corner}")?;
+        if self.columns {
+            write!(f, "-columns")?;
+        }
+        if self.serpentine {
+            write!(f, "-serpentine")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when a word is not a [`GridLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLayoutError;
+
+impl fmt::Display for ParseLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a grid layout")
+    }
+}
+
+impl FromStr for GridLayout {
+    type Err = ParseLayoutError;
+
+    fn from_str(word: &str) -> Result<Self, Self::Err> {
+        let mut parts = word.split('-');
+        let corner = parts.next().ok_or(ParseLayoutError)?;
+        let (origin, _) = Corner::ALL
+            .into_iter()
+            .find(|(_, name)| *name == corner)
+            .ok_or(ParseLayoutError)?;
+        let mut layout = GridLayout {
+            origin,
+            ..GridLayout::default()
+        };
+        for part in parts {
+            match part {
+                "columns" => layout.columns = true,
+                "serpentine" => layout.serpentine = true,
+                _ => return Err(ParseLayoutError),
+            }
+        }
+        Ok(layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_round_trip() {
+        for (origin, _) in Corner::ALL {
+            for columns in [false, true] {
+                for serpentine in [false, true] {
+                    let layout = GridLayout {
+                        origin,
+                        columns,
+                        serpentine,
+                    };
+                    assert_eq!(layout.to_string().parse(), Ok(layout));
+                }
+            }
+        }
+        assert_eq!("bl".parse(), Ok(GridLayout::default()));
+        assert_eq!("middle".parse::<GridLayout>(), Err(ParseLayoutError));
+        assert_eq!("tl-diagonal".parse::<GridLayout>(), Err(ParseLayoutError));
+    }
+
+    #[test]
+    fn every_layout_puts_one_glyph_in_each_cell() {
+        let size = GridSize {
+            columns: 4,
+            rows: 3,
+        };
+        for layout in ["bl", "tr", "br-columns-serpentine", "tl-serpentine"] {
+            let layout: GridLayout = layout.parse().unwrap();
+            let mut indices: Vec<u32> = size.cells().map(|cell| layout.index(cell, size)).collect();
+            indices.sort_unstable();
+            assert_eq!(indices, (0..12).collect::<Vec<_>>(), "{layout}");
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! The state of every cell of the grid, apart from the entities that show it.
+//!
+//! [`GridModel`] is what refinement changes; the number entities only copy it, in
+//! [`RefineSet::Sync`](super::RefineSet::Sync). Being plain data, it can be saved, replayed, or
+//! drawn by something other than the grid's entities.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{cluster_centers, cluster_tempers, initial_glyph, Cell, GridSize, CLUSTER_RADIUS};
+use crate::files::ActiveFile;
+
+/// The feeling a number stirs in the refiner who looks at it. Only the scary numbers have one.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Temper {
+    #[default]
+    Calm,
+    Woe,
+    Frolic,
+    Dread,
+    Malice,
+}
+
+impl Temper {
+    /// The tempers of scary numbers, which share out the clusters of a file.
+    pub const SCARY: [Temper; 4] = [Temper::Woe, Temper::Frolic, Temper::Dread, Temper::Malice];
+
+    /// A mark telling the temper apart, for hints that may give it away. Calm numbers have none.
+    pub fn glyph(self) -> Option<char> {
+        match self {
+            Temper::Calm => None,
+            Temper::Woe => Some('~'),
+            Temper::Frolic => Some('^'),
+            Temper::Dread => Some('!'),
+            Temper::Malice => Some('#'),
+        }
+    }
+}
+
+/// What a cell of the grid holds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct CellState {
+    /// Index of the glyph shown, in the file's glyph set.
+    pub value: u32,
+    pub temper: Temper,
+    /// Whether the number has been refined since the file was opened.
+    pub refined: bool,
+    /// Where the number is along its idle drift, in radians.
+    pub phase: f32,
+}
+
+/// Every cell of the grid, row by row from the bottom.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GridModel {
+    columns: u32,
+    rows: u32,
+    cells: Vec<CellState>,
+}
+
+impl GridModel {
+    /// The grid of a file, as it is when the file is opened on a grid of the given size.
+    pub fn new(file: &ActiveFile, size: GridSize) -> Self {
+        let seed = file.seed;
+        let glyphs = file.glyphs.count();
+        let centers: Vec<Vec2> = cluster_centers(seed, size).collect();
+        let tempers = cluster_tempers(seed, file.temper_scale, size);
+        let cells = size
+            .cells()
+            .map(|cell| {
+                let at = Vec2::new(cell.col as f32, cell.row as f32);
+                let temper = centers
+                    .iter()
+                    .position(|center| at.distance(*center) <= CLUSTER_RADIUS)
+                    .map_or(Temper::Calm, |index| tempers[index]);
+                CellState {
+                    // The glyph is the one the stream has wherever the layout puts it.
+                    value: initial_glyph(seed, file.layout.source(cell, size), glyphs),
+                    temper,
+                    refined: false,
+                    // The field shader works this out for itself, and must agree.
+                    phase: (cell.col * 31 + cell.row * 17) as f32,
+                }
+            })
+            .collect();
+        Self {
+            columns: size.columns,
+            rows: size.rows,
+            cells,
+        }
+    }
+
+    pub fn size(&self) -> GridSize {
+        GridSize {
+            columns: self.columns,
+            rows: self.rows,
+        }
+    }
+
+    /// Crops or extends the grid to another size. Cells it keeps keep their glyphs and whether
+    /// they were refined, and cells it gains hold what they would in a file opened at that size.
+    /// Tempers follow the clusters, which spread across the grid whatever its size.
+    pub fn resize(&mut self, file: &ActiveFile, size: GridSize) {
+        let mut resized = GridModel::new(file, size);
+        for (cell, state) in resized.cells_mut() {
+            if let Some(kept) = self.get(cell) {
+                state.value = kept.value;
+                state.refined = kept.refined;
+            }
+        }
+        *self = resized;
+    }
+
+    fn index(&self, cell: Cell) -> Option<usize> {
+        (cell.col < self.columns && cell.row < self.rows)
+            .then(|| (cell.row * self.columns + cell.col) as usize)
+    }
+
+    pub fn get(&self, cell: Cell) -> Option<&CellState> {
+        self.cells.get(self.index(cell)?)
+    }
+
+    /// Every cell, with what it holds.
+    pub fn cells_mut(&mut self) -> impl Iterator<Item = (Cell, &mut CellState)> {
+        let columns = self.columns;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, state)| {
+                let index = index as u32;
+                let cell = Cell {
+                    col: index % columns,
+                    row: index / columns,
+                };
+                (cell, state)
+            })
+    }
+
+    /// Every cell in the inclusive range, with what it holds.
+    pub fn range_mut(&mut self, range: URect) -> impl Iterator<Item = (Cell, &mut CellState)> {
+        self.cells_mut()
+            .filter(move |(cell, _)| range.contains(UVec2::new(cell.col, cell.row)))
+    }
+}
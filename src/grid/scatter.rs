@@ -0,0 +1,78 @@
+//! Numbers refusing a grab: refining a selection with no scary numbers in it, or one too large,
+//! sends them scattering away from the cursor, to settle back on their cells a moment later.
+//!
+//! Each number of the selection is given a [`Scatter`] away from the cursor, or from the middle
+//! of the selection when it was refined from the keyboard, stronger the nearer it was, which a
+//! [`Tween`] eases out and back to nothing. The drift of the numbers adds it to where they are.
+
+use bevy::prelude::*;
+
+use super::{Cell, Number, NUMBER_SPACING};
+use crate::{
+    audio::{PlaySound, Sound},
+    canvas::CanvasCursor,
+    tween::Tween,
+};
+
+/// Pixels the numbers nearest the cursor are pushed away by, at the furthest.
+const SCATTER_DISTANCE: f32 = 8.;
+
+/// Seconds the numbers take to scatter and settle back.
+const SCATTER_TIME: f32 = 0.6;
+
+/// A refinement was refused, for holding no scary numbers or too many cells; the selection stays
+/// as it was.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RefinementRefused {
+    /// The selection that would have been refined.
+    pub cells: URect,
+}
+
+/// How far a number is pushed off its cell.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Scatter(pub Vec2);
+
+/// Sends the numbers of refused selections scattering, with a dull tick.
+pub(super) fn scatter_numbers(
+    mut commands: Commands,
+    mut refusals: EventReader<RefinementRefused>,
+    pointer: CanvasCursor,
+    numbers: Query<(Entity, &Cell), With<Number>>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    for refusal in refusals.read() {
+        let cells = refusal.cells;
+        let from = pointer.grid().unwrap_or_else(|| {
+            let first = Cell {
+                col: cells.min.x,
+                row: cells.min.y,
+            };
+            let last = Cell {
+                col: cells.max.x,
+                row: cells.max.y,
+            };
+            (first.position() + last.position()) / 2.
+        });
+        for (entity, cell) in &numbers {
+            if !cells.contains(UVec2::new(cell.col, cell.row)) {
+                continue;
+            }
+            let away = cell.position() - from;
+            // A number right under the cursor goes any way at all.
+            let direction = away
+                .try_normalize()
+                .unwrap_or_else(|| Vec2::from_angle(fastrand::f32() * std::f32::consts::TAU));
+            let push = SCATTER_DISTANCE / (1. + away.length() / NUMBER_SPACING);
+            let impulse = direction * push;
+            commands.entity(entity).insert((
+                Scatter(impulse),
+                Tween::new(SCATTER_TIME, move |scatter: &mut Scatter, along| {
+                    // Out fast, then back more slowly, at the full push at the furthest.
+                    let out = (std::f32::consts::PI * along).sin() * (1. - along);
+                    scatter.0 = impulse * out * 1.7;
+                }),
+            ));
+        }
+        sounds.write(PlaySound::new(Sound::Tick).with_pitch(0.5));
+    }
+}
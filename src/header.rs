@@ -0,0 +1,159 @@
+//! The header along the top of the canvas: the name of the file being refined and how complete
+//! it is overall, counting up as its bins fill, beside a sparkline of how that has gone lately.
+//!
+//! Other parts of the app may show a [`HeaderMessage`] in place of the file name, and a host
+//! application may give the file a [`FileTitle`] to show by. The header makes way for the
+//! timeline while a replay plays back.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, sprite::Anchor, time::common_conditions::on_real_timer};
+
+use crate::{
+    bins::{percent, Bin},
+    canvas::{CanvasAnchor, PIXEL_PERFECT_LAYERS},
+    chart::{SparkStyle, Sparkline},
+    files::ActiveFile,
+    grid::RefineSet,
+    replay::Playback,
+    ui::AnimatedNumber,
+};
+
+const HEADER_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+const TEXT_COLOR: Color = Color::srgb(0.0, 0.9, 1.0);
+
+/// How often the sparkline takes the file's completion.
+const TREND_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct HeaderPlugin;
+
+impl Plugin for HeaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HeaderMessage>()
+            .init_resource::<FileTitle>()
+            .add_systems(Startup, setup_header)
+            .add_systems(
+                Update,
+                (
+                    name_file.run_if(
+                        resource_changed::<ActiveFile>
+                            .or(resource_changed::<HeaderMessage>)
+                            .or(resource_changed::<FileTitle>),
+                    ),
+                    total_bins,
+                    chart_trend.run_if(on_real_timer(TREND_INTERVAL)),
+                )
+                    .in_set(RefineSet::React)
+                    .run_if(not(resource_exists::<Playback>)),
+            );
+    }
+}
+
+/// A message the header shows in place of the file name while there is one.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct HeaderMessage(pub Option<String>);
+
+/// A name the header shows in place of the file's own, while there is one.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct FileTitle(pub Option<String>);
+
+#[derive(Component)]
+struct FileName;
+
+/// The file's overall completion: the mean progress of its bins.
+#[derive(Component)]
+struct Total;
+
+/// Sparkline of the file's completion over the last minutes.
+#[derive(Component)]
+struct Trend;
+
+fn setup_header(mut commands: Commands, file: Res<ActiveFile>, playback: Option<Res<Playback>>) {
+    let font = TextFont {
+        font_size: 8.0,
+        ..default()
+    };
+    commands
+        .spawn((
+            Sprite {
+                color: HEADER_COLOR,
+                custom_size: Some(Vec2::new(180., 11.)),
+                ..default()
+            },
+            // Under the widgets of the top corners, which an upright canvas has no room beside.
+            CanvasAnchor::TOP
+                .offset(0., -8.)
+                .in_portrait(CanvasAnchor::TOP.offset(0., -22.)),
+            Transform::from_xyz(0., 0., 16.),
+            if playback.is_some() {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            },
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_children(|header| {
+            header.spawn((
+                FileName,
+                Text2d::new(file.name.clone()),
+                font.clone(),
+                TextColor(TEXT_COLOR),
+                Anchor::CenterLeft,
+                Transform::from_xyz(-86., 0., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+            header.spawn((
+                Total,
+                AnimatedNumber::new(0., percent),
+                font,
+                TextColor(TEXT_COLOR),
+                Anchor::CenterRight,
+                Transform::from_xyz(86., 0., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+            header.spawn((
+                Trend,
+                Sparkline::new(UVec2::new(40, 7))
+                    .with_spacing(2)
+                    .with_style(SparkStyle::Step)
+                    .with_range(0., 1.),
+                Transform::from_xyz(40., 0., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        });
+}
+
+fn name_file(
+    file: Res<ActiveFile>,
+    (message, title): (Res<HeaderMessage>, Res<FileTitle>),
+    mut name: Single<&mut Text2d, With<FileName>>,
+) {
+    let text = message
+        .0
+        .as_ref()
+        .or(title.0.as_ref())
+        .unwrap_or(&file.name);
+    if name.0 != *text {
+        name.0.clone_from(text);
+    }
+}
+
+fn total_bins(
+    bins: Query<&Bin>,
+    changed: Query<(), Changed<Bin>>,
+    mut total: Single<&mut AnimatedNumber, With<Total>>,
+) {
+    if changed.is_empty() || bins.is_empty() {
+        return;
+    }
+    let sum: f32 = bins.iter().map(|bin| bin.progress).sum();
+    total.set(sum / bins.iter().count() as f32);
+}
+
+fn chart_trend(bins: Query<&Bin>, mut trend: Single<&mut Sparkline, With<Trend>>) {
+    if bins.is_empty() {
+        return;
+    }
+    let sum: f32 = bins.iter().map(|bin| bin.progress).sum();
+    trend.push(sum / bins.iter().count() as f32);
+}
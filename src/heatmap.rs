@@ -0,0 +1,290 @@
+//! Heatmap overlay: the cells of the grid tinted by what has gone on in them, beneath the numbers.
+//!
+//! Three kinds of activity are kept track of for every cell, whether or not the overlay is on, so
+//! that turning it on shows the whole session so far: refinement, which fades over a minute or
+//! so; hovering, which builds up the more often the cursor passes over and fades over many
+//! minutes; and how many times its number has been replaced, which never fades. Each has a color
+//! of its own, mixed by how much of each a cell has seen. Everything is forgotten as the
+//! refinement is reset or the grid resized.
+//!
+//! Toggled with H or from the settings. The overlay is a single image of a pixel per cell,
+//! stretched over the grid and painted anew a few times a second.
+
+use std::time::Duration;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    time::common_conditions::on_real_timer,
+};
+
+use crate::{
+    canvas::GRID_LAYERS,
+    config::Config,
+    grid::{
+        grid_bounds, Cell, GridModel, GridSize, RefineSet, Refined, ResetRefinement, NUMBER_SPACING,
+    },
+    picking::HoverChanged,
+    state::AppState,
+};
+
+/// Seconds it takes the heat of a refinement to halve.
+const REFINED_HALF_LIFE: f32 = 60.;
+
+/// Seconds it takes the heat of hovering to halve.
+const HOVERED_HALF_LIFE: f32 = 600.;
+
+/// Heat a cell gains every time the cursor moves onto it.
+const HOVER_STEP: f32 = 0.1;
+
+/// How often the overlay is painted anew while it is on.
+const PAINT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How opaque the tint of a cell at its hottest is.
+const MAX_ALPHA: f32 = 0.4;
+
+const REFINED_COLOR: Vec3 = Vec3::new(0.0, 0.9, 1.0);
+const HOVERED_COLOR: Vec3 = Vec3::new(1.0, 0.6, 0.1);
+const REPLACED_COLOR: Vec3 = Vec3::new(0.7, 0.3, 1.0);
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Heatmap>()
+            .add_systems(Startup, setup_heatmap)
+            .add_systems(
+                Update,
+                (
+                    toggle_key.run_if(in_state(AppState::Refining)),
+                    (
+                        track_activity,
+                        fit_heatmap.run_if(resource_changed::<GridSize>),
+                        show_heatmap.run_if(resource_changed::<Config>),
+                        paint_heatmap.run_if(on_real_timer(PAINT_INTERVAL)),
+                    )
+                        .chain()
+                        .after(RefineSet::Sync),
+                ),
+            );
+    }
+}
+
+/// What has gone on in every cell of the grid, in the order images of it lay the cells out.
+#[derive(Resource, Default)]
+struct Heatmap {
+    size: GridSize,
+    /// Heat of refinement, from 0 to 1.
+    refined: Vec<f32>,
+    /// Heat of hovering, from 0 to 1.
+    hovered: Vec<f32>,
+    /// Times the number has been replaced.
+    replaced: Vec<u32>,
+    /// The glyph each cell held when last looked at.
+    glyphs: Vec<u32>,
+}
+
+impl Heatmap {
+    /// Nothing gone on yet in a grid holding what `model` does.
+    fn new(size: GridSize, model: &GridModel) -> Self {
+        let count = (size.columns * size.rows) as usize;
+        let mut glyphs = vec![0; count];
+        for cell in size.cells() {
+            if let Some(state) = model.get(cell) {
+                glyphs[size.image_index(cell)] = state.value;
+            }
+        }
+        Self {
+            size,
+            refined: vec![0.; count],
+            hovered: vec![0.; count],
+            replaced: vec![0; count],
+            glyphs,
+        }
+    }
+
+    fn index(&self, cell: Cell) -> Option<usize> {
+        self.size
+            .contains(cell)
+            .then(|| self.size.image_index(cell))
+    }
+}
+
+/// Marks the overlay's sprite.
+#[derive(Component)]
+struct HeatmapOverlay;
+
+fn setup_heatmap(
+    mut commands: Commands,
+    (config, size): (Res<Config>, Res<GridSize>),
+    mut images: ResMut<Assets<Image>>,
+) {
+    let bounds = grid_bounds(*size);
+    commands.spawn((
+        HeatmapOverlay,
+        Sprite {
+            image: images.add(heatmap_image(*size)),
+            custom_size: Some(grid_extent(*size)),
+            ..default()
+        },
+        // Under the gridlines and the numbers.
+        Transform::from_translation(bounds.center().extend(-0.6)),
+        if config.gameplay.heatmap {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        },
+        GRID_LAYERS,
+    ));
+}
+
+/// Size of the whole grid, out to the edges of its outer cells.
+fn grid_extent(size: GridSize) -> Vec2 {
+    Vec2::new(size.columns as f32, size.rows as f32) * NUMBER_SPACING
+}
+
+/// A clear image of a pixel per cell of a grid of the given size, kept sharp when stretched.
+fn heatmap_image(size: GridSize) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size.columns,
+            height: size.rows,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn toggle_key(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<Config>) {
+    if keys.just_pressed(KeyCode::KeyH) {
+        config.gameplay.heatmap ^= true;
+    }
+}
+
+/// Heats the cells refined and hovered this frame and counts those whose numbers were replaced,
+/// letting what has gone before cool down, and forgets it all as the grid starts over.
+fn track_activity(
+    time: Res<Time<Virtual>>,
+    (size, model): (Res<GridSize>, Res<GridModel>),
+    mut heatmap: ResMut<Heatmap>,
+    mut resets: EventReader<ResetRefinement>,
+    mut refined: EventReader<Refined>,
+    mut hovers: EventReader<HoverChanged>,
+) {
+    if heatmap.size != *size || !resets.is_empty() {
+        resets.clear();
+        refined.clear();
+        *heatmap = Heatmap::new(*size, &model);
+        return;
+    }
+    let heatmap = &mut *heatmap;
+    let cooling = |half_life: f32| 0.5_f32.powf(time.delta_secs() / half_life);
+    let (refined_cooling, hovered_cooling) =
+        (cooling(REFINED_HALF_LIFE), cooling(HOVERED_HALF_LIFE));
+    for heat in &mut heatmap.refined {
+        *heat *= refined_cooling;
+    }
+    for heat in &mut heatmap.hovered {
+        *heat *= hovered_cooling;
+    }
+    for event in refined.read() {
+        for row in event.cells.min.y..=event.cells.max.y {
+            for col in event.cells.min.x..=event.cells.max.x {
+                if let Some(index) = heatmap.index(Cell { col, row }) {
+                    heatmap.refined[index] = 1.;
+                }
+            }
+        }
+    }
+    for HoverChanged(hovered) in hovers.read() {
+        if let Some(index) = hovered.cell.and_then(|cell| heatmap.index(cell)) {
+            heatmap.hovered[index] = (heatmap.hovered[index] + HOVER_STEP).min(1.);
+        }
+    }
+    if model.is_changed() {
+        for cell in size.cells() {
+            let (Some(state), Some(index)) = (model.get(cell), heatmap.index(cell)) else {
+                continue;
+            };
+            if heatmap.glyphs[index] != state.value {
+                heatmap.glyphs[index] = state.value;
+                heatmap.replaced[index] += 1;
+            }
+        }
+    }
+}
+
+/// Stretches a new overlay over the grid as it is resized.
+fn fit_heatmap(
+    size: Res<GridSize>,
+    mut images: ResMut<Assets<Image>>,
+    mut overlay: Single<(&mut Sprite, &mut Transform), With<HeatmapOverlay>>,
+) {
+    let (sprite, transform) = &mut *overlay;
+    sprite.image = images.add(heatmap_image(*size));
+    sprite.custom_size = Some(grid_extent(*size));
+    let center = grid_bounds(*size).center();
+    transform.translation = center.extend(transform.translation.z);
+}
+
+/// Shows or hides the overlay as the config says.
+fn show_heatmap(config: Res<Config>, mut overlay: Single<&mut Visibility, With<HeatmapOverlay>>) {
+    overlay.set_if_neq(if config.gameplay.heatmap {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+}
+
+/// Paints every cell in the mix of colors of what has gone on in it, while the overlay is on.
+fn paint_heatmap(
+    config: Res<Config>,
+    heatmap: Res<Heatmap>,
+    overlay: Single<&Sprite, With<HeatmapOverlay>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !config.gameplay.heatmap {
+        return;
+    }
+    let Some(data) = images
+        .get_mut(&overlay.image)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+    // Replacements are counted against the most any cell has seen.
+    let most = heatmap
+        .replaced
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1) as f32;
+    for (index, pixel) in data.chunks_exact_mut(4).enumerate() {
+        let (Some(&refined), Some(&hovered), Some(&replaced)) = (
+            heatmap.refined.get(index),
+            heatmap.hovered.get(index),
+            heatmap.replaced.get(index),
+        ) else {
+            break;
+        };
+        let replaced = replaced as f32 / most;
+        let total = refined + hovered + replaced;
+        if total <= 0.001 {
+            pixel.copy_from_slice(&[0, 0, 0, 0]);
+            continue;
+        }
+        let color =
+            (REFINED_COLOR * refined + HOVERED_COLOR * hovered + REPLACED_COLOR * replaced) / total;
+        let alpha = refined.max(hovered).max(replaced) * MAX_ALPHA;
+        let [red, green, blue] = (color * 255.).to_array();
+        pixel.copy_from_slice(&[red as u8, green as u8, blue as u8, (alpha * 255.) as u8]);
+    }
+}
@@ -0,0 +1,250 @@
+//! "Feel for the numbers": after a long search without finding a cluster, the scary numbers near
+//! the cursor begin to pulse faintly, as if the refiner could sense them.
+//!
+//! How long it takes for hints to appear, how far from the cursor they reach and how strongly
+//! the numbers pulse all follow the difficulty setting. Finding scary numbers starts the wait
+//! over, and a cluster once caught is hinted at no more.
+//!
+//! Hints also get harder as the file nears completion: early clusters telegraph themselves
+//! soon and strongly, while the last ones take longer to stir and barely move. How much harder,
+//! and how soon, follows the difficulty too (see [`Ramp`]).
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    ambient::shimmering,
+    bins::Bin,
+    canvas::{CanvasCursor, Supersampling},
+    config::{Config, Difficulty},
+    files::ActiveFile,
+    grid::{
+        detect::ClusterCaught, scary_cells, Cell, ClusterCache, GridSize, Number, RefineSet,
+        Refined, ResetRefinement,
+    },
+    jazz::DefiantJazz,
+    overtime::Overtime,
+    state::AppState,
+};
+
+/// Seconds hints take to fade in once they start.
+const HINT_RAMP: f32 = 10.;
+
+/// Pulses per second of a hinted number.
+const PULSE_RATE: f32 = 0.8;
+
+pub struct HintsPlugin;
+
+impl Plugin for HintsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hints>().add_systems(
+            Update,
+            (
+                track_found,
+                forget_caught.run_if(on_event::<ClusterCaught>),
+                wait.run_if(in_state(AppState::Refining)),
+                // With Defiant Jazz on the music moves the numbers instead, as does the sound of
+                // the room in ambient mode.
+                pulse_numbers.run_if(not(resource_exists::<DefiantJazz>).and(not(shimmering))),
+            )
+                .chain()
+                .in_set(RefineSet::React),
+        );
+    }
+}
+
+/// How hints behave at a difficulty, as the file is opened.
+struct Tuning {
+    /// Seconds without finding a cluster before hints start.
+    delay: f32,
+    /// How far from the cursor numbers are hinted, in world units.
+    reach: f32,
+    /// How much a hinted number grows at the peak of its pulse.
+    strength: f32,
+    ramp: Ramp,
+}
+
+/// How hints get harder as the file nears completion.
+struct Ramp {
+    /// How many times as long hints take to start once the file is complete.
+    delay: f32,
+    /// Share of their strength hints keep once the file is complete.
+    strength: f32,
+    /// Power of the file's completion the ramp follows: above 1, hints stay easy for longer and
+    /// harden towards the end; below 1, they harden early on.
+    curve: f32,
+}
+
+impl Tuning {
+    fn new(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => Self {
+                delay: 20.,
+                reach: 100.,
+                strength: 0.5,
+                ramp: Ramp {
+                    delay: 1.5,
+                    strength: 0.6,
+                    curve: 2.,
+                },
+            },
+            Difficulty::Normal => Self {
+                delay: 45.,
+                reach: 60.,
+                strength: 0.3,
+                ramp: Ramp {
+                    delay: 2.,
+                    strength: 0.3,
+                    curve: 1.5,
+                },
+            },
+            Difficulty::Hard => Self {
+                delay: 90.,
+                reach: 40.,
+                strength: 0.15,
+                ramp: Ramp {
+                    delay: 3.,
+                    strength: 0.15,
+                    curve: 1.,
+                },
+            },
+        }
+    }
+
+    /// How hints behave at a difficulty, with the file as complete as `completion`.
+    fn at(difficulty: Difficulty, completion: f32) -> Self {
+        let tuning = Self::new(difficulty);
+        let along = completion.clamp(0., 1.).powf(tuning.ramp.curve);
+        Self {
+            delay: tuning.delay * (1. + (tuning.ramp.delay - 1.) * along),
+            strength: tuning.strength * (1. + (tuning.ramp.strength - 1.) * along),
+            ..tuning
+        }
+    }
+}
+
+/// The scary numbers and which of them were found, and how long the refiner has been searching.
+#[derive(Resource, Default)]
+pub struct Hints {
+    scary: HashSet<Cell>,
+    found: HashSet<Cell>,
+    /// Seconds of refining since scary numbers were last found.
+    searching: f32,
+    /// How complete the open file is, from 0 to 1.
+    completion: f32,
+}
+
+impl Hints {
+    /// The scary numbers not found yet.
+    pub fn unfound(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.scary.difference(&self.found).copied()
+    }
+
+    /// How strongly hints show, from 0 before they start to 1 once they have faded in.
+    pub fn ramp(&self, config: &Config) -> f32 {
+        if !config.gameplay.hints {
+            return 0.;
+        }
+        let tuning = Tuning::at(config.gameplay.difficulty, self.completion);
+        ((self.searching - tuning.delay) / HINT_RAMP).clamp(0., 1.)
+    }
+}
+
+/// Keeps track of the scary numbers found, starting over when the file is reopened or reset.
+pub fn track_found(
+    file: Res<ActiveFile>,
+    size: Res<GridSize>,
+    overtime: Res<Overtime>,
+    bins: Query<&Bin>,
+    mut resets: EventReader<ResetRefinement>,
+    mut refined: EventReader<Refined>,
+    mut hints: ResMut<Hints>,
+) {
+    if file.is_changed() || !resets.is_empty() {
+        resets.clear();
+        hints.found.clear();
+        hints.searching = 0.;
+    }
+    if file.is_changed() || overtime.is_changed() || size.is_changed() {
+        hints.scary = scary_cells(file.seed, overtime.cluster_spread(), *size)
+            .into_iter()
+            .collect();
+    }
+    for event in refined.read() {
+        let Hints { scary, found, .. } = &mut *hints;
+        let mut any = false;
+        for row in event.cells.min.y..=event.cells.max.y {
+            for col in event.cells.min.x..=event.cells.max.x {
+                let cell = Cell { col, row };
+                any |= scary.contains(&cell) && found.insert(cell);
+            }
+        }
+        if any {
+            hints.searching = 0.;
+        }
+    }
+    let completion =
+        bins.iter().map(|bin| bin.progress).sum::<f32>() / bins.iter().len().max(1) as f32;
+    if hints.completion != completion {
+        hints.completion = completion;
+    }
+}
+
+/// Counts every number of a caught cluster as found, refined or not.
+fn forget_caught(
+    file: Res<ActiveFile>,
+    size: Res<GridSize>,
+    mut caught: EventReader<ClusterCaught>,
+    mut cached: Local<ClusterCache>,
+    mut hints: ResMut<Hints>,
+) {
+    let clusters = cached.get(file.seed, *size);
+    for event in caught.read() {
+        if let Some(cluster) = clusters.get(event.cluster) {
+            hints.found.extend(cluster.iter().copied());
+        }
+    }
+}
+
+fn wait(time: Res<Time<Virtual>>, mut hints: ResMut<Hints>) {
+    hints.searching += time.delta_secs();
+}
+
+/// Pulses the unfound scary numbers near the cursor, once the search has gone on long enough.
+fn pulse_numbers(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    state: Res<State<AppState>>,
+    hints: Res<Hints>,
+    supersampling: Res<Supersampling>,
+    pointer: CanvasCursor,
+    mut numbers: Query<(&Cell, &mut Transform), With<Number>>,
+) {
+    let tuning = Tuning::at(config.gameplay.difficulty, hints.completion);
+    let ramp = hints.ramp(&config);
+    let cursor = pointer
+        .grid()
+        .filter(|_| ramp > 0. && *state.get() == AppState::Refining);
+    // A steady glow rather than a pulse for those who asked for less motion.
+    let wave = if config.accessibility.reduced_motion {
+        1.
+    } else {
+        0.5 - 0.5 * (time.elapsed_secs() * PULSE_RATE * std::f32::consts::TAU).cos()
+    };
+
+    for (cell, mut transform) in &mut numbers {
+        let nearness = match cursor {
+            Some(cursor) if hints.scary.contains(cell) && !hints.found.contains(cell) => {
+                (1. - cell.position().distance(cursor) / tuning.reach).max(0.)
+            }
+            _ => 0.,
+        };
+        let pulse = 1. + tuning.strength * ramp * nearness * wave;
+        let scale = Vec3::splat(pulse * supersampling.text_scale());
+        // Only touch numbers whose scale changes, so the rest are not marked as changed.
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
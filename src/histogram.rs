@@ -0,0 +1,243 @@
+//! Histogram mode: the bins chart the distribution of a dataset.
+//!
+//! Started with `--histogram <path>`, or `--histogram -` to read standard input. Every number in
+//! the data counts, so a CSV file, a JSON array or a plain list all work; anything else in it is
+//! skipped. The range of the values is split evenly across as many bins as new files get, each
+//! labelled with its range and filled relative to the fullest, and the grid shows the leading
+//! digits of values sampled from the dataset.
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::{Bin, DrivenBins, MAX_BIN_COUNT},
+    config::Config,
+    files::{ActiveFile, BinStyle},
+    grid::{GridModel, GridSize, RefineSet, ResetRefinement},
+};
+
+/// Where the dataset is read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Dataset {
+    File(PathBuf),
+    Stdin,
+}
+
+pub struct HistogramPlugin {
+    pub dataset: Option<Dataset>,
+}
+
+impl HistogramPlugin {
+    /// Reads `--histogram <path|->` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut dataset = None;
+        while let Some(arg) = args.next() {
+            if arg == "--histogram" {
+                dataset = args.next().map(|path| match path.as_str() {
+                    "-" => Dataset::Stdin,
+                    _ => Dataset::File(path.into()),
+                });
+            }
+        }
+        Self { dataset }
+    }
+}
+
+impl Plugin for HistogramPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(dataset) = &self.dataset else {
+            return;
+        };
+        let (name, text) = match dataset {
+            Dataset::File(path) => (
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                fs::read_to_string(path),
+            ),
+            Dataset::Stdin => {
+                let mut text = String::new();
+                let read = io::stdin().read_to_string(&mut text).map(|_| text);
+                ("stdin".to_string(), read)
+            }
+        };
+        let values = match text {
+            Ok(text) => parse_values(&text),
+            Err(error) => {
+                error!("Could not read dataset {name}: {error}");
+                return;
+            }
+        };
+        if values.is_empty() {
+            error!("There are no numbers in dataset {name}");
+            return;
+        }
+
+        let bins = app.world().resource::<Config>().gameplay.bins;
+        let histogram = Histogram::new(values, bins.clamp(1, MAX_BIN_COUNT));
+        info!(
+            "Charting {} values from {:.3} to {:.3}",
+            histogram.values.len(),
+            histogram.min,
+            histogram.max
+        );
+        // The dataset stands in for a file of its own.
+        app.insert_resource(ActiveFile {
+            name,
+            progress: histogram.progress(),
+            styles: histogram
+                .labels()
+                .into_iter()
+                .map(BinStyle::named)
+                .collect(),
+            ..default()
+        })
+        .insert_resource(histogram)
+        .insert_resource(DrivenBins)
+        .add_systems(
+            Update,
+            (
+                show_samples.run_if(
+                    resource_added::<Histogram>
+                        .or(on_event::<ResetRefinement>)
+                        .or(resource_changed::<GridSize>),
+                ),
+                hold_bins,
+            )
+                .in_set(RefineSet::React),
+        );
+    }
+}
+
+/// The dataset being charted, and how it falls into the bins.
+#[derive(Resource)]
+struct Histogram {
+    values: Vec<f64>,
+    min: f64,
+    max: f64,
+    /// How many values fall into each bin.
+    counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn new(values: Vec<f64>, bins: usize) -> Self {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut histogram = Self {
+            values,
+            min,
+            max,
+            counts: vec![0; bins],
+        };
+        for index in 0..histogram.values.len() {
+            let bin = histogram.bin_of(histogram.values[index]);
+            histogram.counts[bin] += 1;
+        }
+        histogram
+    }
+
+    fn width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    /// The bin a value falls into; the top of the range goes in the last.
+    fn bin_of(&self, value: f64) -> usize {
+        let width = self.width();
+        if width <= 0. {
+            return 0;
+        }
+        (((value - self.min) / width) as usize).min(self.counts.len() - 1)
+    }
+
+    /// How full each bin is, the fullest being full.
+    fn progress(&self) -> Vec<f32> {
+        let most = self.counts.iter().copied().max().unwrap_or_default().max(1);
+        self.counts
+            .iter()
+            .map(|&count| count as f32 / most as f32)
+            .collect()
+    }
+
+    /// The range of values each bin holds.
+    fn labels(&self) -> Vec<String> {
+        let width = self.width();
+        // Enough decimals to tell neighbouring bins apart.
+        let decimals = if width > 0. {
+            (1. - width.log10().floor()).clamp(0., 3.) as usize
+        } else {
+            0
+        };
+        (0..self.counts.len())
+            .map(|bin| {
+                let low = self.min + width * bin as f64;
+                format!("{low:.decimals$}..{:.decimals$}", low + width)
+            })
+            .collect()
+    }
+}
+
+/// Every number in `text`, whatever separates them.
+fn parse_values(text: &str) -> Vec<f64> {
+    text.split(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+        .filter_map(|word| word.parse().ok())
+        .filter(|value: &f64| value.is_finite())
+        .collect()
+}
+
+/// The leading significant digit of a value.
+fn leading_digit(value: f64) -> u32 {
+    let value = value.abs();
+    if value == 0. {
+        return 0;
+    }
+    (value / 10f64.powf(value.log10().floor())) as u32 % 10
+}
+
+/// Shows a value sampled from the dataset in every cell, the same one for the same cell.
+fn show_samples(histogram: Res<Histogram>, mut model: ResMut<GridModel>) {
+    let count = histogram.values.len() as u64;
+    for (cell, state) in model.cells_mut() {
+        let index = (u64::from(cell.col) << 32 | u64::from(cell.row)).wrapping_mul(0x9e37_79b9);
+        state.value = leading_digit(histogram.values[(index % count) as usize]);
+    }
+}
+
+/// Keeps the bins charting the dataset, whatever is refined into them.
+fn hold_bins(file: Res<ActiveFile>, mut bins: Query<&mut Bin>) {
+    for mut bin in &mut bins {
+        let progress = file.progress.get(bin.index).copied().unwrap_or_default();
+        if bin.progress != progress {
+            bin.progress = progress;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_fall_into_the_bins_of_their_range() {
+        let histogram = Histogram::new(vec![0., 1., 2.5, 5., 9.9, 10.], 5);
+        assert_eq!(histogram.bin_of(0.), 0);
+        assert_eq!(histogram.bin_of(1.99), 0);
+        assert_eq!(histogram.bin_of(2.), 1);
+        assert_eq!(histogram.bin_of(5.), 2);
+        // The top of the range goes in the last bin rather than past it.
+        assert_eq!(histogram.bin_of(10.), 4);
+        assert_eq!(histogram.counts, vec![2, 1, 1, 0, 2]);
+    }
+
+    #[test]
+    fn a_single_value_fills_the_first_bin() {
+        let histogram = Histogram::new(vec![3., 3., 3.], 4);
+        assert_eq!(histogram.counts, vec![3, 0, 0, 0]);
+    }
+}
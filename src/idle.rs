@@ -0,0 +1,117 @@
+//! Idle dimming: with no input for a while, the canvas dims and its animations slow down, to
+//! spare OLED screens left on all day from burn-in.
+//!
+//! The wait is set in minutes in the video config, or turned off with 0. The canvas fades down
+//! over a couple of seconds, and is back the moment a key is pressed, a button clicked or the
+//! mouse moved. The tray dims it on request too, like a screensaver, and then only a key or a
+//! button brings it back, so the mouse can be moved away from the tray. Animations slow by
+//! slowing the virtual clock (see [speed](crate::speed)).
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+};
+
+use crate::{canvas::Canvas, config::Config};
+
+/// Brightness of the canvas once dimmed.
+const DIM_BRIGHTNESS: f32 = 0.25;
+
+/// Seconds the canvas takes to dim.
+const DIM_FADE: f32 = 2.;
+
+/// Speed of the virtual clock while dimmed.
+const IDLE_SPEED: f32 = 0.25;
+
+pub struct IdlePlugin;
+
+impl Plugin for IdlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Idle>()
+            .add_systems(PreUpdate, (watch_input, dim_canvas).chain());
+    }
+}
+
+/// How long the app has gone without input.
+#[derive(Resource, Default)]
+pub struct Idle {
+    /// Real seconds since startup at the last input.
+    last_input: f32,
+    /// How far the canvas has dimmed, from 0 to 1.
+    dimmed: f32,
+    /// Whether the canvas was dimmed on request, until the next key or button.
+    dozing: bool,
+}
+
+impl Idle {
+    /// Seconds since the last input, or forever while dozing.
+    pub fn seconds(&self, time: &Time<Real>) -> f32 {
+        if self.dozing {
+            f32::INFINITY
+        } else {
+            time.elapsed_secs() - self.last_input
+        }
+    }
+
+    pub fn dozing(&self) -> bool {
+        self.dozing
+    }
+
+    /// How fast the virtual clock runs for how dim the canvas is, from 0 to 1 times as fast.
+    pub fn speed(&self) -> f32 {
+        if self.dimmed > 0. {
+            IDLE_SPEED
+        } else {
+            1.
+        }
+    }
+
+    /// Dims the canvas now, or brings it back.
+    pub fn set_dozing(&mut self, dozing: bool) {
+        self.dozing = dozing;
+    }
+}
+
+fn watch_input(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut idle: ResMut<Idle>,
+) {
+    let moved = motion.read().count() > 0;
+    let scrolled = wheel.read().count() > 0;
+    let pressed = keys.get_pressed().next().is_some() || buttons.get_pressed().next().is_some();
+    if moved || scrolled || pressed {
+        idle.last_input = time.elapsed_secs();
+    }
+    if pressed && idle.dozing {
+        idle.dozing = false;
+    }
+}
+
+fn dim_canvas(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut idle: ResMut<Idle>,
+    mut layers: Query<&mut Sprite, With<Canvas>>,
+) {
+    let wait = config.video.idle_dim_minutes as f32 * 60.;
+    let idle_for = idle.seconds(&time);
+    let dimmed = if idle.dozing || (wait > 0. && idle_for >= wait) {
+        (idle.dimmed + time.delta_secs() / DIM_FADE).min(1.)
+    } else {
+        // Back at once, so the refiner never works on a dim screen.
+        0.
+    };
+    if dimmed != idle.dimmed {
+        idle.dimmed = dimmed;
+        let brightness = 1. - (1. - DIM_BRIGHTNESS) * dimmed;
+        for mut layer in &mut layers {
+            // Its alpha is left to ghost mode.
+            let alpha = layer.color.alpha();
+            layer.color = Color::srgba(brightness, brightness, brightness, alpha);
+        }
+    }
+}
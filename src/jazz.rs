@@ -0,0 +1,384 @@
+//! Defiant Jazz: a mode where a song drives the grid.
+//!
+//! Started with `--defiant-jazz`, which plays the bundled tune, or `--defiant-jazz <file>` to
+//! play one of the refiner's own. The song is analysed into an [`Envelope`] before it starts;
+//! the numbers then pulse with its loudness and the bins fill as it plays, all of them reaching
+//! 100% with its last note.
+//!
+//! The bundled tune is synthesized like the sound effects: a walking bass, a swung ride cymbal
+//! and piano comping over a ii-V-I in F.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source},
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+
+use crate::{
+    audio::Envelope,
+    bins::{Bin, DrivenBins, DEFAULT_BIN_COUNT},
+    canvas::Supersampling,
+    config::Config,
+    files::ActiveFile,
+    grid::{Number, RefineSet},
+};
+
+/// Sample rate the bundled tune is synthesized at.
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Tempo of the bundled tune, in beats per minute.
+const TEMPO: f32 = 160.;
+
+/// Roots of the chords of the bundled tune, one per bar, as MIDI notes.
+const ROOTS: [u8; 8] = [43, 48, 41, 41, 45, 50, 43, 48];
+
+/// Whether each chord of [`ROOTS`] is minor (a minor seventh) rather than dominant or major.
+const MINOR: [bool; 8] = [true, false, false, false, true, false, true, false];
+
+/// Times round the chords the bundled tune plays.
+const CHORUSES: usize = 4;
+
+/// How much numbers grow at the loudest point of the song.
+const PULSE_SCALE: f32 = 0.35;
+
+/// How the song is picked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum JazzMode {
+    /// No song; the grid behaves as usual.
+    #[default]
+    Off,
+    /// The tune that comes with the app.
+    Bundled,
+    /// An audio file of the refiner's.
+    File(PathBuf),
+}
+
+impl JazzMode {
+    /// Reads `--defiant-jazz [file]` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter().peekable();
+        let mut mode = JazzMode::Off;
+        while let Some(arg) = args.next() {
+            if arg == "--defiant-jazz" {
+                mode = match args.next_if(|next| !next.starts_with("--")) {
+                    Some(path) => JazzMode::File(path.into()),
+                    None => JazzMode::Bundled,
+                };
+            }
+        }
+        mode
+    }
+}
+
+pub struct JazzPlugin {
+    pub mode: JazzMode,
+}
+
+impl Plugin for JazzPlugin {
+    fn build(&self, app: &mut App) {
+        let track = match &self.mode {
+            JazzMode::Off => return,
+            JazzMode::Bundled => {
+                let mut tunes = app
+                    .add_audio_source::<JazzTune>()
+                    .world_mut()
+                    .resource_mut::<Assets<JazzTune>>();
+                let tune = JazzTune;
+                let handle = tunes.add(tune);
+                let task = AsyncComputeTaskPool::get()
+                    .spawn(async move { Ok(Envelope::analyse(tune.decoder(), 1, SAMPLE_RATE)) });
+                (Track::Tune(handle), task)
+            }
+            JazzMode::File(path) => match fs::read(path) {
+                Ok(bytes) => {
+                    let source = AudioSource {
+                        bytes: bytes.into(),
+                    };
+                    let handle = app
+                        .world_mut()
+                        .resource_mut::<Assets<AudioSource>>()
+                        .add(source.clone());
+                    let task = AsyncComputeTaskPool::get().spawn(async move { analyse(source) });
+                    (Track::File(handle), task)
+                }
+                Err(error) => {
+                    error!("Could not read song {}: {error}", path.display());
+                    return;
+                }
+            },
+        };
+
+        // The song stands in for a file of its own, which starts out empty.
+        app.insert_resource(ActiveFile {
+            name: "Defiant Jazz".to_string(),
+            progress: vec![0.; DEFAULT_BIN_COUNT],
+            ..default()
+        })
+        .insert_resource(DefiantJazz)
+        .insert_resource(DrivenBins)
+        .insert_resource(Analysis(track.0, track.1))
+        .add_systems(
+            Update,
+            (
+                start_song.run_if(resource_exists::<Analysis>),
+                (follow_song, pulse_numbers, fill_bins)
+                    .chain()
+                    .run_if(resource_exists::<Song>)
+                    .in_set(RefineSet::React),
+            ),
+        );
+    }
+}
+
+/// Present while the Defiant Jazz mode is on, for systems that leave the grid to the music.
+#[derive(Resource)]
+pub struct DefiantJazz;
+
+/// The audio to play.
+#[derive(Clone)]
+enum Track {
+    Tune(Handle<JazzTune>),
+    File(Handle<AudioSource>),
+}
+
+/// The song being analysed before it starts.
+#[derive(Resource)]
+struct Analysis(Track, Task<Result<Envelope, String>>);
+
+/// Marks the entity playing the song.
+#[derive(Component)]
+struct SongPlayer;
+
+/// The song playing, and how far into it the grid is.
+#[derive(Resource)]
+struct Song {
+    envelope: Envelope,
+    elapsed: f32,
+}
+
+/// Measures an audio file, which the decoder can only reject by panicking.
+fn analyse(source: AudioSource) -> Result<Envelope, String> {
+    std::panic::catch_unwind(move || {
+        let decoder = source.decoder();
+        let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+        let samples = decoder.map(|sample| f32::from(sample) / f32::from(i16::MAX));
+        Envelope::analyse(samples, channels, sample_rate)
+    })
+    .map_err(|_| "not an audio format this build can play".to_string())
+}
+
+/// Starts the song once it has been analysed.
+fn start_song(mut commands: Commands, mut analysis: ResMut<Analysis>) {
+    let Some(result) = block_on(future::poll_once(&mut analysis.1)) else {
+        return;
+    };
+    commands.remove_resource::<Analysis>();
+    let envelope = match result {
+        Ok(envelope) => envelope,
+        Err(error) => {
+            error!("Could not play song: {error}");
+            return;
+        }
+    };
+    info!("Playing a {:.0}s song", envelope.duration());
+    match analysis.0.clone() {
+        Track::Tune(handle) => {
+            commands.spawn((SongPlayer, AudioPlayer(handle), PlaybackSettings::ONCE))
+        }
+        Track::File(handle) => {
+            commands.spawn((SongPlayer, AudioPlayer(handle), PlaybackSettings::ONCE))
+        }
+    };
+    commands.insert_resource(Song {
+        envelope,
+        elapsed: 0.,
+    });
+}
+
+/// Keeps time with the song, pausing it along with the virtual clock.
+///
+/// The song plays at its own speed however fast the virtual clock runs, so it is followed in
+/// real time, from when it has started playing and for as long as it is not paused.
+fn follow_song(
+    (real, virtual_time): (Res<Time<Real>>, Res<Time<Virtual>>),
+    mut song: ResMut<Song>,
+    sink: Option<Single<&AudioSink, With<SongPlayer>>>,
+) {
+    let Some(sink) = sink else {
+        return;
+    };
+    if virtual_time.is_paused() {
+        if !sink.is_paused() {
+            sink.pause();
+        }
+        return;
+    }
+    if sink.is_paused() {
+        sink.play();
+    }
+    song.elapsed += real.delta_secs();
+}
+
+fn pulse_numbers(
+    song: Res<Song>,
+    config: Res<Config>,
+    supersampling: Res<Supersampling>,
+    mut numbers: Query<&mut Transform, With<Number>>,
+) {
+    let pulse = if config.accessibility.reduced_motion {
+        PULSE_SCALE / 4.
+    } else {
+        PULSE_SCALE
+    };
+    let scale =
+        Vec3::splat((1. + pulse * song.envelope.level(song.elapsed)) * supersampling.text_scale());
+    for mut transform in &mut numbers {
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+/// Fills every bin with the song's progress, so that they are full when it ends.
+fn fill_bins(song: Res<Song>, mut bins: Query<&mut Bin>) {
+    let progress = (song.elapsed / song.envelope.duration().max(f32::EPSILON)).min(1.);
+    for mut bin in &mut bins {
+        if bin.progress != progress {
+            bin.progress = progress;
+        }
+    }
+}
+
+/// The bundled tune.
+#[derive(Asset, TypePath, Clone, Copy, Debug, Default)]
+pub struct JazzTune;
+
+impl JazzTune {
+    fn duration() -> f32 {
+        (ROOTS.len() * CHORUSES * 4) as f32 * 60. / TEMPO
+    }
+}
+
+/// Produces the samples of the [`JazzTune`].
+pub struct JazzDecoder {
+    sample: u32,
+    samples: u32,
+}
+
+/// Frequency of a MIDI note.
+fn frequency(note: u8) -> f32 {
+    440. * 2f32.powf((f32::from(note) - 69.) / 12.)
+}
+
+/// A deterministic noise sample in `-1..1`.
+fn noise(sample: u32) -> f32 {
+    let hash = u64::from(sample).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40;
+    hash as f32 / (1u64 << 23) as f32 - 1.
+}
+
+impl JazzDecoder {
+    /// The tune at `t` seconds.
+    fn at(&self, t: f32) -> f32 {
+        let beats = t * TEMPO / 60.;
+        let bar = beats as usize / 4;
+        let beat = beats.fract();
+        let in_bar = beats as usize % 4;
+        let chord = bar % ROOTS.len();
+        let chorus = bar / ROOTS.len();
+        let root = ROOTS[chord];
+        let third = if MINOR[chord] { 3 } else { 4 };
+        let seventh = if MINOR[chord] || chord % 4 == 1 {
+            10
+        } else {
+            11
+        };
+
+        // Walking bass: root, third, fifth, then a step towards the next chord.
+        let next_root = ROOTS[(chord + 1) % ROOTS.len()];
+        let bass_note = match in_bar {
+            0 => root,
+            1 => root + third,
+            2 => root + 7,
+            _ => next_root + 1,
+        };
+        let bass_time = beat * 60. / TEMPO;
+        let bass = (std::f32::consts::TAU * frequency(bass_note) * bass_time).sin()
+            * (-bass_time * 4.).exp()
+            * 0.5;
+
+        // Ride cymbal on the beats and the swung upbeats of two and four.
+        let swung = beat - 2. / 3.;
+        let hit = if in_bar % 2 == 1 && swung >= 0. {
+            swung
+        } else {
+            beat
+        } * 60.
+            / TEMPO;
+        let ride =
+            (noise(self.sample) - noise(self.sample.wrapping_sub(1))) * (-hit * 18.).exp() * 0.08;
+
+        // Piano comping on the upbeat of two, from the second chorus on.
+        let comp = if chorus > 0 && in_bar == 1 && swung >= 0. {
+            let comp_time = swung * 60. / TEMPO;
+            [12, 12 + third, 19, 12 + seventh]
+                .iter()
+                .map(|interval| {
+                    (std::f32::consts::TAU * frequency(root + interval) * comp_time).sin()
+                })
+                .sum::<f32>()
+                * (-comp_time * 5.).exp()
+                * 0.06
+        } else {
+            0.
+        };
+
+        // Louder towards the last chorus.
+        let dynamics = 0.6 + 0.4 * chorus as f32 / (CHORUSES - 1) as f32;
+        (bass + ride + comp) * dynamics * 0.6
+    }
+}
+
+impl Iterator for JazzDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.samples {
+            return None;
+        }
+        let sample = self.at(self.sample as f32 / SAMPLE_RATE as f32);
+        self.sample += 1;
+        Some(sample)
+    }
+}
+
+impl Source for JazzDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.samples - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(JazzTune::duration()))
+    }
+}
+
+impl Decodable for JazzTune {
+    type DecoderItem = f32;
+    type Decoder = JazzDecoder;
+
+    fn decoder(&self) -> JazzDecoder {
+        JazzDecoder {
+            sample: 0,
+            samples: (JazzTune::duration() * SAMPLE_RATE as f32) as u32,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! The progress of a long-running job of the host application's, such as a build, a data
+//! pipeline or a download, shown on the bins and in the header.
+//!
+//! A [`Job`] reads [`JobUpdate`]s from a channel, or from an iterator it drives on a thread of its
+//! own, so that the iterator may block on the work it reports. The job's overall progress fills
+//! the bins one after another, so that the header's percentage is the job's; a job in stages can
+//! fill a bin per stage instead. Inserted as a resource ahead of the [`MdrPlugin`], the job stands
+//! in for a file of its own and the app goes straight to its grid. Once every update has been
+//! read the job is done, and the header keeps its last message, such as what went wrong.
+//!
+//! [`MdrPlugin`]: crate::MdrPlugin
+
+use std::{
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Mutex,
+    },
+    thread,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    api::MdrCommands,
+    bins::{BinLayout, DEFAULT_BIN_COUNT},
+    files::ActiveFile,
+};
+
+/// Something a job has to report.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobUpdate {
+    /// How far along the whole job is, from 0 to 1.
+    Progress(f32),
+    /// How far along a stage of the job is, counting from 0, from 0 to 1: a bin to each stage.
+    Stage(usize, f32),
+    /// What the job is doing, shown in the header in place of its name.
+    Message(String),
+}
+
+impl From<f32> for JobUpdate {
+    fn from(progress: f32) -> Self {
+        JobUpdate::Progress(progress)
+    }
+}
+
+/// A job whose progress the screen shows, for as long as it reports any.
+#[derive(Resource)]
+pub struct Job {
+    name: String,
+    updates: Mutex<Receiver<JobUpdate>>,
+}
+
+impl Job {
+    /// A job named `name` reporting its progress over a channel.
+    pub fn from_receiver(name: impl Into<String>, updates: Receiver<JobUpdate>) -> Self {
+        Self {
+            name: name.into(),
+            updates: Mutex::new(updates),
+        }
+    }
+
+    /// A job named `name` reporting its progress as `updates` yields it, driven to its end on a
+    /// thread of its own.
+    pub fn from_iter<I>(name: impl Into<String>, updates: I) -> Self
+    where
+        I: IntoIterator + Send + 'static,
+        I::Item: Into<JobUpdate>,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for update in updates {
+                // Nobody is left to tell once the app has gone.
+                if sender.send(update.into()).is_err() {
+                    return;
+                }
+            }
+        });
+        Self::from_receiver(name, receiver)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Sets a job up as the file to refine, should there be one.
+pub struct JobPlugin;
+
+impl Plugin for JobPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(job) = app.world().get_resource::<Job>() else {
+            return;
+        };
+        // The job stands in for a file of its own, starting empty.
+        let file = ActiveFile {
+            name: job.name.clone(),
+            progress: vec![0.; DEFAULT_BIN_COUNT],
+            ..default()
+        };
+        app.insert_resource(file)
+            .add_systems(Update, follow_job.run_if(resource_exists::<Job>));
+    }
+}
+
+/// Shows the updates the job has reported since the last frame, and lets it go once it is done.
+fn follow_job(mut commands: Commands, job: Res<Job>, layout: Res<BinLayout>, mut mdr: MdrCommands) {
+    let updates = job.updates.lock().unwrap();
+    loop {
+        match updates.try_recv() {
+            Ok(JobUpdate::Progress(progress)) => {
+                // Each bin holds an equal share of the job, filled in order.
+                let bins = layout.count();
+                for bin in 0..bins {
+                    mdr.set_bin(bin, progress * bins as f32 - bin as f32);
+                }
+            }
+            Ok(JobUpdate::Stage(stage, progress)) => mdr.set_bin(stage, progress),
+            Ok(JobUpdate::Message(message)) => mdr.push_message(message),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                info!("{} is done", job.name);
+                commands.remove_resource::<Job>();
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Kiosk mode (`--kiosk`): for a lobby or office display that runs unattended.
+//!
+//! The window goes borderless fullscreen and cannot be closed, the cursor hides once the mouse
+//! has been left alone for a few seconds, and the menus offer no way to quit. Only
+//! Ctrl+Alt+Shift+Q does. Once a file is complete, its summary stays up for a while and then the
+//! kiosk's own file is started over on a new grid, so the display always has something being
+//! refined on it without the library filling up with files. A summary brought up from the pause
+//! menu stays up until it is dismissed.
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    config::Config,
+    files::{FileLibrary, OpenFile},
+    finale::FinaleEnded,
+    state::AppState,
+    transition::{in_transition, TransitionEffect, TransitionTo},
+};
+
+/// Seconds without input before the cursor hides.
+pub const CURSOR_TIMEOUT: f32 = 3.;
+
+/// Seconds the summary of a complete file stays up before a new file is started.
+const RESTART_DELAY: f32 = 20.;
+
+/// Name of the file the kiosk refines over and over.
+const KIOSK_FILE: &str = "Kiosk";
+
+/// Keys held together to quit.
+const QUIT_CHORD: [[KeyCode; 2]; 3] = [
+    [KeyCode::ControlLeft, KeyCode::ControlRight],
+    [KeyCode::AltLeft, KeyCode::AltRight],
+    [KeyCode::ShiftLeft, KeyCode::ShiftRight],
+];
+
+pub struct KioskPlugin {
+    pub enabled: bool,
+}
+
+impl KioskPlugin {
+    /// Reads `--kiosk` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            enabled: args.into_iter().any(|arg| arg == "--kiosk"),
+        }
+    }
+}
+
+impl Plugin for KioskPlugin {
+    fn build(&self, app: &mut App) {
+        if !self.enabled {
+            return;
+        }
+        info!("Running as a kiosk; quit with Ctrl+Alt+Shift+Q");
+        app.insert_resource(Kiosk)
+            .add_systems(OnExit(AppState::Summary), stop_waiting)
+            .add_systems(
+                Update,
+                (
+                    quit_chord,
+                    wait_to_restart.run_if(on_event::<FinaleEnded>),
+                    restart.run_if(
+                        in_state(AppState::Summary)
+                            .and(resource_exists::<RestartAt>)
+                            .and(not(in_transition)),
+                    ),
+                ),
+            );
+    }
+}
+
+/// Present while the app runs as a kiosk.
+#[derive(Resource)]
+pub struct Kiosk;
+
+/// When the summary on screen gives way to a new file, in real seconds since startup.
+#[derive(Resource)]
+struct RestartAt(f32);
+
+fn quit_chord(keys: Res<ButtonInput<KeyCode>>, mut exit: EventWriter<AppExit>) {
+    if keys.just_pressed(KeyCode::KeyQ) && QUIT_CHORD.iter().all(|either| keys.any_pressed(*either))
+    {
+        exit.write(AppExit::Success);
+    }
+}
+
+/// Counts down to a new file once the finale of a complete one has played.
+fn wait_to_restart(mut commands: Commands, time: Res<Time<Real>>) {
+    commands.insert_resource(RestartAt(time.elapsed_secs() + RESTART_DELAY));
+}
+
+fn stop_waiting(mut commands: Commands) {
+    commands.remove_resource::<RestartAt>();
+}
+
+fn restart(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    at: Res<RestartAt>,
+    config: Res<Config>,
+    mut library: ResMut<FileLibrary>,
+    mut opens: EventWriter<OpenFile>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    if time.elapsed_secs() < at.0 {
+        return;
+    }
+    commands.remove_resource::<RestartAt>();
+    opens.write(OpenFile(library.recycle(KIOSK_FILE, config.gameplay.bins)));
+    transitions.write(TransitionTo::new(
+        AppState::Refining,
+        TransitionEffect::Wipe,
+    ));
+}
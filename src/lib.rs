@@ -0,0 +1,293 @@
+//! Macrodata refinement on a pixel-perfect canvas.
+//!
+//! All of it is the [`MdrPlugin`], which the `mdr` binary adds to an app of its own and
+//! embedders can add to theirs, after bevy's `DefaultPlugins`. A host application shows its own
+//! work on the screen, such as the progress of a long-running job, through [`MdrCommands`].
+//!
+//! Plugins of its own may also drive the bins with a [`BinDataSource`], picked with
+//! `--bin-source`, and decide what counts as catching a cluster of scary numbers with a
+//! [`ScaryDetector`], picked by the config's `detector`:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use mdr::{
+//!     BinDataSource, BinSample, Cell, MdrCommands, MdrPlugin, RegisterBinSource,
+//!     RegisterScaryDetector, ScaryDetector, Temper,
+//! };
+//!
+//! /// Catches a cluster with a selection holding its first number.
+//! struct FirstNumber;
+//!
+//! impl ScaryDetector for FirstNumber {
+//!     fn catches(&self, selection: URect, cluster: &[Cell]) -> bool {
+//!         let first = cluster[0];
+//!         selection.contains(UVec2::new(first.col, first.row))
+//!     }
+//! }
+//!
+//! /// Queues of tickets, each a bin.
+//! struct Queues(Vec<(&'static str, f32)>);
+//!
+//! impl BinDataSource for Queues {
+//!     fn poll(&mut self) -> Vec<BinSample> {
+//!         self.0
+//!             .iter()
+//!             .map(|&(name, fill)| BinSample::new(fill).named(name))
+//!             .collect()
+//!     }
+//! }
+//!
+//! fn label_bins(mut mdr: MdrCommands) {
+//!     mdr.set_bin_name(0, "Urgent");
+//!     mdr.set_bin_temper(0, Some(Temper::Dread));
+//! }
+//!
+//! App::new()
+//!     .add_plugins((DefaultPlugins, MdrPlugin::from_args(std::env::args().skip(1))))
+//!     .register_scary_detector("first", FirstNumber)
+//!     .register_bin_source("queues", |_| {
+//!         Ok(Box::new(Queues(vec![("Support", 0.4), ("Billing", 0.7)])))
+//!     })
+//!     .add_systems(Startup, label_bins)
+//!     .run();
+//! ```
+
+mod achievements;
+mod ambient;
+mod animation;
+mod announce;
+mod api;
+mod audio;
+mod backdrop;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod bezel;
+mod bins;
+mod board;
+mod boot;
+mod canvas;
+mod card;
+mod chart;
+mod clock;
+mod config;
+mod cursor;
+mod daily;
+mod diagnose;
+mod directory;
+#[cfg(all(feature = "discord", unix))]
+mod discord;
+mod fault;
+mod field;
+mod files;
+mod finale;
+mod footer;
+mod ghost;
+mod glow;
+mod glyphs;
+mod grain;
+mod grid;
+mod header;
+mod heatmap;
+mod hints;
+mod histogram;
+mod idle;
+mod jazz;
+mod job;
+mod kiosk;
+mod lids;
+mod loading;
+mod macros;
+mod menu;
+mod milestone;
+mod minimap;
+mod net;
+mod night_shift;
+#[cfg(feature = "notifications")]
+mod notifications;
+mod overtime;
+mod particles;
+mod pause;
+mod picking;
+mod pomodoro;
+mod readout;
+mod replay;
+mod rulers;
+mod settings;
+mod shake;
+mod shift;
+mod signature;
+mod snapshot;
+mod soundscape;
+mod source;
+mod speed;
+mod state;
+mod summary;
+mod theme;
+mod toast;
+mod tooltip;
+mod transition;
+mod tray;
+mod tutorial;
+mod tween;
+mod ui;
+mod wellness;
+mod zoom;
+
+use bevy::prelude::*;
+
+pub use api::{BinChanged, BinRelabeled, FileNameChanged, MdrCommands, MessageChanged};
+pub use grid::{
+    detect::{RegisterScaryDetector, ScaryDetector},
+    Cell, Temper,
+};
+pub use job::{Job, JobUpdate};
+pub use source::{BinDataSource, BinSample, RegisterBinSource, SourceFactory};
+
+/// The refinement screen, set up from command line arguments like the binary's. Without any, it
+/// boots to the main menu, unless there is a [`Job`] to show.
+///
+/// Ghost mode needs the primary window to be created see-through, as the binary's is.
+#[derive(Clone, Debug, Default)]
+pub struct MdrPlugin {
+    args: Vec<String>,
+}
+
+impl MdrPlugin {
+    /// Set up from `args`, a command line without the program's name.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            args: args.into_iter().collect(),
+        }
+    }
+
+    /// Whether the arguments ask for a kiosk, whose window only closes when told to with its
+    /// key chord.
+    pub fn kiosk(&self) -> bool {
+        kiosk::KioskPlugin::from_args(self.args.iter().cloned()).enabled
+    }
+}
+
+impl Plugin for MdrPlugin {
+    fn build(&self, app: &mut App) {
+        let args = self.args.clone();
+        let role = net::NetRole::from_args(args.iter().cloned());
+        let jazz = jazz::JazzMode::from_args(args.iter().cloned());
+        let directory = directory::DirectoryPlugin::from_args(args.iter().cloned());
+        let source = source::SourceSpec::from_args(args.iter().cloned());
+        let histogram = histogram::HistogramPlugin::from_args(args.iter().cloned());
+        let kiosk = kiosk::KioskPlugin::from_args(args.iter().cloned());
+        let macros = macros::MacroMode::from_args(args.iter().cloned());
+        let daily = daily::DailyPlugin::from_args(args.iter().cloned());
+        let snapshot = snapshot::SnapshotPlugin::from_args(args.iter().cloned());
+        let diagnose = diagnose::DiagnosePlugin::from_args(args.iter().cloned());
+        let replay = replay::ReplayMode::from_args(args);
+        // Shared sessions, replays, input macros, songs, directories, bin sources, datasets, the
+        // daily challenge, snapshots, jobs and the self-test are about a grid that is not picked
+        // from the menu, so once loaded they skip the boot and go straight to it.
+        let initial = if role == net::NetRole::Offline
+            && !matches!(replay, replay::ReplayMode::Play(_))
+            && !matches!(macros, macros::MacroMode::Play(_))
+            && jazz == jazz::JazzMode::Off
+            && directory.path.is_none()
+            && source.is_none()
+            && histogram.dataset.is_none()
+            && !daily.enabled
+            && snapshot.restore.is_none()
+            && !diagnose.enabled
+            && !app.world().contains_resource::<job::Job>()
+        {
+            state::AppState::Boot
+        } else {
+            state::AppState::Refining
+        };
+        app.add_plugins((
+            config::ConfigPlugin,
+            theme::ThemePlugin,
+            files::FilesPlugin,
+            state::AppStatePlugin,
+            loading::LoadingPlugin { then: initial },
+            audio::SoundPlugin,
+            ui::UiPlugin,
+            transition::TransitionPlugin,
+            canvas::CanvasPlugin,
+            chart::ChartPlugin,
+            clock::ClockPlugin,
+            cursor::CursorPlugin,
+            grain::GrainPlugin,
+        ))
+        .add_plugins((
+            grid::GridPlugin,
+            bins::BinsPlugin,
+            header::HeaderPlugin,
+            net::NetPlugin { role },
+            replay::ReplayPlugin { mode: replay },
+            rulers::RulersPlugin,
+            minimap::MinimapPlugin,
+            hints::HintsPlugin,
+            glow::GlowPlugin,
+            overtime::OvertimePlugin,
+            jazz::JazzPlugin { mode: jazz },
+            pomodoro::PomodoroPlugin,
+            directory,
+            source::SourcePlugin { spec: source },
+            histogram,
+        ))
+        .add_plugins((
+            boot::BootPlugin,
+            menu::MainMenuPlugin,
+            pause::PausePlugin,
+            settings::SettingsPlugin,
+            wellness::WellnessPlugin,
+            toast::ToastPlugin,
+            milestone::MilestonePlugin,
+            tween::TweenPlugin,
+            particles::ParticlePlugin,
+            shake::ShakePlugin,
+            finale::FinalePlugin,
+            announce::AnnouncePlugin,
+            field::FieldPlugin,
+        ))
+        .add_plugins((
+            picking::PickingPlugin,
+            tooltip::TooltipPlugin,
+            summary::SummaryPlugin,
+            card::CardPlugin,
+            achievements::AchievementsPlugin,
+            idle::IdlePlugin,
+            shift::ShiftPlugin,
+            ghost::GhostPlugin,
+            kiosk,
+            zoom::ZoomPlugin,
+            tutorial::TutorialPlugin,
+            signature::SignaturePlugin,
+            animation::AnimationPlugin,
+            lids::LidsPlugin,
+            #[cfg(all(feature = "discord", unix))]
+            discord::DiscordPlugin,
+        ))
+        .add_plugins((
+            macros::MacroPlugin { mode: macros },
+            daily,
+            snapshot,
+            ambient::AmbientPlugin,
+            tray::TrayPlugin,
+            bezel::BezelPlugin,
+            soundscape::SoundscapePlugin,
+            board::BoardPlugin,
+            night_shift::NightShiftPlugin,
+            api::ApiPlugin,
+            job::JobPlugin,
+            footer::FooterPlugin,
+            fault::FaultPlugin,
+            backdrop::BackdropPlugin,
+            #[cfg(feature = "notifications")]
+            notifications::NotificationsPlugin,
+        ))
+        .add_plugins((
+            readout::ReadoutPlugin,
+            speed::SpeedPlugin,
+            heatmap::HeatmapPlugin,
+            diagnose,
+        ));
+    }
+}
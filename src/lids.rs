@@ -0,0 +1,169 @@
+//! The lids of the bins, which open to take in refined numbers and close again, and the chutes
+//! above them, which flash as the numbers go down.
+//!
+//! Both are played from the `bins.sheet.ron` [`SpriteSheet`] in the assets: the `open` and
+//! `close` clips on the lids and the `chute` clip on the chutes, each resting on the last frame
+//! of its clip. Without the sheet the bins are shown as they always were, with neither.
+
+use bevy::prelude::*;
+
+use crate::{
+    animation::{AnimationFinished, FrameAnimation, SpriteSheet},
+    bins::Bin,
+    canvas::PIXEL_PERFECT_LAYERS,
+    grid::{RefineSet, Refined},
+    loading::LoadingAssets,
+};
+
+/// Path of the sprite sheet in the assets.
+const SHEET: &str = "bins.sheet.ron";
+
+/// Height of a lid, and of a chute, relative to their bin's.
+const LID_HEIGHT: f32 = 0.2;
+const CHUTE_HEIGHT: f32 = 0.2;
+
+/// Width of a chute, relative to its bin's.
+const CHUTE_WIDTH: f32 = 0.5;
+
+pub struct LidsPlugin;
+
+impl Plugin for LidsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_sheet).add_systems(
+            Update,
+            (fit_lids, open_lids, close_lids)
+                .chain()
+                .in_set(RefineSet::React),
+        );
+    }
+}
+
+/// The sprite sheet of the lids and chutes.
+#[derive(Resource)]
+struct BinSheet(Handle<SpriteSheet>);
+
+/// The lid of a bin, and whether it is opening rather than closing.
+#[derive(Component)]
+pub struct Lid {
+    pub bin: usize,
+    pub open: bool,
+}
+
+/// The chute above a bin.
+#[derive(Component)]
+struct Chute(usize);
+
+/// Marks a bin that has been given its lid and chute.
+#[derive(Component)]
+struct Fitted;
+
+fn load_sheet(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+) {
+    let sheet = asset_server.load(SHEET);
+    loading.wait_for(sheet.clone());
+    commands.insert_resource(BinSheet(sheet));
+}
+
+/// Gives every bin its lid and chute as it is spawned, once the sheet has loaded.
+fn fit_lids(
+    mut commands: Commands,
+    sheet: Res<BinSheet>,
+    sheets: Res<Assets<SpriteSheet>>,
+    bins: Query<(Entity, &Bin, &Sprite), Without<Fitted>>,
+) {
+    let Some(sheet) = sheets.get(&sheet.0) else {
+        return;
+    };
+    for (entity, bin, sprite) in &bins {
+        let size = sprite.custom_size.unwrap_or_default();
+        let lid = size * Vec2::new(1., LID_HEIGHT);
+        let chute = size * Vec2::new(CHUTE_WIDTH, CHUTE_HEIGHT);
+        // Children of the bin, so that they go with it when the bins are laid out again.
+        commands
+            .entity(entity)
+            .insert(Fitted)
+            .with_children(|parts| {
+                parts.spawn((
+                    Lid {
+                        bin: bin.index,
+                        open: false,
+                    },
+                    Sprite {
+                        custom_size: Some(lid),
+                        ..sheet.sprite("close")
+                    },
+                    Transform::from_xyz(0., (size.y + lid.y) / 2., 0.2),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+                parts.spawn((
+                    Chute(bin.index),
+                    Sprite {
+                        custom_size: Some(chute),
+                        ..sheet.sprite("chute")
+                    },
+                    Transform::from_xyz(0., size.y / 2. + lid.y + chute.y / 2., 0.2),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            });
+    }
+}
+
+/// Opens the lid of each bin numbers are refined into, and flashes its chute.
+fn open_lids(
+    mut commands: Commands,
+    mut refined: EventReader<Refined>,
+    sheet: Res<BinSheet>,
+    sheets: Res<Assets<SpriteSheet>>,
+    mut lids: Query<(Entity, &mut Lid)>,
+    chutes: Query<(Entity, &Chute)>,
+) {
+    let Some(sheet) = sheets.get(&sheet.0) else {
+        refined.clear();
+        return;
+    };
+    for event in refined.read() {
+        for (entity, mut lid) in &mut lids {
+            if let Some(clip) = sheet.clip("open").filter(|_| lid.bin == event.bin) {
+                lid.open = true;
+                commands
+                    .entity(entity)
+                    .try_insert(FrameAnimation::new(clip.clone()));
+            }
+        }
+        for (entity, chute) in &chutes {
+            if let Some(clip) = sheet.clip("chute").filter(|_| chute.0 == event.bin) {
+                commands
+                    .entity(entity)
+                    .try_insert(FrameAnimation::new(clip.clone()));
+            }
+        }
+    }
+}
+
+/// Closes lids once they have opened.
+fn close_lids(
+    mut commands: Commands,
+    mut finished: EventReader<AnimationFinished>,
+    sheet: Res<BinSheet>,
+    sheets: Res<Assets<SpriteSheet>>,
+    mut lids: Query<&mut Lid>,
+) {
+    let clip = sheets.get(&sheet.0).and_then(|sheet| sheet.clip("close"));
+    for event in finished.read() {
+        let Ok(mut lid) = lids.get_mut(event.entity) else {
+            continue;
+        };
+        if !lid.open {
+            continue;
+        }
+        lid.open = false;
+        if let Some(clip) = clip {
+            commands
+                .entity(event.entity)
+                .try_insert(FrameAnimation::new(clip.clone()));
+        }
+    }
+}
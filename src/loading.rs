@@ -0,0 +1,220 @@
+//! The loading screen shown while assets load, before anything else is drawn with them.
+//!
+//! Plugins that load assets from disk hand their handles to [`LoadingAssets`] while the app is
+//! built or at startup; the app then stays in [`AppState::Loading`] until every one of them has
+//! loaded, so that the first frames of the boot or the menu never show fallback fonts or missing
+//! textures. Assets made in memory, like the synthesized sounds, are ready at once. One that
+//! fails is reported as a [`Fault`], and is waited on until it is retried and loads, or the
+//! refiner goes on without it.
+//!
+//! The loading bar only appears once loading has taken long enough to notice, so a quick load
+//! goes straight on without flashing it.
+
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    canvas::{CanvasFill, PIXEL_PERFECT_LAYERS},
+    fault::{Fault, FaultAnswered, FaultKind, Faults},
+    state::AppState,
+};
+
+/// Seconds of loading before the loading bar appears.
+const GRACE_TIME: f32 = 0.25;
+
+const BAR_WIDTH: f32 = 120.;
+const BAR_HEIGHT: f32 = 6.;
+const BAR_COLOR: Color = Color::srgb(0.0, 0.7, 0.8);
+
+pub struct LoadingPlugin {
+    /// The state the app goes on to once everything has loaded.
+    pub then: AppState,
+}
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        // The font all text on the canvas is set in.
+        let mut assets = LoadingAssets::default();
+        assets.wait_for(TextFont::default().font);
+        app.insert_resource(assets)
+            .insert_resource(AfterLoading(self.then))
+            .add_systems(OnEnter(AppState::Loading), spawn_loading_screen)
+            .add_systems(
+                Update,
+                (
+                    retry_assets.run_if(on_event::<FaultAnswered>),
+                    wait_for_assets,
+                    show_progress,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Loading)),
+            );
+    }
+}
+
+/// Assets the app waits for before leaving [`AppState::Loading`].
+#[derive(Resource, Default)]
+pub struct LoadingAssets {
+    pending: Vec<UntypedHandle>,
+    /// Assets that failed to load, until their faults are answered.
+    failed: Vec<UntypedHandle>,
+    total: usize,
+}
+
+impl LoadingAssets {
+    /// Waits for `handle` to load before going on.
+    pub fn wait_for(&mut self, handle: impl Into<UntypedHandle>) {
+        self.pending.push(handle.into());
+        self.total += 1;
+    }
+
+    /// How much has loaded, from 0 to 1.
+    fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            1. - self.pending.len() as f32 / self.total as f32
+        }
+    }
+}
+
+/// The state to go on to once loaded.
+#[derive(Resource)]
+struct AfterLoading(AppState);
+
+#[derive(Component)]
+struct LoadingBar;
+
+#[derive(Component)]
+struct LoadingFill;
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands.spawn((
+        Sprite {
+            color: Color::BLACK,
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 30.),
+        StateScoped(AppState::Loading),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    commands
+        .spawn((
+            LoadingBar,
+            Sprite {
+                color: BAR_COLOR.with_alpha(0.25),
+                custom_size: Some(Vec2::new(BAR_WIDTH, BAR_HEIGHT)),
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 31.),
+            Visibility::Hidden,
+            StateScoped(AppState::Loading),
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                LoadingFill,
+                Sprite {
+                    color: BAR_COLOR,
+                    custom_size: Some(Vec2::new(0., BAR_HEIGHT)),
+                    ..default()
+                },
+                Transform::from_xyz(-BAR_WIDTH / 2., 0., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+            bar.spawn((
+                Text2d::new("LOADING"),
+                TextFont {
+                    font_size: 8.0,
+                    ..default()
+                },
+                TextColor(BAR_COLOR),
+                Transform::from_xyz(0., 10., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        });
+}
+
+/// Lets go of the assets that are done loading, and goes on once they all are.
+fn wait_for_assets(
+    asset_server: Res<AssetServer>,
+    after: Res<AfterLoading>,
+    mut assets: ResMut<LoadingAssets>,
+    mut faults: ResMut<Faults>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let LoadingAssets {
+        pending, failed, ..
+    } = &mut *assets;
+    pending.retain(|handle| {
+        match asset_server.get_load_state(handle.id()) {
+            // Assets the server is not loading were made in memory.
+            None | Some(LoadState::Loaded) => false,
+            Some(LoadState::Failed(error)) => {
+                faults.report(Fault::new(asset_fault(handle), "ASSET MISSING", error));
+                failed.push(handle.clone());
+                false
+            }
+            Some(LoadState::NotLoaded | LoadState::Loading) => true,
+        }
+    });
+    if assets.pending.is_empty() && assets.failed.is_empty() {
+        info!("Loaded {} assets", assets.total);
+        next.set(after.0);
+    }
+}
+
+/// Loads again the assets whose faults are retried, and lets go of those gone on without.
+fn retry_assets(
+    asset_server: Res<AssetServer>,
+    mut answered: EventReader<FaultAnswered>,
+    mut assets: ResMut<LoadingAssets>,
+) {
+    for answer in answered.read() {
+        let Some(index) = assets
+            .failed
+            .iter()
+            .position(|handle| asset_fault(handle) == answer.kind)
+        else {
+            continue;
+        };
+        let handle = assets.failed.remove(index);
+        if !answer.retry {
+            warn!("Going on without {:?}", answer.kind);
+            continue;
+        }
+        if let Some(path) = handle.path() {
+            asset_server.reload(path.clone());
+        }
+        assets.pending.push(handle);
+    }
+}
+
+/// The kind of fault of an asset that failed to load.
+fn asset_fault(handle: &UntypedHandle) -> FaultKind {
+    FaultKind::Asset(
+        handle
+            .path()
+            .map_or_else(|| "?".to_string(), ToString::to_string),
+    )
+}
+
+fn show_progress(
+    time: Res<Time<Real>>,
+    mut elapsed: Local<f32>,
+    assets: Res<LoadingAssets>,
+    mut bar: Single<&mut Visibility, With<LoadingBar>>,
+    mut fill: Single<(&mut Sprite, &mut Transform), With<LoadingFill>>,
+) {
+    *elapsed += time.delta_secs();
+    if *elapsed < GRACE_TIME {
+        return;
+    }
+    bar.set_if_neq(Visibility::Inherited);
+    let (sprite, transform) = &mut *fill;
+    let size = Vec2::new(BAR_WIDTH * assets.progress(), BAR_HEIGHT);
+    if sprite.custom_size != Some(size) {
+        sprite.custom_size = Some(size);
+        transform.translation.x = (size.x - BAR_WIDTH) / 2.;
+    }
+}
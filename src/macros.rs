@@ -0,0 +1,401 @@
+//! Input macros: the raw input of a session recorded to a file, and played back as if the keys and
+//! mouse it records were being used again, for trailers and for reproducing bug reports.
+//!
+//! Unlike a [replay](crate::replay), a macro knows nothing of the grid. It holds the keys and
+//! buttons pressed and released, where the cursor was, and how far the wheel turned, with the
+//! time of each, from the moment the refiner first starts refining. Playing it opens the same
+//! file afresh and feeds the input back at the same times, and everything else follows from that
+//! as it did the first time, timers and all, so long as the settings are the same. The keys and
+//! mouse still work during playback, and whatever they do goes on top of the macro's.
+//!
+//! Macros are text: a `MDR-INPUT <version>` header line, the `FILE ...` line (see
+//! [`ActiveFile`]) of the file being refined, then one `<seconds> <input>` line per input, which
+//! is one of `key <key code> down|up`, `button <button> down|up`, `cursor <x> <y>` as fractions
+//! of the window from its top left, `cursor -` for a cursor that left the window, or
+//! `wheel <x> <y>`.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use bevy::{
+    input::{
+        mouse::{MouseScrollUnit, MouseWheel},
+        InputSystem,
+    },
+    prelude::*,
+    reflect::{DynamicEnum, Enum, TypeInfo, Typed, VariantInfo},
+    window::PrimaryWindow,
+};
+
+use crate::{files::ActiveFile, state::AppState};
+
+/// Version written to the header of macro files.
+const FORMAT_VERSION: u32 = 1;
+
+/// Whether this session's input is recorded or played back.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MacroMode {
+    #[default]
+    Off,
+    /// Write all input to the given file.
+    Record(PathBuf),
+    /// Play back the input of the given file.
+    Play(PathBuf),
+}
+
+impl MacroMode {
+    /// Parses `--record-input PATH` and `--play-input PATH` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut mode = MacroMode::Off;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record-input" => match args.next() {
+                    Some(path) => mode = MacroMode::Record(path.into()),
+                    None => eprintln!("--record-input needs a file to write the input to"),
+                },
+                "--play-input" => match args.next() {
+                    Some(path) => mode = MacroMode::Play(path.into()),
+                    None => eprintln!("--play-input needs an input file to play"),
+                },
+                _ => {}
+            }
+        }
+        mode
+    }
+}
+
+pub struct MacroPlugin {
+    pub mode: MacroMode,
+}
+
+impl Plugin for MacroPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            MacroMode::Off => {}
+            MacroMode::Record(path) => match File::create(path) {
+                Ok(file) => {
+                    info!("Recording input to {}", path.display());
+                    app.insert_resource(MacroRecorder { file, start: None })
+                        .add_systems(OnEnter(AppState::Refining), start_recording)
+                        .add_systems(PreUpdate, record_input.after(InputSystem));
+                }
+                Err(error) => error!("Could not record input to {}: {error}", path.display()),
+            },
+            MacroMode::Play(path) => match MacroPlayback::load(path) {
+                Ok((file, playback)) => {
+                    info!("Playing input {}", path.display());
+                    app.insert_resource(file)
+                        .insert_resource(playback)
+                        .add_systems(OnEnter(AppState::Refining), start_playing)
+                        .add_systems(
+                            PreUpdate,
+                            play_input
+                                .after(InputSystem)
+                                .run_if(resource_exists::<MacroPlayback>),
+                        );
+                }
+                Err(error) => error!("Could not play input {}: {error}", path.display()),
+            },
+        }
+    }
+}
+
+/// One change of the input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Input {
+    Key(KeyCode, bool),
+    Button(MouseButton, bool),
+    /// Position of the cursor as a fraction of the window, from its top left, if it is over it.
+    Cursor(Option<Vec2>),
+    Wheel(Vec2),
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = |pressed: bool| if pressed { "down" } else { "up" };
+        match self {
+            Input::Key(key, pressed) => write!(f, "key {} {}", key.variant_name(), state(*pressed)),
+            Input::Button(button, pressed) => {
+                let name = match button {
+                    MouseButton::Left => "left".to_string(),
+                    MouseButton::Right => "right".to_string(),
+                    MouseButton::Middle => "middle".to_string(),
+                    MouseButton::Back => "back".to_string(),
+                    MouseButton::Forward => "forward".to_string(),
+                    MouseButton::Other(index) => index.to_string(),
+                };
+                write!(f, "button {name} {}", state(*pressed))
+            }
+            Input::Cursor(Some(at)) => write!(f, "cursor {} {}", at.x, at.y),
+            Input::Cursor(None) => write!(f, "cursor -"),
+            Input::Wheel(delta) => write!(f, "wheel {} {}", delta.x, delta.y),
+        }
+    }
+}
+
+/// Error returned when a line of text is not an [`Input`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParseInputError;
+
+impl FromStr for Input {
+    type Err = ParseInputError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let pressed = |word: &str| match word {
+            "down" => Ok(true),
+            "up" => Ok(false),
+            _ => Err(ParseInputError),
+        };
+        let vec = |x: &str, y: &str| -> Result<Vec2, ParseInputError> {
+            let x = x.parse().map_err(|_| ParseInputError)?;
+            let y = y.parse().map_err(|_| ParseInputError)?;
+            Ok(Vec2::new(x, y))
+        };
+        match words[..] {
+            ["key", name, state] => {
+                // Key codes are named as their variants are, which only reflection knows all of.
+                // Reflection panics on variants there are none of, so those are refused first.
+                let TypeInfo::Enum(info) = KeyCode::type_info() else {
+                    return Err(ParseInputError);
+                };
+                if !matches!(info.variant(name), Some(VariantInfo::Unit(_))) {
+                    return Err(ParseInputError);
+                }
+                let key =
+                    KeyCode::from_reflect(&DynamicEnum::new(name, ())).ok_or(ParseInputError)?;
+                Ok(Input::Key(key, pressed(state)?))
+            }
+            ["button", name, state] => {
+                let button = match name {
+                    "left" => MouseButton::Left,
+                    "right" => MouseButton::Right,
+                    "middle" => MouseButton::Middle,
+                    "back" => MouseButton::Back,
+                    "forward" => MouseButton::Forward,
+                    index => MouseButton::Other(index.parse().map_err(|_| ParseInputError)?),
+                };
+                Ok(Input::Button(button, pressed(state)?))
+            }
+            ["cursor", "-"] => Ok(Input::Cursor(None)),
+            ["cursor", x, y] => Ok(Input::Cursor(Some(vec(x, y)?))),
+            ["wheel", x, y] => Ok(Input::Wheel(vec(x, y)?)),
+            _ => Err(ParseInputError),
+        }
+    }
+}
+
+/// Writes input to a macro file as it happens.
+#[derive(Resource)]
+struct MacroRecorder {
+    file: File,
+    /// Real seconds since startup at which recording started, once the refiner has started
+    /// refining.
+    start: Option<f32>,
+}
+
+fn start_recording(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    file: Res<ActiveFile>,
+    recorder: Option<ResMut<MacroRecorder>>,
+) {
+    let Some(mut recorder) = recorder.filter(|recorder| recorder.start.is_none()) else {
+        return;
+    };
+    recorder.start = Some(time.elapsed_secs());
+    if let Err(error) = writeln!(recorder.file, "MDR-INPUT {FORMAT_VERSION}\n{}", *file) {
+        error!("Stopped recording input: {error}");
+        commands.remove_resource::<MacroRecorder>();
+    }
+}
+
+fn record_input(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut wheel: EventReader<MouseWheel>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    (recorder, mut cursor): (Option<ResMut<MacroRecorder>>, Local<Option<Vec2>>),
+) {
+    let scroll: Vec2 = wheel.read().map(|event| Vec2::new(event.x, event.y)).sum();
+    let Some((mut recorder, start)) =
+        recorder.and_then(|recorder| recorder.start.map(|start| (recorder, start)))
+    else {
+        return;
+    };
+    let mut inputs = Vec::new();
+    let at = window
+        .cursor_position()
+        .map(|position| position / window.size());
+    if at != *cursor {
+        *cursor = at;
+        inputs.push(Input::Cursor(at));
+    }
+    inputs.extend(keys.get_just_pressed().map(|key| Input::Key(*key, true)));
+    inputs.extend(keys.get_just_released().map(|key| Input::Key(*key, false)));
+    inputs.extend(buttons.get_just_pressed().map(|b| Input::Button(*b, true)));
+    inputs.extend(
+        buttons
+            .get_just_released()
+            .map(|b| Input::Button(*b, false)),
+    );
+    if scroll != Vec2::ZERO {
+        inputs.push(Input::Wheel(scroll));
+    }
+    let now = time.elapsed_secs() - start;
+    let result = inputs
+        .iter()
+        // Keys the platform has no code for cannot be played back.
+        .filter(|input| !matches!(input, Input::Key(KeyCode::Unidentified(_), _)))
+        .try_for_each(|input| writeln!(recorder.file, "{now:.3} {input}"));
+    if let Err(error) = result {
+        error!("Stopped recording input: {error}");
+        commands.remove_resource::<MacroRecorder>();
+    }
+}
+
+/// A macro being played back.
+#[derive(Resource)]
+pub struct MacroPlayback {
+    /// Every input of the macro with the time it happened, in order.
+    inputs: Vec<(f32, Input)>,
+    /// Index of the next input to play.
+    next: usize,
+    /// Real seconds since startup at which playback started.
+    start: Option<f32>,
+}
+
+impl MacroPlayback {
+    /// Reads a macro, and the file it was recorded on.
+    fn load(path: &Path) -> io::Result<(ActiveFile, Self)> {
+        let invalid = |line: usize, what: &str| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {what}"))
+        };
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        match lines.next().map(|(_, header)| header.trim()) {
+            Some(header) if header == format!("MDR-INPUT {FORMAT_VERSION}") => {}
+            Some(header) if header.starts_with("MDR-INPUT ") => {
+                return Err(invalid(1, "unsupported input version"))
+            }
+            _ => return Err(invalid(1, "not an input file")),
+        }
+        let file = lines
+            .next()
+            .and_then(|(_, line)| line.parse().ok())
+            .ok_or_else(|| invalid(2, "bad file"))?;
+
+        let mut inputs = Vec::new();
+        for (line, text) in lines.filter(|(_, text)| !text.trim().is_empty()) {
+            let (time, input) = text
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| invalid(line, "expected a time and an input"))?;
+            let time: f32 = time.parse().map_err(|_| invalid(line, "bad time"))?;
+            let input = input.parse().map_err(|_| invalid(line, "bad input"))?;
+            inputs.push((time, input));
+        }
+        // Stable, so inputs of the same instant stay in the order they were recorded.
+        inputs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let playback = Self {
+            inputs,
+            next: 0,
+            start: None,
+        };
+        Ok((file, playback))
+    }
+}
+
+fn start_playing(time: Res<Time<Real>>, playback: Option<ResMut<MacroPlayback>>) {
+    if let Some(mut playback) = playback.filter(|playback| playback.start.is_none()) {
+        playback.start = Some(time.elapsed_secs());
+    }
+}
+
+/// Presses and releases whatever the macro did by now, after the real input of the frame.
+fn play_input(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut playback: ResMut<MacroPlayback>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut buttons: ResMut<ButtonInput<MouseButton>>,
+    mut wheel: EventWriter<MouseWheel>,
+    mut window: Single<(Entity, &mut Window), With<PrimaryWindow>>,
+) {
+    let Some(start) = playback.start else {
+        return;
+    };
+    let now = time.elapsed_secs() - start;
+    let (entity, window) = &mut *window;
+    while let Some(&(at, input)) = playback.inputs.get(playback.next) {
+        if at > now {
+            break;
+        }
+        playback.next += 1;
+        match input {
+            Input::Key(key, true) => keys.press(key),
+            Input::Key(key, false) => keys.release(key),
+            Input::Button(button, true) => buttons.press(button),
+            Input::Button(button, false) => buttons.release(button),
+            Input::Cursor(at) => {
+                let size = window.size();
+                window.set_cursor_position(at.map(|at| at * size));
+            }
+            Input::Wheel(delta) => {
+                wheel.write(MouseWheel {
+                    unit: MouseScrollUnit::Line,
+                    x: delta.x,
+                    y: delta.y,
+                    window: *entity,
+                });
+            }
+        }
+    }
+    if playback.next >= playback.inputs.len() {
+        info!("Finished playing input");
+        commands.remove_resource::<MacroPlayback>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inputs_round_trip() {
+        for input in [
+            Input::Key(KeyCode::KeyR, true),
+            Input::Key(KeyCode::ArrowLeft, false),
+            Input::Button(MouseButton::Left, true),
+            Input::Button(MouseButton::Other(7), false),
+            Input::Cursor(Some(Vec2::new(0.25, 0.5))),
+            Input::Cursor(None),
+            Input::Wheel(Vec2::new(0., -1.5)),
+        ] {
+            assert_eq!(input.to_string().parse(), Ok(input));
+        }
+    }
+
+    #[test]
+    fn refuses_what_is_not_an_input() {
+        for line in [
+            "",
+            "key KeyR",
+            "key Nope down",
+            "button left sideways",
+            "wheel 1",
+        ] {
+            assert_eq!(line.parse::<Input>(), Err(ParseInputError), "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! The main menu the app boots into, where the refiner picks a file to work on.
+
+use bevy::{app::AppExit, prelude::*, text::TextBounds};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    files::{FileLibrary, LibraryError, OpenFile},
+    kiosk::Kiosk,
+    state::AppState,
+    transition::{TransitionEffect, TransitionTo},
+    ui::{spawn_menu, Menu, MenuChosen, MenuEntry},
+};
+
+/// How many files the menu lists, newest last.
+const LISTED_FILES: usize = 6;
+
+/// Width the message of the error screen wraps at.
+const ERROR_WIDTH: f32 = 320.;
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Menu), spawn_main_menu)
+            .add_systems(
+                Update,
+                (escape_to_quit, choose, dismiss_error).run_if(in_state(AppState::Menu)),
+            );
+    }
+}
+
+/// The main menu, with the action behind each of its items.
+#[derive(Component)]
+struct MainMenu {
+    items: Vec<MainItem>,
+}
+
+/// Marks the screen that says why the file library could not be read, shown in place of the
+/// main menu until it is dismissed.
+#[derive(Component)]
+struct ErrorScreen;
+
+/// Items of the error screen.
+const ERROR_ITEMS: [&str; 2] = ["Continue Without Saving", "Quit"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MainItem {
+    Continue,
+    Open(usize),
+    NewFile,
+    Settings,
+    Achievements,
+    Board,
+    Quit,
+}
+
+impl MainItem {
+    fn label(self, library: &FileLibrary) -> String {
+        match self {
+            MainItem::Continue => match library.last_opened.and_then(|i| library.files.get(i)) {
+                Some(file) => format!("Continue: {}", file.name),
+                None => "Continue".to_string(),
+            },
+            // Name and how much of the file is refined
+            MainItem::Open(index) => {
+                let file = &library.files[index];
+                format!("{:<16}{:>4.0}%", file.name, file.completion() * 100.)
+            }
+            MainItem::NewFile => "New File".to_string(),
+            MainItem::Settings => "Settings".to_string(),
+            MainItem::Achievements => "Achievements".to_string(),
+            MainItem::Board => "Quarterly Board".to_string(),
+            MainItem::Quit => "Quit".to_string(),
+        }
+    }
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    library: Res<FileLibrary>,
+    error: Option<Res<LibraryError>>,
+    kiosk: Option<Res<Kiosk>>,
+) {
+    // Hide the grid behind the menu
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.0, 0.04, 0.05),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 19.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Menu),
+    ));
+
+    commands.spawn((
+        Text2d::new("LUMON INDUSTRIES"),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        CanvasAnchor::TOP.offset(0., -12.),
+        Transform::from_xyz(0., 0., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Menu),
+    ));
+
+    match error {
+        Some(error) => spawn_error_screen(&mut commands, &error.0),
+        None => spawn_menu_items(&mut commands, &library, kiosk.is_some()),
+    }
+}
+
+fn spawn_error_screen(commands: &mut Commands, message: &str) {
+    commands.spawn((
+        ErrorScreen,
+        Text2d::new(message),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextBounds::new_horizontal(ERROR_WIDTH),
+        TextColor(Color::srgb(1.0, 0.45, 0.45)),
+        Transform::from_xyz(0., 52., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Menu),
+    ));
+    let entries = ERROR_ITEMS.map(MenuEntry::new);
+    spawn_menu(
+        commands,
+        "FILES UNREADABLE",
+        &entries,
+        0,
+        Vec3::new(0., -30., 20.),
+        (ErrorScreen, StateScoped(AppState::Menu)),
+    );
+}
+
+/// Leaves the error screen for the main menu, or quits.
+fn dismiss_error(
+    mut commands: Commands,
+    mut chosen: EventReader<MenuChosen>,
+    screens: Query<Entity, With<ErrorScreen>>,
+    library: Res<FileLibrary>,
+    kiosk: Option<Res<Kiosk>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in chosen.read() {
+        if !screens.contains(event.menu) {
+            continue;
+        }
+        if event.item == 1 {
+            exit.write(AppExit::Success);
+            continue;
+        }
+        for entity in &screens {
+            commands.entity(entity).despawn();
+        }
+        commands.remove_resource::<LibraryError>();
+        spawn_menu_items(&mut commands, &library, kiosk.is_some());
+    }
+}
+
+fn spawn_menu_items(commands: &mut Commands, library: &FileLibrary, kiosk: bool) {
+    let can_continue = library
+        .last_opened
+        .is_some_and(|index| index < library.files.len());
+    let listed = library.files.len().saturating_sub(LISTED_FILES)..library.files.len();
+    let items: Vec<MainItem> = [MainItem::Continue]
+        .into_iter()
+        .chain(listed.map(MainItem::Open))
+        .chain([
+            MainItem::NewFile,
+            MainItem::Settings,
+            MainItem::Achievements,
+            MainItem::Board,
+            MainItem::Quit,
+        ])
+        // A kiosk is not to be quit from its menus.
+        .filter(|item| !kiosk || *item != MainItem::Quit)
+        .collect();
+    let labels: Vec<String> = items.iter().map(|item| item.label(library)).collect();
+    let entries: Vec<MenuEntry> = items
+        .iter()
+        .zip(&labels)
+        .map(|(item, label)| {
+            MenuEntry::new(label).enabled(*item != MainItem::Continue || can_continue)
+        })
+        .collect();
+
+    spawn_menu(
+        commands,
+        "MACRODATA REFINEMENT",
+        &entries,
+        0,
+        Vec3::new(0., -6., 20.),
+        (MainMenu { items }, StateScoped(AppState::Menu)),
+    );
+}
+
+/// Esc highlights Quit, so that leaving takes a second, deliberate press.
+fn escape_to_quit(keys: Res<ButtonInput<KeyCode>>, menu: Option<Single<(&MainMenu, &mut Menu)>>) {
+    let Some((main, mut menu)) = menu.map(Single::into_inner) else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        if let Some(quit) = main.items.iter().position(|item| *item == MainItem::Quit) {
+            menu.selected = quit;
+        }
+    }
+}
+
+fn choose(
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<&MainMenu>,
+    mut library: ResMut<FileLibrary>,
+    config: Res<Config>,
+    mut opens: EventWriter<OpenFile>,
+    mut transitions: EventWriter<TransitionTo>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in chosen.read() {
+        let Ok(menu) = menus.get(event.menu) else {
+            continue;
+        };
+        let open = match menu.items[event.item] {
+            MainItem::Continue => library.last_opened,
+            MainItem::Open(index) => Some(index),
+            MainItem::NewFile => Some(library.create(config.gameplay.bins)),
+            MainItem::Settings => {
+                transitions.write(TransitionTo::new(
+                    AppState::Settings,
+                    TransitionEffect::Wipe,
+                ));
+                None
+            }
+            MainItem::Achievements => {
+                transitions.write(TransitionTo::new(
+                    AppState::Achievements,
+                    TransitionEffect::Wipe,
+                ));
+                None
+            }
+            MainItem::Board => {
+                transitions.write(TransitionTo::new(AppState::Board, TransitionEffect::Wipe));
+                None
+            }
+            MainItem::Quit => {
+                exit.write(AppExit::Success);
+                None
+            }
+        };
+        if let Some(index) = open {
+            opens.write(OpenFile(index));
+            transitions.write(TransitionTo::new(
+                AppState::Refining,
+                TransitionEffect::Wipe,
+            ));
+        }
+    }
+}
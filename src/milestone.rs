@@ -0,0 +1,67 @@
+//! Praise for the refiner as a file passes 25, 50 and 75% complete.
+//!
+//! Each milestone of a file is praised once, with a card and a chime: the milestones reached
+//! are kept with the file in the library, so draining or reloading a file does not earn its
+//! praise again.
+
+use bevy::prelude::*;
+
+use crate::{
+    audio::{PlaySound, Sound},
+    files::{track_progress, ActiveFile, FileLibrary},
+    ui::spawn_praise,
+};
+
+/// Seconds a milestone's praise stays up.
+const PRAISE_TIME: f32 = 3.;
+
+pub struct MilestonePlugin;
+
+impl Plugin for MilestonePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MilestoneReached>().add_systems(
+            PostUpdate,
+            praise_milestones
+                .after(track_progress)
+                .run_if(resource_changed::<FileLibrary>),
+        );
+    }
+}
+
+/// The open file passed a milestone, and was praised for it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MilestoneReached;
+
+fn praise_milestones(
+    mut commands: Commands,
+    active: Res<ActiveFile>,
+    mut library: ResMut<FileLibrary>,
+    mut sounds: EventWriter<PlaySound>,
+    mut reached: EventWriter<MilestoneReached>,
+) {
+    let Some(index) = active.record else {
+        return;
+    };
+    // Checking the file every time the library changes is no change of its own.
+    let Some(file) = library.bypass_change_detection().files.get_mut(index) else {
+        return;
+    };
+    let quarters = file.quarters();
+    if file.praised.is_some_and(|praised| quarters <= praised) {
+        return;
+    }
+    // Files saved before praise was kept track of have earned what they are past already.
+    let earned = file.praised.is_some();
+    file.praised = Some(quarters);
+    if !earned {
+        return;
+    }
+    info!("{} is {}% complete", file.name, quarters * 25);
+    spawn_praise(
+        &mut commands,
+        format!("Praise Kier!\n{}% complete", quarters * 25),
+        PRAISE_TIME,
+    );
+    sounds.write(PlaySound::new(Sound::Chime).with_pitch(1. + f32::from(quarters - 1) * 0.25));
+    reached.write(MilestoneReached);
+}
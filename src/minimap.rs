@@ -0,0 +1,247 @@
+//! A minimap of the whole grid on the right edge of the canvas.
+//!
+//! Every cell is one pixel, lit once it has been refined, with the part of the grid in view
+//! outlined. Clicking or dragging on the minimap moves the view there. The image is painted
+//! incrementally from [`Refined`] events rather than redrawn, except as the grid is resized.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasCursor, CanvasSize, GridCamera, PIXEL_PERFECT_LAYERS},
+    grid::{
+        clamp_view, Cell, GridModel, GridSize, RefineSet, Refined, ResetRefinement, NUMBER_SPACING,
+    },
+    zoom::Zoom,
+};
+
+/// Colors of the minimap's pixels, as RGBA bytes.
+const UNREFINED: [u8; 4] = [0, 20, 26, 220];
+const REFINED: [u8; 4] = [0, 180, 200, 255];
+
+const VIEW_COLOR: Color = Color::WHITE;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_minimap).add_systems(
+            Update,
+            (
+                jump.in_set(RefineSet::Input),
+                (
+                    fit_minimap
+                        .run_if(resource_changed::<GridSize>.and(not(resource_added::<GridSize>))),
+                    paint_refined,
+                )
+                    .chain()
+                    .in_set(RefineSet::React),
+                outline_view.after(RefineSet::Input),
+            ),
+        );
+    }
+}
+
+/// Where the minimap of a grid of the given size sits, below the top right corner of the canvas,
+/// and below the header on a portrait one.
+fn minimap_anchor(size: GridSize) -> CanvasAnchor {
+    let half = Vec2::new(size.columns as f32, size.rows as f32) / 2.;
+    CanvasAnchor::TOP_RIGHT
+        .offset(-4. - half.x, -20. - half.y)
+        .in_portrait(CanvasAnchor::TOP_RIGHT.offset(-4. - half.x, -34. - half.y))
+}
+
+fn minimap_rect(canvas: CanvasSize, size: GridSize) -> Rect {
+    Rect::from_center_size(
+        minimap_anchor(size).position(canvas),
+        Vec2::new(size.columns as f32, size.rows as f32),
+    )
+}
+
+/// Whether a canvas position is on the minimap, where clicks belong to it rather than the grid.
+pub fn covers(position: Vec2, canvas: CanvasSize, size: GridSize) -> bool {
+    minimap_rect(canvas, size).contains(position)
+}
+
+/// Grid world position shown at a point of the minimap.
+fn grid_position(position: Vec2, canvas: CanvasSize, size: GridSize) -> Vec2 {
+    let origin = Cell { col: 0, row: 0 }.position();
+    origin + (position - minimap_rect(canvas, size).min - 0.5) * NUMBER_SPACING
+}
+
+#[derive(Component)]
+struct Minimap;
+
+/// Outline of the part of the grid in view.
+#[derive(Component)]
+struct ViewOutline;
+
+/// One side of the [`ViewOutline`], the side of it given as for a [`CanvasAnchor`].
+#[derive(Component)]
+struct ViewEdge(Vec2);
+
+/// A minimap of a grid of the given size with nothing refined.
+fn minimap_image(size: GridSize) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: size.columns,
+            height: size.rows,
+            ..default()
+        },
+        TextureDimension::D2,
+        &UNREFINED,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+fn setup_minimap(mut commands: Commands, size: Res<GridSize>, mut images: ResMut<Assets<Image>>) {
+    commands.spawn((
+        Minimap,
+        Sprite::from_image(images.add(minimap_image(*size))),
+        minimap_anchor(*size),
+        Transform::from_xyz(0., 0., 12.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+
+    // The outline is four thin sprites around its centre, sized to the view.
+    commands
+        .spawn((
+            ViewOutline,
+            Transform::from_xyz(0., 0., 12.1),
+            Visibility::default(),
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_children(|outline| {
+            for side in [Vec2::Y, Vec2::NEG_Y, Vec2::NEG_X, Vec2::X] {
+                outline.spawn((
+                    ViewEdge(side),
+                    Sprite {
+                        color: VIEW_COLOR.with_alpha(0.8),
+                        ..default()
+                    },
+                    Transform::default(),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            }
+        });
+}
+
+/// Fits the minimap to the grid as it is resized, lighting the cells it keeps that were refined.
+fn fit_minimap(
+    size: Res<GridSize>,
+    model: Res<GridModel>,
+    mut images: ResMut<Assets<Image>>,
+    mut minimap: Single<(&mut Sprite, &mut CanvasAnchor), With<Minimap>>,
+) {
+    let (sprite, anchor) = &mut *minimap;
+    let mut image = minimap_image(*size);
+    if let Some(data) = image.data.as_mut() {
+        for cell in size.cells() {
+            if model.get(cell).is_some_and(|state| state.refined) {
+                let index = size.image_index(cell) * 4;
+                data[index..index + 4].copy_from_slice(&REFINED);
+            }
+        }
+    }
+    sprite.image = images.add(image);
+    **anchor = minimap_anchor(*size);
+}
+
+/// Lights the pixels of refined cells, and clears them all when the refinement is reset.
+fn paint_refined(
+    mut refined: EventReader<Refined>,
+    mut resets: EventReader<ResetRefinement>,
+    size: Res<GridSize>,
+    minimap: Single<&Sprite, With<Minimap>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if refined.is_empty() && resets.is_empty() {
+        return;
+    }
+    let Some(image) = images.get_mut(&minimap.image) else {
+        return;
+    };
+    let Some(data) = image.data.as_mut() else {
+        return;
+    };
+    if !resets.is_empty() {
+        resets.clear();
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&UNREFINED);
+        }
+    }
+    for event in refined.read() {
+        for row in event.cells.min.y..=event.cells.max.y {
+            for col in event.cells.min.x..=event.cells.max.x {
+                let cell = Cell { col, row };
+                if !size.contains(cell) {
+                    continue;
+                }
+                // Images start at the top, the grid at the bottom.
+                let index = size.image_index(cell) * 4;
+                if let Some(pixel) = data.get_mut(index..index + 4) {
+                    pixel.copy_from_slice(&REFINED);
+                }
+            }
+        }
+    }
+}
+
+/// Moves the outline to the part of the grid the grid camera shows, and sizes it to the view.
+fn outline_view(
+    (canvas, size): (Res<CanvasSize>, Res<GridSize>),
+    zoom: Res<Zoom>,
+    camera: Single<Ref<Transform>, With<GridCamera>>,
+    mut outline: Single<&mut Transform, (With<ViewOutline>, Without<GridCamera>)>,
+    mut edges: Query<(&ViewEdge, &mut Sprite, &mut Transform), Without<ViewOutline>>,
+) {
+    if !camera.is_changed() && !canvas.is_changed() && !zoom.is_changed() && !size.is_changed() {
+        return;
+    }
+    let origin = Cell { col: 0, row: 0 }.position();
+    let center = minimap_rect(*canvas, *size).min
+        + 0.5
+        + (camera.translation.truncate() - origin) / NUMBER_SPACING;
+    outline.translation = center.extend(outline.translation.z);
+    if !canvas.is_changed() && !zoom.is_changed() {
+        return;
+    }
+    let size = zoom.view(*canvas) / NUMBER_SPACING;
+    for (ViewEdge(side), mut sprite, mut transform) in &mut edges {
+        transform.translation = (*side * size / 2.).extend(0.);
+        sprite.custom_size = Some(if side.x == 0. {
+            Vec2::new(size.x, 1.)
+        } else {
+            Vec2::new(1., size.y)
+        });
+    }
+}
+
+/// Clicking or dragging on the minimap moves the view there.
+fn jump(
+    buttons: Res<ButtonInput<MouseButton>>,
+    pointer: CanvasCursor,
+    zoom: Res<Zoom>,
+    mut dragging: Local<bool>,
+    mut camera: Single<&mut Transform, With<GridCamera>>,
+) {
+    let cursor = pointer.world();
+    if buttons.just_pressed(MouseButton::Left) {
+        *dragging =
+            cursor.is_some_and(|cursor| covers(cursor, pointer.canvas(), pointer.grid_size()));
+    }
+    if !buttons.pressed(MouseButton::Left) {
+        *dragging = false;
+    }
+    if let (true, Some(cursor)) = (*dragging, cursor) {
+        let (canvas, size) = (pointer.canvas(), pointer.grid_size());
+        let rect = minimap_rect(canvas, size);
+        let target = grid_position(cursor.clamp(rect.min, rect.max), canvas, size);
+        let view = zoom.view(canvas);
+        camera.translation = clamp_view(target, view, size).extend(camera.translation.z);
+    }
+}
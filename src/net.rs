@@ -0,0 +1,787 @@
+//! Cooperative refinement: several refiners sharing one grid over TCP.
+//!
+//! The host is the single source of ordering. Clients send their [`GridAction`]s to the host,
+//! which applies every action (its own included) in the order it receives them and relays each
+//! one to all clients, so every peer applies the same actions in the same order. The grid is
+//! deterministic, which is what makes replaying actions enough to keep peers in sync, as long as
+//! every peer refines the same file by the same [rules](RefineRules): the host's. Clients are sent
+//! both as they join and whenever they change, and clients joining late are sent the history of
+//! actions since the host opened its file, starting with the size its grid was then, so that
+//! whatever size their settings give it they take on the host's.
+//!
+//! Spectators connect like any other client but never send actions or a cursor, and the host
+//! ignores any that arrive anyway; they simply mirror the host's grid.
+//!
+//! The protocol is plain text, one message per line:
+//!
+//! - `WELCOME <peer>`: host to client, assigns the client its peer id
+//! - `FILE ...`: host to client, the file the host refines, as [`ActiveFile`] writes it
+//! - `RULES <difficulty> <max_selection> [<detector>]`: host to client, the rules it refines by
+//! - `SPECTATE`: client to host, the client only watches
+//! - `SELECT <col> <row> <col> <row>`, `CLEAR`, `REFINE <bin>`, `SIZE <columns> <rows>`: a
+//!   [`GridAction`]
+//! - `CURSOR <peer> [<x> <y>]`: a refiner's cursor moved, or left the canvas
+//! - `LEAVE <peer>`: host to client, a refiner disconnected
+//! - `DAILY <day>`: host to client, the day of the [daily grid](crate::daily) the host refines
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::{CanvasCursor, GRID_LAYERS},
+    config::Difficulty,
+    daily::DailyChallenge,
+    files::ActiveFile,
+    grid::{
+        ApplyAction, GridAction, GridSize, HostRules, RefineRules, RefineSet, RequestAction,
+        ResetRefinement,
+    },
+    toast::Toast,
+};
+
+pub mod http;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+
+/// Port used when an address is given without one.
+const DEFAULT_PORT: u16 = 7777;
+
+/// Peer id of the host.
+const HOST_PEER: u32 = 0;
+
+/// Colors cycled through for the cursors of other refiners.
+const CURSOR_COLORS: [Color; 4] = [
+    Color::srgb(1.0, 0.8, 0.2),
+    Color::srgb(1.0, 0.4, 0.6),
+    Color::srgb(0.5, 1.0, 0.4),
+    Color::srgb(0.7, 0.6, 1.0),
+];
+
+/// How this instance takes part in a shared session.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum NetRole {
+    /// Refine alone.
+    #[default]
+    Offline,
+    /// Listen on the given address and own the shared grid.
+    Host(String),
+    /// Connect to a host at the given address.
+    Join(String),
+    /// Connect to a host at the given address and mirror its grid without taking part.
+    Spectate(String),
+}
+
+impl NetRole {
+    /// Parses `--host [ADDR]`, `--join ADDR` and `--spectate ADDR` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter().peekable();
+        let mut role = NetRole::Offline;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--host" => {
+                    let addr = args
+                        .next_if(|next| !next.starts_with("--"))
+                        .unwrap_or_else(|| format!("0.0.0.0:{DEFAULT_PORT}"));
+                    role = NetRole::Host(with_default_port(addr));
+                }
+                "--join" => match args.next_if(|next| !next.starts_with("--")) {
+                    Some(addr) => role = NetRole::Join(with_default_port(addr)),
+                    None => eprintln!("--join needs the address of a host"),
+                },
+                "--spectate" => match args.next_if(|next| !next.starts_with("--")) {
+                    Some(addr) => role = NetRole::Spectate(with_default_port(addr)),
+                    None => eprintln!("--spectate needs the address of a host"),
+                },
+                _ => {}
+            }
+        }
+        role
+    }
+}
+
+fn with_default_port(addr: String) -> String {
+    if addr.contains(':') {
+        addr
+    } else {
+        format!("{addr}:{DEFAULT_PORT}")
+    }
+}
+
+pub struct NetPlugin {
+    pub role: NetRole,
+}
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        if matches!(self.role, NetRole::Spectate(_)) {
+            app.insert_resource(Spectating).configure_sets(
+                Update,
+                RefineSet::Input.run_if(not(resource_exists::<Spectating>)),
+            );
+        }
+        let session = Session::start(&self.role);
+        if !matches!(session, Session::Offline) {
+            app.insert_resource(SharedSession);
+        }
+        app.insert_resource(session)
+            .init_resource::<RemoteCursors>()
+            .add_systems(
+                Update,
+                (
+                    (receive, route_requests).chain().in_set(RefineSet::Route),
+                    share_cursor.after(RefineSet::Input),
+                    sync_remote_cursors.in_set(RefineSet::React),
+                ),
+            )
+            .add_systems(Last, flush);
+        #[cfg(feature = "mqtt")]
+        app.add_plugins(mqtt::MqttPlugin);
+    }
+}
+
+/// A message of the co-op protocol.
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    Welcome { peer: u32 },
+    File(ActiveFile),
+    Rules(RefineRules),
+    Spectate,
+    Action(GridAction),
+    Cursor { peer: u32, position: Option<Vec2> },
+    Leave { peer: u32 },
+    Daily { day: i64 },
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Welcome { peer } => write!(f, "WELCOME {peer}"),
+            Message::File(file) => write!(f, "{file}"),
+            Message::Rules(rules) => {
+                let difficulty = match rules.difficulty {
+                    Difficulty::Easy => "easy",
+                    Difficulty::Normal => "normal",
+                    Difficulty::Hard => "hard",
+                };
+                write!(f, "RULES {difficulty} {}", rules.max_selection)?;
+                match &rules.detector {
+                    Some(detector) => write!(f, " {detector}"),
+                    None => Ok(()),
+                }
+            }
+            Message::Spectate => write!(f, "SPECTATE"),
+            Message::Action(action) => write!(f, "{action}"),
+            Message::Cursor {
+                peer,
+                position: Some(position),
+            } => write!(f, "CURSOR {peer} {:.1} {:.1}", position.x, position.y),
+            Message::Cursor {
+                peer,
+                position: None,
+            } => write!(f, "CURSOR {peer}"),
+            Message::Leave { peer } => write!(f, "LEAVE {peer}"),
+            Message::Daily { day } => write!(f, "DAILY {day}"),
+        }
+    }
+}
+
+impl Message {
+    fn parse(line: &str) -> Option<Self> {
+        if let Ok(action) = line.parse() {
+            return Some(Message::Action(action));
+        }
+        if let Ok(file) = line.parse() {
+            return Some(Message::File(file));
+        }
+        let mut words = line.split_whitespace();
+        let kind = words.next()?;
+        if kind == "RULES" {
+            let difficulty = match words.next()? {
+                "easy" => Difficulty::Easy,
+                "normal" => Difficulty::Normal,
+                "hard" => Difficulty::Hard,
+                _ => return None,
+            };
+            let max_selection = words.next()?.parse().ok()?;
+            let detector = words.next().map(str::to_string);
+            if words.next().is_some() {
+                return None;
+            }
+            return Some(Message::Rules(RefineRules {
+                difficulty,
+                detector,
+                max_selection,
+            }));
+        }
+        let numbers: Vec<f32> = words
+            .map(|word| word.parse().ok().filter(|number: &f32| number.is_finite()))
+            .collect::<Option<_>>()?;
+        let message = match (kind, numbers.as_slice()) {
+            ("WELCOME", &[peer]) => Message::Welcome { peer: peer as u32 },
+            ("SPECTATE", &[]) => Message::Spectate,
+            ("CURSOR", &[peer, x, y]) => Message::Cursor {
+                peer: peer as u32,
+                position: Some(Vec2::new(x, y)),
+            },
+            ("CURSOR", &[peer]) => Message::Cursor {
+                peer: peer as u32,
+                position: None,
+            },
+            ("LEAVE", &[peer]) => Message::Leave { peer: peer as u32 },
+            ("DAILY", &[day]) => Message::Daily { day: day as i64 },
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+/// A non-blocking, line-buffered connection to another peer.
+struct Connection {
+    stream: TcpStream,
+    inbox: Vec<u8>,
+    outbox: Vec<u8>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            inbox: Vec::new(),
+            outbox: Vec::new(),
+        })
+    }
+
+    fn send(&mut self, message: &Message) {
+        self.outbox.extend(format!("{message}\n").into_bytes());
+    }
+
+    /// Moves whatever has arrived into the inbox, or fails once the peer is gone.
+    fn fill_inbox(&mut self) -> io::Result<()> {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => self.inbox.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns every complete message that has arrived, or an error once the peer is gone.
+    fn receive(&mut self) -> io::Result<Vec<Message>> {
+        self.fill_inbox()?;
+        let mut messages = Vec::new();
+        while let Some(end) = self.inbox.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.inbox.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            match Message::parse(&line) {
+                Some(message) => messages.push(message),
+                None => warn!("Ignoring malformed co-op message {:?}", line.trim_end()),
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Writes as much of the pending output as the socket accepts.
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outbox.is_empty() {
+            match self.stream.write(&self.outbox) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outbox.drain(..written);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Client {
+    peer: u32,
+    connection: Connection,
+    /// Set once the client announces it only watches.
+    spectator: bool,
+}
+
+/// The state of this instance's part in a shared session.
+#[derive(Resource)]
+enum Session {
+    Offline,
+    Host {
+        listener: TcpListener,
+        clients: Vec<Client>,
+        next_peer: u32,
+        /// Every action applied since the open file was opened, replayed to clients that join
+        /// late, starting with a [`GridAction::Resize`] to the size the grid was then.
+        history: Vec<GridAction>,
+    },
+    Client {
+        host: Connection,
+        /// Assigned by the host on connection.
+        peer: Option<u32>,
+        /// Messages that arrived after the host opened a file, held back a frame for the grid to
+        /// be reset for the file first.
+        held: Vec<Message>,
+    },
+}
+
+impl Session {
+    fn start(role: &NetRole) -> Self {
+        match role {
+            NetRole::Offline => Session::Offline,
+            NetRole::Host(addr) => match TcpListener::bind(addr)
+                .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            {
+                Ok(listener) => {
+                    info!("Hosting co-op refinement on {addr}");
+                    Session::Host {
+                        listener,
+                        clients: Vec::new(),
+                        next_peer: HOST_PEER + 1,
+                        // Started as the file is seen opened.
+                        history: Vec::new(),
+                    }
+                }
+                Err(error) => {
+                    error!("Could not host on {addr}, refining alone: {error}");
+                    Session::Offline
+                }
+            },
+            NetRole::Join(addr) => match TcpStream::connect(addr).and_then(Connection::new) {
+                Ok(host) => {
+                    info!("Joined co-op refinement at {addr}");
+                    Session::Client {
+                        host,
+                        peer: None,
+                        held: Vec::new(),
+                    }
+                }
+                Err(error) => {
+                    error!("Could not join {addr}, refining alone: {error}");
+                    Session::Offline
+                }
+            },
+            NetRole::Spectate(addr) => match TcpStream::connect(addr).and_then(Connection::new) {
+                Ok(mut host) => {
+                    info!("Spectating refinement at {addr}");
+                    host.send(&Message::Spectate);
+                    Session::Client {
+                        host,
+                        peer: None,
+                        held: Vec::new(),
+                    }
+                }
+                Err(error) => {
+                    error!("Could not spectate {addr}: {error}");
+                    Session::Offline
+                }
+            },
+        }
+    }
+
+    /// The peer id of this instance, once known.
+    fn local_peer(&self) -> Option<u32> {
+        match self {
+            Session::Offline => None,
+            Session::Host { .. } => Some(HOST_PEER),
+            Session::Client { peer, .. } => *peer,
+        }
+    }
+}
+
+/// Present while this instance shares its grid with other peers.
+#[derive(Resource)]
+pub struct SharedSession;
+
+/// Present when this instance only mirrors a host's grid.
+#[derive(Resource)]
+struct Spectating;
+
+/// Where the cursors of the other refiners are, by peer id.
+#[derive(Resource, Default)]
+struct RemoteCursors(HashMap<u32, Vec2>);
+
+/// Marks the crosshair drawn for another refiner's cursor.
+#[derive(Component)]
+struct RemoteCursor(u32);
+
+/// Handles everything that arrived from other peers.
+fn receive(
+    mut commands: Commands,
+    mut session: ResMut<Session>,
+    mut cursors: ResMut<RemoteCursors>,
+    (mut applied, mut resets): (EventWriter<ApplyAction>, EventWriter<ResetRefinement>),
+    (mut file, size, rules): (ResMut<ActiveFile>, Res<GridSize>, Res<RefineRules>),
+    daily: Option<Res<DailyChallenge>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    match &mut *session {
+        Session::Offline => {}
+        Session::Host {
+            listener,
+            clients,
+            next_peer,
+            history,
+        } => {
+            // A file starts the grid over, so what was done before it no longer needs replaying.
+            if file.is_changed() {
+                history.clear();
+                history.push(GridAction::Resize(*size));
+                for client in clients.iter_mut() {
+                    client.connection.send(&Message::File(file.clone()));
+                    client.connection.send(&Message::Action(history[0]));
+                }
+            }
+            if rules.is_changed() {
+                for client in clients.iter_mut() {
+                    client.connection.send(&Message::Rules(rules.clone()));
+                }
+            }
+
+            loop {
+                match listener.accept() {
+                    Ok((stream, addr)) => {
+                        let mut connection = match Connection::new(stream) {
+                            Ok(connection) => connection,
+                            Err(error) => {
+                                warn!("Could not accept refiner from {addr}: {error}");
+                                continue;
+                            }
+                        };
+                        let peer = *next_peer;
+                        *next_peer += 1;
+                        info!("Refiner {peer} joined from {addr}");
+                        connection.send(&Message::Welcome { peer });
+                        if let Some(daily) = &daily {
+                            connection.send(&Message::Daily { day: daily.day });
+                        }
+                        connection.send(&Message::File(file.clone()));
+                        connection.send(&Message::Rules(rules.clone()));
+                        for action in history.iter() {
+                            connection.send(&Message::Action(*action));
+                        }
+                        for (&peer, &position) in &cursors.0 {
+                            connection.send(&Message::Cursor {
+                                peer,
+                                position: Some(position),
+                            });
+                        }
+                        clients.push(Client {
+                            peer,
+                            connection,
+                            spectator: false,
+                        });
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(error) => {
+                        warn!("Could not accept refiner: {error}");
+                        break;
+                    }
+                }
+            }
+
+            let mut relay = Vec::new();
+            let mut left = Vec::new();
+            for client in clients.iter_mut() {
+                match client.connection.receive() {
+                    Ok(messages) => {
+                        for message in messages {
+                            match message {
+                                Message::Spectate => {
+                                    info!("Refiner {} is spectating", client.peer);
+                                    client.spectator = true;
+                                }
+                                // Spectators have no say in the grid.
+                                Message::Action(_) | Message::Cursor { .. } if client.spectator => {
+                                }
+                                Message::Action(action) => {
+                                    applied.write(ApplyAction(action));
+                                    history.push(action);
+                                    relay.push((None, message));
+                                }
+                                Message::Cursor { position, .. } => {
+                                    // Clients can only move their own cursor.
+                                    update_cursor(&mut cursors, client.peer, position);
+                                    let message = Message::Cursor {
+                                        peer: client.peer,
+                                        position,
+                                    };
+                                    relay.push((Some(client.peer), message));
+                                }
+                                // The file and the rules are the host's to decide.
+                                Message::Welcome { .. }
+                                | Message::File(_)
+                                | Message::Rules(_)
+                                | Message::Leave { .. }
+                                | Message::Daily { .. } => {}
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        info!("Refiner {} left: {error}", client.peer);
+                        left.push(client.peer);
+                    }
+                }
+            }
+
+            clients.retain(|client| !left.contains(&client.peer));
+            for peer in left {
+                cursors.0.remove(&peer);
+                relay.push((None, Message::Leave { peer }));
+            }
+            for (origin, message) in relay {
+                for client in clients.iter_mut() {
+                    if origin != Some(client.peer) {
+                        client.connection.send(&message);
+                    }
+                }
+            }
+        }
+        Session::Client { host, peer, held } => match host.receive() {
+            Ok(messages) => {
+                let mut messages = std::mem::take(held).into_iter().chain(messages);
+                while let Some(message) = messages.next() {
+                    match message {
+                        Message::Welcome { peer: assigned } => *peer = Some(assigned),
+                        Message::File(opened) => {
+                            *file = opened;
+                            resets.write(ResetRefinement);
+                            held.extend(messages);
+                            break;
+                        }
+                        Message::Rules(host_rules) => {
+                            commands.insert_resource(host_rules);
+                            commands.insert_resource(HostRules);
+                        }
+                        Message::Spectate => {}
+                        Message::Action(action) => {
+                            applied.write(ApplyAction(action));
+                        }
+                        Message::Cursor {
+                            peer: remote,
+                            position,
+                        } => update_cursor(&mut cursors, remote, position),
+                        Message::Leave { peer: remote } => {
+                            cursors.0.remove(&remote);
+                        }
+                        // The actions that follow only make sense on the host's grid.
+                        Message::Daily { day }
+                            if daily.as_ref().map(|daily| daily.day) != Some(day) =>
+                        {
+                            warn!("The host refines the daily grid of another day, {day}");
+                            toasts.write(Toast::warning("Not the host's daily grid"));
+                        }
+                        Message::Daily { .. } => {}
+                    }
+                }
+            }
+            Err(error) => {
+                warn!("Lost connection to the host: {error}");
+                cursors.0.clear();
+                *session = Session::Offline;
+                commands.remove_resource::<SharedSession>();
+                commands.remove_resource::<HostRules>();
+            }
+        },
+    }
+}
+
+fn update_cursor(cursors: &mut RemoteCursors, peer: u32, position: Option<Vec2>) {
+    match position {
+        Some(position) => cursors.0.insert(peer, position),
+        None => cursors.0.remove(&peer),
+    };
+}
+
+/// Decides when local actions take effect.
+///
+/// Alone, or as the host, actions apply immediately. A client only forwards them to the host
+/// and applies them once they are relayed back, so that the host's ordering wins.
+fn route_requests(
+    mut session: ResMut<Session>,
+    mut requests: EventReader<RequestAction>,
+    mut applied: EventWriter<ApplyAction>,
+) {
+    for RequestAction(action) in requests.read() {
+        match &mut *session {
+            Session::Offline => {
+                applied.write(ApplyAction(*action));
+            }
+            Session::Host {
+                clients, history, ..
+            } => {
+                applied.write(ApplyAction(*action));
+                history.push(*action);
+                for client in clients.iter_mut() {
+                    client.connection.send(&Message::Action(*action));
+                }
+            }
+            Session::Client { host, .. } => host.send(&Message::Action(*action)),
+        }
+    }
+}
+
+/// Tells the other refiners where the local cursor is.
+fn share_cursor(
+    mut last_sent: Local<Option<Vec2>>,
+    mut session: ResMut<Session>,
+    spectating: Option<Res<Spectating>>,
+    pointer: CanvasCursor,
+) {
+    let Some(peer) = session.local_peer().filter(|_| spectating.is_none()) else {
+        return;
+    };
+    let position = pointer.grid().map(Vec2::round);
+    if position == *last_sent {
+        return;
+    }
+    *last_sent = position;
+
+    let message = Message::Cursor { peer, position };
+    match &mut *session {
+        Session::Offline => {}
+        Session::Host { clients, .. } => {
+            for client in clients.iter_mut() {
+                client.connection.send(&message);
+            }
+        }
+        Session::Client { host, .. } => host.send(&message),
+    }
+}
+
+fn flush(mut session: ResMut<Session>) {
+    match &mut *session {
+        Session::Offline => {}
+        Session::Host { clients, .. } => {
+            // Clients that fail here are dropped on their next read.
+            for client in clients.iter_mut() {
+                if let Err(error) = client.connection.flush() {
+                    warn!("Could not send to refiner {}: {error}", client.peer);
+                }
+            }
+        }
+        Session::Client { host, .. } => {
+            if let Err(error) = host.flush() {
+                warn!("Could not send to the host: {error}");
+            }
+        }
+    }
+}
+
+/// Keeps a crosshair on the canvas for every other refiner.
+fn sync_remote_cursors(
+    mut commands: Commands,
+    cursors: Res<RemoteCursors>,
+    mut crosshairs: Query<(Entity, &RemoteCursor, &mut Transform)>,
+) {
+    if !cursors.is_changed() {
+        return;
+    }
+    for (entity, cursor, mut transform) in &mut crosshairs {
+        match cursors.0.get(&cursor.0) {
+            Some(position) => {
+                let translation = position.extend(transform.translation.z);
+                if transform.translation != translation {
+                    transform.translation = translation;
+                }
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    for (&peer, position) in &cursors.0 {
+        if crosshairs.iter().any(|(_, cursor, _)| cursor.0 == peer) {
+            continue;
+        }
+        let color = CURSOR_COLORS[peer as usize % CURSOR_COLORS.len()];
+        commands
+            .spawn((
+                RemoteCursor(peer),
+                Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(7., 1.)),
+                    ..default()
+                },
+                Transform::from_translation(position.extend(5.)),
+                GRID_LAYERS,
+            ))
+            .with_children(|crosshair| {
+                crosshair.spawn((
+                    Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(1., 7.)),
+                        ..default()
+                    },
+                    GRID_LAYERS,
+                ));
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        for message in [
+            Message::Welcome { peer: 3 },
+            Message::File(ActiveFile::default()),
+            Message::Rules(RefineRules {
+                difficulty: Difficulty::Hard,
+                detector: Some("exact".to_string()),
+                max_selection: 40,
+            }),
+            Message::Rules(RefineRules {
+                difficulty: Difficulty::Easy,
+                detector: None,
+                max_selection: 0,
+            }),
+            Message::Spectate,
+            Message::Action(GridAction::Refine { bin: 1 }),
+            Message::Cursor {
+                peer: 2,
+                position: Some(Vec2::new(-12.5, 40.)),
+            },
+            Message::Cursor {
+                peer: 2,
+                position: None,
+            },
+            Message::Leave { peer: 4 },
+            Message::Daily { day: 20_740 },
+        ] {
+            assert_eq!(Message::parse(&message.to_string()), Some(message));
+        }
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_message() {
+        for line in [
+            "",
+            "WELCOME",
+            "CURSOR 1 NaN 4",
+            "CURSOR 1 2 inf",
+            "CURSOR 1 2",
+            "RULES",
+            "RULES cruel 0",
+            "RULES hard many",
+            "RULES hard 0 exact extra",
+            "FILE 7",
+            "SHOUT 1",
+        ] {
+            assert_eq!(Message::parse(line), None, "{line:?}");
+        }
+    }
+}
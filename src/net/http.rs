@@ -0,0 +1,154 @@
+//! Feeds: documents fetched over plain HTTP again and again in the background, each kept once
+//! fetched, for what shows outside data, such as the `http` bin source and the footer.
+//!
+//! Only plain `http://` URLs are fetched, with a bare HTTP/1.0 GET; `file://` URLs are read
+//! from disk instead, for documents another program keeps up to date. Each [`Feed`] fetches on
+//! a thread of its own and parses what it fetched there, keeping the last document that parsed
+//! through any failures after it, so what it shows only goes stale rather than missing. The
+//! thread stops once the feed is dropped.
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+
+/// Longest a fetch waits on the server for, to connect and then for each read.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parses a fetched document.
+pub type Parser<T> = fn(&str) -> Result<T, String>;
+
+/// A document fetched every so often and parsed.
+pub struct Feed<T> {
+    url: String,
+    shared: Arc<Mutex<Fetched<T>>>,
+}
+
+/// What a feed's thread has fetched.
+struct Fetched<T> {
+    /// The last document that parsed, kept when fetching it again fails.
+    value: Option<T>,
+    /// Whether the value is new since it was last taken.
+    updated: bool,
+    /// Set when fetching starts failing, until taken.
+    error: Option<String>,
+}
+
+impl<T: Clone + Send + 'static> Feed<T> {
+    /// Fetches `url` every `interval`, parsing it with `parse`.
+    pub fn spawn(url: &str, interval: Duration, parse: Parser<T>) -> Self {
+        let shared = Arc::new(Mutex::new(Fetched {
+            value: None,
+            updated: false,
+            error: None,
+        }));
+        let fetched = shared.clone();
+        let address = url.to_string();
+        thread::spawn(move || {
+            let mut failing = false;
+            // The feed's own handle is the other; once it is dropped, nobody is left to tell.
+            while Arc::strong_count(&fetched) > 1 {
+                match get(&address).and_then(|body| {
+                    parse(&body).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+                }) {
+                    Ok(value) => {
+                        failing = false;
+                        let mut fetched = fetched.lock().unwrap();
+                        fetched.value = Some(value);
+                        fetched.updated = true;
+                    }
+                    Err(error) => {
+                        warn!("Could not fetch {address}: {error}");
+                        // Only the first of a run of failures is worth telling the player.
+                        if !failing {
+                            fetched.lock().unwrap().error = Some(error.to_string());
+                        }
+                        failing = true;
+                    }
+                }
+                thread::sleep(interval);
+            }
+        });
+        Self {
+            url: url.to_string(),
+            shared,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The last document fetched, if there is one newer than was last taken.
+    pub fn take_update(&self) -> Option<T> {
+        let mut fetched = self.shared.lock().unwrap();
+        if !fetched.updated {
+            return None;
+        }
+        fetched.updated = false;
+        fetched.value.clone()
+    }
+
+    /// A problem the feed ran into since it was last asked.
+    pub fn take_error(&self) -> Option<String> {
+        self.shared.lock().unwrap().error.take()
+    }
+}
+
+/// The body at `url`: a plain `http://` URL, fetched, or a `file://` one, read.
+pub fn get(url: &str) -> io::Result<String> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return fs::read_to_string(path);
+    }
+    let (host, path) = url
+        .strip_prefix("http://")
+        .map(|rest| rest.split_once('/').unwrap_or((rest, "")))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only plain http:// URLs are supported",
+            )
+        })?;
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    };
+    let mut stream = connect(&address)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "GET /{path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: mdr\r\n\r\n"
+    )?;
+    // Bodies are read even where they are not quite UTF-8, as plenty of feeds are not.
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an HTTP response"))?;
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        return Err(io::Error::other(format!("the server answered {status}")));
+    }
+    Ok(body.to_string())
+}
+
+/// A connection to the first of the addresses `address` resolves to that answers in time.
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the host has no addresses")))
+}
@@ -0,0 +1,464 @@
+//! Bins and header messages driven by the topics of an MQTT broker, for home automation
+//! dashboards.
+//!
+//! Only built with the `mqtt` feature, and only connects when the config names a broker. This is
+//! a minimal MQTT 3.1.1 client over the same non-blocking [`Connection`] as co-op refinement: it
+//! subscribes to the configured topics at QoS 0, keeps the connection alive with pings, and
+//! connects again with a growing delay whenever the broker goes away. The last message of every
+//! topic is kept through reconnections, so the bins hold their values while the broker is gone.
+
+use std::{collections::HashMap, io, net::TcpStream};
+
+use bevy::prelude::*;
+
+use super::Connection;
+use crate::{
+    bins::{Bin, DrivenBins},
+    config::{Config, MqttBinding, MqttTarget},
+    grid::RefineSet,
+    header::HeaderMessage,
+    toast::Toast,
+};
+
+/// Seconds the broker is told to expect a packet within.
+const KEEP_ALIVE: u16 = 60;
+
+/// Seconds between pings, well within [`KEEP_ALIVE`].
+const PING_INTERVAL: f32 = 30.;
+
+/// Seconds before connecting again after the first failure, and at most.
+const FIRST_RETRY: f32 = 1.;
+const MAX_RETRY: f32 = 30.;
+
+/// Longest header message shown.
+const MESSAGE_LENGTH: usize = 24;
+
+// Fixed header bytes of the packets the client sends or understands.
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xc0;
+
+pub struct MqttPlugin;
+
+impl Plugin for MqttPlugin {
+    fn build(&self, app: &mut App) {
+        let config = &app.world().resource::<Config>().mqtt;
+        if config.broker.is_empty() {
+            return;
+        }
+        let drives_bins = config
+            .topics
+            .iter()
+            .any(|binding| matches!(binding.target, MqttTarget::Bin(_)));
+        if drives_bins {
+            app.insert_resource(DrivenBins);
+        }
+        app.insert_resource(Broker::default()).add_systems(
+            Update,
+            (poll_broker, apply_topics).chain().in_set(RefineSet::React),
+        );
+    }
+}
+
+/// The link to the broker, and the last message of every topic.
+#[derive(Resource, Default)]
+struct Broker {
+    link: Link,
+    retained: HashMap<String, String>,
+    /// Seconds to wait after the next failure.
+    retry: f32,
+}
+
+#[derive(Default)]
+enum Link {
+    /// Not connected; connects on the next poll.
+    #[default]
+    Down,
+    /// Waiting out the seconds left before connecting again.
+    Waiting(f32),
+    Up {
+        connection: Connection,
+        /// Whether the broker has accepted the connection.
+        accepted: bool,
+        /// Seconds until the next ping.
+        ping: f32,
+        /// Seconds since the broker last sent anything.
+        silent: f32,
+    },
+}
+
+/// Appends the MQTT encoding of a packet's remaining length.
+fn encode_length(mut length: usize, packet: &mut Vec<u8>) {
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+}
+
+/// Appends a length-prefixed string.
+fn encode_string(string: &str, body: &mut Vec<u8>) {
+    body.extend((string.len() as u16).to_be_bytes());
+    body.extend(string.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    encode_length(body.len(), &mut packet);
+    packet.extend(body);
+    packet
+}
+
+fn connect_packet(client_id: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    encode_string("MQTT", &mut body);
+    // Protocol level 4 (3.1.1) and a clean session.
+    body.extend([4, 0x02]);
+    body.extend(KEEP_ALIVE.to_be_bytes());
+    encode_string(client_id, &mut body);
+    packet(CONNECT, &body)
+}
+
+fn subscribe_packet(topics: &[MqttBinding]) -> Vec<u8> {
+    // Packet id 1; there is only ever one subscription in flight.
+    let mut body = vec![0, 1];
+    for binding in topics {
+        encode_string(&binding.topic, &mut body);
+        body.push(0);
+    }
+    packet(SUBSCRIBE, &body)
+}
+
+/// Takes the next whole packet off the front of `inbox`, as its fixed header byte and body.
+fn take_packet(inbox: &mut Vec<u8>) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut length = 0;
+    let mut shift = 0;
+    let mut index = 1;
+    loop {
+        let Some(&byte) = inbox.get(index) else {
+            return Ok(None);
+        };
+        length |= usize::from(byte & 0x7f) << shift;
+        index += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed MQTT packet",
+            ));
+        }
+    }
+    if inbox.len() < index + length {
+        return Ok(None);
+    }
+    let header = inbox[0];
+    let body = inbox[index..index + length].to_vec();
+    inbox.drain(..index + length);
+    Ok(Some((header, body)))
+}
+
+/// Reads the topic and payload of a PUBLISH packet.
+fn read_publish(header: u8, body: &[u8]) -> Option<(String, String)> {
+    let length = usize::from(u16::from_be_bytes([*body.first()?, *body.get(1)?]));
+    let topic = String::from_utf8_lossy(body.get(2..2 + length)?).into_owned();
+    // Messages sent at QoS 1 or 2 carry a packet id ahead of the payload.
+    let start = 2 + length + if header & 0x06 != 0 { 2 } else { 0 };
+    let payload = String::from_utf8_lossy(body.get(start..)?).into_owned();
+    Some((topic, payload))
+}
+
+/// Whether a topic filter, which may hold `+` and `#` wildcards, covers a topic.
+fn covers(filter: &str, topic: &str) -> bool {
+    let mut levels = topic.split('/');
+    for part in filter.split('/') {
+        match (part, levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (part, Some(level)) if part == level => {}
+            _ => return false,
+        }
+    }
+    levels.next().is_none()
+}
+
+/// Reads a bin's progress from a message like `0.4` or `40%`.
+fn parse_progress(payload: &str) -> Option<f32> {
+    let payload = payload.trim();
+    let progress = match payload.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.,
+        None => payload.parse().ok()?,
+    };
+    progress.is_finite().then(|| progress.clamp(0., 1.))
+}
+
+/// Keeps the link to the broker up and collects the messages it sends.
+fn poll_broker(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut broker: ResMut<Broker>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let config = &config.mqtt;
+    let delta = time.delta_secs();
+    let broker = &mut *broker;
+    let result = match &mut broker.link {
+        Link::Down => {
+            broker.link = match TcpStream::connect(&config.broker).and_then(Connection::new) {
+                Ok(mut connection) => {
+                    connection.outbox.extend(connect_packet(&config.client_id));
+                    Link::Up {
+                        connection,
+                        accepted: false,
+                        ping: PING_INTERVAL,
+                        silent: 0.,
+                    }
+                }
+                Err(error) => {
+                    // Only the first of a run of failures is worth telling the player.
+                    if broker.retry == 0. {
+                        toasts.write(Toast::warning("MQTT broker unreachable"));
+                    }
+                    broker.retry = (broker.retry * 2.).clamp(FIRST_RETRY, MAX_RETRY);
+                    warn!(
+                        "Could not reach MQTT broker {}, trying again in {}s: {error}",
+                        config.broker, broker.retry
+                    );
+                    Link::Waiting(broker.retry)
+                }
+            };
+            return;
+        }
+        Link::Waiting(left) => {
+            *left -= delta;
+            if *left <= 0. {
+                broker.link = Link::Down;
+            }
+            return;
+        }
+        Link::Up {
+            connection,
+            accepted,
+            ping,
+            silent,
+        } => exchange(
+            connection,
+            accepted,
+            ping,
+            silent,
+            delta,
+            &config.topics,
+            &mut broker.retained,
+        ),
+    };
+    if let Link::Up { accepted: true, .. } = broker.link {
+        if broker.retry > 0. {
+            toasts.write(Toast::success("MQTT broker back"));
+        }
+        broker.retry = 0.;
+    }
+    if let Err(error) = result {
+        if broker.retry == 0. {
+            toasts.write(Toast::warning("Lost MQTT broker"));
+        }
+        broker.retry = (broker.retry * 2.).clamp(FIRST_RETRY, MAX_RETRY);
+        warn!(
+            "Lost MQTT broker {}, connecting again in {}s: {error}",
+            config.broker, broker.retry
+        );
+        broker.link = Link::Waiting(broker.retry);
+    }
+}
+
+/// Sends and receives what is due on a live link.
+fn exchange(
+    connection: &mut Connection,
+    accepted: &mut bool,
+    ping: &mut f32,
+    silent: &mut f32,
+    delta: f32,
+    topics: &[MqttBinding],
+    retained: &mut HashMap<String, String>,
+) -> io::Result<()> {
+    let before = connection.inbox.len();
+    connection.fill_inbox()?;
+    *silent = if connection.inbox.len() > before {
+        0.
+    } else {
+        *silent + delta
+    };
+    if *silent > f32::from(KEEP_ALIVE) * 1.5 {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "the broker went quiet",
+        ));
+    }
+
+    while let Some((header, body)) = take_packet(&mut connection.inbox)? {
+        match header & 0xf0 {
+            CONNACK => match body.get(1) {
+                Some(0) => {
+                    info!("Connected to MQTT broker");
+                    *accepted = true;
+                    connection.outbox.extend(subscribe_packet(topics));
+                }
+                code => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        format!("the broker refused the connection ({code:?})"),
+                    ))
+                }
+            },
+            PUBLISH => {
+                if let Some((topic, payload)) = read_publish(header, &body) {
+                    retained.insert(topic, payload);
+                }
+            }
+            // Subscription acknowledgements and ping responses need nothing doing.
+            _ => {}
+        }
+    }
+
+    if *accepted {
+        *ping -= delta;
+        if *ping <= 0. {
+            *ping = PING_INTERVAL;
+            connection.outbox.extend([PINGREQ, 0]);
+        }
+    }
+    connection.flush()
+}
+
+/// Drives the bins and the header from the last message of every topic.
+fn apply_topics(
+    config: Res<Config>,
+    broker: Res<Broker>,
+    mut message: ResMut<HeaderMessage>,
+    mut bins: Query<&mut Bin>,
+) {
+    for (topic, payload) in &broker.retained {
+        for binding in &config.mqtt.topics {
+            if !covers(&binding.topic, topic) {
+                continue;
+            }
+            match binding.target {
+                MqttTarget::Bin(index) => {
+                    let Some(progress) = parse_progress(payload) else {
+                        continue;
+                    };
+                    for mut bin in &mut bins {
+                        if bin.index == index && bin.progress != progress {
+                            bin.progress = progress;
+                        }
+                    }
+                }
+                MqttTarget::Header => {
+                    let text = payload.trim();
+                    let shown = (!text.is_empty())
+                        .then(|| text.chars().take(MESSAGE_LENGTH).collect::<String>());
+                    message.set_if_neq(HeaderMessage(shown));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PUBLISH packet of `payload` on `topic`, at QoS 1 with packet id 7 if `qos1`.
+    fn publish(topic: &str, payload: &str, qos1: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        encode_string(topic, &mut body);
+        if qos1 {
+            body.extend([0, 7]);
+        }
+        body.extend(payload.as_bytes());
+        packet(if qos1 { PUBLISH | 0x02 } else { PUBLISH }, &body)
+    }
+
+    #[test]
+    fn takes_packets_once_they_are_whole() {
+        let message = publish("mdr/bin/1", "0.4", false);
+        let mut inbox = message[..message.len() - 1].to_vec();
+        assert!(take_packet(&mut inbox).unwrap().is_none());
+        inbox.push(*message.last().unwrap());
+        inbox.extend(packet(PINGREQ, &[]));
+        let (header, body) = take_packet(&mut inbox).unwrap().unwrap();
+        assert_eq!(header, PUBLISH);
+        assert_eq!(body, message[2..]);
+        assert_eq!(
+            take_packet(&mut inbox).unwrap(),
+            Some((PINGREQ, Vec::new()))
+        );
+        assert!(inbox.is_empty());
+        assert!(take_packet(&mut inbox).unwrap().is_none());
+    }
+
+    #[test]
+    fn takes_packets_with_long_lengths() {
+        let body = vec![b'x'; 300];
+        let mut inbox = packet(PUBLISH, &body);
+        // 300 takes two bytes to encode.
+        assert_eq!(inbox[1..3], [0xac, 0x02]);
+        assert_eq!(take_packet(&mut inbox).unwrap(), Some((PUBLISH, body)));
+    }
+
+    #[test]
+    fn refuses_lengths_longer_than_mqtt_allows() {
+        let mut inbox = vec![PUBLISH, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert!(take_packet(&mut inbox).is_err());
+    }
+
+    #[test]
+    fn reads_published_messages() {
+        for qos1 in [false, true] {
+            let mut inbox = publish("mdr/header", "Hello", qos1);
+            let (header, body) = take_packet(&mut inbox).unwrap().unwrap();
+            assert_eq!(
+                read_publish(header, &body),
+                Some(("mdr/header".to_string(), "Hello".to_string())),
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_truncated_messages() {
+        let body = publish("mdr/header", "", false)[2..].to_vec();
+        assert!(read_publish(PUBLISH, &body).is_some());
+        assert!(read_publish(PUBLISH, &body[..body.len() - 1]).is_none());
+        assert!(read_publish(PUBLISH, &[0]).is_none());
+        // A QoS 1 message with no room for its packet id.
+        assert!(read_publish(PUBLISH | 0x02, &body).is_none());
+    }
+
+    #[test]
+    fn filters_cover_topics() {
+        assert!(covers("mdr/bin/+", "mdr/bin/3"));
+        assert!(covers("mdr/#", "mdr/bin/3"));
+        assert!(covers("mdr/header", "mdr/header"));
+        assert!(!covers("mdr/bin/+", "mdr/bin/3/extra"));
+        assert!(!covers("mdr/bin/+", "mdr/header"));
+    }
+
+    #[test]
+    fn reads_progress() {
+        assert_eq!(parse_progress(" 0.4 "), Some(0.4));
+        assert_eq!(parse_progress("40 %"), Some(0.4));
+        assert_eq!(parse_progress("150%"), Some(1.));
+        assert_eq!(parse_progress("NaN"), None);
+        assert_eq!(parse_progress("inf"), None);
+        assert_eq!(parse_progress("full"), None);
+    }
+}
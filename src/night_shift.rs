@@ -0,0 +1,167 @@
+//! Night shift: a warm tint over the whole screen, for a display left running into the night.
+//!
+//! The tint is a pass of its own over what the [`OuterCamera`] draws, a quad multiplying every
+//! pixel by the color of a light of the configured temperature, so the canvas, the bezel and the
+//! clear color around them all warm up together. It is on always or between the hours of its
+//! schedule, easing in and out rather than switching at once. Turned off, the quad is
+//! despawned, so it costs nothing.
+//!
+//! [`OuterCamera`]: crate::canvas::OuterCamera
+
+use bevy::{
+    asset::{load_internal_asset, weak_handle},
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_resource::{
+            AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState,
+            RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+    sprite::{AlphaMode2d, Material2d, Material2dKey, Material2dPlugin},
+};
+
+use crate::{canvas::HIGH_RES_LAYERS, clock::local_hour, config::Config};
+
+/// Depth of the tint, above the canvas and the bezel.
+const NIGHT_SHIFT_Z: f32 = 100.;
+
+/// Size of the quad, large enough to cover the screen however the outer camera is scaled.
+const QUAD_SIZE: f32 = 100_000.;
+
+/// Seconds the tint takes to ease in or out.
+const FADE: f32 = 3.;
+
+const NIGHT_SHIFT_SHADER: Handle<Shader> = weak_handle!("b1f4c7a2-6d3e-4e8b-9c05-7a2d9e4f1b36");
+
+pub struct NightShiftPlugin;
+
+impl Plugin for NightShiftPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            NIGHT_SHIFT_SHADER,
+            "shaders/night_shift.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(Material2dPlugin::<NightShiftMaterial>::default())
+            .add_systems(Update, tint_screen);
+    }
+}
+
+/// Material of the tint quad.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct NightShiftMaterial {
+    /// Color every pixel is multiplied by, in linear RGB.
+    #[uniform(0)]
+    tint: Vec4,
+}
+
+impl Material2d for NightShiftMaterial {
+    fn fragment_shader() -> ShaderRef {
+        NIGHT_SHIFT_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        // Drawn with what is see-through, in order, after everything beneath it.
+        AlphaMode2d::Blend
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _: &MeshVertexBufferLayoutRef,
+        _: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let targets = descriptor
+            .fragment
+            .iter_mut()
+            .flat_map(|fragment| &mut fragment.targets);
+        for target in targets.flatten() {
+            target.blend = Some(BlendState {
+                // What is on the screen times the tint, leaving its alpha to ghost mode.
+                color: BlendComponent {
+                    src_factor: BlendFactor::Dst,
+                    dst_factor: BlendFactor::Zero,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent {
+                    src_factor: BlendFactor::Zero,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Marks the tint quad.
+#[derive(Component)]
+struct NightShiftQuad;
+
+/// The color of a light at `kelvin`, with 6500 as white, after Tanner Helland's fit of the
+/// black-body colors.
+fn light_color(kelvin: u32) -> LinearRgba {
+    let t = kelvin.clamp(1000, 6500) as f32 / 100.;
+    let red = if t <= 66. {
+        255.
+    } else {
+        329.7 * (t - 60.).powf(-0.1332)
+    };
+    let green = if t <= 66. {
+        99.47 * t.ln() - 161.12
+    } else {
+        288.12 * (t - 60.).powf(-0.0755)
+    };
+    let blue = if t >= 66. {
+        255.
+    } else if t <= 19. {
+        0.
+    } else {
+        138.52 * (t - 10.).ln() - 305.04
+    };
+    let [red, green, blue] = [red, green, blue].map(|channel| (channel / 255.).clamp(0., 1.));
+    Color::srgb(red, green, blue).to_linear()
+}
+
+/// Eases the tint towards on or off, spawning the quad while it shows and despawning it after.
+fn tint_screen(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    quads: Query<(Entity, &MeshMaterial2d<NightShiftMaterial>), With<NightShiftQuad>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<NightShiftMaterial>>,
+    mut strength: Local<f32>,
+) {
+    let night_shift = &config.video.night_shift;
+    let step = time.delta_secs() / FADE;
+    *strength = if night_shift.active(local_hour()) {
+        (*strength + step).min(1.)
+    } else {
+        (*strength - step).max(0.)
+    };
+    let tint = LinearRgba::WHITE.mix(&light_color(night_shift.temperature), *strength);
+    match quads.single() {
+        Ok((entity, _)) if *strength <= 0. => commands.entity(entity).despawn(),
+        Ok((_, material)) => {
+            if let Some(material) = materials.get_mut(&material.0) {
+                if material.tint != tint.to_vec4() {
+                    material.tint = tint.to_vec4();
+                }
+            }
+        }
+        Err(_) if *strength > 0. => {
+            commands.spawn((
+                NightShiftQuad,
+                Mesh2d(meshes.add(Rectangle::from_length(QUAD_SIZE))),
+                MeshMaterial2d(materials.add(NightShiftMaterial {
+                    tint: tint.to_vec4(),
+                })),
+                Transform::from_xyz(0., 0., NIGHT_SHIFT_Z),
+                HIGH_RES_LAYERS,
+            ));
+        }
+        Err(_) => {}
+    }
+}
@@ -0,0 +1,111 @@
+//! Desktop notifications of quotas met, for when the window is out of sight: a bin reaching
+//! 100%, and the file being complete.
+//!
+//! Only built with the `notifications` feature. Notices are only sent while the window is in the
+//! background, which is where it goes when minimized, or while it is a see-through ghost over
+//! other work; otherwise the bins and the finale say as much on screen. Each can be turned off
+//! in the settings. They are handed to the platform's own notifier, `notify-send` on Linux and
+//! the BSDs and `osascript` on macOS, on a thread of their own. Other platforms get none, for now.
+
+use std::{process::Command, thread};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{bins::Bin, config::Config, files::ActiveFile, finale::FinaleEnded, grid::RefineSet};
+
+/// Title of every notice.
+const APP_NAME: &str = "Macrodata Refinement";
+
+pub struct NotificationsPlugin;
+
+impl Plugin for NotificationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                notify_full_bins.after(RefineSet::React),
+                notify_complete.run_if(on_event::<FinaleEnded>),
+            ),
+        );
+    }
+}
+
+/// Whether the refiner would miss what the canvas shows.
+fn out_of_sight(config: &Config, window: &Window) -> bool {
+    !window.focused || config.video.ghost
+}
+
+/// Sends a notice as a bin reaches 100%.
+fn notify_full_bins(
+    config: Res<Config>,
+    file: Res<ActiveFile>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    bins: Query<&Bin, Changed<Bin>>,
+    mut full: Local<Vec<bool>>,
+) {
+    // The bins of another file start out as full as they were when it was closed.
+    if file.is_changed() {
+        full.clear();
+    }
+    for bin in &bins {
+        if full.len() <= bin.index {
+            full.resize(bin.index + 1, true);
+        }
+        let now_full = bin.progress >= 1.;
+        let filled = now_full && !full[bin.index];
+        full[bin.index] = now_full;
+        if filled && config.notifications.bin_full && out_of_sight(&config, &window) {
+            notify(format!("Bin {} of {} is at 100%", bin.index + 1, file.name));
+        }
+    }
+}
+
+/// Sends a notice as the file is complete.
+fn notify_complete(
+    mut ended: EventReader<FinaleEnded>,
+    config: Res<Config>,
+    file: Res<ActiveFile>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    ended.clear();
+    if config.notifications.file_complete && out_of_sight(&config, &window) {
+        notify(format!("{} is 100% complete", file.name));
+    }
+}
+
+/// Hands a notice to the platform, without waiting on it.
+fn notify(body: String) {
+    let Some(mut command) = notifier(&body) else {
+        debug!("No notifier on this platform for {body:?}");
+        return;
+    };
+    thread::spawn(move || {
+        if let Err(error) = command.status() {
+            warn!("Could not send a notification: {error}");
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn notifier(body: &str) -> Option<Command> {
+    let quoted = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(format!(
+        "display notification {} with title {}",
+        quoted(body),
+        quoted(APP_NAME)
+    ));
+    Some(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notifier(body: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name", APP_NAME, APP_NAME, body]);
+    Some(command)
+}
+
+#[cfg(not(unix))]
+fn notifier(_: &str) -> Option<Command> {
+    None
+}
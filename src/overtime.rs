@@ -0,0 +1,139 @@
+//! Overtime Contingency: a night mode layered over whatever file is open.
+//!
+//! Overtime does not change the file itself. It swaps the [`Theme`]'s palette for a darker one,
+//! makes the numbers drift
+//! faster, packs the scary numbers into denser clusters, and keeps a red indicator in the
+//! header for as long as it lasts. It is toggled with O while refining, or from the pause menu.
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::{BackdropCamera, CanvasAnchor, PIXEL_PERFECT_LAYERS},
+    state::AppState,
+    theme::Theme,
+};
+
+const INDICATOR_COLOR: Color = Color::srgb(0.9, 0.1, 0.1);
+
+pub struct OvertimePlugin;
+
+impl Plugin for OvertimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Overtime>()
+            .add_systems(Update, toggle_key.run_if(in_state(AppState::Refining)))
+            .add_systems(
+                PostUpdate,
+                (
+                    apply_palette
+                        .run_if(resource_changed::<Overtime>.or(resource_changed::<Theme>)),
+                    show_indicator.run_if(resource_changed::<Overtime>),
+                ),
+            );
+    }
+}
+
+/// Whether the Overtime Contingency is in effect.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Overtime(pub bool);
+
+impl Overtime {
+    /// Color of numbers outside the selection.
+    pub fn number_color(self, theme: &Theme) -> Color {
+        if self.0 {
+            Color::srgb(0.55, 0.6, 0.68)
+        } else {
+            theme.numbers()
+        }
+    }
+
+    /// Color behind the grid.
+    fn background(self, theme: &Theme) -> Color {
+        if self.0 {
+            Color::srgb(0.04, 0.05, 0.08)
+        } else {
+            theme.background()
+        }
+    }
+
+    /// How much faster than usual the numbers drift.
+    pub fn drift_speed(self) -> f32 {
+        if self.0 {
+            3.
+        } else {
+            1.
+        }
+    }
+
+    /// How much further than usual clusters of scary numbers reach.
+    pub fn cluster_spread(self) -> f32 {
+        if self.0 {
+            1.5
+        } else {
+            1.
+        }
+    }
+}
+
+/// The red indicator in the header.
+#[derive(Component)]
+struct OvertimeIndicator;
+
+fn toggle_key(keys: Res<ButtonInput<KeyCode>>, mut overtime: ResMut<Overtime>) {
+    if keys.just_pressed(KeyCode::KeyO) {
+        overtime.0 ^= true;
+    }
+}
+
+fn apply_palette(
+    overtime: Res<Overtime>,
+    theme: Res<Theme>,
+    mut camera: Single<&mut Camera, With<BackdropCamera>>,
+) {
+    camera.clear_color = ClearColorConfig::Custom(overtime.background(&theme));
+}
+
+fn show_indicator(
+    mut commands: Commands,
+    overtime: Res<Overtime>,
+    indicator: Option<Single<Entity, With<OvertimeIndicator>>>,
+) {
+    match (overtime.0, indicator) {
+        (true, None) => {
+            commands
+                .spawn((
+                    OvertimeIndicator,
+                    Sprite {
+                        color: Color::srgba(0.0, 0.0, 0.0, 0.8),
+                        custom_size: Some(Vec2::new(58., 11.)),
+                        ..default()
+                    },
+                    CanvasAnchor::TOP_LEFT.offset(32., -8.),
+                    Transform::from_xyz(0., 0., 16.),
+                    PIXEL_PERFECT_LAYERS,
+                ))
+                .with_children(|indicator| {
+                    indicator.spawn((
+                        Sprite {
+                            color: INDICATOR_COLOR,
+                            custom_size: Some(Vec2::splat(5.)),
+                            ..default()
+                        },
+                        Transform::from_xyz(-22., 0., 0.1),
+                        PIXEL_PERFECT_LAYERS,
+                    ));
+                    indicator.spawn((
+                        Text2d::new("OVERTIME"),
+                        TextFont {
+                            font_size: 8.0,
+                            ..default()
+                        },
+                        TextColor(INDICATOR_COLOR),
+                        Transform::from_xyz(4., 0., 0.1),
+                        PIXEL_PERFECT_LAYERS,
+                    ));
+                });
+        }
+        (false, Some(indicator)) => commands.entity(*indicator).despawn(),
+        _ => {}
+    }
+}
@@ -0,0 +1,229 @@
+//! Bursts of pixel specks that fly up and fall away, such as when numbers land in a bin, and
+//! their slower kin: confetti that flutters down and balloons that float up.
+//!
+//! Refining fires bursts often, so the specks are a fixed pool spawned up front and handed out in
+//! turn; a burst that needs more than are idle takes over the oldest. Anything can fire one by
+//! writing a [`ParticleBurst`].
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::Bin,
+    canvas::PIXEL_PERFECT_LAYERS,
+    config::Config,
+    grid::{RefineSet, Refined},
+    theme::Theme,
+};
+
+/// Specks in the pool.
+const POOL_SIZE: usize = 256;
+
+/// Seconds a sprayed speck lives.
+const SPECK_LIFE: f32 = 0.7;
+
+/// Pixels per second squared that sprayed specks fall with.
+const GRAVITY: f32 = 240.;
+
+/// Most specks a refinement throws up, however many numbers were refined.
+const MAX_REFINE_SPECKS: usize = 24;
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ParticleBurst>()
+            .add_systems(Startup, setup_pool)
+            .add_systems(
+                Update,
+                (
+                    burst_refinements.in_set(RefineSet::React),
+                    (emit_bursts, move_specks).chain().after(RefineSet::React),
+                ),
+            );
+    }
+}
+
+/// Throws up specks of a color from a point of the canvas.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ParticleBurst {
+    pub position: Vec2,
+    pub color: Color,
+    pub count: usize,
+    pub motion: Motion,
+}
+
+/// How the specks of a burst move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Motion {
+    /// Sprayed upwards, quickly falling away.
+    #[default]
+    Spray,
+    /// Confetti, fluttering down from side to side.
+    Flutter,
+    /// Balloons, drifting up from side to side.
+    Float,
+}
+
+impl Motion {
+    /// Size of a speck, in pixels.
+    fn size(self) -> Vec2 {
+        match self {
+            Self::Spray => Vec2::ONE,
+            Self::Flutter => Vec2::new(2., 1.),
+            Self::Float => Vec2::new(3., 4.),
+        }
+    }
+
+    /// Seconds a speck lives at most.
+    fn life(self) -> f32 {
+        match self {
+            Self::Spray => SPECK_LIFE,
+            Self::Flutter => 4.,
+            Self::Float => 6.,
+        }
+    }
+
+    /// A speck's velocity as it sets out, in pixels per second.
+    fn launch(self) -> Vec2 {
+        match self {
+            Self::Spray => {
+                // Mostly upwards, fanning out to either side.
+                let angle = std::f32::consts::FRAC_PI_2 + (fastrand::f32() - 0.5) * 1.8;
+                Vec2::from_angle(angle) * (50. + fastrand::f32() * 70.)
+            }
+            Self::Flutter => Vec2::new(
+                (fastrand::f32() - 0.5) * 20.,
+                -(35. + fastrand::f32() * 30.),
+            ),
+            Self::Float => Vec2::new(0., 30. + fastrand::f32() * 15.),
+        }
+    }
+}
+
+/// A speck of the pool, idle once its life is over.
+#[derive(Component, Default)]
+struct Speck {
+    /// Where the speck is, of which it shows the nearest whole pixel.
+    position: Vec2,
+    velocity: Vec2,
+    motion: Motion,
+    /// Seconds of life left.
+    left: f32,
+    /// Where the speck is along its sway, in radians.
+    sway: f32,
+}
+
+/// Every speck, in the order they are handed out.
+#[derive(Resource)]
+struct SpeckPool {
+    specks: Vec<Entity>,
+    next: usize,
+}
+
+fn setup_pool(mut commands: Commands) {
+    let specks = (0..POOL_SIZE)
+        .map(|_| {
+            commands
+                .spawn((
+                    Speck::default(),
+                    Sprite {
+                        custom_size: Some(Vec2::ONE),
+                        ..default()
+                    },
+                    Transform::from_xyz(0., 0., 3.),
+                    Visibility::Hidden,
+                    PIXEL_PERFECT_LAYERS,
+                ))
+                .id()
+        })
+        .collect();
+    commands.insert_resource(SpeckPool { specks, next: 0 });
+}
+
+/// Bursts from the top of every bin numbers were refined into.
+fn burst_refinements(
+    config: Res<Config>,
+    theme: Res<Theme>,
+    mut refined: EventReader<Refined>,
+    bins: Query<(&Bin, &Transform, &Sprite)>,
+    mut bursts: EventWriter<ParticleBurst>,
+) {
+    for event in refined.read() {
+        if config.accessibility.reduced_motion {
+            continue;
+        }
+        for (bin, transform, sprite) in &bins {
+            if bin.index != event.bin {
+                continue;
+            }
+            let height = sprite.custom_size.unwrap_or_default().y;
+            bursts.write(ParticleBurst {
+                position: transform.translation.truncate() + Vec2::Y * height / 2.,
+                color: theme.selection(),
+                count: (event.count as usize).clamp(6, MAX_REFINE_SPECKS),
+                motion: Motion::Spray,
+            });
+        }
+    }
+}
+
+fn emit_bursts(
+    mut bursts: EventReader<ParticleBurst>,
+    mut pool: ResMut<SpeckPool>,
+    mut specks: Query<(&mut Speck, &mut Sprite, &mut Transform, &mut Visibility)>,
+) {
+    let pool = &mut *pool;
+    for burst in bursts.read() {
+        for _ in 0..burst.count.min(POOL_SIZE) {
+            let entity = pool.specks[pool.next];
+            pool.next = (pool.next + 1) % POOL_SIZE;
+            let Ok((mut speck, mut sprite, mut transform, mut visibility)) = specks.get_mut(entity)
+            else {
+                continue;
+            };
+            *speck = Speck {
+                position: burst.position,
+                velocity: burst.motion.launch(),
+                motion: burst.motion,
+                left: burst.motion.life() * (0.6 + 0.4 * fastrand::f32()),
+                sway: fastrand::f32() * std::f32::consts::TAU,
+            };
+            sprite.color = burst.color;
+            sprite.custom_size = Some(burst.motion.size());
+            transform.translation = burst.position.round().extend(transform.translation.z);
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn move_specks(
+    time: Res<Time<Virtual>>,
+    mut specks: Query<(&mut Speck, &mut Sprite, &mut Transform, &mut Visibility)>,
+) {
+    let delta = time.delta_secs();
+    for (mut speck, mut sprite, mut transform, mut visibility) in &mut specks {
+        if speck.left <= 0. {
+            continue;
+        }
+        speck.left -= delta;
+        if speck.left <= 0. {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        let mut velocity = speck.velocity;
+        match speck.motion {
+            Motion::Spray => {
+                speck.velocity.y -= GRAVITY * delta;
+                velocity = speck.velocity;
+            }
+            Motion::Flutter | Motion::Float => {
+                speck.sway += delta * 3.;
+                velocity.x += speck.sway.sin() * 12.;
+            }
+        }
+        speck.position += velocity * delta;
+        transform.translation = speck.position.round().extend(transform.translation.z);
+        let alpha = (speck.left / speck.motion.life() * 2.).min(1.);
+        sprite.color.set_alpha(alpha);
+    }
+}
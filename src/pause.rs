@@ -0,0 +1,178 @@
+//! The pause menu, opened with Esc while refining.
+//!
+//! Abandoning the file starts it over, its bins emptied in the library, after a look back at the
+//! session; quitting leaves it as it is for the main menu to continue.
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    canvas::{CanvasFill, PIXEL_PERFECT_LAYERS},
+    files::{ActiveFile, FileLibrary},
+    kiosk::Kiosk,
+    net::SharedSession,
+    overtime::Overtime,
+    replay::Playback,
+    state::AppState,
+    transition::{TransitionEffect, TransitionTo},
+    ui::{spawn_menu, MenuChosen, MenuEntry},
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_pause.run_if(in_state(AppState::Refining).or(in_state(AppState::Paused))),
+                choose.run_if(in_state(AppState::Paused)),
+            ),
+        )
+        .add_systems(OnEnter(AppState::Paused), (pause_time, spawn_pause_menu))
+        .add_systems(OnEnter(AppState::Refining), resume_time);
+    }
+}
+
+/// Marks the pause menu.
+#[derive(Component)]
+struct PauseMenu;
+
+/// Items of the pause menu, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PauseItem {
+    Resume,
+    Overtime,
+    Settings,
+    AbandonFile,
+    Quit,
+}
+
+impl PauseItem {
+    const ALL: [PauseItem; 5] = [
+        PauseItem::Resume,
+        PauseItem::Overtime,
+        PauseItem::Settings,
+        PauseItem::AbandonFile,
+        PauseItem::Quit,
+    ];
+
+    fn label(self, overtime: Overtime) -> &'static str {
+        match self {
+            PauseItem::Resume => "Resume",
+            PauseItem::Overtime if overtime.0 => "End Overtime",
+            PauseItem::Overtime => "Overtime Contingency",
+            PauseItem::Settings => "Settings",
+            PauseItem::AbandonFile => "Abandon File",
+            PauseItem::Quit => "Quit",
+        }
+    }
+}
+
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next.set(match state.get() {
+            AppState::Paused => AppState::Refining,
+            _ => AppState::Paused,
+        });
+    }
+}
+
+/// Stops the virtual clock, which every simulation and animation system runs on, until the
+/// player is back to refining (the settings screen is reached from here, and keeps it stopped).
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn spawn_pause_menu(
+    mut commands: Commands,
+    shared: Option<Res<SharedSession>>,
+    playback: Option<Res<Playback>>,
+    overtime: Res<Overtime>,
+    kiosk: Option<Res<Kiosk>>,
+) {
+    // Abandoning only makes sense for a grid this instance owns alone.
+    let can_abandon = shared.is_none() && playback.is_none();
+    let entries = PauseItem::ALL.map(|item| {
+        let enabled = match item {
+            PauseItem::AbandonFile => can_abandon,
+            // A kiosk is not to be quit from its menus.
+            PauseItem::Quit => kiosk.is_none(),
+            PauseItem::Resume | PauseItem::Overtime | PauseItem::Settings => true,
+        };
+        MenuEntry::new(item.label(*overtime)).enabled(enabled)
+    });
+
+    // Dim the grid behind the menu
+    commands.spawn((
+        Sprite {
+            color: Color::srgba(0.0, 0.0, 0.0, 0.6),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 19.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Paused),
+    ));
+
+    spawn_menu(
+        &mut commands,
+        "PAUSED",
+        &entries,
+        0,
+        Vec3::new(0., 0., 20.),
+        (PauseMenu, StateScoped(AppState::Paused)),
+    );
+}
+
+fn choose(
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<(), With<PauseMenu>>,
+    mut next: ResMut<NextState<AppState>>,
+    mut overtime: ResMut<Overtime>,
+    (mut active, mut library): (ResMut<ActiveFile>, ResMut<FileLibrary>),
+    mut transitions: EventWriter<TransitionTo>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in chosen.read() {
+        if !menus.contains(event.menu) {
+            continue;
+        }
+        match PauseItem::ALL[event.item] {
+            PauseItem::Resume => next.set(AppState::Refining),
+            // Straight back to the grid, to see it take effect.
+            PauseItem::Overtime => {
+                overtime.0 ^= true;
+                next.set(AppState::Refining);
+            }
+            PauseItem::Settings => {
+                transitions.write(TransitionTo::new(
+                    AppState::Settings,
+                    TransitionEffect::Wipe,
+                ));
+            }
+            // The bins on screen keep their progress for the summary, but are no longer kept
+            // track of in the library; unnoticed, so that the session is not taken for a new one.
+            PauseItem::AbandonFile => {
+                let record = active.bypass_change_detection().record.take();
+                if let Some(file) = record.and_then(|index| library.files.get_mut(index)) {
+                    file.start_over();
+                }
+                transitions.write(TransitionTo::new(
+                    AppState::Summary,
+                    TransitionEffect::PowerOff,
+                ));
+            }
+            PauseItem::Quit => {
+                exit.write(AppExit::Success);
+            }
+        }
+    }
+}
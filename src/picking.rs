@@ -0,0 +1,94 @@
+//! Which number of the grid is under the cursor.
+//!
+//! The [`CanvasCursor`] already sees through the grid camera's pan and zoom to the cell under
+//! the cursor; picking keeps that cell and its number entity in [`Hovered`] for everything to
+//! read, and sends [`HoverChanged`] as the cursor moves from one number to another, or off the
+//! grid. Hovering a number gives it a little wiggle, and the audio ticks.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::CanvasCursor,
+    grid::{Cell, Number, RefineSet},
+    signature::Tilt,
+    tween::Tween,
+};
+
+/// Seconds a hovered number wiggles for.
+const WIGGLE_TIME: f32 = 0.3;
+
+/// Radians a hovered number tilts by at the start of its wiggle.
+const WIGGLE_ANGLE: f32 = 0.25;
+
+/// Times a hovered number tilts back and forth.
+const WIGGLE_SWINGS: f32 = 1.5;
+
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hovered>()
+            .add_event::<HoverChanged>()
+            .add_systems(
+                Update,
+                (pick_number, wiggle_hovered)
+                    .chain()
+                    .before(RefineSet::Input),
+            );
+    }
+}
+
+/// The cell under the cursor and its number, if the cursor is over the grid.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hovered {
+    pub cell: Option<Cell>,
+    pub number: Option<Entity>,
+}
+
+/// The cursor moved onto another cell, or off the grid, and this is what it is over now.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HoverChanged(pub Hovered);
+
+fn pick_number(
+    pointer: CanvasCursor,
+    mut hovered: ResMut<Hovered>,
+    numbers: Query<(Entity, &Cell), With<Number>>,
+    mut changed: EventWriter<HoverChanged>,
+) {
+    let cell = pointer.cell();
+    if cell == hovered.cell {
+        return;
+    }
+    // Only looked for as the cell changes, which is seldom enough to go through them all.
+    let number = cell.and_then(|cell| {
+        numbers
+            .iter()
+            .find(|(_, number)| **number == cell)
+            .map(|(entity, _)| entity)
+    });
+    *hovered = Hovered { cell, number };
+    changed.write(HoverChanged(*hovered));
+}
+
+/// Tilts a newly hovered number back and forth, settling back to its [`Tilt`].
+fn wiggle_hovered(
+    mut commands: Commands,
+    mut changed: EventReader<HoverChanged>,
+    tilts: Query<&Tilt>,
+) {
+    for event in changed.read() {
+        let Some(number) = event.0.number else {
+            continue;
+        };
+        let tilt = tilts.get(number).map_or(0., |tilt| tilt.0);
+        commands.entity(number).try_insert(Tween::new(
+            WIGGLE_TIME,
+            move |transform: &mut Transform, t| {
+                let angle = (t * WIGGLE_SWINGS * TAU).sin() * (1. - t) * WIGGLE_ANGLE;
+                transform.rotation = Quat::from_rotation_z(tilt + angle);
+            },
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+//! The work timer: a mode where the open file's completion follows a real timer, in the manner
+//! of a pomodoro.
+//!
+//! While it is on, every bin fills over one work session, reaching 100% as it ends, and the
+//! refiner is praised for their focus. A break follows, after which the next session starts
+//! either right away or with the first refinement, as configured. The timer keeps to the
+//! [session clock](crate::speed::Session), so pausing the grid pauses it too, while its minutes
+//! stay real however fast the grid runs.
+
+use bevy::prelude::*;
+
+use crate::{
+    audio::{PlaySound, Sound},
+    bins::{Bin, DrivenBins},
+    canvas::{CanvasAnchor, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    grid::{RefineSet, Refined},
+    replay::{clock, Playback},
+    speed::Session,
+    state::AppState,
+    ui::spawn_praise,
+};
+
+const TIMER_COLOR: Color = Color::srgb(0.0, 0.9, 1.0);
+
+/// Seconds the praise stays up at the end of a work session.
+const PRAISE_TIME: f32 = 5.;
+
+pub struct PomodoroPlugin;
+
+impl Plugin for PomodoroPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorkTimer>().add_systems(
+            Update,
+            (
+                show_timer.run_if(resource_changed::<Config>),
+                (
+                    tick.run_if(in_state(AppState::Refining)),
+                    fill_bins,
+                    label_timer,
+                )
+                    .chain()
+                    .run_if(|config: Res<Config>| config.pomodoro.enabled),
+            )
+                .chain()
+                .in_set(RefineSet::React)
+                .run_if(not(resource_exists::<Playback>).and(not(resource_exists::<DrivenBins>))),
+        );
+    }
+}
+
+/// What the work timer is timing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Phase {
+    /// Waiting for the first refinement to start a work session.
+    #[default]
+    Waiting,
+    Work,
+    Break,
+}
+
+/// How far through its current phase the work timer is.
+#[derive(Resource, Default)]
+struct WorkTimer {
+    phase: Phase,
+    elapsed: f32,
+}
+
+/// The readout of the work timer in the header.
+#[derive(Component)]
+struct TimerLabel;
+
+/// Spawns or despawns the readout as the work timer is turned on and off.
+fn show_timer(
+    mut commands: Commands,
+    config: Res<Config>,
+    mut timer: ResMut<WorkTimer>,
+    label: Option<Single<Entity, With<TimerLabel>>>,
+) {
+    match (config.pomodoro.enabled, label) {
+        (true, None) => {
+            commands.spawn((
+                TimerLabel,
+                Text2d::default(),
+                TextFont {
+                    font_size: 8.0,
+                    ..default()
+                },
+                TextColor(TIMER_COLOR),
+                CanvasAnchor::TOP_RIGHT.offset(-32., -8.),
+                Transform::from_xyz(0., 0., 16.),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        }
+        (false, Some(label)) => {
+            commands.entity(*label).despawn();
+            *timer = WorkTimer::default();
+        }
+        _ => {}
+    }
+}
+
+fn tick(
+    mut commands: Commands,
+    time: Res<Time<Session>>,
+    config: Res<Config>,
+    mut refined: EventReader<Refined>,
+    mut timer: ResMut<WorkTimer>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    let pomodoro = &config.pomodoro;
+    if timer.phase == Phase::Waiting {
+        if refined.is_empty() {
+            return;
+        }
+        refined.clear();
+        *timer = WorkTimer {
+            phase: Phase::Work,
+            elapsed: 0.,
+        };
+    }
+    timer.elapsed += time.delta_secs();
+
+    match timer.phase {
+        Phase::Work if timer.elapsed >= pomodoro.work_minutes as f32 * 60. => {
+            info!("Finished a {} minute work session", pomodoro.work_minutes);
+            sounds.write(PlaySound::new(Sound::Beep));
+            spawn_praise(
+                &mut commands,
+                format!(
+                    "{} minutes of focused refinement.\n\
+                     You have earned a Music Dance Experience.",
+                    pomodoro.work_minutes
+                ),
+                PRAISE_TIME,
+            );
+            *timer = WorkTimer {
+                phase: if pomodoro.break_minutes > 0 {
+                    Phase::Break
+                } else {
+                    Phase::Waiting
+                },
+                elapsed: 0.,
+            };
+        }
+        Phase::Break if timer.elapsed >= pomodoro.break_minutes as f32 * 60. => {
+            sounds.write(PlaySound::new(Sound::Beep).with_pitch(1.5));
+            *timer = WorkTimer {
+                phase: if pomodoro.auto_continue {
+                    Phase::Work
+                } else {
+                    Phase::Waiting
+                },
+                elapsed: 0.,
+            };
+        }
+        _ => {}
+    }
+}
+
+/// Fills every bin with the progress of the work session, so that they are full as it ends.
+fn fill_bins(config: Res<Config>, timer: Res<WorkTimer>, mut bins: Query<&mut Bin>) {
+    if timer.phase != Phase::Work {
+        return;
+    }
+    let work = (config.pomodoro.work_minutes as f32 * 60.).max(f32::EPSILON);
+    let progress = (timer.elapsed / work).min(1.);
+    for mut bin in &mut bins {
+        if bin.progress != progress {
+            bin.progress = progress;
+        }
+    }
+}
+
+fn label_timer(
+    config: Res<Config>,
+    timer: Res<WorkTimer>,
+    mut label: Single<&mut Text2d, With<TimerLabel>>,
+) {
+    let minutes = match timer.phase {
+        Phase::Waiting | Phase::Work => config.pomodoro.work_minutes,
+        Phase::Break => config.pomodoro.break_minutes,
+    };
+    let left = clock((minutes as f32 * 60. - timer.elapsed).max(0.));
+    let text = match timer.phase {
+        Phase::Waiting => format!("READY {left}"),
+        Phase::Work => format!("WORK {left}"),
+        Phase::Break => format!("BREAK {left}"),
+    };
+    if label.0 != text {
+        label.0 = text;
+    }
+}
@@ -0,0 +1,145 @@
+//! Selection readout: while a selection is being dragged out, a tag by the cursor tells how many
+//! cells it holds, whether that is more than the config's `max_selection` lets be refined, and
+//! below the hard difficulty whether any of them are scary.
+//!
+//! It follows the [`Dragging`] range the grid keeps up to date every frame of the drag, and takes
+//! the place of the [tooltip](crate::tooltip), which stays out of the way while a button is held.
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    canvas::{CanvasCursor, PIXEL_PERFECT_LAYERS},
+    config::{Config, Difficulty},
+    grid::{Cell, Dragging, GridModel, RefineSet, Selection, Temper},
+    state::AppState,
+    theme::Theme,
+};
+
+/// Where the readout sits from the cursor, to the lower right of its tip.
+const READOUT_OFFSET: Vec2 = Vec2::new(6., -8.);
+
+/// Height a line of the readout takes up.
+const LINE_HEIGHT: f32 = 8.;
+
+/// Width a character of the readout takes up.
+const CHAR_WIDTH: f32 = 4.5;
+
+const BACKING_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+
+pub struct ReadoutPlugin;
+
+impl Plugin for ReadoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_readout)
+            .add_systems(Update, show_readout.after(RefineSet::Sync));
+    }
+}
+
+/// Marks the readout's backing.
+#[derive(Component)]
+struct Readout;
+
+/// Marks the readout's text.
+#[derive(Component)]
+struct ReadoutText;
+
+fn setup_readout(mut commands: Commands) {
+    commands
+        .spawn((
+            Readout,
+            Sprite {
+                color: BACKING_COLOR,
+                custom_size: Some(Vec2::ZERO),
+                anchor: Anchor::TopLeft,
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 28.),
+            Visibility::Hidden,
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_children(|readout| {
+            readout.spawn((
+                ReadoutText,
+                Text2d::default(),
+                TextFont {
+                    font_size: 7.0,
+                    ..default()
+                },
+                Anchor::TopLeft,
+                Transform::from_xyz(2., -1., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        });
+}
+
+/// What the readout says of `range`, and the color it says it in: the warning color for a
+/// selection that would be refused.
+fn describe(range: URect, model: &GridModel, config: &Config, theme: &Theme) -> (String, Color) {
+    let count = Selection::cell_count(range);
+    let mut lines = vec![if count == 1 {
+        "1 CELL".to_string()
+    } else {
+        format!("{count} CELLS")
+    }];
+    let mut color = theme.info;
+    let limit = config.gameplay.max_selection;
+    if limit > 0 && count > limit {
+        lines.push(format!("OVER {limit}"));
+        color = theme.error;
+    }
+    if config.gameplay.difficulty != Difficulty::Hard {
+        let cells = (range.min.y..=range.max.y)
+            .flat_map(|row| (range.min.x..=range.max.x).map(move |col| Cell { col, row }));
+        let tagged = cells
+            .filter_map(|cell| model.get(cell))
+            .any(|state| state.temper != Temper::Calm);
+        if tagged {
+            lines.push("TAGGED".to_string());
+        } else {
+            lines.push("NONE TAGGED".to_string());
+            if color == theme.info {
+                color = theme.warning;
+            }
+        }
+    }
+    (lines.join("\n"), Color::srgb_from_array(color))
+}
+
+fn show_readout(
+    (config, theme, state): (Res<Config>, Res<Theme>, Res<State<AppState>>),
+    (dragging, model): (Res<Dragging>, Res<GridModel>),
+    pointer: CanvasCursor,
+    mut readout: Single<(&mut Sprite, &mut Transform, &mut Visibility), With<Readout>>,
+    mut text: Single<(&mut Text2d, &mut TextColor), With<ReadoutText>>,
+) {
+    let (sprite, transform, visibility) = &mut *readout;
+    let range = dragging.0.filter(|_| *state.get() == AppState::Refining);
+    let (Some(range), Some(cursor)) = (range, pointer.world()) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    let (label, color) = describe(range, &model, &config, &theme);
+    let longest = label.lines().map(|line| line.chars().count()).max();
+    let lines = label.lines().count();
+    let size = Vec2::new(
+        longest.unwrap_or_default() as f32 * CHAR_WIDTH + 3.,
+        lines as f32 * LINE_HEIGHT + 2.,
+    );
+    // Kept on the canvas, flipping to the other side of the cursor near the right and bottom.
+    let half = pointer.canvas().half();
+    let mut position = cursor + READOUT_OFFSET;
+    if position.x + size.x > half.x {
+        position.x = cursor.x - READOUT_OFFSET.x - size.x;
+    }
+    if position.y - size.y < -half.y {
+        position.y = cursor.y - READOUT_OFFSET.y + size.y;
+    }
+    let (label_text, label_color) = &mut *text;
+    if label_text.0 != label {
+        label_text.0 = label;
+        sprite.custom_size = Some(size);
+    }
+    label_color.set_if_neq(TextColor(color));
+    transform.translation = position.round().extend(transform.translation.z);
+    visibility.set_if_neq(Visibility::Inherited);
+}
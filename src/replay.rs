@@ -0,0 +1,541 @@
+//! Recording sessions to a file and playing them back.
+//!
+//! A replay is the timestamped stream of every [`ApplyAction`] of a session. The grid is
+//! deterministic, so re-applying the stream re-simulates the session exactly; seeking backwards
+//! resets the grid and fast-forwards through the stream up to the new time.
+//!
+//! Replays are text: a `MDR-REPLAY <version>` header line followed by one `<seconds> <action>`
+//! line per action, with actions written as in [`GridAction`]'s `Display` impl. Since version 2,
+//! a `<seconds> FILE ...` line (see [`ActiveFile`]) marks each file opened during the session;
+//! version 3 added the number of bins to those lines, which version 2 files always had five of,
+//! and version 4 the limits of the bins, which older files never had. Since version 5 each is
+//! followed by a `SIZE` action with the size of the grid, which it otherwise played back at
+//! whatever size the settings gave it.
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasCursor, CanvasSize, PIXEL_PERFECT_LAYERS},
+    files::ActiveFile,
+    grid::{ApplyAction, GridAction, GridSize, RefineSet, ResetRefinement},
+    state::AppState,
+};
+
+/// Version written to the header of replay files.
+const FORMAT_VERSION: u32 = 5;
+
+/// Oldest version that can still be played back.
+const OLDEST_VERSION: u32 = 1;
+
+/// Width of the canvas left beside the playback timeline, for its margin and label.
+const TIMELINE_ROOM: f32 = 112.;
+
+/// Height of the playback timeline.
+const TIMELINE_HEIGHT: f32 = 4.;
+
+/// Seconds skipped by the arrow keys during playback.
+const SEEK_STEP: f32 = 5.;
+
+/// Whether this session is recorded or played back.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    /// Write every applied action to the given file.
+    Record(PathBuf),
+    /// Play back the given file instead of refining.
+    Play(PathBuf),
+}
+
+impl ReplayMode {
+    /// Parses `--record PATH` and `--replay PATH` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut mode = ReplayMode::Off;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => match args.next() {
+                    Some(path) => mode = ReplayMode::Record(path.into()),
+                    None => eprintln!("--record needs a file to write the replay to"),
+                },
+                "--replay" => match args.next() {
+                    Some(path) => mode = ReplayMode::Play(path.into()),
+                    None => eprintln!("--replay needs a replay file to play"),
+                },
+                _ => {}
+            }
+        }
+        mode
+    }
+}
+
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            ReplayMode::Off => {}
+            ReplayMode::Record(path) => match Recorder::create(path) {
+                Ok(recorder) => {
+                    info!("Recording replay to {}", path.display());
+                    app.insert_resource(recorder)
+                        .add_systems(Update, record.in_set(RefineSet::React));
+                }
+                Err(error) => error!("Could not record replay to {}: {error}", path.display()),
+            },
+            ReplayMode::Play(path) => match Playback::load(path) {
+                Ok(playback) => {
+                    info!("Playing replay {}", path.display());
+                    app.insert_resource(playback)
+                        .configure_sets(
+                            Update,
+                            RefineSet::Input.run_if(not(resource_exists::<Playback>)),
+                        )
+                        .add_systems(
+                            Update,
+                            (
+                                (scrub.run_if(in_state(AppState::Refining)), play)
+                                    .chain()
+                                    .in_set(RefineSet::Route),
+                                (
+                                    lay_out_timeline.run_if(resource_changed::<CanvasSize>),
+                                    update_timeline,
+                                )
+                                    .chain()
+                                    .in_set(RefineSet::React),
+                            ),
+                        );
+                }
+                Err(error) => error!("Could not play replay {}: {error}", path.display()),
+            },
+        }
+    }
+}
+
+/// Writes applied actions to a replay file as they happen.
+#[derive(Resource)]
+struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    fn create(path: &Path) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        writeln!(file, "MDR-REPLAY {FORMAT_VERSION}")?;
+        Ok(Self { file })
+    }
+}
+
+fn record(
+    mut commands: Commands,
+    recorder: Option<ResMut<Recorder>>,
+    time: Res<Time>,
+    (file, size): (Res<ActiveFile>, Res<GridSize>),
+    mut actions: EventReader<ApplyAction>,
+) {
+    let Some(mut recorder) = recorder else {
+        actions.clear();
+        return;
+    };
+    let now = time.elapsed_secs();
+    let mut result = Ok(());
+    // Files are laid out for the size of the grid, which playing back a file starts out at.
+    if file.is_changed() {
+        result = writeln!(recorder.file, "{now:.3} {}", *file)
+            .and_then(|()| writeln!(recorder.file, "{now:.3} {}", GridAction::Resize(*size)));
+    }
+    for ApplyAction(action) in actions.read() {
+        result = result.and_then(|()| writeln!(recorder.file, "{now:.3} {action}"));
+    }
+    if let Err(error) = result {
+        error!("Stopped recording replay: {error}");
+        commands.remove_resource::<Recorder>();
+    }
+}
+
+/// Something that happened at a point of a replay.
+#[derive(Clone, Debug, PartialEq)]
+enum Entry {
+    File(ActiveFile),
+    Action(GridAction),
+}
+
+/// A replay being played back.
+#[derive(Resource)]
+pub struct Playback {
+    /// Every entry of the replay with the time it happened, in order.
+    entries: Vec<(f32, Entry)>,
+    /// Index of the next entry to apply.
+    next: usize,
+    /// Position of the playhead in seconds.
+    time: f32,
+    playing: bool,
+}
+
+impl Playback {
+    fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the text of a replay of any version since [`OLDEST_VERSION`].
+    fn parse(contents: &str) -> io::Result<Self> {
+        let invalid = |line: usize, what: &str| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {what}"))
+        };
+        let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        let version = match lines.next() {
+            Some((line, header)) => match header.trim().strip_prefix("MDR-REPLAY ") {
+                Some(version) => match version.parse::<u32>() {
+                    Ok(version) if (OLDEST_VERSION..=FORMAT_VERSION).contains(&version) => version,
+                    _ => return Err(invalid(line, "unsupported replay version")),
+                },
+                None => return Err(invalid(line, "not a replay file")),
+            },
+            None => return Err(invalid(1, "not a replay file")),
+        };
+
+        let mut entries = Vec::new();
+        for (line, text) in lines.filter(|(_, text)| !text.trim().is_empty()) {
+            let (time, entry) = text
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| invalid(line, "expected a time and an action"))?;
+            let time: f32 = time.parse().map_err(|_| invalid(line, "bad time"))?;
+            let entry = if let Some(file) = entry.strip_prefix("FILE ") {
+                // Older files lack the fields added since, which had fixed values back then.
+                let file = match (version, file.split_once(' ')) {
+                    (..=2, Some((seed, rest))) => format!("FILE {seed} 5 - 0 {rest}"),
+                    (3, Some((seed, rest))) => match rest.split_once(' ') {
+                        Some((bins, rest)) => format!("FILE {seed} {bins} - 0 {rest}"),
+                        None => entry.to_string(),
+                    },
+                    _ => entry.to_string(),
+                };
+                Entry::File(file.parse().map_err(|_| invalid(line, "bad file"))?)
+            } else {
+                Entry::Action(entry.parse().map_err(|_| invalid(line, "bad action"))?)
+            };
+            entries.push((time, entry));
+        }
+        // Stable, so entries of the same instant stay in the order they were recorded.
+        entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(Self {
+            entries,
+            next: 0,
+            time: 0.,
+            playing: true,
+        })
+    }
+
+    fn duration(&self) -> f32 {
+        self.entries.last().map_or(0., |(time, _)| *time)
+    }
+
+    /// Moves the playhead, returning whether the grid has to be reset and re-simulated.
+    fn seek(&mut self, time: f32) -> bool {
+        let time = time.clamp(0., self.duration());
+        let rewind = time < self.time;
+        if rewind {
+            self.next = 0;
+        }
+        self.time = time;
+        rewind
+    }
+}
+
+/// Marks everything that makes up the timeline, which is laid out anew as the canvas changes
+/// shape.
+#[derive(Component)]
+struct TimelinePart;
+
+#[derive(Component)]
+struct TimelineFill;
+
+#[derive(Component)]
+struct TimelineLabel;
+
+/// The clickable area of the playback timeline, along the top of the canvas.
+fn timeline_rect(canvas: CanvasSize) -> Rect {
+    let width = canvas.size().x - TIMELINE_ROOM;
+    Rect::from_center_size(
+        CanvasAnchor::TOP_LEFT
+            .offset(8. + width / 2., -8.)
+            .position(canvas),
+        Vec2::new(width, TIMELINE_HEIGHT + 8.),
+    )
+}
+
+/// Formats seconds as `mm:ss`.
+pub fn clock(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn lay_out_timeline(
+    mut commands: Commands,
+    canvas: Res<CanvasSize>,
+    playback: Res<Playback>,
+    parts: Query<Entity, With<TimelinePart>>,
+) {
+    for entity in &parts {
+        commands.entity(entity).despawn();
+    }
+    let rect = timeline_rect(*canvas);
+    let (center, left, width) = (rect.center(), rect.min.x, rect.width());
+
+    // Timeline background (dark cyan)
+    commands.spawn((
+        TimelinePart,
+        Sprite {
+            color: Color::srgba(0.0, 0.2, 0.25, 0.9),
+            custom_size: Some(Vec2::new(width, TIMELINE_HEIGHT)),
+            ..default()
+        },
+        Transform::from_translation(center.extend(10.)),
+        PIXEL_PERFECT_LAYERS,
+    ));
+
+    // A tick above the timeline for every refinement
+    let duration = playback.duration().max(f32::EPSILON);
+    for (time, entry) in &playback.entries {
+        if let Entry::Action(GridAction::Refine { .. }) = entry {
+            commands.spawn((
+                TimelinePart,
+                Sprite {
+                    color: Color::srgba(0.0, 0.7, 0.8, 0.9),
+                    custom_size: Some(Vec2::new(1., 2.)),
+                    ..default()
+                },
+                Transform::from_xyz(
+                    left + width * time / duration,
+                    center.y + TIMELINE_HEIGHT / 2. + 1.,
+                    10.,
+                ),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        }
+    }
+
+    // Played part of the timeline (bright cyan)
+    commands.spawn((
+        TimelineFill,
+        TimelinePart,
+        Sprite {
+            color: Color::srgba(0.0, 0.9, 1.0, 0.9),
+            custom_size: Some(Vec2::new(0., TIMELINE_HEIGHT)),
+            ..default()
+        },
+        Transform::from_translation(center.extend(10.1)),
+        PIXEL_PERFECT_LAYERS,
+    ));
+
+    commands.spawn((
+        TimelineLabel,
+        TimelinePart,
+        Text2d::default(),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Transform::from_xyz(left + width + 52., center.y, 10.),
+        PIXEL_PERFECT_LAYERS,
+    ));
+}
+
+/// Space plays and pauses, the arrow keys skip, and the timeline can be clicked or dragged.
+fn scrub(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    pointer: CanvasCursor,
+    mut playback: ResMut<Playback>,
+    mut resets: EventWriter<ResetRefinement>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        if playback.time >= playback.duration() {
+            playback.seek(0.);
+            resets.write(ResetRefinement);
+        }
+        playback.playing = !playback.playing;
+    }
+
+    let mut target = None;
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        target = Some(playback.time - SEEK_STEP);
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        target = Some(playback.time + SEEK_STEP);
+    }
+    if keys.just_pressed(KeyCode::Home) {
+        target = Some(0.);
+    }
+    if buttons.pressed(MouseButton::Left) {
+        if let Some(cursor) = pointer.world() {
+            let rect = timeline_rect(pointer.canvas());
+            if rect.contains(cursor) {
+                let fraction = (cursor.x - rect.min.x) / rect.width();
+                target = Some(fraction * playback.duration());
+            }
+        }
+    }
+
+    if let Some(target) = target {
+        if playback.seek(target) {
+            resets.write(ResetRefinement);
+        }
+    }
+}
+
+/// Advances the playhead and applies every entry it has passed.
+///
+/// Opening a file resets the grid at the start of [`RefineSet::Apply`], so the actions after it
+/// wait for the next frame rather than being applied before the reset.
+fn play(
+    time: Res<Time>,
+    mut playback: ResMut<Playback>,
+    mut file: ResMut<ActiveFile>,
+    mut applied: EventWriter<ApplyAction>,
+    mut resets: EventWriter<ResetRefinement>,
+) {
+    if playback.playing {
+        let time = playback.time + time.delta_secs();
+        playback.seek(time);
+        if playback.time >= playback.duration() {
+            playback.playing = false;
+        }
+    }
+
+    let Playback {
+        entries,
+        next,
+        time,
+        ..
+    } = &mut *playback;
+    while let Some((at, entry)) = entries.get(*next) {
+        if *at > *time {
+            break;
+        }
+        *next += 1;
+        match entry {
+            Entry::Action(action) => {
+                applied.write(ApplyAction(*action));
+            }
+            Entry::File(opened) => {
+                *file = opened.clone();
+                resets.write(ResetRefinement);
+                break;
+            }
+        }
+    }
+}
+
+fn update_timeline(
+    canvas: Res<CanvasSize>,
+    playback: Res<Playback>,
+    mut fill: Single<(&mut Sprite, &mut Transform), With<TimelineFill>>,
+    mut label: Single<&mut Text2d, With<TimelineLabel>>,
+) {
+    if !playback.is_changed() && !canvas.is_changed() {
+        return;
+    }
+    let (sprite, transform) = &mut *fill;
+    let rect = timeline_rect(*canvas);
+    let width = rect.width() * (playback.time / playback.duration().max(f32::EPSILON));
+    sprite.custom_size = Some(Vec2::new(width, TIMELINE_HEIGHT));
+    transform.translation.x = rect.min.x + width / 2.;
+
+    label.0 = format!(
+        "{} {} / {}",
+        if playback.playing { "PLAY" } else { "PAUSE" },
+        clock(playback.time),
+        clock(playback.duration()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_what_is_recorded() {
+        let file = ActiveFile::default();
+        let select = GridAction::Select(URect::new(1, 2, 3, 4));
+        let refine = GridAction::Refine { bin: 1 };
+        let size = GridAction::Resize(GridSize::default());
+        let contents = format!(
+            "MDR-REPLAY {FORMAT_VERSION}\n0.000 {file}\n0.000 {size}\n1.500 {select}\n\n\
+             2.250 {refine}\n"
+        );
+        let playback = Playback::parse(&contents).unwrap();
+        assert_eq!(
+            playback.entries,
+            vec![
+                (
+                    0.,
+                    Entry::File(ActiveFile {
+                        styles: Vec::new(),
+                        ..file
+                    })
+                ),
+                (0., Entry::Action(size)),
+                (1.5, Entry::Action(select)),
+                (2.25, Entry::Action(refine)),
+            ]
+        );
+        assert_eq!(playback.duration(), 2.25);
+    }
+
+    #[test]
+    fn entries_are_played_in_time_order() {
+        let contents = "MDR-REPLAY 4\n2 CLEAR\n1 REFINE 0\n1 REFINE 1\n";
+        let playback = Playback::parse(contents).unwrap();
+        assert_eq!(
+            playback.entries,
+            vec![
+                (1., Entry::Action(GridAction::Refine { bin: 0 })),
+                (1., Entry::Action(GridAction::Refine { bin: 1 })),
+                (2., Entry::Action(GridAction::ClearSelection)),
+            ]
+        );
+    }
+
+    #[test]
+    fn older_file_lines_get_the_fields_added_since() {
+        let playback =
+            Playback::parse("MDR-REPLAY 2\n0 FILE 7 0.1 0.2 0.3 0.4 0.5 Siena\n").unwrap();
+        let Entry::File(file) = &playback.entries[0].1 else {
+            panic!("not a file");
+        };
+        assert_eq!(file.progress, vec![0.1, 0.2, 0.3, 0.4, 0.5]);
+        assert_eq!(file.limits.capacity, None);
+        assert_eq!(file.name, "Siena");
+        let playback = Playback::parse("MDR-REPLAY 3\n0 FILE 7 2 0.1 0.2 Siena\n").unwrap();
+        let Entry::File(file) = &playback.entries[0].1 else {
+            panic!("not a file");
+        };
+        assert_eq!(file.progress, vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_replay() {
+        for contents in [
+            "",
+            "MDR-REPLAY 99\n",
+            "REPLAY 4\n",
+            "MDR-REPLAY 4\nsoon CLEAR\n",
+            "MDR-REPLAY 4\n1 JUMP\n",
+        ] {
+            assert!(Playback::parse(contents).is_err(), "{contents:?}");
+        }
+    }
+}
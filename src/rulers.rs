@@ -0,0 +1,199 @@
+//! Assist overlay: faint gridlines between the numbers, and coordinate rulers along the edges of
+//! the canvas so that clusters are easy to point out ("column 12, rows 3 to 5").
+//!
+//! Toggled with G or from the settings. The rulers stay on the edges of the view and follow the
+//! grid camera as it pans, so their labels always line up with the columns and rows on screen.
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::{CanvasSize, GridCamera, InGameCamera, GRID_LAYERS, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    grid::{Cell, GridSize, NUMBER_SPACING},
+    state::AppState,
+    zoom::Zoom,
+};
+
+/// Thickness of the ruler strips along the edges.
+const RULER_SIZE: f32 = 10.;
+
+const LINE_COLOR: Color = Color::srgba(0.0, 0.7, 0.8, 0.15);
+const RULER_COLOR: Color = Color::srgba(0.0, 0.08, 0.1, 0.85);
+const LABEL_COLOR: Color = Color::srgb(0.0, 0.7, 0.8);
+
+pub struct RulersPlugin;
+
+impl Plugin for RulersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, toggle_key.run_if(in_state(AppState::Refining)))
+            .add_systems(
+                PostUpdate,
+                (
+                    show_rulers.run_if(resource_changed::<Config>.or(resource_changed::<GridSize>)),
+                    follow_camera,
+                )
+                    .chain()
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Marks everything the overlay spawns.
+#[derive(Component)]
+struct Ruler;
+
+/// An edge of the view with a ruler on it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Strip {
+    /// Along the top, labelling columns.
+    Columns,
+    /// Along the left, labelling rows.
+    Rows,
+}
+
+/// Something kept on a ruler: its background, or the label of a column or row.
+#[derive(Component, Clone, Copy)]
+struct OnRuler {
+    strip: Strip,
+    label: Option<u32>,
+}
+
+fn toggle_key(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<Config>) {
+    if keys.just_pressed(KeyCode::KeyG) {
+        config.accessibility.gridlines ^= true;
+    }
+}
+
+/// Shows or hides the overlay as the config says, laying it out anew as the grid is resized.
+fn show_rulers(
+    mut commands: Commands,
+    config: Res<Config>,
+    size: Res<GridSize>,
+    rulers: Query<Entity, With<Ruler>>,
+) {
+    let shown = !rulers.is_empty();
+    if config.accessibility.gridlines == shown && !size.is_changed() {
+        return;
+    }
+    for entity in &rulers {
+        commands.entity(entity).despawn();
+    }
+    if !config.accessibility.gridlines {
+        return;
+    }
+
+    // Gridlines run between cells, so they sit half a spacing before each one.
+    let origin = Cell { col: 0, row: 0 }.position() - NUMBER_SPACING / 2.;
+    let width = size.columns as f32 * NUMBER_SPACING;
+    let height = size.rows as f32 * NUMBER_SPACING;
+    for col in 0..=size.columns {
+        commands.spawn((
+            Ruler,
+            Sprite {
+                color: LINE_COLOR,
+                custom_size: Some(Vec2::new(1., height)),
+                ..default()
+            },
+            Transform::from_xyz(
+                origin.x + col as f32 * NUMBER_SPACING,
+                origin.y + height / 2.,
+                -0.5,
+            ),
+            GRID_LAYERS,
+        ));
+    }
+    for row in 0..=size.rows {
+        commands.spawn((
+            Ruler,
+            Sprite {
+                color: LINE_COLOR,
+                custom_size: Some(Vec2::new(width, 1.)),
+                ..default()
+            },
+            Transform::from_xyz(
+                origin.x + width / 2.,
+                origin.y + row as f32 * NUMBER_SPACING,
+                -0.5,
+            ),
+            GRID_LAYERS,
+        ));
+    }
+
+    for strip in [Strip::Columns, Strip::Rows] {
+        commands.spawn((
+            Ruler,
+            OnRuler { strip, label: None },
+            Sprite {
+                color: RULER_COLOR,
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 15.),
+            PIXEL_PERFECT_LAYERS,
+        ));
+    }
+    let labels = (0..size.columns)
+        .map(|index| (Strip::Columns, index))
+        .chain((0..size.rows).map(|index| (Strip::Rows, index)));
+    for (strip, index) in labels {
+        commands.spawn((
+            Ruler,
+            OnRuler {
+                strip,
+                label: Some(index),
+            },
+            Text2d::new(index.to_string()),
+            TextFont {
+                font_size: 7.0,
+                ..default()
+            },
+            TextColor(LABEL_COLOR),
+            Transform::from_xyz(0., 0., 15.1),
+            PIXEL_PERFECT_LAYERS,
+        ));
+    }
+}
+
+/// Keeps the strips on the edges of the view and as long as them, and their labels in line with
+/// the grid.
+fn follow_camera(
+    canvas: Res<CanvasSize>,
+    camera: Single<&Transform, With<InGameCamera>>,
+    grid_camera: Single<Ref<Transform>, (With<GridCamera>, Without<InGameCamera>)>,
+    zoom: Res<Zoom>,
+    added: Query<(), Added<Ruler>>,
+    mut rulers: Query<(&OnRuler, &mut Transform, Option<&mut Sprite>), Without<Camera>>,
+) {
+    if !grid_camera.is_changed() && !canvas.is_changed() && !zoom.is_changed() && added.is_empty() {
+        return;
+    }
+    let center = camera.translation.truncate();
+    let grid_center = grid_camera.translation.truncate();
+    let half = canvas.half();
+    let top = center.y + half.y - RULER_SIZE / 2.;
+    let left = center.x - half.x + RULER_SIZE / 2.;
+
+    for (on_ruler, mut transform, sprite) in &mut rulers {
+        if let Some(mut sprite) = sprite {
+            sprite.custom_size = Some(match on_ruler.strip {
+                Strip::Columns => Vec2::new(canvas.size().x, RULER_SIZE),
+                Strip::Rows => Vec2::new(RULER_SIZE, canvas.size().y),
+            });
+        }
+        // Labels sit in line with their column or row, backgrounds in the middle of the view.
+        let along = match on_ruler.label {
+            Some(index) => {
+                let cell = Cell {
+                    col: index,
+                    row: index,
+                };
+                center + (cell.position() - grid_center) * zoom.scale()
+            }
+            None => center,
+        };
+        let position = match on_ruler.strip {
+            Strip::Columns => Vec2::new(along.x, top),
+            Strip::Rows => Vec2::new(left, along.y),
+        };
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
@@ -0,0 +1,714 @@
+//! The settings screen, reached from the main menu and the pause menu.
+//!
+//! Settings are grouped in tabs, switched with Tab and Shift+Tab or by clicking their titles.
+//! Left and Right (or choosing a setting) change the highlighted value, which edits the
+//! [`Config`] resource directly so changes apply immediately. The config is saved when the
+//! screen is left.
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::MAX_BIN_COUNT,
+    canvas::{CanvasCursor, CanvasFill, PIXEL_PERFECT_LAYERS, SUPERSAMPLING},
+    config::{
+        save_config, CanvasPreset, Config, ConfigPath, Difficulty, DigitRenderer, NightShift,
+        ScaleMode,
+    },
+    grid::GridSize,
+    speed::TIME_SCALES,
+    state::AppState,
+    toast::Toast,
+    transition::{TransitionEffect, TransitionTo},
+    ui::{spawn_menu, KeepsTab, Menu, MenuChosen, MenuEntry},
+};
+
+/// Horizontal distance between tab titles.
+const TAB_SPACING: f32 = 84.;
+
+/// Height of the row of tab titles.
+const TAB_Y: f32 = 64.;
+
+/// Step of volume settings.
+const VOLUME_STEP: f32 = 0.1;
+
+/// Choices of the wellness session interval, in refinements.
+const WELLNESS_INTERVALS: [u32; 5] = [0, 10, 25, 50, 100];
+
+/// Choices of the break reminder, in minutes.
+const BREAK_REMINDERS: [u32; 5] = [0, 30, 45, 60, 90];
+
+/// Step of the grid size setting, in columns and rows.
+const GRID_SIZE_STEP: f32 = 10.;
+
+/// Step and maximum of the film grain setting.
+const GRAIN_STEP: f32 = 0.02;
+const MAX_GRAIN: f32 = 0.2;
+
+/// Choices of the night shift's color temperature, in kelvin, from the mildest.
+const WARMTHS: [u32; 5] = [5000, 4200, 3400, 2700, 1900];
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsScreen>()
+            .add_systems(OnEnter(AppState::Settings), open_settings)
+            .add_systems(OnExit(AppState::Settings), close_settings)
+            .add_systems(
+                Update,
+                (
+                    (switch_tabs, adjust, choose),
+                    rebuild
+                        .run_if(resource_changed::<Config>.or(resource_changed::<SettingsScreen>)),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Settings)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tab {
+    Video,
+    Audio,
+    Input,
+    Gameplay,
+    Widgets,
+    Accessibility,
+}
+
+impl Tab {
+    const ALL: [Tab; 6] = [
+        Tab::Video,
+        Tab::Audio,
+        Tab::Input,
+        Tab::Gameplay,
+        Tab::Widgets,
+        Tab::Accessibility,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Tab::Video => "Video",
+            Tab::Audio => "Audio",
+            Tab::Input => "Input",
+            Tab::Gameplay => "Gameplay",
+            Tab::Widgets => "Widgets",
+            Tab::Accessibility => "Accessibility",
+        }
+    }
+
+    fn settings(self) -> &'static [Setting] {
+        match self {
+            Tab::Video => &[
+                Setting::Canvas,
+                Setting::ScaleMode,
+                Setting::Fullscreen,
+                Setting::Vsync,
+                Setting::BootIntro,
+                Setting::FilmGrain,
+                Setting::Supersampling,
+                Setting::Digits,
+                Setting::NightShift,
+                Setting::Warmth,
+            ],
+            Tab::Audio => &[
+                Setting::MasterVolume,
+                Setting::EffectsVolume,
+                Setting::SoundscapeVolume,
+                Setting::Mute,
+                Setting::MuteUnfocused,
+            ],
+            Tab::Input => &[Setting::BinHotkeys, Setting::RightClickClears],
+            Tab::Gameplay => &[
+                Setting::Difficulty,
+                Setting::Hints,
+                Setting::Wellness,
+                Setting::BreakReminder,
+                Setting::Bins,
+                Setting::GridSize,
+                Setting::WorkTimer,
+                Setting::GameSpeed,
+                Setting::Heatmap,
+            ],
+            Tab::Widgets => &[
+                Setting::Clock,
+                Setting::ClockFormat,
+                Setting::BlinkingColon,
+                Setting::ClockDate,
+                Setting::NotifyBinFull,
+                Setting::NotifyFileComplete,
+                Setting::TrayIcon,
+            ],
+            Tab::Accessibility => &[
+                Setting::ReducedMotion,
+                Setting::HighContrast,
+                Setting::Gridlines,
+                Setting::Tooltips,
+                Setting::Announcements,
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    Canvas,
+    ScaleMode,
+    Fullscreen,
+    Vsync,
+    BootIntro,
+    FilmGrain,
+    Supersampling,
+    Digits,
+    NightShift,
+    Warmth,
+    MasterVolume,
+    EffectsVolume,
+    SoundscapeVolume,
+    Mute,
+    MuteUnfocused,
+    BinHotkeys,
+    RightClickClears,
+    Difficulty,
+    Hints,
+    Wellness,
+    BreakReminder,
+    Bins,
+    GridSize,
+    WorkTimer,
+    GameSpeed,
+    Heatmap,
+    Clock,
+    ClockFormat,
+    BlinkingColon,
+    ClockDate,
+    NotifyBinFull,
+    NotifyFileComplete,
+    TrayIcon,
+    ReducedMotion,
+    HighContrast,
+    Gridlines,
+    Tooltips,
+    Announcements,
+}
+
+impl Setting {
+    fn name(self) -> &'static str {
+        match self {
+            Setting::Canvas => "Canvas",
+            Setting::ScaleMode => "Scale mode",
+            Setting::Fullscreen => "Fullscreen",
+            Setting::Vsync => "VSync",
+            Setting::BootIntro => "Boot intro",
+            Setting::FilmGrain => "Film grain",
+            Setting::Supersampling => "Supersampling",
+            Setting::Digits => "Digits",
+            Setting::NightShift => "Night shift",
+            Setting::Warmth => "Warmth",
+            Setting::MasterVolume => "Volume",
+            Setting::EffectsVolume => "Effects",
+            Setting::SoundscapeVolume => "Soundscape",
+            Setting::Mute => "Mute",
+            Setting::MuteUnfocused => "Mute in background",
+            Setting::BinHotkeys => "Bin hotkeys",
+            Setting::RightClickClears => "Right click clears",
+            Setting::Difficulty => "Difficulty",
+            Setting::GameSpeed => "Game speed",
+            Setting::Heatmap => "Heatmap",
+            Setting::Hints => "Hints",
+            Setting::Wellness => "Wellness sessions",
+            Setting::BreakReminder => "Break reminder",
+            Setting::Bins => "Bins in new files",
+            Setting::GridSize => "Grid size",
+            Setting::WorkTimer => "Work timer",
+            Setting::Clock => "Clock",
+            Setting::ClockFormat => "Clock format",
+            Setting::BlinkingColon => "Blinking colon",
+            Setting::ClockDate => "Date",
+            Setting::NotifyBinFull => "Notify full bins",
+            Setting::NotifyFileComplete => "Notify done files",
+            Setting::TrayIcon => "Tray icon",
+            Setting::ReducedMotion => "Reduced motion",
+            Setting::HighContrast => "High contrast",
+            Setting::Gridlines => "Gridlines",
+            Setting::Tooltips => "Tooltips",
+            Setting::Announcements => "Announcements",
+        }
+    }
+
+    fn value(self, config: &Config) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        match self {
+            Setting::Canvas => {
+                let size = config.video.canvas.size();
+                format!("{}x{}", size.x, size.y)
+            }
+            Setting::ScaleMode => match config.video.scale_mode {
+                ScaleMode::Integer => "Integer".to_string(),
+                ScaleMode::Fractional => "Fractional".to_string(),
+            },
+            Setting::Fullscreen => on_off(config.video.fullscreen),
+            Setting::Vsync => on_off(config.video.vsync),
+            Setting::BootIntro => on_off(config.video.boot_intro),
+            Setting::FilmGrain if config.video.film_grain <= 0. => "Off".to_string(),
+            Setting::FilmGrain => format!("{:.0}%", config.video.film_grain * 100.),
+            Setting::Supersampling if config.video.supersampling <= 1 => "Off".to_string(),
+            Setting::Supersampling => format!("{}x", config.video.supersampling),
+            Setting::Digits => format!("{:?}", config.video.digits),
+            Setting::NightShift => format!("{:?}", config.video.night_shift.mode),
+            Setting::Warmth => format!("{}K", config.video.night_shift.temperature),
+            Setting::MasterVolume => format!("{:.0}%", config.audio.master_volume * 100.),
+            Setting::EffectsVolume => format!("{:.0}%", config.audio.effects_volume * 100.),
+            Setting::SoundscapeVolume if config.audio.soundscape_volume == 0. => "Off".to_string(),
+            Setting::SoundscapeVolume => {
+                format!("{:.0}%", config.audio.soundscape_volume * 100.)
+            }
+            Setting::Mute => on_off(config.audio.muted),
+            Setting::MuteUnfocused => on_off(config.audio.mute_unfocused),
+            Setting::BinHotkeys => on_off(config.input.bin_hotkeys),
+            Setting::RightClickClears => on_off(config.input.right_click_clears),
+            Setting::Difficulty => format!("{:?}", config.gameplay.difficulty),
+            Setting::GameSpeed => format!("{}x", config.gameplay.time_scale),
+            Setting::Heatmap => on_off(config.gameplay.heatmap),
+            Setting::Hints => on_off(config.gameplay.hints),
+            Setting::Bins => config.gameplay.bins.to_string(),
+            Setting::GridSize => {
+                let size = config.gameplay.grid.clamped();
+                format!("{}x{}", size.columns, size.rows)
+            }
+            Setting::Wellness => match config.gameplay.wellness_interval {
+                0 => "Off".to_string(),
+                interval => format!("Every {interval}"),
+            },
+            Setting::BreakReminder => match config.gameplay.break_reminder_minutes {
+                0 => "Off".to_string(),
+                minutes => format!("After {minutes} min"),
+            },
+            Setting::WorkTimer => on_off(config.pomodoro.enabled),
+            Setting::Clock => on_off(config.clock.enabled),
+            Setting::ClockFormat if config.clock.twenty_four_hour => "24-hour".to_string(),
+            Setting::ClockFormat => "12-hour".to_string(),
+            Setting::BlinkingColon => on_off(config.clock.blinking_colon),
+            Setting::ClockDate => on_off(config.clock.date),
+            Setting::NotifyBinFull => on_off(config.notifications.bin_full),
+            Setting::NotifyFileComplete => on_off(config.notifications.file_complete),
+            Setting::TrayIcon => on_off(config.tray.enabled),
+            Setting::ReducedMotion => on_off(config.accessibility.reduced_motion),
+            Setting::HighContrast => on_off(config.accessibility.high_contrast),
+            Setting::Gridlines => on_off(config.accessibility.gridlines),
+            Setting::Tooltips => on_off(config.accessibility.tooltips),
+            Setting::Announcements => on_off(config.accessibility.announcements),
+        }
+    }
+
+    /// Changes the setting by one step; toggles and two-way choices ignore the direction.
+    fn adjust(self, config: &mut Config, step: f32) {
+        match self {
+            Setting::Canvas => {
+                const ALL: [CanvasPreset; 5] = [
+                    CanvasPreset::Wide,
+                    CanvasPreset::Crt,
+                    CanvasPreset::FiveFour,
+                    CanvasPreset::Ultrawide,
+                    CanvasPreset::Portrait,
+                ];
+                let index = ALL
+                    .iter()
+                    .position(|&canvas| canvas == config.video.canvas)
+                    .unwrap_or_default();
+                let index = if step < 0. {
+                    index + ALL.len() - 1
+                } else {
+                    index + 1
+                };
+                config.video.canvas = ALL[index % ALL.len()];
+            }
+            Setting::ScaleMode => {
+                config.video.scale_mode = match config.video.scale_mode {
+                    ScaleMode::Integer => ScaleMode::Fractional,
+                    ScaleMode::Fractional => ScaleMode::Integer,
+                }
+            }
+            Setting::Fullscreen => config.video.fullscreen ^= true,
+            Setting::Vsync => config.video.vsync ^= true,
+            Setting::BootIntro => config.video.boot_intro ^= true,
+            Setting::FilmGrain => {
+                let grain = config.video.film_grain + step * GRAIN_STEP;
+                config.video.film_grain = (grain / GRAIN_STEP)
+                    .round()
+                    .clamp(0., MAX_GRAIN / GRAIN_STEP)
+                    * GRAIN_STEP;
+            }
+            Setting::Supersampling => {
+                let index = SUPERSAMPLING
+                    .iter()
+                    .position(|&factor| factor >= config.video.supersampling)
+                    .unwrap_or_default();
+                let count = SUPERSAMPLING.len();
+                let index = if step < 0. {
+                    index + count - 1
+                } else {
+                    index + 1
+                };
+                config.video.supersampling = SUPERSAMPLING[index % count];
+            }
+            Setting::Digits => {
+                const ALL: [DigitRenderer; 3] = [
+                    DigitRenderer::Auto,
+                    DigitRenderer::Field,
+                    DigitRenderer::Text,
+                ];
+                let index = ALL
+                    .iter()
+                    .position(|&digits| digits == config.video.digits)
+                    .unwrap_or_default();
+                let index = if step < 0. {
+                    index + ALL.len() - 1
+                } else {
+                    index + 1
+                };
+                config.video.digits = ALL[index % ALL.len()];
+            }
+            Setting::NightShift => {
+                const ALL: [NightShift; 3] =
+                    [NightShift::Off, NightShift::On, NightShift::Scheduled];
+                let index = ALL
+                    .iter()
+                    .position(|&mode| mode == config.video.night_shift.mode)
+                    .unwrap_or_default();
+                let index = if step < 0. {
+                    index + ALL.len() - 1
+                } else {
+                    index + 1
+                };
+                config.video.night_shift.mode = ALL[index % ALL.len()];
+            }
+            Setting::Warmth => {
+                let night_shift = &mut config.video.night_shift;
+                let index = WARMTHS
+                    .iter()
+                    .position(|&temperature| temperature <= night_shift.temperature)
+                    .unwrap_or_default();
+                let count = WARMTHS.len();
+                let index = if step < 0. {
+                    index + count - 1
+                } else {
+                    index + 1
+                };
+                night_shift.temperature = WARMTHS[index % count];
+            }
+            Setting::MasterVolume => step_volume(&mut config.audio.master_volume, step),
+            Setting::EffectsVolume => step_volume(&mut config.audio.effects_volume, step),
+            Setting::SoundscapeVolume => step_volume(&mut config.audio.soundscape_volume, step),
+            Setting::Mute => config.audio.muted ^= true,
+            Setting::MuteUnfocused => config.audio.mute_unfocused ^= true,
+            Setting::BinHotkeys => config.input.bin_hotkeys ^= true,
+            Setting::RightClickClears => config.input.right_click_clears ^= true,
+            Setting::Difficulty => {
+                const ALL: [Difficulty; 3] =
+                    [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+                let index = ALL
+                    .iter()
+                    .position(|&difficulty| difficulty == config.gameplay.difficulty)
+                    .unwrap_or_default();
+                let index = if step < 0. {
+                    index + ALL.len() - 1
+                } else {
+                    index + 1
+                };
+                config.gameplay.difficulty = ALL[index % ALL.len()];
+            }
+            Setting::GameSpeed => {
+                let gameplay = &mut config.gameplay;
+                let index = TIME_SCALES
+                    .iter()
+                    .position(|&scale| scale >= gameplay.time_scale)
+                    .unwrap_or_default();
+                let count = TIME_SCALES.len();
+                let index = if step < 0. {
+                    index + count - 1
+                } else {
+                    index + 1
+                };
+                gameplay.time_scale = TIME_SCALES[index % count];
+            }
+            Setting::Hints => config.gameplay.hints ^= true,
+            Setting::Bins => {
+                let bins = config.gameplay.bins as f32 + step;
+                config.gameplay.bins = bins.clamp(1., MAX_BIN_COUNT as f32) as usize;
+            }
+            Setting::GridSize => {
+                // Both ways at once, so a grid keeps its shape as it grows and shrinks.
+                let step = |cells: u32| {
+                    let cells = (cells as f32 / GRID_SIZE_STEP).round() + step.signum();
+                    (cells * GRID_SIZE_STEP) as u32
+                };
+                let size = config.gameplay.grid.clamped();
+                let resized = GridSize {
+                    columns: step(size.columns),
+                    rows: step(size.rows),
+                };
+                // Unless one side would go past the ends, which both stay at.
+                if resized.clamped() == resized {
+                    config.gameplay.grid = resized;
+                }
+            }
+            Setting::Wellness => {
+                let index = WELLNESS_INTERVALS
+                    .iter()
+                    .position(|&interval| interval >= config.gameplay.wellness_interval)
+                    .unwrap_or_default();
+                let count = WELLNESS_INTERVALS.len();
+                let index = if step < 0. {
+                    index + count - 1
+                } else {
+                    index + 1
+                };
+                config.gameplay.wellness_interval = WELLNESS_INTERVALS[index % count];
+            }
+            Setting::BreakReminder => {
+                let index = BREAK_REMINDERS
+                    .iter()
+                    .position(|&minutes| minutes >= config.gameplay.break_reminder_minutes)
+                    .unwrap_or_default();
+                let count = BREAK_REMINDERS.len();
+                let index = if step < 0. {
+                    index + count - 1
+                } else {
+                    index + 1
+                };
+                config.gameplay.break_reminder_minutes = BREAK_REMINDERS[index % count];
+            }
+            Setting::WorkTimer => config.pomodoro.enabled ^= true,
+            Setting::Clock => config.clock.enabled ^= true,
+            Setting::ClockFormat => config.clock.twenty_four_hour ^= true,
+            Setting::BlinkingColon => config.clock.blinking_colon ^= true,
+            Setting::ClockDate => config.clock.date ^= true,
+            Setting::NotifyBinFull => config.notifications.bin_full ^= true,
+            Setting::NotifyFileComplete => config.notifications.file_complete ^= true,
+            Setting::TrayIcon => config.tray.enabled ^= true,
+            Setting::ReducedMotion => config.accessibility.reduced_motion ^= true,
+            Setting::HighContrast => config.accessibility.high_contrast ^= true,
+            Setting::Gridlines => config.accessibility.gridlines ^= true,
+            Setting::Heatmap => config.gameplay.heatmap ^= true,
+            Setting::Tooltips => config.accessibility.tooltips ^= true,
+            Setting::Announcements => config.accessibility.announcements ^= true,
+        }
+    }
+}
+
+/// Turns a volume up or down a step, keeping it to whole steps from 0 to 1.
+fn step_volume(volume: &mut f32, step: f32) {
+    *volume = ((*volume + step * VOLUME_STEP) * 10.)
+        .round()
+        .clamp(0., 10.)
+        / 10.;
+}
+
+/// Which tab is open and which of its rows is highlighted.
+#[derive(Resource, Default)]
+struct SettingsScreen {
+    tab: usize,
+    selected: usize,
+    /// The state the screen was opened from, and goes back to.
+    back: AppState,
+}
+
+impl SettingsScreen {
+    fn tab(&self) -> Tab {
+        Tab::ALL[self.tab]
+    }
+
+    /// The highlighted setting, or `None` when "Back" is highlighted.
+    fn setting(&self) -> Option<Setting> {
+        self.tab().settings().get(self.selected).copied()
+    }
+}
+
+/// Marks everything that is rebuilt when a setting or the tab changes.
+#[derive(Component)]
+struct SettingsView;
+
+#[derive(Component)]
+struct SettingsMenu;
+
+#[derive(Component)]
+struct TabTitle(usize);
+
+fn open_settings(
+    mut commands: Commands,
+    mut transitions: EventReader<StateTransitionEvent<AppState>>,
+    mut screen: ResMut<SettingsScreen>,
+) {
+    *screen = SettingsScreen {
+        back: transitions
+            .read()
+            .filter_map(|transition| transition.exited)
+            .last()
+            .unwrap_or(AppState::Paused),
+        ..default()
+    };
+
+    // Dim the grid behind the settings
+    commands.spawn((
+        Sprite {
+            color: Color::srgba(0.0, 0.0, 0.0, 0.6),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 19.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Settings),
+    ));
+}
+
+fn close_settings(config: Res<Config>, path: Res<ConfigPath>, mut toasts: EventWriter<Toast>) {
+    toasts.write(if save_config(&config, &path) {
+        Toast::success("Settings saved")
+    } else {
+        Toast::error("Could not save settings")
+    });
+}
+
+fn tab_x(index: usize) -> f32 {
+    (index as f32 - (Tab::ALL.len() as f32 - 1.) / 2.) * TAB_SPACING
+}
+
+fn rebuild(
+    mut commands: Commands,
+    config: Res<Config>,
+    screen: Res<SettingsScreen>,
+    views: Query<Entity, With<SettingsView>>,
+) {
+    for entity in &views {
+        commands.entity(entity).despawn();
+    }
+
+    for (index, tab) in Tab::ALL.into_iter().enumerate() {
+        let color = if index == screen.tab {
+            Color::srgb(0.0, 0.9, 1.0)
+        } else {
+            Color::srgb(0.5, 0.5, 0.5)
+        };
+        commands.spawn((
+            TabTitle(index),
+            SettingsView,
+            Text2d::new(tab.label()),
+            TextFont {
+                font_size: 10.0,
+                ..default()
+            },
+            TextColor(color),
+            Transform::from_xyz(tab_x(index), TAB_Y, 20.),
+            PIXEL_PERFECT_LAYERS,
+            StateScoped(AppState::Settings),
+        ));
+    }
+
+    let labels: Vec<String> = screen
+        .tab()
+        .settings()
+        .iter()
+        .map(|setting| format!("{}: {}", setting.name(), setting.value(&config)))
+        .chain(["Back".to_string()])
+        .collect();
+    let entries: Vec<MenuEntry> = labels.iter().map(|label| MenuEntry::new(label)).collect();
+    spawn_menu(
+        &mut commands,
+        "SETTINGS",
+        &entries,
+        screen.selected,
+        Vec3::new(0., -8., 20.),
+        (
+            SettingsMenu,
+            SettingsView,
+            KeepsTab,
+            StateScoped(AppState::Settings),
+        ),
+    );
+}
+
+/// Tab and Shift+Tab cycle through the tabs, and tab titles can be clicked.
+fn switch_tabs(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    pointer: CanvasCursor,
+    titles: Query<(&TabTitle, &GlobalTransform)>,
+    mut screen: ResMut<SettingsScreen>,
+) {
+    let count = Tab::ALL.len();
+    let mut tab = screen.tab;
+    if keys.just_pressed(KeyCode::Tab) {
+        let back = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        tab = (if back { tab + count - 1 } else { tab + 1 }) % count;
+    }
+    if buttons.just_pressed(MouseButton::Left) {
+        if let Some(cursor) = pointer.world() {
+            for (title, transform) in &titles {
+                let area = Rect::from_center_size(
+                    transform.translation().truncate(),
+                    Vec2::new(TAB_SPACING, 12.),
+                );
+                if area.contains(cursor) {
+                    tab = title.0;
+                }
+            }
+        }
+    }
+    if tab != screen.tab {
+        screen.tab = tab;
+        screen.selected = 0;
+    }
+}
+
+/// Left and Right change the highlighted setting; Esc goes back to where the screen was opened.
+fn adjust(
+    keys: Res<ButtonInput<KeyCode>>,
+    menu: Option<Single<&Menu, With<SettingsMenu>>>,
+    mut screen: ResMut<SettingsScreen>,
+    mut config: ResMut<Config>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    // Follow the menu's highlight without rebuilding the screen for it.
+    if let Some(menu) = menu {
+        screen.bypass_change_detection().selected = menu.selected;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        transitions.write(TransitionTo::new(screen.back, TransitionEffect::Wipe));
+        return;
+    }
+
+    let step = if keys.any_just_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+        -1.
+    } else if keys.any_just_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+        1.
+    } else {
+        return;
+    };
+    if let Some(setting) = screen.setting() {
+        setting.adjust(&mut config, step);
+    }
+}
+
+fn choose(
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<(), With<SettingsMenu>>,
+    mut screen: ResMut<SettingsScreen>,
+    mut config: ResMut<Config>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    for event in chosen.read() {
+        if !menus.contains(event.menu) {
+            continue;
+        }
+        screen.bypass_change_detection().selected = event.item;
+        match screen.setting() {
+            Some(setting) => setting.adjust(&mut config, 1.),
+            None => {
+                transitions.write(TransitionTo::new(screen.back, TransitionEffect::Wipe));
+            }
+        }
+    }
+}
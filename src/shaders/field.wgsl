@@ -0,0 +1,69 @@
+// The numbers of the grid, all drawn in one pass.
+//
+// Every fragment looks at the cells around it and draws the glyph of whichever number covers
+// it, from a map holding each number's offset from its cell, scale and glyph, and another
+// holding its color. Glyphs come from an atlas of them laid out side by side. Numbers
+// drift around their cells on their own, each on a path of its own, as they would as text.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct Field {
+    // Grid position of the first cell, the spacing between cells, and the size of a glyph's
+    // pixel at a scale of 1
+    grid: vec4<f32>,
+    // Size of a glyph in the atlas, in pixels
+    glyph: vec4<f32>,
+    // How far numbers have drifted along their paths, and how far from their cells they drift
+    drift: vec4<f32>,
+}
+
+@group(2) @binding(0) var<uniform> field: Field;
+// Per cell, row by row from the bottom: offset from the cell, scale, and glyph, or a negative
+// glyph for none
+@group(2) @binding(1) var numbers: texture_2d<f32>;
+// Per cell, the linear color of the number
+@group(2) @binding(2) var colors: texture_2d<f32>;
+@group(2) @binding(3) var glyphs: texture_2d<f32>;
+
+// Where the number of a cell has drifted to from it, a whole pixel at a time.
+fn drift(cell: vec2<i32>) -> vec2<f32> {
+    // As the grid model sets it.
+    let phase = f32(cell.x * 31 + cell.y * 17);
+    let t = field.drift.x;
+    return round(vec2<f32>(sin(t + phase), cos(t * 0.8 + phase * 1.3)) * field.drift.y);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(numbers));
+    let position = in.world_position.xy;
+    let nearest = vec2<i32>(round((position - field.grid.xy) / field.grid.z));
+    let glyph_size = field.glyph.xy;
+
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let cell = nearest + vec2<i32>(dx, dy);
+            if any(cell < vec2<i32>(0)) || any(cell >= size) {
+                continue;
+            }
+            let number = textureLoad(numbers, cell, 0);
+            if number.w < 0.0 {
+                continue;
+            }
+            let centre = field.grid.xy + vec2<f32>(cell) * field.grid.z + number.xy + drift(cell);
+            // Pixel of the glyph under the fragment, counted from its top left.
+            let from_centre = (position - centre) / (field.grid.w * number.z);
+            let pixel = vec2<f32>(from_centre.x, -from_centre.y) + glyph_size / 2.0;
+            if any(pixel < vec2<f32>(0.0)) || any(pixel >= glyph_size) {
+                continue;
+            }
+            let atlas = vec2<i32>(floor(pixel)) + vec2<i32>(i32(number.w) * i32(glyph_size.x), 0);
+            let coverage = textureLoad(glyphs, atlas, 0).a;
+            if coverage > 0.0 {
+                let color = textureLoad(colors, cell, 0);
+                return vec4<f32>(color.rgb, color.a * coverage);
+            }
+        }
+    }
+    discard;
+}
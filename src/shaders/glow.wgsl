@@ -0,0 +1,57 @@
+// Soft glow behind the numbers near the cursor.
+//
+// Every fragment looks at the cells around it and adds up a glow for each of them that falls
+// off with the distance from the cell's number, and that is brighter the closer the number is
+// to the cursor. Unfound scary numbers, marked in the scary map, glow brighter still once hints
+// have started.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct Glow {
+    // Grid position of the cursor, how far from it numbers glow, and how much brighter scary
+    // numbers do
+    cursor: vec4<f32>,
+    // Grid position of the first cell, and the spacing between cells
+    grid: vec4<f32>,
+    color: vec4<f32>,
+}
+
+@group(2) @binding(0) var<uniform> glow: Glow;
+@group(2) @binding(1) var scary_map: texture_2d<f32>;
+
+// How bright a number near the cursor glows before scariness is added
+const HOVER: f32 = 0.35;
+
+// Radius of the glow around a number, relative to the cell spacing
+const RADIUS: f32 = 0.35;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let reach = glow.cursor.z;
+    if reach <= 0.0 {
+        discard;
+    }
+    let size = vec2<i32>(textureDimensions(scary_map));
+    let position = in.world_position.xy;
+    let nearest = vec2<i32>(round((position - glow.grid.xy) / glow.grid.z));
+
+    var total = 0.0;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let cell = nearest + vec2<i32>(dx, dy);
+            if any(cell < vec2<i32>(0)) || any(cell >= size) {
+                continue;
+            }
+            let centre = glow.grid.xy + vec2<f32>(cell) * glow.grid.z;
+            let near = 1.0 - distance(centre, glow.cursor.xy) / reach;
+            if near <= 0.0 {
+                continue;
+            }
+            // The map starts at the top, the grid at the bottom.
+            let scary = textureLoad(scary_map, vec2<i32>(cell.x, size.y - 1 - cell.y), 0).r;
+            let falloff = distance(position, centre) / (glow.grid.z * RADIUS);
+            total += exp(-falloff * falloff) * near * (HOVER + scary * glow.cursor.w);
+        }
+    }
+    return vec4<f32>(glow.color.rgb, clamp(total, 0.0, 1.0) * glow.color.a);
+}
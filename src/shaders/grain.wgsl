@@ -0,0 +1,34 @@
+// Animated film grain over the pixel-perfect canvas.
+//
+// Every canvas pixel gets its own noise value, re-rolled a few dozen times a second, which
+// lightens or darkens it slightly.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct Grain {
+    // Opacity of the strongest grain
+    intensity: f32,
+    // Seconds since startup
+    time: f32,
+    // Size of the canvas in pixels
+    resolution: vec2<f32>,
+}
+
+@group(2) @binding(0) var<uniform> grain: Grain;
+
+const FRAMES_PER_SECOND: f32 = 24.0;
+
+fn hash(p: vec2<f32>) -> f32 {
+    var q = fract(p * vec2<f32>(123.34, 456.21));
+    q += dot(q, q + 45.32);
+    return fract(q.x * q.y);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = floor(in.uv * grain.resolution);
+    let frame = floor(grain.time * FRAMES_PER_SECOND);
+    let noise = hash(pixel + frame * vec2<f32>(17.0, 59.0));
+    let shade = select(0.0, 1.0, noise > 0.5);
+    return vec4<f32>(vec3<f32>(shade), abs(noise - 0.5) * 2.0 * grain.intensity);
+}
@@ -0,0 +1,14 @@
+// The night shift's warm tint, multiplied over everything the outer camera has drawn.
+//
+// The pipeline blends the output by multiplying it with what is already on the screen, so the
+// tint is all there is to return.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// Color every pixel is multiplied by, in linear RGB
+@group(2) @binding(0) var<uniform> tint: vec4<f32>;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(tint.rgb, 1.0);
+}
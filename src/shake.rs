@@ -0,0 +1,83 @@
+//! A short shake of the screen chrome when an unusually large cluster is refined.
+//!
+//! Only the [`InGameCamera`] shakes, and only by whole canvas pixels, so the canvas stays pixel
+//! perfect and the outer camera never moves. The bigger the cluster, the harder the shake, up to
+//! a few pixels. There is no shaking for those who asked for less motion.
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::InGameCamera,
+    config::Config,
+    grid::{RefineSet, Refined},
+};
+
+/// Fewest numbers refined at once that shake the screen.
+const LARGE_CLUSTER: u32 = 16;
+
+/// Numbers past [`LARGE_CLUSTER`] that add a pixel to the shake.
+const NUMBERS_PER_PIXEL: u32 = 24;
+
+/// Furthest the shake moves the camera, in pixels.
+const MAX_SHAKE: f32 = 3.;
+
+/// Seconds a shake lasts.
+const SHAKE_TIME: f32 = 0.25;
+
+pub struct ShakePlugin;
+
+impl Plugin for ShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Shake>().add_systems(
+            Update,
+            (start_shakes, shake_camera)
+                .chain()
+                .in_set(RefineSet::React),
+        );
+    }
+}
+
+/// The shake under way.
+#[derive(Resource, Default)]
+struct Shake {
+    /// Pixels the camera may move.
+    strength: f32,
+    /// Seconds left of it.
+    left: f32,
+    /// How far the camera is moved off its place.
+    offset: Vec2,
+}
+
+fn start_shakes(config: Res<Config>, mut refined: EventReader<Refined>, mut shake: ResMut<Shake>) {
+    for event in refined.read() {
+        if config.accessibility.reduced_motion || event.count < LARGE_CLUSTER {
+            continue;
+        }
+        let strength = 1. + ((event.count - LARGE_CLUSTER) / NUMBERS_PER_PIXEL) as f32;
+        shake.strength = shake.strength.max(strength.min(MAX_SHAKE));
+        shake.left = SHAKE_TIME;
+    }
+}
+
+fn shake_camera(
+    time: Res<Time<Real>>,
+    mut shake: ResMut<Shake>,
+    mut camera: Single<&mut Transform, With<InGameCamera>>,
+) {
+    if shake.left <= 0. && shake.offset == Vec2::ZERO {
+        return;
+    }
+    shake.left -= time.delta_secs();
+    let offset = if shake.left > 0. {
+        // Dying down as it ends.
+        let reach = (shake.strength * shake.left / SHAKE_TIME).ceil();
+        let random = || (fastrand::f32() * 2. - 1.) * reach;
+        Vec2::new(random(), random()).round()
+    } else {
+        shake.strength = 0.;
+        Vec2::ZERO
+    };
+    let moved = camera.translation.truncate() - shake.offset + offset;
+    camera.translation = moved.extend(camera.translation.z);
+    shake.offset = offset;
+}
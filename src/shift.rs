@@ -0,0 +1,102 @@
+//! Pixel shift: on dashboards and replays, which are left up unattended for hours, the whole
+//! canvas moves by a pixel every few minutes, so that the header, the bins and the rest of the
+//! chrome that otherwise never moves do not burn into the screen.
+//!
+//! The canvas walks around a ring of the eight pixels about its place and back through it,
+//! never straying further than one pixel. The [`CanvasCursor`] makes up for the shift, so what
+//! is pointed at stays under the pointer. Once neither is up, the canvas goes back to its place.
+//!
+//! The canvas can also drift, as a CRT's picture wanders: never more than a fraction of a pixel,
+//! and so slowly that it is only felt. It is turned on in the video config, and kept still by
+//! reduced motion. Only the sprites showing the canvas drift, smoothly across the window's pixels,
+//! while whatever is drawn on the canvas stays on its pixels. The drift is too small for the
+//! cursor to make up for.
+//!
+//! [`CanvasCursor`]: crate::canvas::CanvasCursor
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+
+use crate::{bins::DrivenBins, canvas::Canvas, config::Config, replay::Playback};
+
+/// How often the canvas moves.
+const SHIFT_INTERVAL: Duration = Duration::from_secs(180);
+
+/// Where the canvas moves to in turn, in canvas pixels from its place.
+const SHIFTS: [Vec2; 9] = [
+    Vec2::ZERO,
+    Vec2::new(1., 0.),
+    Vec2::new(1., -1.),
+    Vec2::new(0., -1.),
+    Vec2::new(-1., -1.),
+    Vec2::new(-1., 0.),
+    Vec2::new(-1., 1.),
+    Vec2::new(0., 1.),
+    Vec2::new(1., 1.),
+];
+
+/// Farthest the canvas drifts from its place, in canvas pixels.
+const DRIFT_REACH: f32 = 0.4;
+
+/// Seconds the drift takes to come around, across and up and down, apart so it never repeats.
+const DRIFT_PERIODS: Vec2 = Vec2::new(47., 61.);
+
+pub struct ShiftPlugin;
+
+impl Plugin for ShiftPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PixelShift>().add_systems(
+            Update,
+            (
+                shift_canvas.run_if(
+                    on_real_timer(SHIFT_INTERVAL)
+                        .and(resource_exists::<DrivenBins>.or(resource_exists::<Playback>)),
+                ),
+                recenter_canvas.run_if(
+                    not(resource_exists::<DrivenBins>).and(not(resource_exists::<Playback>)),
+                ),
+                move_canvas.run_if(
+                    resource_changed::<PixelShift>
+                        .or(resource_changed::<Config>)
+                        .or(drifting),
+                ),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// How far the canvas is shifted from its place, in canvas pixels.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PixelShift(pub Vec2);
+
+fn shift_canvas(mut shift: ResMut<PixelShift>, mut step: Local<usize>) {
+    *step = (*step + 1) % SHIFTS.len();
+    shift.set_if_neq(PixelShift(SHIFTS[*step]));
+}
+
+fn recenter_canvas(mut shift: ResMut<PixelShift>) {
+    shift.set_if_neq(PixelShift::default());
+}
+
+fn drifting(config: Res<Config>) -> bool {
+    config.video.crt_drift && !config.accessibility.reduced_motion
+}
+
+fn move_canvas(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    shift: Res<PixelShift>,
+    mut layers: Query<&mut Transform, With<Canvas>>,
+) {
+    let drift = if drifting(config) {
+        let phase = time.elapsed_secs() * std::f32::consts::TAU / DRIFT_PERIODS;
+        Vec2::new(phase.x.sin(), phase.y.sin()) * DRIFT_REACH
+    } else {
+        Vec2::ZERO
+    };
+    for mut layer in &mut layers {
+        layer.translation = (shift.0 + drift).extend(layer.translation.z);
+    }
+}
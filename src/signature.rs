@@ -0,0 +1,118 @@
+//! Temper signatures: the numbers of each temper set slightly apart from the calm ones, leaning a
+//! little, set a little heavier, shaded a little towards a hue, as the theme's [`Signatures`] say.
+//!
+//! The marks are faint until the cursor has found a cluster, and then stand out more on all its
+//! numbers, for the rest of the file or until its refinement is reset. The shade is worked into
+//! the numbers' colors by the grid, which colors them; the lean is kept in each number's [`Tilt`]
+//! for whatever else turns the numbers to come back to.
+//!
+//! [`Signatures`]: crate::theme::Signatures
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    files::ActiveFile,
+    grid::{
+        Cell, ClusterCache, GridModel, Number, RefineSet, ResetRefinement, Temper, NUMBER_FONT_SIZE,
+    },
+    picking::HoverChanged,
+    theme::Theme,
+    tween::Tween,
+};
+
+pub struct SignaturePlugin;
+
+impl Plugin for SignaturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Noticed>().add_systems(
+            Update,
+            (
+                notice_clusters.in_set(RefineSet::Input),
+                sign_numbers
+                    .run_if(
+                        resource_changed::<Theme>
+                            .or(resource_changed::<Noticed>)
+                            .or(resource_changed::<GridModel>),
+                    )
+                    .in_set(RefineSet::React),
+            ),
+        );
+    }
+}
+
+/// Radians a number leans by at rest, which turns of it come back to.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tilt(pub f32);
+
+/// The cells of the clusters the cursor has found in the open file.
+#[derive(Resource, Default)]
+pub struct Noticed(HashSet<Cell>);
+
+impl Noticed {
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.0.contains(&cell)
+    }
+}
+
+fn notice_clusters(
+    file: Res<ActiveFile>,
+    model: Res<GridModel>,
+    mut hovers: EventReader<HoverChanged>,
+    mut resets: EventReader<ResetRefinement>,
+    mut noticed: ResMut<Noticed>,
+    mut cached: Local<ClusterCache>,
+) {
+    if file.is_changed() || resets.read().count() > 0 {
+        noticed.0.clear();
+    }
+    let clusters = cached.get(file.seed, model.size());
+    for cell in hovers.read().filter_map(|event| event.0.cell) {
+        if noticed.0.contains(&cell)
+            || model
+                .get(cell)
+                .is_none_or(|state| state.temper == Temper::Calm)
+        {
+            continue;
+        }
+        if let Some(cluster) = clusters.iter().find(|cluster| cluster.contains(&cell)) {
+            noticed.0.extend(cluster.iter().copied());
+        }
+    }
+}
+
+/// What a temper's signature changes about a number, and whether it is turning just now.
+type SignedNumber<'a> = (
+    &'a Cell,
+    &'a mut Tilt,
+    &'a mut Transform,
+    Option<&'a mut TextFont>,
+    Has<Tween<Transform>>,
+);
+
+/// Leans and weighs every number as its temper's signature says.
+fn sign_numbers(
+    theme: Res<Theme>,
+    model: Res<GridModel>,
+    noticed: Res<Noticed>,
+    mut numbers: Query<SignedNumber, With<Number>>,
+) {
+    for (cell, mut tilt, mut transform, font, turning) in &mut numbers {
+        let Some(state) = model.get(*cell) else {
+            continue;
+        };
+        let signature = theme.signatures.of(state.temper, noticed.contains(*cell));
+        tilt.set_if_neq(Tilt(signature.tilt));
+        // A number turning comes back to its tilt by itself.
+        let rotation = Quat::from_rotation_z(signature.tilt);
+        if !turning && transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+        // Numbers the field draws have no text to weigh.
+        let size = NUMBER_FONT_SIZE * (1. + signature.weight);
+        if let Some(mut font) = font.filter(|font| font.font_size != size) {
+            font.font_size = size;
+        }
+    }
+}
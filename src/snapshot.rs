@@ -0,0 +1,294 @@
+//! Snapshots of the screen being refined, for catching visual bugs in the act.
+//!
+//! F9 writes what the grid is showing to a RON file in the `snapshots` folder of the config
+//! directory: the open file, the [`GridModel`] and the selection, how full the bins are, where
+//! the grid camera is and how far it is zoomed, and the frame animations of the bins' lids and
+//! chutes, each as far into its clip as it had got. Started with `--restore-snapshot PATH`, the
+//! app opens the file again and puts all of it back as it was, so a bug seen once can be looked
+//! at as long as it takes.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{Clip, FrameAnimation},
+    bins::Bin,
+    canvas::GridCamera,
+    config::config_dir,
+    files::ActiveFile,
+    grid::{GridModel, GridSize, RefineSet, ResizeGrid, Selection},
+    lids::Lid,
+    state::AppState,
+    toast::Toast,
+    zoom::Zoom,
+};
+
+/// Version of the snapshot format, bumped whenever it changes.
+const FORMAT_VERSION: u32 = 2;
+
+pub struct SnapshotPlugin {
+    /// The snapshot to start from, if any.
+    pub restore: Option<PathBuf>,
+}
+
+impl SnapshotPlugin {
+    /// Reads `--restore-snapshot PATH` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut restore = None;
+        while let Some(arg) = args.next() {
+            if arg == "--restore-snapshot" {
+                restore = args.next().map(PathBuf::from);
+            }
+        }
+        Self { restore }
+    }
+}
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, take_snapshot.run_if(in_state(AppState::Refining)));
+        let Some(path) = &self.restore else {
+            return;
+        };
+        match Snapshot::load(path) {
+            Ok((file, snapshot)) => {
+                info!("Restoring snapshot {}", path.display());
+                app.insert_resource(file)
+                    .insert_resource(PendingSnapshot(snapshot))
+                    .insert_resource(RestoredSnapshot)
+                    .add_systems(
+                        Update,
+                        restore_snapshot
+                            .run_if(
+                                in_state(AppState::Refining)
+                                    .and(resource_exists::<PendingSnapshot>),
+                            )
+                            .after(RefineSet::Apply)
+                            .before(RefineSet::React),
+                    );
+            }
+            Err(error) => error!("Could not restore snapshot {}: {error}", path.display()),
+        }
+    }
+}
+
+/// What the screen was showing when the snapshot was taken.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    /// The open file, down to how each of its bins looks.
+    file: ActiveFile,
+    grid: GridModel,
+    /// Corners of the selection: the first column and row, then the last.
+    selection: Option<[u32; 4]>,
+    /// How full each bin was, in order.
+    bins: Vec<f32>,
+    /// Where the grid camera was, in world space.
+    camera: [f32; 2],
+    /// Index of the zoom level.
+    zoom: usize,
+    /// Bins whose lids were open, or opening.
+    open_lids: Vec<usize>,
+    animations: Vec<PartAnimation>,
+}
+
+/// A frame animation playing on a part of a bin.
+#[derive(Serialize, Deserialize)]
+struct PartAnimation {
+    bin: usize,
+    /// Index of the part among the bin's children.
+    part: usize,
+    clip: Clip,
+    elapsed: f32,
+}
+
+impl Snapshot {
+    fn save(&self) -> io::Result<PathBuf> {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+        let path = config_dir()
+            .join("snapshots")
+            .join(format!("snapshot-{stamp}.ron"));
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Reads a snapshot, and the file it was taken of.
+    fn load(path: &Path) -> io::Result<(ActiveFile, Self)> {
+        let invalid = |what: String| io::Error::new(io::ErrorKind::InvalidData, what);
+        let snapshot: Snapshot = ron::from_str(&fs::read_to_string(path)?)
+            .map_err(|error| invalid(error.to_string()))?;
+        if snapshot.version != FORMAT_VERSION {
+            return Err(invalid("unsupported snapshot version".to_string()));
+        }
+        Ok((snapshot.file.clone(), snapshot))
+    }
+}
+
+/// A snapshot waiting for the grid and the bins to be there to be put back on.
+#[derive(Resource)]
+struct PendingSnapshot(Snapshot);
+
+/// Present when the app was started from a snapshot, for systems that would get in its way.
+#[derive(Resource)]
+pub struct RestoredSnapshot;
+
+/// Takes a snapshot when F9 is pressed.
+fn take_snapshot(
+    keys: Res<ButtonInput<KeyCode>>,
+    (file, model, selection): (Res<ActiveFile>, Res<GridModel>, Res<Selection>),
+    (camera, zoom): (Single<&Transform, With<GridCamera>>, Res<Zoom>),
+    bins: Query<(&Bin, Option<&Children>)>,
+    animations: Query<&FrameAnimation>,
+    lids: Query<&Lid>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let mut bins: Vec<_> = bins.iter().collect();
+    bins.sort_by_key(|(bin, _)| bin.index);
+    let mut animated = Vec::new();
+    for (bin, children) in &bins {
+        let children = children.iter().flat_map(|children| children.iter());
+        for (part, child) in children.enumerate() {
+            if let Ok(animation) = animations.get(child) {
+                animated.push(PartAnimation {
+                    bin: bin.index,
+                    part,
+                    clip: animation.clip().clone(),
+                    elapsed: animation.elapsed(),
+                });
+            }
+        }
+    }
+    let snapshot = Snapshot {
+        version: FORMAT_VERSION,
+        file: file.clone(),
+        grid: model.clone(),
+        selection: selection
+            .0
+            .map(|range| [range.min.x, range.min.y, range.max.x, range.max.y]),
+        bins: bins.iter().map(|(bin, _)| bin.progress).collect(),
+        camera: camera.translation.truncate().to_array(),
+        zoom: zoom.level(),
+        open_lids: lids
+            .iter()
+            .filter(|lid| lid.open)
+            .map(|lid| lid.bin)
+            .collect(),
+        animations: animated,
+    };
+    match snapshot.save() {
+        Ok(path) => {
+            info!("Saved a snapshot to {}", path.display());
+            toasts.write(Toast::success("Snapshot saved"));
+        }
+        Err(error) => {
+            error!("Could not save a snapshot: {error}");
+            toasts.write(Toast::error("Could not save snapshot"));
+        }
+    }
+}
+
+/// Puts the snapshot back once the bins are there, after resizing the grid to its size.
+fn restore_snapshot(
+    mut commands: Commands,
+    pending: Res<PendingSnapshot>,
+    (size, mut resizes): (Res<GridSize>, EventWriter<ResizeGrid>),
+    (mut model, mut selection): (ResMut<GridModel>, ResMut<Selection>),
+    (mut camera, mut zoom): (Single<&mut Transform, With<GridCamera>>, ResMut<Zoom>),
+    mut bins: Query<(&mut Bin, Option<&Children>)>,
+    mut lids: Query<&mut Lid>,
+) {
+    let snapshot = &pending.0;
+    if *size != snapshot.grid.size() {
+        resizes.write(ResizeGrid(snapshot.grid.size()));
+        return;
+    }
+    if bins.iter().len() != snapshot.bins.len() {
+        return;
+    }
+    commands.remove_resource::<PendingSnapshot>();
+    *model = snapshot.grid.clone();
+    selection.0 = snapshot
+        .selection
+        .map(|[col, row, last_col, last_row]| URect::new(col, row, last_col, last_row));
+    camera.translation = Vec2::from_array(snapshot.camera).extend(camera.translation.z);
+    *zoom = Zoom::at_level(snapshot.zoom);
+    for (mut bin, children) in &mut bins {
+        let index = bin.index;
+        bin.progress = snapshot.bins.get(index).copied().unwrap_or(bin.progress);
+        let Some(children) = children else {
+            continue;
+        };
+        for animation in snapshot.animations.iter().filter(|a| a.bin == index) {
+            if let Some(&part) = children.get(animation.part) {
+                commands.entity(part).try_insert(FrameAnimation::resumed(
+                    animation.clip.clone(),
+                    animation.elapsed,
+                ));
+            }
+        }
+    }
+    for mut lid in &mut lids {
+        lid.open = snapshot.open_lids.contains(&lid.bin);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::BinStyle;
+
+    #[test]
+    fn snapshots_keep_how_the_bins_look() {
+        let file = ActiveFile {
+            styles: vec![
+                BinStyle {
+                    color: Some([0.2, 0.4, 0.6]),
+                    icon: Some('★'),
+                    ..BinStyle::named("Woe")
+                },
+                BinStyle::default(),
+            ],
+            ..default()
+        };
+        let size = GridSize {
+            columns: 12,
+            rows: 10,
+        };
+        let snapshot = Snapshot {
+            version: FORMAT_VERSION,
+            file: file.clone(),
+            grid: GridModel::new(&file, size),
+            selection: Some([1, 2, 3, 4]),
+            bins: file.progress.clone(),
+            camera: [10., -20.],
+            zoom: 2,
+            open_lids: vec![1],
+            animations: Vec::new(),
+        };
+        let contents =
+            ron::ser::to_string_pretty(&snapshot, ron::ser::PrettyConfig::default()).unwrap();
+        let read: Snapshot = ron::from_str(&contents).unwrap();
+        assert_eq!(read.file, file);
+        assert_eq!(read.grid, snapshot.grid);
+        assert_eq!(read.selection, snapshot.selection);
+        assert_eq!(read.camera, snapshot.camera);
+        assert_eq!(read.open_lids, snapshot.open_lids);
+    }
+}
@@ -0,0 +1,265 @@
+//! The soundscape: the hum of the severed floor, for long sessions left running.
+//!
+//! Three layers are synthesized like the sound effects are (see [`Tone`](crate::audio::Tone)):
+//! the tone of the room, always there; the air handling, which kicks on for a few minutes at a
+//! time and then rests; and now and then a fluorescent tube buzzing and flickering for a few
+//! seconds. Each layer comes and goes at random times, crossfading in and out rather than
+//! starting or stopping short. The soundscape has a volume of its own in the audio config, apart
+//! from the sound effects', and is off at 0, which is where it starts.
+
+use std::time::Duration;
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source, Volume},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::config::Config;
+
+/// Sample rate the soundscape is synthesized at, low as it has little in the way of treble.
+const SAMPLE_RATE: u32 = 22_050;
+
+/// Seconds after which every layer's sines are back where they started.
+const LOOP_SECONDS: u64 = 400;
+
+pub struct SoundscapePlugin;
+
+impl Plugin for SoundscapePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Drone>().add_systems(
+            Update,
+            (
+                start_soundscape.run_if(resource_changed::<Config>),
+                (schedule_layers, mix_layers).chain(),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// A layer of the soundscape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layer {
+    RoomTone,
+    Hvac,
+    Buzz,
+}
+
+impl Layer {
+    const ALL: [Layer; 3] = [Layer::RoomTone, Layer::Hvac, Layer::Buzz];
+
+    /// Loudness of the layer, from 0 to 1, before the soundscape's volume.
+    fn gain(self) -> f32 {
+        match self {
+            Layer::RoomTone => 0.5,
+            Layer::Hvac => 0.35,
+            Layer::Buzz => 0.08,
+        }
+    }
+
+    /// Seconds the layer takes to fade in or out.
+    fn fade(self) -> f32 {
+        match self {
+            Layer::RoomTone => 3.,
+            Layer::Hvac => 6.,
+            Layer::Buzz => 0.3,
+        }
+    }
+
+    /// Seconds the layer plays for at a time, and rests for in between, as ranges to pick from;
+    /// `None` for a layer that never rests.
+    fn schedule(self) -> Option<([f32; 2], [f32; 2])> {
+        match self {
+            Layer::RoomTone => None,
+            Layer::Hvac => Some(([60., 240.], [30., 120.])),
+            Layer::Buzz => Some(([2., 8.], [20., 90.])),
+        }
+    }
+}
+
+/// A layer playing, and where it is in its schedule.
+#[derive(Component)]
+pub(crate) struct SoundscapeLayer {
+    layer: Layer,
+    playing: bool,
+    /// Real seconds since startup at which the layer starts or stops next.
+    until: f32,
+    /// How far the layer has faded in, from 0 to 1.
+    fade: f32,
+}
+
+/// Picks a time within `[least, most]` seconds.
+fn pick([least, most]: [f32; 2]) -> f32 {
+    least + fastrand::f32() * (most - least)
+}
+
+/// Starts the layers once the soundscape is turned up from 0, and stops them as it is turned
+/// back down.
+fn start_soundscape(
+    mut commands: Commands,
+    config: Res<Config>,
+    time: Res<Time<Real>>,
+    mut drones: ResMut<Assets<Drone>>,
+    layers: Query<Entity, With<SoundscapeLayer>>,
+) {
+    let on = config.audio.soundscape_volume > 0.;
+    let running = !layers.is_empty();
+    if on == running {
+        return;
+    }
+    if !on {
+        for entity in &layers {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    for layer in Layer::ALL {
+        // Resting layers wait a while first, so that they don't all start together.
+        let (playing, until) = match layer.schedule() {
+            Some((_, rest)) => (false, time.elapsed_secs() + pick(rest)),
+            None => (true, f32::INFINITY),
+        };
+        commands.spawn((
+            SoundscapeLayer {
+                layer,
+                playing,
+                until,
+                fade: 0.,
+            },
+            AudioPlayer(drones.add(Drone(layer))),
+            // Silent until the first mix, which sets the volume every frame.
+            PlaybackSettings::ONCE.with_volume(Volume::SILENT),
+        ));
+    }
+}
+
+/// Starts and stops the layers as their times come, and fades them towards playing or not.
+fn schedule_layers(time: Res<Time<Real>>, mut layers: Query<&mut SoundscapeLayer>) {
+    let now = time.elapsed_secs();
+    for mut layer in &mut layers {
+        if now >= layer.until {
+            if let Some((play, rest)) = layer.layer.schedule() {
+                layer.playing ^= true;
+                layer.until = now + pick(if layer.playing { play } else { rest });
+            }
+        }
+        let step = time.delta_secs() / layer.layer.fade();
+        let fade = if layer.playing {
+            (layer.fade + step).min(1.)
+        } else {
+            (layer.fade - step).max(0.)
+        };
+        if layer.fade != fade {
+            layer.fade = fade;
+        }
+    }
+}
+
+/// Sets each layer's volume from its fade and the volumes of the config. Playing audio misses
+/// changes to the global volume, so the master volume is applied here too.
+fn mix_layers(
+    config: Res<Config>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut layers: Query<(&SoundscapeLayer, &mut AudioSink)>,
+) {
+    let volume = config.audio.effective_volume(window.focused) * config.audio.soundscape_volume;
+    for (layer, mut sink) in &mut layers {
+        sink.set_volume(Volume::Linear(volume * layer.layer.gain() * layer.fade));
+    }
+}
+
+/// An endless layer of the soundscape.
+#[derive(Asset, TypePath, Clone, Copy, Debug)]
+struct Drone(Layer);
+
+/// Produces the samples of a [`Drone`], for as long as it plays.
+struct DroneDecoder {
+    layer: Layer,
+    sample: u64,
+    /// State of the noise generator.
+    seed: u32,
+    /// Noise, filtered down to a rumble.
+    low: f32,
+    /// Brightness of a flickering tube, held for a few milliseconds at a time.
+    flicker: f32,
+}
+
+impl DroneDecoder {
+    /// White noise from -1 to 1.
+    fn noise(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2. - 1.
+    }
+}
+
+impl Iterator for DroneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Time within a loop every sine fits a whole number of cycles into, as time since the
+        // start would soon be too large for an `f32` to count samples of.
+        let t = (self.sample % (u64::from(SAMPLE_RATE) * LOOP_SECONDS)) as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+        let white = self.noise();
+        let tau = std::f32::consts::TAU;
+        let sample = match self.layer {
+            // Noise with the hiss filtered out of it.
+            Layer::RoomTone => {
+                self.low += (white - self.low) * 0.02;
+                self.low * 4.
+            }
+            // A deeper rumble, swelling slowly, over the hum of its fan.
+            Layer::Hvac => {
+                self.low += (white - self.low) * 0.008;
+                let swell = 0.8 + 0.2 * (tau * 0.15 * t).sin();
+                (self.low * 8. + 0.15 * (tau * 57. * t).sin()) * swell
+            }
+            // Twice the mains frequency and its odd harmonics, flickering.
+            Layer::Buzz => {
+                if self.sample.is_multiple_of(SAMPLE_RATE as u64 / 40) {
+                    self.flicker = 0.6 + 0.4 * self.noise().abs();
+                }
+                let phase = tau * 120. * t;
+                let wave = phase.sin() + (3. * phase).sin() / 3. + (5. * phase).sin() / 5.;
+                wave * self.flicker
+            }
+        };
+        Some(sample.clamp(-1., 1.))
+    }
+}
+
+impl Source for DroneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for Drone {
+    type DecoderItem = f32;
+    type Decoder = DroneDecoder;
+
+    fn decoder(&self) -> DroneDecoder {
+        DroneDecoder {
+            layer: self.0,
+            sample: 0,
+            seed: 0x9e37_79b9 ^ self.0 as u32,
+            low: 0.,
+            flicker: 1.,
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Bins driven by outside data, through the [`BinDataSource`] trait.
+//!
+//! Started with `--bin-source <name> [argument]`, which picks a source registered under that
+//! name. The source is polled every [`POLL_INTERVAL`] and each bin is set to its reading, a bin
+//! per sample; the bins are laid out anew whenever the source reports a different number of
+//! them, and polling is paused and resumed from the tray. Built in are:
+//!
+//! - `static 0.2,0.5,0.9`: fixed readings
+//! - `demo [bins]`: readings that wander at random
+//! - `system`: CPU, memory and swap use and load, read from `/proc` where there is one
+//! - `http <url>`: comma or whitespace separated readings served at a plain `http://` URL,
+//!   fetched in the background, or kept in a `file://` a script writes to
+//!
+//! A source may also name the bin of each reading, renaming its bins as what they stand for
+//! changes; a reading that names none leaves its bin as it is. An `http` reading does so as
+//! `NAME=0.4`, underscores in the name standing for spaces.
+//!
+//! Plugins add their own sources with [`RegisterBinSource::register_bin_source`].
+
+use std::{collections::HashMap, fs, thread, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+
+use crate::{
+    bins::{Bin, BinLabels, BinLayout, DrivenBins, RelayBins, DEFAULT_BIN_COUNT, MAX_BIN_COUNT},
+    files::ActiveFile,
+    net::http::Feed,
+    toast::Toast,
+};
+
+/// How often the source is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the `http` source fetches its readings.
+const FETCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Most a `demo` reading moves per poll.
+const DEMO_STEP: f32 = 0.05;
+
+/// One reading of a bin.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BinSample {
+    /// How full the bin is, from 0 to 1.
+    pub progress: f32,
+    /// What the bin is named, or `None` to leave it as it is.
+    pub name: Option<String>,
+}
+
+impl BinSample {
+    pub fn new(progress: f32) -> Self {
+        Self {
+            progress: progress.clamp(0., 1.),
+            ..default()
+        }
+    }
+
+    /// The reading naming its bin.
+    pub fn named(self, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+}
+
+/// Something that can drive the bins.
+pub trait BinDataSource: Send + Sync + 'static {
+    /// The current reading of every bin, in order, or none while there are no readings yet.
+    fn poll(&mut self) -> Vec<BinSample>;
+
+    /// A problem the source ran into since it was last asked, for the player to hear of.
+    fn take_error(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Makes a source from the argument it was given on the command line, if any.
+pub type SourceFactory = fn(Option<&str>) -> Result<Box<dyn BinDataSource>, String>;
+
+/// Registers sources that `--bin-source` can pick by name.
+pub trait RegisterBinSource {
+    fn register_bin_source(&mut self, name: &'static str, factory: SourceFactory) -> &mut Self;
+}
+
+impl RegisterBinSource for App {
+    fn register_bin_source(&mut self, name: &'static str, factory: SourceFactory) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<SourceRegistry>()
+            .0
+            .insert(name, factory);
+        self
+    }
+}
+
+/// The source picked on the command line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceSpec {
+    pub name: String,
+    pub argument: Option<String>,
+}
+
+impl SourceSpec {
+    /// Reads `--bin-source <name> [argument]` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter().peekable();
+        let mut spec = None;
+        while let Some(arg) = args.next() {
+            if arg == "--bin-source" {
+                if let Some(name) = args.next() {
+                    spec = Some(Self {
+                        name,
+                        argument: args.next_if(|next| !next.starts_with("--")),
+                    });
+                }
+            }
+        }
+        spec
+    }
+}
+
+pub struct SourcePlugin {
+    pub spec: Option<SourceSpec>,
+}
+
+impl Plugin for SourcePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_bin_source("static", StaticSource::open)
+            .register_bin_source("demo", RandomWalk::open)
+            .register_bin_source("system", SystemMetrics::open)
+            .register_bin_source("http", HttpSource::open);
+        let Some(spec) = &self.spec else {
+            return;
+        };
+        // The source stands in for a file of its own.
+        app.insert_resource(ActiveFile {
+            name: format!("{} feed", spec.name),
+            progress: vec![0.; DEFAULT_BIN_COUNT],
+            ..default()
+        })
+        .insert_resource(DrivenBins)
+        .insert_resource(PickedSource(spec.clone()))
+        .add_systems(Startup, start_source)
+        .add_systems(
+            Update,
+            poll_source
+                .run_if(resource_exists::<ActiveSource>)
+                .run_if(not(resource_exists::<PollingPaused>))
+                .run_if(on_real_timer(POLL_INTERVAL)),
+        );
+    }
+}
+
+/// Every source that can be picked, by name.
+#[derive(Resource, Default)]
+struct SourceRegistry(HashMap<&'static str, SourceFactory>);
+
+#[derive(Resource)]
+struct PickedSource(SourceSpec);
+
+/// The source driving the bins, present while there is one.
+#[derive(Resource)]
+pub struct ActiveSource(Box<dyn BinDataSource>);
+
+/// Present while the source is left unpolled, the bins keeping its last readings.
+#[derive(Resource)]
+pub struct PollingPaused;
+
+/// Makes the picked source, once every plugin has had the chance to register theirs.
+fn start_source(
+    mut commands: Commands,
+    registry: Res<SourceRegistry>,
+    picked: Res<PickedSource>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let spec = &picked.0;
+    let Some(factory) = registry.0.get(spec.name.as_str()) else {
+        let mut names: Vec<_> = registry.0.keys().collect();
+        names.sort();
+        error!(
+            "There is no bin source {:?}; there are {names:?}",
+            spec.name
+        );
+        toasts.write(Toast::error(format!("No source {}", spec.name)));
+        return;
+    };
+    match factory(spec.argument.as_deref()) {
+        Ok(source) => {
+            info!("Driving the bins from the {} source", spec.name);
+            commands.insert_resource(ActiveSource(source));
+        }
+        Err(error) => {
+            error!("Could not start the {} source: {error}", spec.name);
+            toasts.write(Toast::error(format!("{} source failed", spec.name)));
+        }
+    }
+}
+
+fn poll_source(
+    mut source: ResMut<ActiveSource>,
+    (layout, mut labels): (Res<BinLayout>, ResMut<BinLabels>),
+    mut bins: Query<&mut Bin>,
+    mut relays: EventWriter<RelayBins>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if let Some(error) = source.0.take_error() {
+        toasts.write(Toast::warning(error));
+    }
+    let mut samples = source.0.poll();
+    if samples.is_empty() {
+        return;
+    }
+    samples.truncate(MAX_BIN_COUNT);
+    for (index, sample) in samples.iter().enumerate() {
+        let Some(name) = &sample.name else {
+            continue;
+        };
+        if labels.name(index) != Some(name.as_str()) {
+            labels.rename(index, Some(name.clone()));
+        }
+    }
+    if samples.len() != layout.count() {
+        relays.write(RelayBins(
+            samples.iter().map(|sample| sample.progress).collect(),
+        ));
+        return;
+    }
+    for mut bin in &mut bins {
+        if let Some(sample) = samples.get(bin.index) {
+            if bin.progress != sample.progress {
+                bin.progress = sample.progress;
+            }
+        }
+    }
+}
+
+/// Reads a list of readings, separated by commas or whitespace, each of them perhaps naming its
+/// bin as `NAME=0.4`.
+fn parse_samples(text: &str) -> Result<Vec<BinSample>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let (label, reading) = match word.rsplit_once('=') {
+                Some((label, reading)) => (Some(label), reading),
+                None => (None, word),
+            };
+            let sample = reading
+                .parse::<f32>()
+                .ok()
+                .filter(|reading| reading.is_finite())
+                .map(BinSample::new)
+                .ok_or_else(|| format!("{word:?} is not a reading"))?;
+            Ok(match label {
+                Some("") => return Err(format!("{word:?} names no bin")),
+                Some(name) => sample.named(name.replace('_', " ")),
+                None => sample,
+            })
+        })
+        .collect()
+}
+
+/// The same readings every time.
+struct StaticSource(Vec<BinSample>);
+
+impl StaticSource {
+    fn open(argument: Option<&str>) -> Result<Box<dyn BinDataSource>, String> {
+        let samples = parse_samples(argument.unwrap_or_default())?;
+        if samples.is_empty() {
+            return Err("no readings given, as in `static 0.2,0.5`".to_string());
+        }
+        Ok(Box::new(Self(samples)))
+    }
+}
+
+impl BinDataSource for StaticSource {
+    fn poll(&mut self) -> Vec<BinSample> {
+        self.0.clone()
+    }
+}
+
+/// Readings that wander at random, for trying out the bins.
+struct RandomWalk(Vec<BinSample>);
+
+impl RandomWalk {
+    fn open(argument: Option<&str>) -> Result<Box<dyn BinDataSource>, String> {
+        let bins = match argument {
+            Some(bins) => bins
+                .parse()
+                .map_err(|_| format!("{bins:?} is not a number of bins"))?,
+            None => DEFAULT_BIN_COUNT,
+        };
+        let samples = (0..bins.clamp(1, MAX_BIN_COUNT))
+            .map(|_| BinSample::new(fastrand::f32()))
+            .collect();
+        Ok(Box::new(Self(samples)))
+    }
+}
+
+impl BinDataSource for RandomWalk {
+    fn poll(&mut self) -> Vec<BinSample> {
+        for sample in &mut self.0 {
+            *sample = BinSample::new(sample.progress + (fastrand::f32() * 2. - 1.) * DEMO_STEP);
+        }
+        self.0.clone()
+    }
+}
+
+/// CPU, memory and swap use, and the load average per CPU, from `/proc`.
+struct SystemMetrics {
+    /// Busy and total CPU time at the last poll.
+    cpu: Option<(u64, u64)>,
+    cpus: f32,
+}
+
+impl SystemMetrics {
+    fn open(_: Option<&str>) -> Result<Box<dyn BinDataSource>, String> {
+        fs::metadata("/proc/stat").map_err(|_| "only Linux has system metrics".to_string())?;
+        let cpus = thread::available_parallelism().map_or(1, |cpus| cpus.get());
+        Ok(Box::new(Self {
+            cpu: None,
+            cpus: cpus as f32,
+        }))
+    }
+
+    fn cpu_use(&mut self) -> Option<f32> {
+        let stat = fs::read_to_string("/proc/stat").ok()?;
+        let times: Vec<u64> = stat
+            .lines()
+            .next()?
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|time| time.parse().ok())
+            .collect();
+        // Idle and waiting on IO.
+        let idle = times.get(3)? + times.get(4).unwrap_or(&0);
+        let total: u64 = times.iter().sum();
+        let last = self.cpu.replace((total - idle, total));
+        let (busy, total) = (total - idle, total);
+        let (last_busy, last_total) = last.unwrap_or_default();
+        let elapsed = total.saturating_sub(last_total).max(1);
+        Some(busy.saturating_sub(last_busy) as f32 / elapsed as f32)
+    }
+
+    fn memory_use() -> Option<(f32, f32)> {
+        let info = fs::read_to_string("/proc/meminfo").ok()?;
+        let value = |key: &str| -> Option<f32> {
+            let line = info.lines().find(|line| line.starts_with(key))?;
+            line.split_whitespace().nth(1)?.parse().ok()
+        };
+        let memory = 1. - value("MemAvailable:")? / value("MemTotal:")?.max(1.);
+        let total = value("SwapTotal:")?;
+        let swap = if total > 0. {
+            1. - value("SwapFree:")? / total
+        } else {
+            0.
+        };
+        Some((memory, swap))
+    }
+
+    fn load(&self) -> Option<f32> {
+        let load: f32 = fs::read_to_string("/proc/loadavg")
+            .ok()?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some(load / self.cpus)
+    }
+}
+
+impl BinDataSource for SystemMetrics {
+    fn poll(&mut self) -> Vec<BinSample> {
+        let cpu = self.cpu_use().unwrap_or_default();
+        let (memory, swap) = Self::memory_use().unwrap_or_default();
+        let load = self.load().unwrap_or_default();
+        [cpu, memory, swap, load]
+            .into_iter()
+            .map(BinSample::new)
+            .collect()
+    }
+}
+
+/// Readings fetched from a plain HTTP URL, or read from a file, on a thread of their own.
+struct HttpSource {
+    feed: Feed<Vec<BinSample>>,
+    /// The last readings fetched.
+    samples: Vec<BinSample>,
+}
+
+impl HttpSource {
+    fn open(argument: Option<&str>) -> Result<Box<dyn BinDataSource>, String> {
+        let url = argument.ok_or("no URL given, as in `http http://localhost:8080/bins`")?;
+        if !url.starts_with("http://") && !url.starts_with("file://") {
+            return Err("only plain http:// and file:// URLs are supported".to_string());
+        }
+        Ok(Box::new(Self {
+            feed: Feed::spawn(url, FETCH_INTERVAL, parse_samples),
+            samples: Vec::new(),
+        }))
+    }
+}
+
+impl BinDataSource for HttpSource {
+    fn poll(&mut self) -> Vec<BinSample> {
+        if let Some(samples) = self.feed.take_update() {
+            self.samples = samples;
+        }
+        self.samples.clone()
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        let url = self.feed.url();
+        let place = match url.strip_prefix("file://") {
+            Some(path) => path.rsplit('/').next().unwrap_or(path),
+            None => {
+                let host = url.trim_start_matches("http://");
+                host.split('/').next().unwrap_or(host)
+            }
+        }
+        .to_string();
+        self.feed.take_error().map(|_| format!("Lost {place}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_readings_however_they_are_separated() {
+        let samples = parse_samples("0.2, 0.5\n0.9\t1").unwrap();
+        let progress: Vec<f32> = samples.iter().map(|sample| sample.progress).collect();
+        assert_eq!(progress, vec![0.2, 0.5, 0.9, 1.]);
+        assert!(samples.iter().all(|sample| sample.name.is_none()));
+    }
+
+    #[test]
+    fn readings_may_name_their_bins() {
+        let samples = parse_samples("open_tickets=0.4,0.1").unwrap();
+        assert_eq!(
+            samples,
+            vec![
+                BinSample::new(0.4).named("open tickets"),
+                BinSample::new(0.1)
+            ]
+        );
+    }
+
+    #[test]
+    fn readings_are_kept_in_range() {
+        let samples = parse_samples("-3 7").unwrap();
+        assert_eq!(samples, vec![BinSample::new(0.), BinSample::new(1.)]);
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_reading() {
+        for text in ["lots", "NaN", "inf", "-inf", "=0.5", "name=", "0.5,a=b"] {
+            assert!(parse_samples(text).is_err(), "{text:?}");
+        }
+        assert_eq!(parse_samples(" , "), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn picks_the_source_from_the_command_line() {
+        let args = [
+            "--kiosk",
+            "--bin-source",
+            "http",
+            "http://localhost/bins",
+            "--x",
+        ];
+        let spec = SourceSpec::from_args(args.map(String::from)).unwrap();
+        assert_eq!(spec.name, "http");
+        assert_eq!(spec.argument.as_deref(), Some("http://localhost/bins"));
+        let spec = SourceSpec::from_args(["--bin-source", "demo", "--kiosk"].map(String::from));
+        assert_eq!(spec.unwrap().argument, None);
+    }
+}
@@ -0,0 +1,59 @@
+//! Game speed: how fast the virtual clock runs, which the grid's drift, the animations and the
+//! bins' timers all go by.
+//!
+//! The gameplay config's `time_scale` runs it from half as fast to three times as fast, for
+//! demo recordings or a livelier kiosk, and [idle dimming](crate::idle) slows it further. What is
+//! measured of a session, such as its length and the work timer's minutes, goes by
+//! [`Time<Session>`] instead: real time, stopped whenever the virtual clock is paused, but never
+//! sped up or slowed down with it.
+
+use std::ops::RangeInclusive;
+
+use bevy::{prelude::*, time::TimeSystem};
+
+use crate::{config::Config, idle::Idle};
+
+/// Time scales the settings step through.
+pub const TIME_SCALES: [f32; 6] = [0.5, 0.75, 1., 1.5, 2., 3.];
+
+/// Slowest and fastest the config can run the virtual clock.
+const TIME_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.;
+
+pub struct SpeedPlugin;
+
+impl Plugin for SpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Time<Session>>()
+            .add_systems(First, advance_session.after(TimeSystem))
+            .add_systems(PreUpdate, apply_speed);
+    }
+}
+
+/// The clock of a [`Time<Session>`].
+#[derive(Default)]
+pub struct Session;
+
+/// Runs the session clock on for as long as the virtual clock is not paused.
+fn advance_session(
+    real: Res<Time<Real>>,
+    virtual_time: Res<Time<Virtual>>,
+    mut session: ResMut<Time<Session>>,
+) {
+    if !virtual_time.is_paused() {
+        session.advance_by(real.delta());
+    }
+}
+
+/// Runs the virtual clock at the configured scale, slowed while the canvas is idle.
+fn apply_speed(config: Res<Config>, idle: Res<Idle>, mut virtual_time: ResMut<Time<Virtual>>) {
+    let scale = config.gameplay.time_scale;
+    let scale = if scale.is_finite() {
+        scale.clamp(*TIME_SCALE_RANGE.start(), *TIME_SCALE_RANGE.end())
+    } else {
+        1.
+    };
+    let speed = scale * idle.speed();
+    if virtual_time.relative_speed() != speed {
+        virtual_time.set_relative_speed(speed);
+    }
+}
@@ -0,0 +1,46 @@
+//! The top-level state machine of the app.
+
+use bevy::prelude::*;
+
+use crate::grid::RefineSet;
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>().configure_sets(
+            Update,
+            RefineSet::Input.run_if(in_state(AppState::Refining)),
+        );
+    }
+}
+
+/// What the app is currently doing.
+///
+/// Gameplay input only runs while [`AppState::Refining`]; every other state owns the keyboard
+/// and mouse for its own screen.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[states(scoped_entities)]
+pub enum AppState {
+    /// Waiting for assets to load, which then leads to the boot or straight to the grid.
+    #[default]
+    Loading,
+    /// The startup animation, which leads to the menu.
+    Boot,
+    /// Choosing a file to work on.
+    Menu,
+    /// Working on the grid.
+    Refining,
+    /// Simulation is stopped and the pause menu is shown.
+    Paused,
+    /// Simulation is stopped and the settings screen is shown.
+    Settings,
+    /// Simulation is stopped for a wellness session.
+    Wellness,
+    /// The session is over, and how it went is shown.
+    Summary,
+    /// Looking through the achievements.
+    Achievements,
+    /// Looking over the progress of every file, on the quarterly board.
+    Board,
+}
@@ -0,0 +1,408 @@
+//! The end of a session: how it went, once the file is complete or the refiner stops.
+//!
+//! [`SessionStats`] keeps count from the moment a file is opened, going by the refinements as
+//! they are applied: how many numbers were refined, how many of them were scary and of which
+//! temper, how many clusters were caught and bins missed, and how far the file had got after
+//! each. The summary screen comes up after the finale, or when the refiner abandons the file from
+//! the pause menu, and shows the time taken, the accuracy, the clusters caught, the
+//! counts per temper and the misses, and a sparkline of the progress. From there the summary can
+//! be exported, or saved as a [result card](crate::card), or a new file started.
+
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    bins::{Bin, BinMissed},
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    card::SaveCard,
+    chart::{SparkStyle, Sparkline},
+    config::{config_dir, Config},
+    files::{ActiveFile, FileLibrary, OpenFile},
+    finale::FinaleEnded,
+    grid::{detect::ClusterCaught, Cell, GridModel, RefineSet, Refined, Temper},
+    kiosk::Kiosk,
+    speed::Session,
+    state::AppState,
+    toast::Toast,
+    transition::{TransitionEffect, TransitionTo},
+    ui::{spawn_menu, MenuChosen, MenuEntry},
+};
+
+/// Size of the progress sparkline.
+const SPARKLINE_SIZE: UVec2 = UVec2::new(96, 16);
+
+/// Pixels between the points of the progress sparkline.
+const SPARKLINE_SPACING: u32 = 2;
+
+const STATS_COLOR: Color = Color::srgb(0.7, 0.85, 0.9);
+
+pub struct SummaryPlugin;
+
+impl Plugin for SummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SessionStats>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        restart_stats.run_if(resource_changed::<ActiveFile>),
+                        count_refinements,
+                    )
+                        .chain()
+                        .after(RefineSet::React),
+                    summarize_finale.run_if(on_event::<FinaleEnded>),
+                    choose.run_if(in_state(AppState::Summary)),
+                ),
+            )
+            .add_systems(
+                OnEnter(AppState::Summary),
+                (end_session, spawn_summary).chain(),
+            );
+    }
+}
+
+/// Tallies of the session on the open file.
+#[derive(Resource, Serialize, Clone, Debug, Default)]
+pub struct SessionStats {
+    pub file: String,
+    /// Seconds of [session time](Session) at which the file was opened.
+    #[serde(skip)]
+    started: f32,
+    /// Seconds the session lasted, kept once it ends.
+    pub duration: f32,
+    /// How complete the file was as the session ended.
+    pub completion: f32,
+    /// Numbers refined, scary or not.
+    pub refined: u32,
+    /// How many of the numbers refined were scary, per temper.
+    pub scary: Vec<(Temper, u32)>,
+    /// Clusters of scary numbers caught, as the detector in use judged them.
+    pub clusters: u32,
+    /// Refinements into a bin expecting another temper.
+    pub misses: u32,
+    /// Seconds into the session of each refinement, and how complete the file was after it.
+    pub progress: Vec<(f32, f32)>,
+}
+
+impl SessionStats {
+    fn new(file: &str, started: f32) -> Self {
+        Self {
+            file: file.to_string(),
+            started,
+            ..default()
+        }
+    }
+
+    /// Share of the numbers refined that were scary, or `None` before any were.
+    pub fn accuracy(&self) -> Option<f32> {
+        let scary: u32 = self.scary.iter().map(|(_, count)| count).sum();
+        (self.refined > 0).then(|| scary as f32 / self.refined as f32)
+    }
+
+    /// The file's completion at evenly spaced times across the session, `count` of them.
+    fn progress_over_time(&self, count: usize) -> Vec<f32> {
+        let mut points = self.progress.iter().peekable();
+        let mut completion = 0.;
+        (0..count)
+            .map(|index| {
+                let at = self.duration * index as f32 / (count - 1).max(1) as f32;
+                while let Some(&(_, after)) = points.next_if(|(time, _)| *time <= at) {
+                    completion = after;
+                }
+                completion
+            })
+            .collect()
+    }
+
+    /// A path for something exported about the session, in `folder` of the config directory,
+    /// named after the file and the moment.
+    pub fn export_path(&self, folder: &str, extension: &str) -> PathBuf {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let name: String = self
+            .file
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        config_dir()
+            .join(folder)
+            .join(format!("{name}-{stamp}.{extension}"))
+    }
+
+    /// Writes the summary to a new file among the exported ones, returning its path.
+    fn export(&self) -> io::Result<PathBuf> {
+        let path = self.export_path("summaries", "ron");
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+/// Starts the tallies afresh for another file.
+fn restart_stats(
+    time: Res<Time<Session>>,
+    file: Res<ActiveFile>,
+    mut stats: ResMut<SessionStats>,
+    mut last: Local<Option<(String, u64)>>,
+) {
+    // Sources that change how many bins there are change the file too, yet it is the same one.
+    let opened = Some((file.name.clone(), file.seed));
+    if *last == opened {
+        return;
+    }
+    *last = opened;
+    *stats = SessionStats::new(&file.name, time.elapsed_secs());
+}
+
+fn count_refinements(
+    time: Res<Time<Session>>,
+    model: Res<GridModel>,
+    mut refined: EventReader<Refined>,
+    (mut caught, mut missed): (EventReader<ClusterCaught>, EventReader<BinMissed>),
+    bins: Query<&Bin>,
+    mut stats: ResMut<SessionStats>,
+) {
+    stats.clusters += caught.read().count() as u32;
+    stats.misses += missed.read().count() as u32;
+    for event in refined.read() {
+        stats.refined += event.count;
+        let cells = (event.cells.min.y..=event.cells.max.y).flat_map(|row| {
+            (event.cells.min.x..=event.cells.max.x).map(move |col| Cell { col, row })
+        });
+        for state in cells.filter_map(|cell| model.get(cell)) {
+            if state.temper == Temper::Calm {
+                continue;
+            }
+            match stats
+                .scary
+                .iter_mut()
+                .find(|(temper, _)| *temper == state.temper)
+            {
+                Some((_, count)) => *count += 1,
+                None => stats.scary.push((state.temper, 1)),
+            }
+        }
+        let at = time.elapsed_secs() - stats.started;
+        stats.progress.push((at, completion(&bins)));
+    }
+}
+
+/// How complete the file is, going by its bins.
+fn completion(bins: &Query<&Bin>) -> f32 {
+    bins.iter().map(|bin| bin.progress).sum::<f32>() / bins.iter().len().max(1) as f32
+}
+
+/// Sums up the session once the finale of its file has played.
+fn summarize_finale(
+    state: Res<State<AppState>>,
+    mut ended: EventReader<FinaleEnded>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    ended.clear();
+    if matches!(state.get(), AppState::Refining | AppState::Paused) {
+        transitions.write(TransitionTo::new(AppState::Summary, TransitionEffect::Wipe));
+    }
+}
+
+/// Stops the clock on the session, and the grid behind the summary with it.
+fn end_session(
+    (mut time, session): (ResMut<Time<Virtual>>, Res<Time<Session>>),
+    bins: Query<&Bin>,
+    mut stats: ResMut<SessionStats>,
+) {
+    stats.duration = session.elapsed_secs() - stats.started;
+    stats.completion = completion(&bins);
+    time.pause();
+}
+
+/// Items of the summary screen, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SummaryItem {
+    Export,
+    Card,
+    NewFile,
+    MainMenu,
+    Quit,
+}
+
+impl SummaryItem {
+    const ALL: [SummaryItem; 5] = [
+        SummaryItem::Export,
+        SummaryItem::Card,
+        SummaryItem::NewFile,
+        SummaryItem::MainMenu,
+        SummaryItem::Quit,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SummaryItem::Export => "Export Summary",
+            SummaryItem::Card => "Save Result Card",
+            SummaryItem::NewFile => "New File",
+            SummaryItem::MainMenu => "Main Menu",
+            SummaryItem::Quit => "Quit",
+        }
+    }
+}
+
+/// Marks the summary screen's menu.
+#[derive(Component)]
+struct SummaryMenu;
+
+/// A length of time as minutes and seconds, or hours, minutes and seconds.
+pub fn clock_time(seconds: f32) -> String {
+    let seconds = seconds.max(0.) as u32;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+fn spawn_summary(mut commands: Commands, stats: Res<SessionStats>, kiosk: Option<Res<Kiosk>>) {
+    // Hide the grid behind the summary
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.0, 0.04, 0.05),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 19.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Summary),
+    ));
+
+    commands.spawn((
+        Text2d::new(stats.file.to_uppercase()),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        CanvasAnchor::TOP.offset(0., -12.),
+        Transform::from_xyz(0., 0., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Summary),
+    ));
+
+    let accuracy = match stats.accuracy() {
+        Some(accuracy) => format!("{:.0}%", accuracy * 100.),
+        None => "-".to_string(),
+    };
+    let tempers = if stats.scary.is_empty() {
+        "No scary numbers refined".to_string()
+    } else {
+        let counts: Vec<String> = stats
+            .scary
+            .iter()
+            .map(|(temper, count)| format!("{temper:?} {count}"))
+            .collect();
+        counts.join("  ")
+    };
+    let lines = format!(
+        "Time {}    Refined {}    Clusters {}    Accuracy {accuracy}\n{tempers}    Misses {}",
+        clock_time(stats.duration),
+        stats.refined,
+        stats.clusters,
+        stats.misses,
+    );
+    commands.spawn((
+        Text2d::new(lines),
+        TextFont {
+            font_size: 8.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(STATS_COLOR),
+        Transform::from_xyz(0., 68., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Summary),
+    ));
+
+    let mut sparkline = Sparkline::new(SPARKLINE_SIZE)
+        .with_spacing(SPARKLINE_SPACING)
+        .with_style(SparkStyle::Step)
+        .with_range(0., 1.);
+    let points = ((SPARKLINE_SIZE.x - 1) / SPARKLINE_SPACING + 1) as usize;
+    for completion in stats.progress_over_time(points) {
+        sparkline.push(completion);
+    }
+    commands.spawn((
+        sparkline,
+        Transform::from_xyz(0., 40., 20.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Summary),
+    ));
+
+    // A kiosk is not to be quit from its menus.
+    let entries = SummaryItem::ALL.map(|item| {
+        MenuEntry::new(item.label()).enabled(item != SummaryItem::Quit || kiosk.is_none())
+    });
+    spawn_menu(
+        &mut commands,
+        "SESSION SUMMARY",
+        &entries,
+        0,
+        Vec3::new(0., -50., 20.),
+        (SummaryMenu, StateScoped(AppState::Summary)),
+    );
+}
+
+fn choose(
+    mut chosen: EventReader<MenuChosen>,
+    menus: Query<(), With<SummaryMenu>>,
+    (stats, config, mut library): (Res<SessionStats>, Res<Config>, ResMut<FileLibrary>),
+    mut opens: EventWriter<OpenFile>,
+    (mut toasts, mut cards): (EventWriter<Toast>, EventWriter<SaveCard>),
+    mut transitions: EventWriter<TransitionTo>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in chosen.read() {
+        if !menus.contains(event.menu) {
+            continue;
+        }
+        match SummaryItem::ALL[event.item] {
+            SummaryItem::Export => match stats.export() {
+                Ok(path) => {
+                    info!("Exported session summary to {}", path.display());
+                    toasts.write(Toast::success("Summary exported"));
+                }
+                Err(error) => {
+                    error!("Could not export session summary: {error}");
+                    toasts.write(Toast::error("Could not export summary"));
+                }
+            },
+            SummaryItem::Card => {
+                cards.write(SaveCard);
+            }
+            SummaryItem::NewFile => {
+                opens.write(OpenFile(library.create(config.gameplay.bins)));
+                transitions.write(TransitionTo::new(
+                    AppState::Refining,
+                    TransitionEffect::Wipe,
+                ));
+            }
+            SummaryItem::MainMenu => {
+                transitions.write(TransitionTo::new(
+                    AppState::Menu,
+                    TransitionEffect::PowerOff,
+                ));
+            }
+            SummaryItem::Quit => {
+                exit.write(AppExit::Success);
+            }
+        }
+    }
+}
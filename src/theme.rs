@@ -0,0 +1,309 @@
+//! The look of the grid, loaded from a RON theme file.
+//!
+//! The file lives at `$MDR_THEME` if set, and otherwise at `theme.ron` next to the config. Like
+//! the config it is watched while the app runs, so that colors can be tuned by hand on a running
+//! wall display. Colors are written as sRGB `(red, green, blue)` triples from 0 to 1.
+//!
+//! The high-contrast setting swaps whatever theme is loaded for [`Theme::high_contrast`], and
+//! back again when it is turned off.
+
+use std::{env, path::PathBuf};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{config_dir, load_ron, Config, WatchedFile, WATCH_INTERVAL},
+    grid::Temper,
+};
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        let path = theme_path();
+        let theme = match load_ron(&path) {
+            Ok(Some(theme)) => {
+                info!("Loaded theme from {}", path.display());
+                theme
+            }
+            Ok(None) => Theme::default(),
+            Err(error) => {
+                warn!("Ignoring theme {}: {error}", path.display());
+                Theme::default()
+            }
+        };
+        app.insert_resource(theme.clone())
+            .insert_resource(ThemeFile {
+                file: WatchedFile::new(path),
+                loaded: theme,
+            })
+            .add_systems(
+                Update,
+                (
+                    reload_theme.run_if(on_real_timer(WATCH_INTERVAL)),
+                    choose_theme
+                        .run_if(resource_changed::<Config>.or(resource_changed::<ThemeFile>)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Colors and motion of the grid outside the Overtime Contingency, which has a palette of its
+/// own.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    /// Color behind the grid.
+    pub background: [f32; 3],
+    /// Patterns drawn faintly over the background.
+    pub backdrop: Backdrop,
+    /// Color of numbers outside the selection.
+    pub numbers: [f32; 3],
+    /// Color of selected numbers and of the selection box.
+    pub selection: [f32; 3],
+    /// How far numbers drift from their cells while idle, in pixels.
+    pub drift: f32,
+    /// Colors of toasts, by severity.
+    pub info: [f32; 3],
+    pub success: [f32; 3],
+    pub warning: [f32; 3],
+    pub error: [f32; 3],
+    /// Color of bins that have none of their own, or `None` for the usual cyan.
+    pub bins: Option<[f32; 3]>,
+    /// Width of the border around the selection box, in pixels, or 0 for none.
+    pub outline: f32,
+    /// How the numbers of each temper set themselves apart.
+    pub signatures: Signatures,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: [0.5, 0.5, 0.5],
+            backdrop: Backdrop::default(),
+            numbers: [1.0, 1.0, 1.0],
+            selection: [0.0, 0.9, 1.0],
+            drift: 1.5,
+            info: [0.0, 0.9, 1.0],
+            success: [0.4, 1.0, 0.5],
+            warning: [1.0, 0.8, 0.2],
+            error: [1.0, 0.3, 0.3],
+            bins: None,
+            outline: 0.,
+            signatures: Signatures::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// The theme for those who need the grid as legible as it gets: white numbers on black,
+    /// selected in yellow, with a thick border around the selection.
+    pub fn high_contrast() -> Self {
+        Self {
+            background: [0.0, 0.0, 0.0],
+            numbers: [1.0, 1.0, 1.0],
+            selection: [1.0, 0.9, 0.0],
+            info: [0.4, 0.9, 1.0],
+            success: [0.4, 1.0, 0.4],
+            warning: [1.0, 0.9, 0.0],
+            error: [1.0, 0.45, 0.45],
+            bins: Some([1.0, 0.9, 0.0]),
+            outline: 2.,
+            ..default()
+        }
+    }
+
+    pub fn background(&self) -> Color {
+        Color::srgb_from_array(self.background)
+    }
+
+    pub fn numbers(&self) -> Color {
+        Color::srgb_from_array(self.numbers)
+    }
+
+    pub fn selection(&self) -> Color {
+        Color::srgb_from_array(self.selection)
+    }
+}
+
+/// The patterns of the backdrop behind the grid, each drawn as strongly as given, from 0 for not
+/// at all to 1.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Backdrop {
+    /// Every other row of pixels darkened, like the scan texture of an old CRT.
+    pub scanlines: f32,
+    /// Corners darkened towards black.
+    pub vignette: f32,
+    /// The Lumon name, large in the middle.
+    pub watermark: f32,
+}
+
+/// The slight marks the numbers of a temper are set in, for a refiner with a feel for them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Signature {
+    /// Radians the numbers lean by, to the left for less than 0.
+    pub tilt: f32,
+    /// How much heavier the numbers are set, as a fraction of their size.
+    pub weight: f32,
+    /// Hue the numbers are shaded towards, in degrees around the color wheel.
+    pub hue: f32,
+    /// How far the numbers are shaded towards [`Signature::hue`], from 0 for none to 1.
+    pub tint: f32,
+}
+
+impl Signature {
+    /// The signature made `factor` times as marked.
+    pub fn scaled(self, factor: f32) -> Self {
+        Self {
+            tilt: self.tilt * factor,
+            weight: self.weight * factor,
+            tint: (self.tint * factor).min(1.),
+            ..self
+        }
+    }
+
+    /// `color` shaded towards the signature's hue.
+    pub fn shade(self, color: Color) -> Color {
+        if self.tint <= 0. {
+            return color;
+        }
+        let lightness = Hsla::from(color).lightness;
+        color.mix(&Color::hsl(self.hue, 1., lightness), self.tint)
+    }
+}
+
+/// The [`Signature`] of each temper of scary numbers. Calm numbers have none.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Signatures {
+    pub woe: Signature,
+    pub frolic: Signature,
+    pub dread: Signature,
+    pub malice: Signature,
+    /// How many times as marked the numbers of a cluster are once the cursor has found it.
+    pub noticed: f32,
+}
+
+impl Default for Signatures {
+    fn default() -> Self {
+        Self {
+            woe: Signature {
+                tilt: -0.05,
+                weight: 0.,
+                hue: 215.,
+                tint: 0.06,
+            },
+            frolic: Signature {
+                tilt: 0.06,
+                weight: 0.04,
+                hue: 50.,
+                tint: 0.06,
+            },
+            dread: Signature {
+                tilt: 0.,
+                weight: 0.08,
+                hue: 275.,
+                tint: 0.06,
+            },
+            malice: Signature {
+                tilt: 0.03,
+                weight: 0.06,
+                hue: 0.,
+                tint: 0.08,
+            },
+            noticed: 2.5,
+        }
+    }
+}
+
+impl Signatures {
+    /// The signature of a temper, made more marked if its cluster has been noticed.
+    pub fn of(&self, temper: Temper, noticed: bool) -> Signature {
+        let signature = match temper {
+            Temper::Calm => return Signature::default(),
+            Temper::Woe => self.woe,
+            Temper::Frolic => self.frolic,
+            Temper::Dread => self.dread,
+            Temper::Malice => self.malice,
+        };
+        if noticed {
+            signature.scaled(self.noticed)
+        } else {
+            signature
+        }
+    }
+}
+
+/// How far apart two colors are in lightness, from 1 for the same to 21 for black on white, as
+/// the WCAG works it out.
+pub fn contrast(a: Color, b: Color) -> f32 {
+    let luminance = |color: Color| {
+        let color = color.to_linear();
+        0.2126 * color.red + 0.7152 * color.green + 0.0722 * color.blue
+    };
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Lightens or darkens `color`, whichever stands out more, until it has at least `ratio` of
+/// [`contrast`] against `background`.
+pub fn with_contrast(color: Color, background: Color, ratio: f32) -> Color {
+    let towards = if contrast(Color::WHITE, background) > contrast(Color::BLACK, background) {
+        Color::WHITE
+    } else {
+        Color::BLACK
+    };
+    (0..=10)
+        .map(|step| color.mix(&towards, step as f32 / 10.))
+        .find(|mixed| contrast(*mixed, background) >= ratio)
+        .unwrap_or(towards)
+}
+
+/// The theme file, watched for changes.
+#[derive(Resource)]
+struct ThemeFile {
+    file: WatchedFile,
+    /// The theme as last read from the file, shown unless high contrast is on.
+    loaded: Theme,
+}
+
+fn theme_path() -> PathBuf {
+    match env::var_os("MDR_THEME") {
+        Some(path) => path.into(),
+        None => config_dir().join("theme.ron"),
+    }
+}
+
+fn reload_theme(mut file: ResMut<ThemeFile>) {
+    // Looking for changes is no change of its own.
+    if !file.bypass_change_detection().file.changed() {
+        return;
+    }
+    let path = file.file.path();
+    let loaded = match load_ron(path) {
+        Ok(Some(loaded)) => {
+            info!("Reloaded theme from {}", path.display());
+            loaded
+        }
+        // Removing the file goes back to the default look.
+        Ok(None) => Theme::default(),
+        Err(error) => {
+            warn!("Ignoring changed theme {}: {error}", path.display());
+            return;
+        }
+    };
+    file.loaded = loaded;
+}
+
+fn choose_theme(config: Res<Config>, file: Res<ThemeFile>, mut theme: ResMut<Theme>) {
+    let chosen = if config.accessibility.high_contrast {
+        Theme::high_contrast()
+    } else {
+        file.loaded.clone()
+    };
+    theme.set_if_neq(chosen);
+}
@@ -0,0 +1,270 @@
+//! Short messages that slide in at the right of the canvas and slide out again: milestones,
+//! saves, and sources or brokers going away.
+//!
+//! Anything can show one by writing a [`Toast`] event. At most [`MAX_VISIBLE`] are on screen at
+//! once, stacked under the minimap, and the rest wait their turn. Each is edged in its
+//! severity's color from the theme, and errors stay up the longest.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    canvas::{CanvasAnchor, CanvasSize, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    theme::Theme,
+    tween::{Tween, TweenDone},
+};
+
+/// Most toasts on screen at once.
+const MAX_VISIBLE: usize = 3;
+
+/// Most toasts waiting for room; the oldest are dropped past this.
+const MAX_WAITING: usize = 8;
+
+/// Longest text shown on a toast.
+const TOAST_LENGTH: usize = 26;
+
+const TOAST_SIZE: Vec2 = Vec2::new(128., 12.);
+const TOAST_GAP: f32 = 2.;
+
+/// Seconds a toast takes to slide in or out.
+const SLIDE_TIME: f32 = 0.3;
+
+/// How far off the canvas toasts slide.
+const OFFSTAGE: f32 = TOAST_SIZE.x + 4.;
+
+/// How quickly toasts close the gap left by one that went, per second.
+const RESTACK_RATE: f32 = 12.;
+
+const BACKING_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Toast>()
+            .init_resource::<WaitingToasts>()
+            .add_systems(
+                Update,
+                (queue_toasts, show_toasts, slide_toasts, remove_toasts).chain(),
+            );
+    }
+}
+
+/// How much a toast matters, which sets its color and how long it stays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self, theme: &Theme) -> Color {
+        Color::srgb_from_array(match self {
+            Self::Info => theme.info,
+            Self::Success => theme.success,
+            Self::Warning => theme.warning,
+            Self::Error => theme.error,
+        })
+    }
+
+    /// Seconds a toast stays, sliding included.
+    fn lifetime(self) -> f32 {
+        match self {
+            Self::Info | Self::Success => 3.,
+            Self::Warning => 4.5,
+            Self::Error => 6.,
+        }
+    }
+}
+
+/// A message to show for a moment.
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub struct Toast {
+    pub text: String,
+    pub severity: Severity,
+}
+
+impl Toast {
+    pub fn new(severity: Severity, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            severity,
+        }
+    }
+
+    pub fn info(text: impl Into<String>) -> Self {
+        Self::new(Severity::Info, text)
+    }
+
+    pub fn success(text: impl Into<String>) -> Self {
+        Self::new(Severity::Success, text)
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, text)
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self::new(Severity::Error, text)
+    }
+}
+
+/// Toasts waiting for room on screen, oldest first.
+#[derive(Resource, Default)]
+struct WaitingToasts(VecDeque<Toast>);
+
+/// A toast on screen.
+#[derive(Component)]
+struct ToastCard {
+    /// Seconds since it came in.
+    elapsed: f32,
+    lifetime: f32,
+    /// Whether it is sliding out.
+    leaving: bool,
+}
+
+/// Slides a card across from `from` to `to`.
+fn slide(from: f32, to: f32) -> Tween<Transform> {
+    Tween::new(SLIDE_TIME, move |transform: &mut Transform, t| {
+        transform.translation.x = from + (to - from) * t;
+    })
+}
+
+/// Where the card in a slot rests.
+fn rest(slot: usize, canvas: CanvasSize) -> Vec2 {
+    let below = slot as f32 * (TOAST_SIZE.y + TOAST_GAP);
+    // Under the minimap, which is lower on a portrait canvas.
+    CanvasAnchor::TOP_RIGHT
+        .offset(-4., -74. - below)
+        .in_portrait(CanvasAnchor::TOP_RIGHT.offset(-4., -88. - below))
+        .position(canvas)
+}
+
+fn queue_toasts(mut toasts: EventReader<Toast>, mut waiting: ResMut<WaitingToasts>) {
+    for toast in toasts.read() {
+        // The same message twice in a row says nothing new.
+        if waiting.0.back() == Some(toast) {
+            continue;
+        }
+        info!("Toast: {}", toast.text);
+        waiting.0.push_back(toast.clone());
+        if waiting.0.len() > MAX_WAITING {
+            waiting.0.pop_front();
+        }
+    }
+}
+
+/// Brings in waiting toasts while there is room.
+fn show_toasts(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    canvas: Res<CanvasSize>,
+    mut waiting: ResMut<WaitingToasts>,
+    cards: Query<(), With<ToastCard>>,
+) {
+    let mut slot = cards.iter().len();
+    while slot < MAX_VISIBLE {
+        let Some(toast) = waiting.0.pop_front() else {
+            return;
+        };
+        let color = toast.severity.color(&theme);
+        let text = if toast.text.chars().count() > TOAST_LENGTH {
+            let cut: String = toast.text.chars().take(TOAST_LENGTH - 2).collect();
+            cut + ".."
+        } else {
+            toast.text
+        };
+        let rest = rest(slot, *canvas);
+        commands
+            .spawn((
+                ToastCard {
+                    elapsed: 0.,
+                    lifetime: toast.severity.lifetime(),
+                    leaving: false,
+                },
+                slide(rest.x + OFFSTAGE, rest.x),
+                Sprite {
+                    color: BACKING_COLOR,
+                    custom_size: Some(TOAST_SIZE),
+                    anchor: Anchor::TopRight,
+                    ..default()
+                },
+                Transform::from_xyz(rest.x + OFFSTAGE, rest.y, 25.),
+                PIXEL_PERFECT_LAYERS,
+            ))
+            .with_children(|card| {
+                card.spawn((
+                    Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(2., TOAST_SIZE.y)),
+                        anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    Transform::from_xyz(-TOAST_SIZE.x, 0., 0.1),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+                card.spawn((
+                    Text2d::new(text),
+                    TextFont {
+                        font_size: 8.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                    Anchor::CenterLeft,
+                    Transform::from_xyz(-TOAST_SIZE.x + 5., -TOAST_SIZE.y / 2., 0.1),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            });
+        slot += 1;
+    }
+}
+
+/// Slides toasts out once their time is nearly up, and moves the ones left up into the room
+/// made.
+fn slide_toasts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    canvas: Res<CanvasSize>,
+    mut cards: Query<(Entity, &mut ToastCard, &mut Transform)>,
+) {
+    let delta = time.delta_secs();
+    let mut cards: Vec<_> = cards.iter_mut().collect();
+    // The oldest toast is at the top.
+    cards.sort_by(|a, b| b.1.elapsed.total_cmp(&a.1.elapsed));
+    for (slot, (entity, mut card, mut transform)) in cards.into_iter().enumerate() {
+        card.elapsed += delta;
+        let rest = rest(slot, *canvas);
+        if !card.leaving && card.elapsed >= card.lifetime - SLIDE_TIME {
+            card.leaving = true;
+            let from = transform.translation.x;
+            commands
+                .entity(entity)
+                .insert(slide(from, rest.x + OFFSTAGE));
+        }
+        transform.translation.y = if config.accessibility.reduced_motion {
+            rest.y
+        } else {
+            let step = (1. - (-RESTACK_RATE * delta).exp()).min(1.);
+            transform.translation.y + (rest.y - transform.translation.y) * step
+        };
+    }
+}
+
+/// Takes away toasts that have slid out.
+fn remove_toasts(
+    mut commands: Commands,
+    mut done: EventReader<TweenDone>,
+    cards: Query<&ToastCard>,
+) {
+    for &TweenDone(entity) in done.read() {
+        if cards.get(entity).is_ok_and(|card| card.leaving) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
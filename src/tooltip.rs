@@ -0,0 +1,126 @@
+//! Assist tooltip: after a moment over a number, a tag by the cursor gives its coordinates, and
+//! below the hard difficulty a glyph for its temper.
+//!
+//! Turned on from the accessibility settings. It follows the [`Hovered`] number, waiting out
+//! [`TOOLTIP_DELAY`] each time the cursor moves onto another, and stays out of the way while a
+//! mouse button is held to drag out a selection or pan.
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    canvas::{CanvasCursor, PIXEL_PERFECT_LAYERS},
+    config::{Config, Difficulty},
+    grid::{GridModel, RefineSet},
+    picking::{HoverChanged, Hovered},
+    state::AppState,
+    theme::Theme,
+};
+
+/// Seconds the cursor rests on a number before its tooltip shows.
+const TOOLTIP_DELAY: f32 = 0.6;
+
+/// Where the tooltip sits from the cursor, to the lower right of its tip.
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(6., -8.);
+
+const TOOLTIP_HEIGHT: f32 = 9.;
+
+/// Width a character of the tooltip takes up.
+const CHAR_WIDTH: f32 = 4.5;
+
+const BACKING_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+
+pub struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_tooltip)
+            .add_systems(Update, show_tooltip.after(RefineSet::Sync));
+    }
+}
+
+/// Marks the tooltip's backing.
+#[derive(Component)]
+struct Tooltip;
+
+/// Marks the tooltip's text.
+#[derive(Component)]
+struct TooltipText;
+
+fn setup_tooltip(mut commands: Commands) {
+    commands
+        .spawn((
+            Tooltip,
+            Sprite {
+                color: BACKING_COLOR,
+                custom_size: Some(Vec2::new(0., TOOLTIP_HEIGHT)),
+                anchor: Anchor::TopLeft,
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 28.),
+            Visibility::Hidden,
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_children(|tooltip| {
+            tooltip.spawn((
+                TooltipText,
+                Text2d::default(),
+                TextFont {
+                    font_size: 7.0,
+                    ..default()
+                },
+                Anchor::CenterLeft,
+                Transform::from_xyz(2., -TOOLTIP_HEIGHT / 2., 0.1),
+                PIXEL_PERFECT_LAYERS,
+            ));
+        });
+}
+
+fn show_tooltip(
+    time: Res<Time<Real>>,
+    (config, theme, state): (Res<Config>, Res<Theme>, Res<State<AppState>>),
+    (hovered, model, mut changed): (Res<Hovered>, Res<GridModel>, EventReader<HoverChanged>),
+    mut since: Local<f32>,
+    (pointer, buttons): (CanvasCursor, Res<ButtonInput<MouseButton>>),
+    mut tooltip: Single<(&mut Sprite, &mut Transform, &mut Visibility), With<Tooltip>>,
+    mut text: Single<(&mut Text2d, &mut TextColor), With<TooltipText>>,
+) {
+    if changed.read().last().is_some() {
+        *since = time.elapsed_secs();
+    }
+    let (sprite, transform, visibility) = &mut *tooltip;
+    let cursor = pointer.world();
+    let cell = hovered.cell.filter(|_| {
+        config.accessibility.tooltips
+            && *state.get() == AppState::Refining
+            && buttons.get_pressed().next().is_none()
+            && time.elapsed_secs() - *since >= TOOLTIP_DELAY
+    });
+    let (Some(cell), Some(cursor)) = (cell, cursor) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    let mut label = format!("{},{}", cell.col, cell.row);
+    let glyph = model.get(cell).and_then(|state| state.temper.glyph());
+    if let Some(glyph) = glyph.filter(|_| config.gameplay.difficulty != Difficulty::Hard) {
+        label.push(' ');
+        label.push(glyph);
+    }
+    let width = label.chars().count() as f32 * CHAR_WIDTH + 3.;
+    // Kept on the canvas, flipping to the other side of the cursor near the right and bottom.
+    let half = pointer.canvas().half();
+    let mut position = cursor + TOOLTIP_OFFSET;
+    if position.x + width > half.x {
+        position.x = cursor.x - TOOLTIP_OFFSET.x - width;
+    }
+    if position.y - TOOLTIP_HEIGHT < -half.y {
+        position.y = cursor.y - TOOLTIP_OFFSET.y + TOOLTIP_HEIGHT;
+    }
+    let (label_text, color) = &mut *text;
+    if label_text.0 != label {
+        label_text.0 = label;
+        sprite.custom_size = Some(Vec2::new(width, TOOLTIP_HEIGHT));
+    }
+    color.set_if_neq(TextColor(Color::srgb_from_array(theme.info)));
+    transform.translation = position.round().extend(transform.translation.z);
+    visibility.set_if_neq(Visibility::Inherited);
+}
@@ -0,0 +1,226 @@
+//! Animated transitions between [`AppState`]s.
+//!
+//! Screens change state by writing a [`TransitionTo`] instead of setting [`NextState`] directly.
+//! The screen is covered by an overlay on the canvas, the state changes while it is fully covered,
+//! and the overlay then uncovers the new screen. Transitions run on real time, so they play even
+//! while the virtual clock is paused.
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::{CanvasSize, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    state::AppState,
+};
+
+/// Seconds taken to cover the screen, and again to uncover it.
+const HALF_DURATION: f32 = 0.3;
+
+/// Height of the bright line a powered-off screen collapses into.
+const SCANLINE_HEIGHT: f32 = 2.;
+
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransitionTo>()
+            .add_systems(Update, (start_transitions, animate_transition).chain());
+    }
+}
+
+/// How the screen is covered and uncovered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransitionEffect {
+    /// Fade out to black and back in.
+    #[default]
+    Fade,
+    /// A black curtain sweeps across from left to right.
+    Wipe,
+    /// The picture collapses into a line like an old CRT being switched off, then powers on.
+    PowerOff,
+}
+
+/// Request to go to another state through a transition.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TransitionTo {
+    pub state: AppState,
+    pub effect: TransitionEffect,
+}
+
+impl TransitionTo {
+    pub fn new(state: AppState, effect: TransitionEffect) -> Self {
+        Self { state, effect }
+    }
+}
+
+/// The transition being played.
+#[derive(Resource)]
+pub struct ActiveTransition {
+    to: AppState,
+    effect: TransitionEffect,
+    elapsed: f32,
+    switched: bool,
+}
+
+/// Run condition that holds while a transition is playing, when screens should ignore input.
+pub fn in_transition(transition: Option<Res<ActiveTransition>>) -> bool {
+    transition.is_some()
+}
+
+/// Parts of the transition overlay.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum Overlay {
+    /// Covers the whole screen, or the part a wipe has reached.
+    Curtain,
+    /// Closes in from the top.
+    Top,
+    /// Closes in from the bottom.
+    Bottom,
+    /// The glowing line of a powered-off screen.
+    Scanline,
+}
+
+fn start_transitions(
+    mut commands: Commands,
+    mut requests: EventReader<TransitionTo>,
+    active: Option<Res<ActiveTransition>>,
+    config: Res<Config>,
+) {
+    // Only the first request counts; anything chosen mid-transition is dropped.
+    let request = requests.read().next().copied();
+    requests.clear();
+    let Some(request) = request else {
+        return;
+    };
+    if active.is_some() {
+        return;
+    }
+    let effect = if config.accessibility.reduced_motion {
+        TransitionEffect::Fade
+    } else {
+        request.effect
+    };
+    commands.insert_resource(ActiveTransition {
+        to: request.state,
+        effect,
+        elapsed: 0.,
+        switched: false,
+    });
+
+    for (overlay, color) in [
+        (Overlay::Curtain, Color::BLACK),
+        (Overlay::Top, Color::BLACK),
+        (Overlay::Bottom, Color::BLACK),
+        (Overlay::Scanline, Color::srgb(0.8, 1.0, 1.0)),
+    ] {
+        commands.spawn((
+            overlay,
+            Sprite {
+                color,
+                custom_size: Some(Vec2::ZERO),
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 60.),
+            PIXEL_PERFECT_LAYERS,
+        ));
+    }
+}
+
+/// Where an overlay part is and how big, for a transition that has covered `cover` of the screen
+/// (0 is uncovered, 1 fully covered) of a canvas of the given size. `covering` is false on the
+/// way back.
+fn overlay_rect(
+    canvas: CanvasSize,
+    effect: TransitionEffect,
+    overlay: Overlay,
+    cover: f32,
+    covering: bool,
+) -> (Rect, f32) {
+    let (width, height) = (canvas.size().x, canvas.size().y);
+    let screen = Rect::from_center_size(Vec2::ZERO, Vec2::new(width, height));
+    let none = (Rect::default(), 0.);
+    match (effect, overlay) {
+        (TransitionEffect::Fade, Overlay::Curtain) => (screen, cover),
+        (TransitionEffect::Wipe, Overlay::Curtain) => {
+            // The curtain enters from the left and leaves to the right.
+            let left = -width / 2.;
+            if covering {
+                (
+                    Rect::new(left, -height / 2., left + width * cover, height / 2.),
+                    1.,
+                )
+            } else {
+                (
+                    Rect::new(
+                        left + width * (1. - cover),
+                        -height / 2.,
+                        width / 2.,
+                        height / 2.,
+                    ),
+                    1.,
+                )
+            }
+        }
+        (TransitionEffect::PowerOff, Overlay::Top | Overlay::Bottom) => {
+            let bar = (height - SCANLINE_HEIGHT) / 2. * (cover * 1.25).min(1.);
+            let rect = if overlay == Overlay::Top {
+                Rect::new(-width / 2., height / 2. - bar, width / 2., height / 2.)
+            } else {
+                Rect::new(-width / 2., -height / 2., width / 2., -height / 2. + bar)
+            };
+            (rect, 1.)
+        }
+        (TransitionEffect::PowerOff, Overlay::Scanline) => {
+            // Once the bars have closed, the remaining line shrinks to nothing.
+            let shrink = ((cover - 0.8) / 0.2).clamp(0., 1.);
+            if cover < 0.8 {
+                return none;
+            }
+            let length = width * (1. - shrink);
+            (
+                Rect::from_center_size(Vec2::ZERO, Vec2::new(length, SCANLINE_HEIGHT)),
+                1.,
+            )
+        }
+        (TransitionEffect::PowerOff, Overlay::Curtain) => {
+            (screen, if cover >= 1. { 1. } else { 0. })
+        }
+        _ => none,
+    }
+}
+
+fn animate_transition(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    canvas: Res<CanvasSize>,
+    transition: Option<ResMut<ActiveTransition>>,
+    mut overlays: Query<(Entity, &Overlay, &mut Sprite, &mut Transform)>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let Some(mut transition) = transition else {
+        return;
+    };
+    transition.elapsed += time.delta_secs();
+
+    let progress = transition.elapsed / HALF_DURATION;
+    if progress >= 1. && !transition.switched {
+        transition.switched = true;
+        next.set(transition.to);
+    }
+    if progress >= 2. {
+        commands.remove_resource::<ActiveTransition>();
+        for (entity, ..) in &overlays {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let covering = progress < 1.;
+    let cover = if covering { progress } else { 2. - progress };
+    for (_, overlay, mut sprite, mut transform) in &mut overlays {
+        let (rect, alpha) = overlay_rect(*canvas, transition.effect, *overlay, cover, covering);
+        sprite.custom_size = Some(rect.size());
+        sprite.color.set_alpha(alpha);
+        transform.translation = rect.center().extend(transform.translation.z);
+    }
+}
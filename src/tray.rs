@@ -0,0 +1,279 @@
+//! The tray icon, with quick actions: showing and hiding the window, dimming the screen like a
+//! screensaver, pausing the bins' data source, and quitting.
+//!
+//! Turned on in the tray config, which names the program that shows the icon, yad by default,
+//! where the desktop has a tray. The program runs for as long as the icon is on, and what it
+//! offers is read from the app every frame and sent to it whenever it changes, so the menu says
+//! what each action would do even when the window was hidden, or shown, some other way. Clicking
+//! the icon shows or hides the window. A kiosk offers no way to quit here either.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use bevy::{app::AppExit, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    config::Config,
+    idle::Idle,
+    kiosk::Kiosk,
+    source::{ActiveSource, PollingPaused},
+    toast::Toast,
+};
+
+pub struct TrayPlugin;
+
+impl Plugin for TrayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_tray.run_if(resource_changed::<Config>),
+                (take_actions, sync_tray)
+                    .chain()
+                    .run_if(resource_exists::<Tray>),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// What can be picked from the tray, by the word the program writes for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TrayAction {
+    ToggleWindow,
+    ToggleScreensaver,
+    TogglePolling,
+    Quit,
+}
+
+impl TrayAction {
+    fn word(self) -> &'static str {
+        match self {
+            TrayAction::ToggleWindow => "window",
+            TrayAction::ToggleScreensaver => "screensaver",
+            TrayAction::TogglePolling => "polling",
+            TrayAction::Quit => "quit",
+        }
+    }
+
+    fn from_word(word: &str) -> Option<Self> {
+        [
+            TrayAction::ToggleWindow,
+            TrayAction::ToggleScreensaver,
+            TrayAction::TogglePolling,
+            TrayAction::Quit,
+        ]
+        .into_iter()
+        .find(|action| action.word() == word)
+    }
+}
+
+/// The program showing the icon, and what it was last told to offer.
+#[derive(Resource)]
+struct Tray {
+    child: Child,
+    input: ChildStdin,
+    /// Actions picked since the last frame.
+    picked: Arc<Mutex<Vec<TrayAction>>>,
+    /// The command it was started with, to start again should the config change it.
+    command: String,
+    shown: Option<TrayState>,
+}
+
+impl Drop for Tray {
+    fn drop(&mut self) {
+        // The icon would otherwise stay in the tray after the app has gone.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// What the app is doing, as far as the tray's menu is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TrayState {
+    visible: bool,
+    dozing: bool,
+    /// Whether polling is paused, if there is a source to poll.
+    paused: Option<bool>,
+    kiosk: bool,
+}
+
+impl TrayState {
+    /// The menu, in the `menu:` line the program takes.
+    fn menu(self) -> String {
+        let window = if self.visible {
+            "Hide window"
+        } else {
+            "Show window"
+        };
+        let screensaver = if self.dozing {
+            "Wake screen"
+        } else {
+            "Start screensaver"
+        };
+        let mut items = vec![
+            (window, TrayAction::ToggleWindow),
+            (screensaver, TrayAction::ToggleScreensaver),
+        ];
+        match self.paused {
+            Some(true) => items.push(("Resume data polling", TrayAction::TogglePolling)),
+            Some(false) => items.push(("Pause data polling", TrayAction::TogglePolling)),
+            None => {}
+        }
+        if !self.kiosk {
+            items.push(("Quit", TrayAction::Quit));
+        }
+        let items: Vec<_> = items
+            .into_iter()
+            .map(|(label, action)| format!("{label}!echo {}", action.word()))
+            .collect();
+        format!("menu:{}", items.join("|"))
+    }
+
+    fn tooltip(self) -> &'static str {
+        match (self.visible, self.paused) {
+            (_, Some(true)) => "tooltip:Macrodata Refinement (data paused)",
+            (false, _) => "tooltip:Macrodata Refinement (hidden)",
+            (true, _) => "tooltip:Macrodata Refinement",
+        }
+    }
+}
+
+/// Starts the program with `command`, and reads what is picked from it on a thread of its own.
+fn start(command: &str) -> io::Result<Tray> {
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no command given"))?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut input = child.stdin.take().ok_or(io::ErrorKind::BrokenPipe)?;
+    let output = child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?;
+    // A click on the icon itself shows or hides the window.
+    writeln!(input, "action:echo {}", TrayAction::ToggleWindow.word())?;
+    let picked = Arc::new(Mutex::new(Vec::new()));
+    let sink = picked.clone();
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            match TrayAction::from_word(line.trim()) {
+                Some(action) => sink.lock().unwrap().push(action),
+                None => debug!("Ignoring {line:?} from the tray"),
+            }
+        }
+    });
+    Ok(Tray {
+        child,
+        input,
+        picked,
+        command: command.to_string(),
+        shown: None,
+    })
+}
+
+/// Starts or stops the program as the icon is turned on and off.
+fn start_tray(
+    mut commands: Commands,
+    config: Res<Config>,
+    tray: Option<Res<Tray>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let running = tray.is_some_and(|tray| tray.command == config.tray.command);
+    if !config.tray.enabled {
+        commands.remove_resource::<Tray>();
+        return;
+    }
+    if running {
+        return;
+    }
+    match start(&config.tray.command) {
+        Ok(tray) => {
+            info!("Showing a tray icon with {}", config.tray.command);
+            commands.insert_resource(tray);
+        }
+        Err(error) => {
+            warn!(
+                "Could not show a tray icon with {}: {error}",
+                config.tray.command
+            );
+            commands.remove_resource::<Tray>();
+            toasts.write(Toast::warning("Could not show tray icon"));
+        }
+    }
+}
+
+/// Does what was picked from the tray.
+fn take_actions(
+    mut commands: Commands,
+    tray: Res<Tray>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    mut idle: ResMut<Idle>,
+    (source, paused): (Option<Res<ActiveSource>>, Option<Res<PollingPaused>>),
+    kiosk: Option<Res<Kiosk>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let picked = std::mem::take(&mut *tray.picked.lock().unwrap());
+    for action in picked {
+        match action {
+            TrayAction::ToggleWindow => {
+                window.visible ^= true;
+                if window.visible {
+                    window.focused = true;
+                }
+            }
+            TrayAction::ToggleScreensaver => {
+                let dozing = idle.dozing();
+                idle.set_dozing(!dozing);
+            }
+            TrayAction::TogglePolling if source.is_some() => {
+                if paused.is_some() {
+                    commands.remove_resource::<PollingPaused>();
+                } else {
+                    commands.insert_resource(PollingPaused);
+                }
+            }
+            TrayAction::TogglePolling => {}
+            TrayAction::Quit if kiosk.is_none() => {
+                exit.write(AppExit::Success);
+            }
+            TrayAction::Quit => {}
+        }
+    }
+}
+
+/// Tells the program what to offer whenever it changes.
+fn sync_tray(
+    mut commands: Commands,
+    mut tray: ResMut<Tray>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    idle: Res<Idle>,
+    (source, paused): (Option<Res<ActiveSource>>, Option<Res<PollingPaused>>),
+    kiosk: Option<Res<Kiosk>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let state = TrayState {
+        visible: window.visible,
+        dozing: idle.dozing(),
+        paused: source.map(|_| paused.is_some()),
+        kiosk: kiosk.is_some(),
+    };
+    if tray.shown == Some(state) {
+        return;
+    }
+    tray.shown = Some(state);
+    let sent = writeln!(tray.input, "{}", state.menu())
+        .and_then(|()| writeln!(tray.input, "{}", state.tooltip()))
+        .and_then(|()| tray.input.flush());
+    if let Err(error) = sent {
+        warn!("The tray icon has gone: {error}");
+        commands.remove_resource::<Tray>();
+        toasts.write(Toast::warning("Tray icon closed"));
+    }
+}
@@ -0,0 +1,366 @@
+//! The first-run tutorial: a few steps over the first file opened, each waiting on the refiner
+//! to do what it asks rather than on a timer.
+//!
+//! It frames the grid until the cursor is moved over a number, sets the nearest cluster of
+//! scary numbers wiggling until one of them is found, waits for a box to be dragged around it,
+//! then points at the bins until the selection is refined into one. Once it is done, or skipped
+//! with T, the config is saved with it turned off, so that it runs only the once. Nothing of it
+//! shows while a replay plays back, a shared session is joined, or the bins are driven by
+//! something other than refinement.
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::{Bin, DrivenBins},
+    canvas::{CanvasAnchor, CanvasSize, GridCamera, PIXEL_PERFECT_LAYERS},
+    config::{save_config, Config, ConfigPath},
+    files::ActiveFile,
+    grid::{clusters, Cell, GridModel, Number, RefineSet, Refined, Selection},
+    macros::MacroPlayback,
+    net::SharedSession,
+    picking::HoverChanged,
+    replay::Playback,
+    signature::Tilt,
+    snapshot::RestoredSnapshot,
+    state::AppState,
+    theme::Theme,
+};
+
+/// Seconds the last word of the tutorial stays up.
+const DONE_TIME: f32 = 4.;
+
+/// Wiggles per second of the cluster shown, and how far it turns each way, in radians.
+const WIGGLE_RATE: f32 = 3.;
+const WIGGLE_ANGLE: f32 = 0.25;
+
+/// Pulses per second of the marks pointing things out.
+const MARK_PULSE_RATE: f32 = 1.2;
+
+const FRAME_WIDTH: f32 = 2.;
+const PANEL_SIZE: Vec2 = Vec2::new(260., 26.);
+const BACKING_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Refining),
+            start_tutorial.run_if(
+                not(resource_exists::<Tutorial>)
+                    .and(not(resource_exists::<Playback>))
+                    .and(not(resource_exists::<MacroPlayback>))
+                    .and(not(resource_exists::<RestoredSnapshot>))
+                    .and(not(resource_exists::<SharedSession>))
+                    .and(not(resource_exists::<DrivenBins>)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                (advance, skip.run_if(in_state(AppState::Refining)))
+                    .run_if(resource_exists::<Tutorial>),
+                // Once more as it ends, to take it down.
+                (show_step, wiggle_cluster, pulse_marks)
+                    .run_if(resource_exists::<Tutorial>.or(resource_removed::<Tutorial>)),
+            )
+                .chain()
+                .after(RefineSet::React),
+        );
+    }
+}
+
+/// A step of the tutorial, in the order they come.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// The grid is framed, until the cursor is over a number.
+    Grid,
+    /// A cluster wiggles, until the cursor is over one of its numbers.
+    Find,
+    /// Until a box is dragged around some of the cluster.
+    Select,
+    /// The bins are pointed at, until the selection is refined into one.
+    Refine,
+    /// The last word, for [`DONE_TIME`].
+    Done,
+}
+
+impl Step {
+    fn text(self) -> &'static str {
+        match self {
+            Step::Grid => "This is your file. Move the cursor over the numbers.",
+            Step::Find => "Some numbers are scary. Find one of those wiggling.",
+            Step::Select => "Drag a box around the scary numbers.",
+            Step::Refine => "Now refine them into a bin below.",
+            Step::Done => "Well done, refiner. The work is mysterious and important.",
+        }
+    }
+}
+
+/// The tutorial under way.
+#[derive(Resource)]
+struct Tutorial {
+    step: Step,
+    /// The cluster shown, which the later steps ask about.
+    cluster: Vec<Cell>,
+    /// Seconds the current step has been up.
+    elapsed: f32,
+}
+
+/// Marks the panel the steps are told in.
+#[derive(Component)]
+struct TutorialPanel;
+
+/// Marks the text of the panel.
+#[derive(Component)]
+struct TutorialText;
+
+/// Marks what the current step points out, taken down with the step.
+#[derive(Component)]
+struct TutorialMark;
+
+fn start_tutorial(
+    mut commands: Commands,
+    config: Res<Config>,
+    file: Res<ActiveFile>,
+    model: Res<GridModel>,
+    camera: Single<&Transform, With<GridCamera>>,
+) {
+    if !config.gameplay.tutorial {
+        return;
+    }
+    // The cluster nearest the middle of the view with something still to refine.
+    let view = camera.translation.truncate();
+    let distance = |cluster: &Vec<Cell>| {
+        let center =
+            cluster.iter().map(|cell| cell.position()).sum::<Vec2>() / cluster.len().max(1) as f32;
+        center.distance_squared(view)
+    };
+    let cluster = clusters(file.seed, model.size())
+        .into_iter()
+        .filter(|cluster| {
+            cluster
+                .iter()
+                .any(|cell| model.get(*cell).is_some_and(|state| !state.refined))
+        })
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+    // A file with nothing left to refine has nothing to show either.
+    let Some(cluster) = cluster else {
+        return;
+    };
+    info!("Starting the tutorial");
+    commands.insert_resource(Tutorial {
+        step: Step::Grid,
+        cluster,
+        elapsed: 0.,
+    });
+    commands
+        .spawn((
+            TutorialPanel,
+            Sprite::from_color(BACKING_COLOR, PANEL_SIZE),
+            CanvasAnchor::TOP.offset(0., -32.),
+            Transform::from_xyz(0., 0., 27.),
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_child((
+            TutorialText,
+            Text2d::default(),
+            TextFont {
+                font_size: 8.0,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Transform::from_xyz(0., 0., 0.1),
+            PIXEL_PERFECT_LAYERS,
+        ));
+}
+
+/// Moves on to the next step once the refiner has done what the current one asks.
+fn advance(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut tutorial: ResMut<Tutorial>,
+    (mut hovers, mut refined): (EventReader<HoverChanged>, EventReader<Refined>),
+    selection: Res<Selection>,
+    (mut config, path): (ResMut<Config>, Res<ConfigPath>),
+) {
+    tutorial.elapsed += time.delta_secs();
+    let cluster = &tutorial.cluster;
+    let hovered = hovers.read().filter_map(|event| event.0.cell).last();
+    let refined = refined.read().count() > 0;
+    let done = match tutorial.step {
+        Step::Grid => hovered.is_some(),
+        Step::Find => hovered.is_some_and(|cell| cluster.contains(&cell)),
+        Step::Select => cluster.iter().any(|cell| selection.contains(*cell)),
+        Step::Refine => refined,
+        Step::Done => tutorial.elapsed >= DONE_TIME,
+    };
+    if !done {
+        return;
+    }
+    tutorial.elapsed = 0.;
+    tutorial.step = match tutorial.step {
+        Step::Grid => Step::Find,
+        Step::Find => Step::Select,
+        Step::Select => Step::Refine,
+        Step::Refine => {
+            finish(&mut config, &path);
+            Step::Done
+        }
+        Step::Done => {
+            commands.remove_resource::<Tutorial>();
+            return;
+        }
+    };
+}
+
+/// Skips the rest of the tutorial with T.
+fn skip(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    tutorial: Res<Tutorial>,
+    (mut config, path): (ResMut<Config>, Res<ConfigPath>),
+) {
+    if !keys.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+    if tutorial.step != Step::Done {
+        finish(&mut config, &path);
+    }
+    commands.remove_resource::<Tutorial>();
+}
+
+/// Turns the tutorial off for good.
+fn finish(config: &mut Config, path: &ConfigPath) {
+    info!("Tutorial finished");
+    config.gameplay.tutorial = false;
+    save_config(config, path);
+}
+
+/// Tells the current step in the panel and puts up what it points out, and takes it all down
+/// once the tutorial is over.
+fn show_step(
+    mut commands: Commands,
+    (tutorial, mut shown): (Option<Res<Tutorial>>, Local<Option<Step>>),
+    (state, theme, canvas): (Res<State<AppState>>, Res<Theme>, Res<CanvasSize>),
+    mut panel: Single<(Entity, &mut Visibility), With<TutorialPanel>>,
+    mut text: Single<(&mut Text2d, &mut TextColor), With<TutorialText>>,
+    marks: Query<Entity, With<TutorialMark>>,
+    bins: Query<(&Transform, &Sprite), With<Bin>>,
+) {
+    let (panel, visibility) = &mut *panel;
+    let Some(tutorial) = tutorial else {
+        commands.entity(*panel).despawn();
+        for mark in &marks {
+            commands.entity(mark).despawn();
+        }
+        *shown = None;
+        return;
+    };
+    // Only over the grid, and not over the pause menu or settings.
+    visibility.set_if_neq(if *state.get() == AppState::Refining {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if *shown == Some(tutorial.step) {
+        return;
+    }
+    *shown = Some(tutorial.step);
+    let (label, color) = &mut *text;
+    label.0 = tutorial.step.text().to_string();
+    color.0 = Color::srgb_from_array(theme.info);
+    for mark in &marks {
+        commands.entity(mark).despawn();
+    }
+
+    let mark_color = theme.selection();
+    match tutorial.step {
+        Step::Grid => {
+            // A frame just inside the edges of the canvas.
+            let size = canvas.size() - 8.;
+            let half = size / 2. - FRAME_WIDTH / 2.;
+            let sides = [
+                (Vec2::new(0., half.y), Vec2::new(size.x, FRAME_WIDTH)),
+                (Vec2::new(0., -half.y), Vec2::new(size.x, FRAME_WIDTH)),
+                (Vec2::new(-half.x, 0.), Vec2::new(FRAME_WIDTH, size.y)),
+                (Vec2::new(half.x, 0.), Vec2::new(FRAME_WIDTH, size.y)),
+            ];
+            for (position, size) in sides {
+                commands.spawn((
+                    TutorialMark,
+                    Sprite::from_color(mark_color, size),
+                    Transform::from_translation(position.extend(26.)),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            }
+        }
+        Step::Refine => {
+            // An arrow over each bin.
+            for (transform, sprite) in &bins {
+                let height = sprite.custom_size.map_or(0., |size| size.y);
+                let position = transform.translation.truncate() + Vec2::Y * (height / 2. + 8.);
+                commands.spawn((
+                    TutorialMark,
+                    Text2d::new("v"),
+                    TextFont {
+                        font_size: 10.0,
+                        ..default()
+                    },
+                    TextColor(mark_color),
+                    Transform::from_translation(position.extend(26.)),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            }
+        }
+        Step::Find | Step::Select | Step::Done => {}
+    }
+}
+
+/// Wiggles the numbers of the cluster shown while they are to be found, and settles them back
+/// to their [`Tilt`] once they have been.
+fn wiggle_cluster(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    tutorial: Option<Res<Tutorial>>,
+    mut numbers: Query<(&Cell, &Tilt, &mut Transform), With<Number>>,
+) {
+    // A steady lean rather than a wiggle for those who asked for less motion.
+    let wiggle = if config.accessibility.reduced_motion {
+        WIGGLE_ANGLE / 2.
+    } else {
+        WIGGLE_ANGLE * (time.elapsed_secs() * WIGGLE_RATE * std::f32::consts::TAU).sin()
+    };
+    for (cell, tilt, mut transform) in &mut numbers {
+        let angle = match &tutorial {
+            Some(tutorial) if !tutorial.cluster.contains(cell) => continue,
+            Some(tutorial) if tutorial.step == Step::Find => tilt.0 + wiggle,
+            // Settled as the step moves on, and everything as the tutorial ends.
+            _ => tilt.0,
+        };
+        let rotation = Quat::from_rotation_z(angle);
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+fn pulse_marks(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut marks: Query<(Option<&mut Sprite>, Option<&mut TextColor>), With<TutorialMark>>,
+) {
+    let alpha = if config.accessibility.reduced_motion {
+        1.
+    } else {
+        0.6 + 0.4 * (time.elapsed_secs() * MARK_PULSE_RATE * std::f32::consts::TAU).cos()
+    };
+    for (sprite, text) in &mut marks {
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_alpha(alpha);
+        }
+        if let Some(mut text) = text {
+            text.0.set_alpha(alpha);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Tweens: a field of a component eased from one value to another over a fixed time.
+//!
+//! A [`Tween`] holds a function that sets the field for a point of the way, from 0 at the start
+//! to 1 at the end, and an [`Ease`] that shapes the way there. Inserting one on an entity starts
+//! it, replacing any tween of the same component; when it ends it is removed and a [`TweenDone`]
+//! is sent. Tweens run on real time, so they play while the game is paused, and end at once for
+//! those who asked for less motion.
+//!
+//! Tweens of [`Transform`], [`Sprite`] and [`TextColor`] work out of the box. Others need their
+//! component registered with [`RegisterTween::register_tween`].
+
+use std::marker::PhantomData;
+
+use bevy::{ecs::component::Mutable, prelude::*};
+
+use crate::config::Config;
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TweenDone>()
+            .register_tween::<Transform>()
+            .register_tween::<Sprite>()
+            .register_tween::<TextColor>();
+    }
+}
+
+/// Lets [`Tween`]s of a component run.
+pub trait RegisterTween {
+    fn register_tween<C: Component<Mutability = Mutable>>(&mut self) -> &mut Self;
+}
+
+impl RegisterTween for App {
+    fn register_tween<C: Component<Mutability = Mutable>>(&mut self) -> &mut Self {
+        self.add_systems(Update, run_tweens::<C>)
+    }
+}
+
+/// The shape of a tween's way from start to end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ease {
+    /// Fast at first, slowing to a stop.
+    #[default]
+    Quadratic,
+    /// Like [`Ease::Quadratic`], only more so.
+    Cubic,
+    /// Slow at both ends.
+    Smooth,
+}
+
+impl Ease {
+    /// How far along the way a tween is `t` of the way through its time.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Self::Quadratic => 1. - (1. - t).powi(2),
+            Self::Cubic => 1. - (1. - t).powi(3),
+            Self::Smooth => 0.5 - 0.5 * (t * std::f32::consts::PI).cos(),
+        }
+    }
+}
+
+type Lens<C> = Box<dyn FnMut(&mut C, f32) + Send + Sync>;
+
+/// Eases a field of the entity's `C` component.
+#[derive(Component)]
+pub struct Tween<C: Component<Mutability = Mutable>> {
+    lens: Lens<C>,
+    ease: Ease,
+    /// Length in seconds.
+    duration: f32,
+    elapsed: f32,
+    component: PhantomData<C>,
+}
+
+impl<C: Component<Mutability = Mutable>> Tween<C> {
+    /// A tween lasting `duration` seconds that sets the field with `lens`, given how far along
+    /// the way it is.
+    pub fn new(duration: f32, lens: impl FnMut(&mut C, f32) + Send + Sync + 'static) -> Self {
+        Self {
+            lens: Box::new(lens),
+            ease: Ease::default(),
+            duration,
+            elapsed: 0.,
+            component: PhantomData,
+        }
+    }
+
+    pub fn with_ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+}
+
+/// A tween ended and was removed from the entity.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TweenDone(pub Entity);
+
+fn run_tweens<C: Component<Mutability = Mutable>>(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut tweens: Query<(Entity, &mut Tween<C>, &mut C)>,
+    mut done: EventWriter<TweenDone>,
+) {
+    for (entity, mut tween, mut component) in &mut tweens {
+        tween.elapsed = if config.accessibility.reduced_motion {
+            tween.duration
+        } else {
+            tween.elapsed + time.delta_secs()
+        };
+        let t = if tween.duration > 0. {
+            tween.elapsed / tween.duration
+        } else {
+            1.
+        };
+        let along = tween.ease.apply(t);
+        (tween.lens)(&mut component, along);
+        if t >= 1. {
+            // Unless another tween took its place in the meantime.
+            commands.entity(entity).queue(|mut entity: EntityWorldMut| {
+                if entity
+                    .get::<Tween<C>>()
+                    .is_some_and(|tween| tween.elapsed >= tween.duration)
+                {
+                    entity.remove::<Tween<C>>();
+                }
+            });
+            done.write(TweenDone(entity));
+        }
+    }
+}
@@ -0,0 +1,554 @@
+//! Menus drawn on the pixel-perfect canvas.
+//!
+//! A menu is a titled panel with a vertical list of items. The arrow keys (or W and S), Tab and
+//! Shift+Tab move the selection, Home and End jump to either end, Enter or Space chooses, and the
+//! mouse selects by hovering and chooses by clicking. Screens spawn a menu with [`spawn_menu`]
+//! and react to [`MenuChosen`].
+//!
+//! Keys go to the menu with the focus, which is the one spawned last while it is around, and a
+//! ring drawn around its selected item shows where they go.
+//!
+//! Also home to [`AnimatedNumber`], text that counts towards new values instead of snapping, and
+//! to the praise cards that [`spawn_praise`] puts up for a few seconds.
+
+use bevy::prelude::*;
+
+use crate::{
+    canvas::{CanvasCursor, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    transition::in_transition,
+    tween::{Ease, Tween},
+};
+
+/// Height of a single menu item.
+const ITEM_HEIGHT: f32 = 14.;
+
+/// Width of the menu panel and its items.
+const MENU_WIDTH: f32 = 176.;
+
+/// Space above the items, taken by the title.
+const TITLE_HEIGHT: f32 = 22.;
+
+const PANEL_COLOR: Color = Color::srgba(0.0, 0.08, 0.1, 0.95);
+const HIGHLIGHT_COLOR: Color = Color::srgba(0.0, 0.7, 0.8, 0.9);
+const TEXT_COLOR: Color = Color::WHITE;
+const DISABLED_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+/// Colors of menus in high contrast, where every item stands out at least 7:1 from behind it.
+const HIGH_CONTRAST_PANEL_COLOR: Color = Color::BLACK;
+const HIGH_CONTRAST_HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 0.9, 0.0);
+const HIGH_CONTRAST_DISABLED_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+
+/// Color of the ring around the item keys go to.
+const FOCUS_COLOR: Color = Color::WHITE;
+
+/// Seconds an [`AnimatedNumber`] takes to count to a new value.
+const COUNT_TIME: f32 = 0.5;
+
+const PRAISE_COLOR: Color = Color::srgb(0.0, 0.9, 1.0);
+
+/// Seconds a praise card takes to pop up, and the scale it pops up from.
+const PRAISE_POP_TIME: f32 = 0.2;
+const PRAISE_POP_SCALE: f32 = 0.8;
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuChosen>()
+            .add_systems(Startup, setup_focus_ring)
+            .add_systems(
+                Update,
+                (
+                    hand_over_focus,
+                    navigate_menus.run_if(not(in_transition)),
+                    highlight_menu_items,
+                    place_focus_ring,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, fade_praise)
+            .add_systems(PostUpdate, count_numbers);
+    }
+}
+
+/// A list of options the player can choose from.
+#[derive(Component)]
+pub struct Menu {
+    /// Index of the highlighted item.
+    pub selected: usize,
+    /// Whether each item can be chosen.
+    enabled: Vec<bool>,
+}
+
+impl Menu {
+    /// Selects the first item that can be chosen from `index` on, going by `step`.
+    fn select_from(&mut self, index: usize, step: isize) {
+        self.selected = index;
+        if self.enabled.get(index) != Some(&true) {
+            self.step(step);
+        }
+    }
+
+    /// Moves the selection by `step` items, skipping disabled ones and wrapping around.
+    fn step(&mut self, step: isize) {
+        let len = self.enabled.len() as isize;
+        let mut index = self.selected as isize;
+        for _ in 0..len {
+            index = (index + step).rem_euclid(len);
+            if self.enabled[index as usize] {
+                self.selected = index as usize;
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct MenuItem {
+    menu: Entity,
+    index: usize,
+}
+
+/// Marks the menu that keys go to.
+#[derive(Component)]
+struct Focused;
+
+/// Marks menus on screens that use Tab for something of their own, such as switching pages, so
+/// that it does not move through their items.
+#[derive(Component)]
+pub struct KeepsTab;
+
+/// Marks a menu over everything else, such as the fault screen's, that keeps the focus for as
+/// long as it is up.
+#[derive(Component)]
+pub struct Modal;
+
+/// The ring around the selected item of the focused menu.
+#[derive(Component)]
+struct FocusRing;
+
+/// An item of a menu was chosen.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MenuChosen {
+    pub menu: Entity,
+    pub item: usize,
+}
+
+/// One line of a menu.
+pub struct MenuEntry<'a> {
+    pub label: &'a str,
+    pub enabled: bool,
+}
+
+impl<'a> MenuEntry<'a> {
+    pub fn new(label: &'a str) -> Self {
+        Self {
+            label,
+            enabled: true,
+        }
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+/// Spawns a menu panel centred on `position` with the `selected` item highlighted, and `extra`
+/// added to the menu entity (typically a marker and a [`StateScoped`]).
+pub fn spawn_menu(
+    commands: &mut Commands,
+    title: &str,
+    entries: &[MenuEntry],
+    selected: usize,
+    position: Vec3,
+    extra: impl Bundle,
+) -> Entity {
+    let height = TITLE_HEIGHT + entries.len() as f32 * ITEM_HEIGHT + 6.;
+    let top = height / 2.;
+    let enabled: Vec<bool> = entries.iter().map(|entry| entry.enabled).collect();
+    let selected = if enabled.get(selected) == Some(&true) {
+        selected
+    } else {
+        enabled.iter().position(|&enabled| enabled).unwrap_or(0)
+    };
+
+    let menu = commands
+        .spawn((
+            Menu { selected, enabled },
+            Focused,
+            Sprite {
+                color: PANEL_COLOR,
+                custom_size: Some(Vec2::new(MENU_WIDTH + 8., height)),
+                ..default()
+            },
+            Transform::from_translation(position),
+            PIXEL_PERFECT_LAYERS,
+            extra,
+        ))
+        .id();
+
+    commands.entity(menu).with_children(|panel| {
+        panel.spawn((
+            Text2d::new(title),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(HIGHLIGHT_COLOR),
+            Transform::from_xyz(0., top - TITLE_HEIGHT / 2., 0.2),
+            PIXEL_PERFECT_LAYERS,
+        ));
+        for (index, entry) in entries.iter().enumerate() {
+            let y = top - TITLE_HEIGHT - (index as f32 + 0.5) * ITEM_HEIGHT;
+            panel
+                .spawn((
+                    MenuItem { menu, index },
+                    Sprite {
+                        color: Color::NONE,
+                        custom_size: Some(Vec2::new(MENU_WIDTH, ITEM_HEIGHT - 2.)),
+                        ..default()
+                    },
+                    Transform::from_xyz(0., y, 0.1),
+                    PIXEL_PERFECT_LAYERS,
+                ))
+                .with_child((
+                    Text2d::new(entry.label),
+                    TextFont {
+                        font_size: 10.0,
+                        ..default()
+                    },
+                    TextColor(if entry.enabled {
+                        TEXT_COLOR
+                    } else {
+                        DISABLED_COLOR
+                    }),
+                    Transform::from_xyz(0., 0., 0.1),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+        }
+    });
+
+    menu
+}
+
+/// Menus that are no longer the newest give up the focus, and a menu takes it when the one that
+/// had it goes. A [`Modal`] menu holds on to it, however new the others are.
+fn hand_over_focus(
+    mut commands: Commands,
+    focused: Query<(Entity, Ref<Focused>)>,
+    menus: Query<Entity, With<Menu>>,
+    modal: Query<Entity, (With<Menu>, With<Modal>)>,
+) {
+    let newest = modal.iter().last().or_else(|| {
+        focused
+            .iter()
+            .filter(|(_, focused)| focused.is_added())
+            .map(|(entity, _)| entity)
+            .last()
+    });
+    match newest {
+        Some(newest) => {
+            for (entity, _) in &focused {
+                if entity != newest {
+                    commands.entity(entity).remove::<Focused>();
+                }
+            }
+            if !focused.contains(newest) {
+                commands.entity(newest).insert(Focused);
+            }
+        }
+        None if focused.is_empty() => {
+            if let Some(menu) = menus.iter().last() {
+                commands.entity(menu).insert(Focused);
+            }
+        }
+        None => {}
+    }
+}
+
+fn navigate_menus(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    pointer: CanvasCursor,
+    mut menus: Query<(Entity, &mut Menu, Has<Focused>, Has<KeepsTab>)>,
+    items: Query<(&MenuItem, &GlobalTransform)>,
+    mut chosen: EventWriter<MenuChosen>,
+) {
+    let cursor = pointer.world();
+    let hovered = cursor.and_then(|cursor| {
+        items.iter().find(|(_, transform)| {
+            Rect::from_center_size(
+                transform.translation().truncate(),
+                Vec2::new(MENU_WIDTH, ITEM_HEIGHT),
+            )
+            .contains(cursor)
+        })
+    });
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for (entity, mut menu, focused, keeps_tab) in &mut menus {
+        let mut choose = false;
+        if focused {
+            if keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW])
+                || (!keeps_tab && shift && keys.just_pressed(KeyCode::Tab))
+            {
+                menu.step(-1);
+            }
+            if keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS])
+                || (!keeps_tab && !shift && keys.just_pressed(KeyCode::Tab))
+            {
+                menu.step(1);
+            }
+            if keys.just_pressed(KeyCode::Home) {
+                menu.select_from(0, 1);
+            }
+            if keys.just_pressed(KeyCode::End) {
+                let last = menu.enabled.len().saturating_sub(1);
+                menu.select_from(last, -1);
+            }
+            choose = keys.any_just_pressed([KeyCode::Enter, KeyCode::Space]);
+        }
+
+        if let Some((item, _)) = hovered.filter(|(item, _)| item.menu == entity) {
+            if menu.enabled[item.index] {
+                if menu.selected != item.index {
+                    menu.selected = item.index;
+                }
+                choose |= buttons.just_pressed(MouseButton::Left);
+            }
+        }
+
+        if choose && menu.enabled.get(menu.selected) == Some(&true) {
+            chosen.write(MenuChosen {
+                menu: entity,
+                item: menu.selected,
+            });
+        }
+    }
+}
+
+/// Colors the items of menus by whether they are highlighted or can be chosen, in high contrast
+/// or not.
+fn highlight_menu_items(
+    config: Res<Config>,
+    mut menus: Query<(Entity, Ref<Menu>, &mut Sprite), Without<MenuItem>>,
+    mut items: Query<(&MenuItem, &Children, &mut Sprite)>,
+    mut texts: Query<&mut TextColor>,
+) {
+    let high_contrast = config.accessibility.high_contrast;
+    for (entity, menu, mut panel) in &mut menus {
+        if !menu.is_changed() && !config.is_changed() {
+            continue;
+        }
+        panel.color = if high_contrast {
+            HIGH_CONTRAST_PANEL_COLOR
+        } else {
+            PANEL_COLOR
+        };
+        for (item, children, mut sprite) in &mut items {
+            if item.menu != entity {
+                continue;
+            }
+            let highlighted = menu.selected == item.index;
+            let enabled = menu.enabled.get(item.index) == Some(&true);
+            let (background, text) = match (high_contrast, highlighted, enabled) {
+                (false, true, _) => (HIGHLIGHT_COLOR, TEXT_COLOR),
+                (false, false, true) => (Color::NONE, TEXT_COLOR),
+                (false, false, false) => (Color::NONE, DISABLED_COLOR),
+                (true, true, _) => (HIGH_CONTRAST_HIGHLIGHT_COLOR, Color::BLACK),
+                (true, false, true) => (Color::NONE, TEXT_COLOR),
+                (true, false, false) => (Color::NONE, HIGH_CONTRAST_DISABLED_COLOR),
+            };
+            if sprite.color != background {
+                sprite.color = background;
+            }
+            for &child in children {
+                if let Ok(mut color) = texts.get_mut(child) {
+                    color.set_if_neq(TextColor(text));
+                }
+            }
+        }
+    }
+}
+
+fn setup_focus_ring(mut commands: Commands) {
+    let size = Vec2::new(MENU_WIDTH + 2., ITEM_HEIGHT);
+    commands
+        .spawn((
+            FocusRing,
+            Transform::default(),
+            Visibility::Hidden,
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_children(|ring| {
+            for side in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+                let edge = if side.x == 0. {
+                    Vec2::new(size.x, 1.)
+                } else {
+                    Vec2::new(1., size.y)
+                };
+                ring.spawn((
+                    Sprite {
+                        color: FOCUS_COLOR,
+                        custom_size: Some(edge),
+                        ..default()
+                    },
+                    Transform::from_translation((side * (size - 1.) / 2.).extend(0.)),
+                    PIXEL_PERFECT_LAYERS,
+                ));
+            }
+        });
+}
+
+fn place_focus_ring(
+    menus: Query<(Entity, &Menu), With<Focused>>,
+    items: Query<(&MenuItem, &GlobalTransform)>,
+    ring: Single<(&mut Transform, &mut Visibility), With<FocusRing>>,
+) {
+    let (mut transform, mut visibility) = ring.into_inner();
+    let selected = menus.iter().find_map(|(entity, menu)| {
+        items
+            .iter()
+            .find(|(item, _)| item.menu == entity && item.index == menu.selected)
+    });
+    let Some((_, item)) = selected else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    // Just above the item and its text.
+    let translation = item.translation() + Vec3::Z * 0.3;
+    if transform.translation != translation {
+        transform.translation = translation;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+}
+
+/// Text showing a number that counts towards each new value, easing out like the bins' bars, so
+/// that its digits tick over instead of snapping.
+#[derive(Component)]
+#[require(Text2d)]
+pub struct AnimatedNumber {
+    from: f32,
+    to: f32,
+    elapsed: f32,
+    format: fn(f32) -> String,
+    /// Shown in place of the number while set.
+    label: Option<&'static str>,
+}
+
+impl AnimatedNumber {
+    /// A number showing `value`, written out by `format`.
+    pub fn new(value: f32, format: fn(f32) -> String) -> Self {
+        Self {
+            from: value,
+            to: value,
+            elapsed: 0.,
+            format,
+            label: None,
+        }
+    }
+
+    /// Starts counting from what is shown now towards `value`.
+    pub fn set(&mut self, value: f32) {
+        if value != self.to {
+            *self = Self {
+                from: self.shown(),
+                to: value,
+                elapsed: 0.,
+                ..*self
+            };
+        }
+    }
+
+    /// Shows `label` instead of the number, or the number again for `None`.
+    pub fn set_label(&mut self, label: Option<&'static str>) {
+        if self.label != label {
+            self.label = label;
+        }
+    }
+
+    fn shown(&self) -> f32 {
+        let t = (self.elapsed / COUNT_TIME).clamp(0., 1.);
+        self.from + (self.to - self.from) * (1. - (1. - t).powi(3))
+    }
+
+    fn text(&self) -> String {
+        match self.label {
+            Some(label) => label.to_string(),
+            None => (self.format)(self.shown()),
+        }
+    }
+}
+
+fn count_numbers(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut numbers: Query<(&mut AnimatedNumber, &mut Text2d)>,
+) {
+    for (mut number, mut text) in &mut numbers {
+        // Settled numbers are left alone until they are given a new value. Counting does not
+        // count as a change, or they would never settle.
+        if number.elapsed >= COUNT_TIME && !number.is_changed() {
+            continue;
+        }
+        number.bypass_change_detection().elapsed = if config.accessibility.reduced_motion {
+            COUNT_TIME
+        } else {
+            number.elapsed + time.delta_secs()
+        };
+        let shown = number.text();
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+}
+
+/// A card of praise, and the seconds it has left.
+#[derive(Component)]
+struct Praise(f32);
+
+/// Puts up a card of praise over the grid for `seconds`.
+pub fn spawn_praise(commands: &mut Commands, text: impl Into<String>, seconds: f32) {
+    commands
+        .spawn((
+            Praise(seconds),
+            // Popping up rather than just appearing.
+            Tween::new(PRAISE_POP_TIME, |transform: &mut Transform, t| {
+                transform.scale = Vec3::splat(PRAISE_POP_SCALE + (1. - PRAISE_POP_SCALE) * t);
+            })
+            .with_ease(Ease::Smooth),
+            Sprite {
+                color: Color::srgba(0.0, 0.0, 0.0, 0.85),
+                custom_size: Some(Vec2::new(240., 32.)),
+                ..default()
+            },
+            Transform::from_xyz(0., 24., 18.),
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .with_child((
+            Text2d::new(text),
+            TextFont {
+                font_size: 10.0,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            TextColor(PRAISE_COLOR),
+            Transform::from_xyz(0., 0., 0.1),
+            PIXEL_PERFECT_LAYERS,
+        ));
+}
+
+fn fade_praise(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut praise: Query<(Entity, &mut Praise)>,
+) {
+    for (entity, mut praise) in &mut praise {
+        praise.0 -= time.delta_secs();
+        if praise.0 <= 0. {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Wellness sessions: a short interlude after every so many refinements.
+//!
+//! The session shows a few calming fact cards over a breathing circle, then returns to the grid
+//! on its own. Any key or click ends it early. Sessions are off by default, since dashboards
+//! have nobody to calm; the interval is set from the gameplay settings.
+//!
+//! The time spent refining without a break is kept too, and after as many minutes as the
+//! settings ask a gentle reminder offers a wellness check, which B begins and N snoozes. Leaving
+//! the grid alone for a few minutes counts as a break. Reminders are off by default too, and
+//! never come while the bins are driven by something other than refinement.
+
+use bevy::prelude::*;
+
+use crate::{
+    bins::DrivenBins,
+    canvas::{CanvasAnchor, CanvasFill, PIXEL_PERFECT_LAYERS},
+    config::Config,
+    grid::{RefineSet, Refined},
+    idle::Idle,
+    jazz::DefiantJazz,
+    net::SharedSession,
+    replay::Playback,
+    state::AppState,
+    transition::{in_transition, TransitionEffect, TransitionTo},
+};
+
+/// The fact cards of a session, in order.
+const FACTS: [&str; 4] = [
+    "Your outie is kind.",
+    "Your outie enjoys\nthe smell of fresh rain.",
+    "Your outie is well liked\nby animals.",
+    "Your outie is grateful\nfor your work.",
+];
+
+/// Seconds each fact card is shown.
+const CARD_TIME: f32 = 5.;
+
+/// Seconds of one breath in and out.
+const BREATH_PERIOD: f32 = 8.;
+
+/// Radius of the breathing circle at its fullest.
+const BREATH_RADIUS: f32 = 36.;
+
+const CALM_COLOR: Color = Color::srgb(0.55, 0.85, 0.8);
+
+/// Seconds without input that count as a break.
+const BREAK_SECONDS: f32 = 300.;
+
+/// Where the break reminder sits, under the header.
+const REMINDER_ANCHOR: CanvasAnchor = CanvasAnchor::TOP.offset(0., -44.);
+
+pub struct WellnessPlugin;
+
+impl Plugin for WellnessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RefinementsSinceSession>()
+            .init_resource::<RefiningTime>()
+            .add_systems(
+                Update,
+                (
+                    (track_refining_time, answer_reminder).chain().run_if(
+                        in_state(AppState::Refining)
+                            .and(not(in_transition))
+                            .and(not(resource_exists::<DrivenBins>))
+                            .and(not(resource_exists::<Playback>))
+                            .and(not(resource_exists::<SharedSession>)),
+                    ),
+                    // Shared sessions, replays and songs are not the refiner's to interrupt.
+                    count_refinements
+                        .run_if(
+                            not(resource_exists::<SharedSession>)
+                                .and(not(resource_exists::<Playback>))
+                                .and(not(resource_exists::<DefiantJazz>)),
+                        )
+                        .in_set(RefineSet::React),
+                    (skip_session, play_session)
+                        .chain()
+                        .run_if(in_state(AppState::Wellness).and(not(in_transition))),
+                ),
+            )
+            .add_systems(OnEnter(AppState::Wellness), start_session);
+    }
+}
+
+/// Refinements since the last wellness session.
+#[derive(Resource, Default)]
+struct RefinementsSinceSession(u32);
+
+/// Real seconds spent refining since the last break.
+#[derive(Resource, Default)]
+struct RefiningTime {
+    seconds: f32,
+    /// Seconds of refining at which a snoozed reminder comes back.
+    snoozed_until: f32,
+}
+
+/// The reminder offering a wellness check.
+#[derive(Component)]
+struct BreakReminder;
+
+/// How far into the session we are.
+#[derive(Resource, Default)]
+struct Session {
+    elapsed: f32,
+}
+
+#[derive(Component)]
+struct FactCard;
+
+#[derive(Component)]
+struct BreathingCircle;
+
+#[derive(Component)]
+struct BreathLabel;
+
+/// Sends the refiner to a session once they have refined enough.
+fn count_refinements(
+    mut refined: EventReader<Refined>,
+    mut count: ResMut<RefinementsSinceSession>,
+    config: Res<Config>,
+    state: Res<State<AppState>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    count.0 += refined.read().count() as u32;
+    let interval = config.gameplay.wellness_interval;
+    if interval == 0 || count.0 < interval || *state.get() != AppState::Refining {
+        return;
+    }
+    count.0 = 0;
+    transitions.write(TransitionTo::new(
+        AppState::Wellness,
+        TransitionEffect::Fade,
+    ));
+}
+
+/// Counts the time spent refining, starting over after a break, and puts up the reminder once
+/// it is due.
+fn track_refining_time(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    idle: Res<Idle>,
+    mut refining: ResMut<RefiningTime>,
+    reminder: Query<(), With<BreakReminder>>,
+) {
+    if idle.seconds(&time) >= BREAK_SECONDS {
+        if refining.seconds > 0. {
+            *refining = RefiningTime::default();
+        }
+        return;
+    }
+    refining.seconds += time.delta_secs();
+    let minutes = config.gameplay.break_reminder_minutes;
+    let due = (minutes * 60) as f32;
+    if minutes == 0 || refining.seconds < due.max(refining.snoozed_until) || !reminder.is_empty() {
+        return;
+    }
+    let text = format!(
+        "{} minutes without a break\nB: wellness check    N: not now",
+        (refining.seconds / 60.) as u32
+    );
+    commands
+        .spawn((
+            BreakReminder,
+            Sprite {
+                color: Color::srgba(0.02, 0.1, 0.12, 0.9),
+                custom_size: Some(Vec2::new(160., 24.)),
+                ..default()
+            },
+            REMINDER_ANCHOR,
+            Transform::from_xyz(0., 0., 25.),
+            PIXEL_PERFECT_LAYERS,
+            StateScoped(AppState::Refining),
+        ))
+        .with_child((
+            Text2d::new(text),
+            TextFont {
+                font_size: 8.0,
+                ..default()
+            },
+            TextColor(CALM_COLOR),
+            Transform::from_xyz(0., 0., 0.1),
+            PIXEL_PERFECT_LAYERS,
+        ));
+}
+
+/// Begins a wellness check from the reminder, or snoozes it.
+fn answer_reminder(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<Config>,
+    mut refining: ResMut<RefiningTime>,
+    reminder: Option<Single<Entity, With<BreakReminder>>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    let Some(reminder) = reminder else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::KeyB) {
+        transitions.write(TransitionTo::new(
+            AppState::Wellness,
+            TransitionEffect::Fade,
+        ));
+    } else if keys.just_pressed(KeyCode::KeyN) {
+        refining.snoozed_until = refining.seconds + (config.gameplay.snooze_minutes * 60) as f32;
+        commands.entity(*reminder).despawn();
+    }
+}
+
+fn start_session(
+    mut commands: Commands,
+    mut refining: ResMut<RefiningTime>,
+    mut time: ResMut<Time<Virtual>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // The grid waits, like it does while paused, and the session counts as a break.
+    time.pause();
+    *refining = RefiningTime::default();
+    commands.insert_resource(Session::default());
+
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.02, 0.1, 0.12),
+            ..default()
+        },
+        CanvasFill,
+        Transform::from_xyz(0., 0., 30.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Wellness),
+    ));
+    commands.spawn((
+        BreathingCircle,
+        Mesh2d(meshes.add(Circle::new(BREATH_RADIUS))),
+        MeshMaterial2d(materials.add(CALM_COLOR.with_alpha(0.35))),
+        Transform::from_xyz(0., -24., 31.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Wellness),
+    ));
+    commands.spawn((
+        FactCard,
+        Text2d::default(),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(CALM_COLOR),
+        Transform::from_xyz(0., 80., 32.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Wellness),
+    ));
+    commands.spawn((
+        BreathLabel,
+        Text2d::default(),
+        TextFont {
+            font_size: 10.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Transform::from_xyz(0., -24., 32.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Wellness),
+    ));
+    commands.spawn((
+        Text2d::new("Press any key to return to your work"),
+        TextFont {
+            font_size: 8.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.4, 0.5, 0.5)),
+        CanvasAnchor::BOTTOM.offset(0., 12.),
+        Transform::from_xyz(0., 0., 32.),
+        PIXEL_PERFECT_LAYERS,
+        StateScoped(AppState::Wellness),
+    ));
+}
+
+/// Any key or mouse button goes back to the grid.
+fn skip_session(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    if keys.get_just_pressed().next().is_some() || buttons.get_just_pressed().next().is_some() {
+        transitions.write(TransitionTo::new(
+            AppState::Refining,
+            TransitionEffect::Fade,
+        ));
+    }
+}
+
+fn play_session(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    mut session: ResMut<Session>,
+    mut card: Single<&mut Text2d, (With<FactCard>, Without<BreathLabel>)>,
+    mut label: Single<&mut Text2d, With<BreathLabel>>,
+    mut circle: Single<&mut Transform, With<BreathingCircle>>,
+    mut transitions: EventWriter<TransitionTo>,
+) {
+    session.elapsed += time.delta_secs();
+    let index = (session.elapsed / CARD_TIME) as usize;
+    let Some(fact) = FACTS.get(index) else {
+        transitions.write(TransitionTo::new(
+            AppState::Refining,
+            TransitionEffect::Fade,
+        ));
+        return;
+    };
+    if card.0 != *fact {
+        card.0 = fact.to_string();
+    }
+
+    // In for the first half of each breath, out for the second.
+    let phase = (session.elapsed / BREATH_PERIOD).fract();
+    let breath = if config.accessibility.reduced_motion {
+        1.
+    } else {
+        0.5 - 0.5 * (phase * std::f32::consts::TAU).cos()
+    };
+    circle.scale = Vec3::splat(0.5 + 0.5 * breath);
+    let prompt = if phase < 0.5 {
+        "Breathe in"
+    } else {
+        "Breathe out"
+    };
+    if label.0 != prompt {
+        label.0 = prompt.to_string();
+    }
+}
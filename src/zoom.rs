@@ -0,0 +1,193 @@
+//! Zoom: the grid camera magnified by whole steps, for a closer look at the numbers.
+//!
+//! With the grid on screen, `=` and `-` or the mouse wheel step through [`ZOOM_LEVELS`], and Z
+//! zooms to fit the selection, as close as it fits in the view. The zoom eases from level to
+//! level and settles exactly on it, with the view snapped to whole canvas pixels, so that the
+//! digits never land between pixels once still. Everything that goes by the size of the grid's
+//! view asks for it through the [`Zoom`].
+
+use bevy::{input::mouse::MouseWheel, prelude::*, render::camera::CameraUpdateSystem};
+
+use crate::{
+    canvas::{CanvasSize, GridCamera, Supersampling},
+    config::Config,
+    grid::{clamp_view, Cell, GridSize, RefineSet, Selection, NUMBER_SPACING},
+    state::AppState,
+};
+
+/// How many canvas pixels a grid pixel covers at each level.
+const ZOOM_LEVELS: [f32; 4] = [1., 2., 3., 4.];
+
+/// How quickly the zoom eases towards its level, as a share of the way per second.
+const ZOOM_RATE: f32 = 12.;
+
+/// How close to its level the zoom settles onto it.
+const SETTLE_DISTANCE: f32 = 0.01;
+
+pub struct ZoomPlugin;
+
+impl Plugin for ZoomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Zoom>()
+            .add_systems(
+                Update,
+                (zoom_input, ease_zoom)
+                    .chain()
+                    .run_if(in_state(AppState::Refining))
+                    .in_set(RefineSet::Input),
+            )
+            .add_systems(
+                PostUpdate,
+                magnify_view
+                    .run_if(resource_changed::<Zoom>.or(resource_changed::<Supersampling>))
+                    .before(CameraUpdateSystem),
+            );
+    }
+}
+
+/// How far in the grid camera is zoomed.
+#[derive(Resource, Debug)]
+pub struct Zoom {
+    /// Index of the level in [`ZOOM_LEVELS`] the zoom is at or easing towards.
+    level: usize,
+    /// Canvas pixels a grid pixel covers right now.
+    scale: f32,
+    /// Where the view is headed as it zooms to fit the selection.
+    focus: Option<Vec2>,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self {
+            level: 0,
+            scale: ZOOM_LEVELS[0],
+            focus: None,
+        }
+    }
+}
+
+impl Zoom {
+    /// Settled at the level with the given index in [`ZOOM_LEVELS`], or the closest there is.
+    pub fn at_level(level: usize) -> Self {
+        let level = level.min(ZOOM_LEVELS.len() - 1);
+        Self {
+            level,
+            scale: ZOOM_LEVELS[level],
+            focus: None,
+        }
+    }
+
+    /// Index of the level in [`ZOOM_LEVELS`] the zoom is at or easing towards.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Canvas pixels a grid pixel covers right now.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Size of the part of the grid the view shows, in grid pixels.
+    pub fn view(&self, canvas: CanvasSize) -> Vec2 {
+        canvas.size() / self.scale
+    }
+
+    fn settled(&self) -> bool {
+        self.scale == ZOOM_LEVELS[self.level]
+    }
+}
+
+fn zoom_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    (selection, canvas): (Res<Selection>, Res<CanvasSize>),
+    mut zoom: ResMut<Zoom>,
+) {
+    let scroll: f32 = wheel.read().map(|event| event.y).sum();
+    let step = if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) || scroll > 0. {
+        1
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) || scroll < 0. {
+        -1
+    } else {
+        0
+    };
+    if step != 0 {
+        let level = zoom
+            .level
+            .saturating_add_signed(step)
+            .min(ZOOM_LEVELS.len() - 1);
+        if level != zoom.level {
+            zoom.level = level;
+            zoom.focus = None;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::KeyZ) {
+        let Some(range) = selection.0 else {
+            return;
+        };
+        let corner = |col, row| Cell { col, row }.position();
+        let rect = Rect::from_corners(
+            corner(range.min.x, range.min.y),
+            corner(range.max.x, range.max.y),
+        )
+        .inflate(NUMBER_SPACING / 2.);
+        // The closest level the selection fits at, or the farthest if it fits at none.
+        zoom.level = ZOOM_LEVELS
+            .iter()
+            .rposition(|&level| (rect.size() * level).cmple(canvas.size()).all())
+            .unwrap_or(0);
+        zoom.focus = Some(rect.center());
+    }
+}
+
+fn ease_zoom(
+    time: Res<Time<Real>>,
+    config: Res<Config>,
+    (canvas, size): (Res<CanvasSize>, Res<GridSize>),
+    mut zoom: ResMut<Zoom>,
+    mut camera: Single<&mut Transform, With<GridCamera>>,
+) {
+    if zoom.settled() && zoom.focus.is_none() {
+        return;
+    }
+    let target = ZOOM_LEVELS[zoom.level];
+    let ease = if config.accessibility.reduced_motion {
+        1.
+    } else {
+        (ZOOM_RATE * time.delta_secs()).min(1.)
+    };
+    zoom.scale += (target - zoom.scale) * ease;
+    let settling = (target - zoom.scale).abs() < SETTLE_DISTANCE;
+    if settling {
+        zoom.scale = target;
+    }
+
+    let view = zoom.view(*canvas);
+    let mut center = camera.translation.truncate();
+    if let Some(focus) = zoom.focus {
+        center = if settling {
+            focus
+        } else {
+            center.lerp(focus, ease)
+        };
+    }
+    center = clamp_view(center, view, *size);
+    if settling {
+        zoom.focus = None;
+        // On whole canvas pixels, for the digits to be drawn crisp.
+        center = (center * target).round() / target;
+    }
+    camera.translation = center.extend(camera.translation.z);
+}
+
+/// Zooms the grid camera in to the zoom's scale, on top of the canvas's supersampling.
+fn magnify_view(
+    zoom: Res<Zoom>,
+    supersampling: Res<Supersampling>,
+    mut projection: Single<&mut Projection, With<GridCamera>>,
+) {
+    if let Projection::Orthographic(projection) = &mut **projection {
+        projection.scale = 1. / (supersampling.0 as f32 * zoom.scale);
+    }
+}